@@ -0,0 +1,910 @@
+//! Privileged helper of System Settings. It is started through `pkexec`, which asks polkit to authorize the action
+//! given as first argument before running it as root.
+#[path = "../helpers/firewall.rs"]
+#[allow(dead_code)]
+mod firewall;
+#[path = "../helpers/login_policy.rs"]
+mod login_policy;
+#[path = "../helpers/polkit.rs"]
+mod polkit;
+
+use firewall::{app_profiles, CommandRunner, Firewall, FirewallChange, FirewallKind, FwRule};
+use libkoompi::system_settings::{
+    datetime::DateTimeManager,
+    locale::{LC_Keywords, LocaleManager},
+    users_groups::UsersGroupsManager,
+};
+use login_policy::{restrictable_apps, DisplayManager, LoginHours, LoginOptions, LoginPolicy, UserPolicy, DAYS, POLICY_PATH};
+use polkit::{is_skel_dir, Action, NewPassword, NewUser, UserEdit, IDLE_LOGOUT_CONF};
+use std::io::{Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const PASSWD: &str = "/etc/passwd";
+const GROUP: &str = "/etc/group";
+/// Members of this group are administrators.
+const ADMIN_GROUP: &str = "wheel";
+const SHELLS: &str = "/etc/shells";
+const TIME_CONF: &str = "/etc/security/time.conf";
+const TIME_BLOCK_BEGIN: &str = "# system-settings parental controls begin";
+const TIME_BLOCK_END: &str = "# system-settings parental controls end";
+/// PAM stacks shared by the login services on Arch and Debian based systems.
+const PAM_ACCOUNT_STACKS: [&str; 2] = ["/etc/pam.d/system-login", "/etc/pam.d/common-account"];
+const PAM_TIME_RULE: &str = "account    required    pam_time.so";
+
+/// A change made as root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    /// Run a program with its arguments.
+    Run(Vec<String>),
+    /// Replace the content of a file.
+    Write { path: String, content: String },
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (action, args) = args.split_first().ok_or_else(|| String::from("Missing action"))?;
+    // Run as root by the pacman hook, not through pkexec, so polkit has no action for it.
+    if action == "restore-acls" {
+        return restore_acls();
+    }
+    match action.parse::<Action>()? {
+        Action::Unlock => Ok(()),
+        Action::DeleteUser => {
+            let (username, archive, remove_home) = match args {
+                [username] => (username, None, false),
+                [username, flag] if flag == "--remove-home" => (username, None, true),
+                [username, flag, archive] if flag == "--archive-home" => (username, Some(archive), true),
+                _ => return Err(String::from("Usage: delete-user USERNAME [--remove-home | --archive-home FILE]")),
+            };
+            let username = &deletable_user(username)?.name;
+            if let Some(archive) = archive {
+                archive_home(username, Path::new(archive))?;
+            }
+            let mut usrgrp_mn = UsersGroupsManager::new().map_err(|e| format!("{:?}", e))?;
+            match usrgrp_mn.delete_user(username, remove_home) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(format!("Failed to delete {}", username)),
+                Err(err) => Err(format!("{:?}", err)),
+            }
+        }
+        Action::SetDateTime => {
+            let datetime = single_arg(args, "set-datetime DATETIME")?;
+            let mut datetime_mn = DateTimeManager::new().map_err(|e| e.to_string())?;
+            match datetime_mn.set_datetime(datetime) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(String::from("Failed to set the date and time")),
+                Err(err) => Err(err.to_string()),
+            }
+        }
+        Action::SetTimeZone => {
+            let timezone = single_arg(args, "set-timezone TIMEZONE")?;
+            let mut datetime_mn = DateTimeManager::new().map_err(|e| e.to_string())?;
+            match datetime_mn.set_timezone(timezone) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(format!("Failed to set the time zone to {}", timezone)),
+                Err(err) => Err(err.to_string()),
+            }
+        }
+        Action::WriteLocale => {
+            let mut locale_mn = LocaleManager::new().map_err(|e| e.to_string())?;
+            for arg in args {
+                let (key, val) = arg.split_at(arg.find('=').ok_or_else(|| format!("Expected KEY=VALUE, got {}", arg))?);
+                locale_mn.set_locale(locale_keyword(key)?, &val[1..]).map_err(|e| format!("{:?}", e))?;
+            }
+            locale_mn.write_conf().map_err(|e| e.to_string())
+        }
+        Action::LoginOptions => {
+            let options: LoginOptions = read_json()?;
+            let mut policy = LoginPolicy::load();
+            login_options_steps(&options, &mut policy)?.iter().try_for_each(exec_step)
+        }
+        Action::AddGroup => {
+            let (name, opts) = args.split_first().ok_or_else(|| String::from("Usage: add-group NAME [--gid GID] [--system]"))?;
+            let mut cmd = vec![String::from("groupadd")];
+            let mut opts = opts.iter();
+            while let Some(opt) = opts.next() {
+                match opt.as_str() {
+                    "--gid" => {
+                        let gid = opts.next().filter(|gid| gid.parse::<u32>().is_ok()).ok_or_else(|| String::from("--gid expects a number"))?;
+                        cmd.extend(vec![opt.to_owned(), gid.to_owned()]);
+                    }
+                    "--system" => cmd.push(opt.to_owned()),
+                    _ => return Err(format!("Unknown option: {}", opt)),
+                }
+            }
+            cmd.push(check_name(name)?);
+            exec_step(&Step::Run(cmd))
+        }
+        Action::EditGroup => {
+            let (name, opts) = args.split_first().ok_or_else(|| String::from("Usage: edit-group NAME [--members USER,...] [--rename NEW_NAME]"))?;
+            let (mut members, mut new_name) = (None, None);
+            let mut opts = opts.iter();
+            while let Some(opt) = opts.next() {
+                let val = opts.next().ok_or_else(|| format!("{} expects a value", opt))?;
+                match opt.as_str() {
+                    "--members" => members = Some(val.split(',').filter(|member| !member.is_empty()).map(String::from).collect()),
+                    "--rename" => new_name = Some(val.to_owned()),
+                    _ => return Err(format!("Unknown option: {}", opt)),
+                }
+            }
+            edit_group(name, members, new_name)
+        }
+        Action::DeleteGroup => {
+            let name = check_name(single_arg(args, "delete-group NAME")?)?;
+            exec_step(&Step::Run(vec![String::from("groupdel"), name]))
+        }
+        Action::CreateUser => create_user(&read_json()?),
+        Action::EditUser => exec_step(&Step::Run(usermod_args(&read_json()?)?)),
+        Action::SetAccountType => {
+            let (username, flag) = match args {
+                [username, kind] if kind == "admin" => (username, "--add"),
+                [username, kind] if kind == "standard" => (username, "--delete"),
+                _ => return Err(String::from("Usage: set-account-type USERNAME admin|standard")),
+            };
+            let username = check_name(username)?;
+            passwd_entry(&username)?;
+            exec_step(&Step::Run(vec![String::from("gpasswd"), flag.to_string(), username, ADMIN_GROUP.to_string()]))
+        }
+        Action::SetGroups => {
+            let (username, groups) = match args {
+                [username, groups] => (check_name(username)?, groups),
+                _ => return Err(String::from("Usage: set-groups USERNAME GROUP,...")),
+            };
+            passwd_entry(&username)?;
+            let groups = groups.split(',').filter(|name| !name.is_empty()).map(check_group).collect::<Result<Vec<String>, String>>()?;
+            exec_step(&Step::Run(vec![String::from("usermod"), String::from("--groups"), groups.join(","), username]))
+        }
+        Action::SetPassword => {
+            let pwd: NewPassword = read_json()?;
+            set_password(&passwd_entry(&check_name(&pwd.username)?)?, &pwd.new_pwd)
+        }
+        Action::ChangePassword => {
+            let pwd: NewPassword = read_json()?;
+            let entry = passwd_entry(&check_name(&pwd.username)?)?;
+            // Any local user may run this action, so it only changes the password of the user who started pkexec.
+            if std::env::var("PKEXEC_UID").ok().as_deref() != Some(entry.uid.as_str()) {
+                return Err(String::from("Only your own password can be changed this way"));
+            }
+            verify_password(&entry.name, pwd.old_pwd.as_deref().unwrap_or_default())?;
+            set_password(&entry, &pwd.new_pwd)
+        }
+        Action::IdleLogout => {
+            let mins = match args {
+                [] => None,
+                [mins] => Some(mins.parse::<u32>().ok().filter(|mins| *mins > 0).ok_or_else(|| String::from("MINUTES expects a positive number"))?),
+                _ => return Err(String::from("Usage: idle-logout [MINUTES]")),
+            };
+            idle_logout_steps(mins).iter().try_for_each(exec_step)
+        }
+        Action::Firewall => {
+            let mut firewall = Firewall::new(FirewallKind::detect(), SystemRunner);
+            match args {
+                [] => {
+                    let change: FirewallChange = read_json()?;
+                    let profiles = app_profiles();
+                    change.prev.rules.iter().try_for_each(FwRule::validate)?;
+                    change.conf.check(&profiles)?;
+                    firewall.apply(&change.prev, &change.conf, &profiles)
+                }
+                [flag] if flag == "--ruleset" => {
+                    print!("{}", firewall.ruleset()?);
+                    Ok(())
+                }
+                _ => Err(String::from("Usage: firewall [--ruleset]")),
+            }
+        }
+    }
+}
+
+/// Runs the `ufw` and `nft` commands of the firewall, giving them their script on the standard input.
+struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&mut self, program: &str, args: &[String], stdin: Option<&str>) -> Result<String, String> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("{}: {}", program, e))?;
+        if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(input.as_bytes()).map_err(|e| format!("{}: {}", program, e))?;
+        }
+        let output = child.wait_with_output().map_err(|e| format!("{}: {}", program, e))?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(format!("{} {}: {}", program, args.join(" "), String::from_utf8_lossy(&output.stderr).trim()))
+        }
+    }
+}
+
+/// An account that may be deleted: neither root nor the user who started pkexec.
+fn deletable_user(username: &str) -> Result<PasswdEntry, String> {
+    let entry = passwd_entry(&check_name(username)?)?;
+    if entry.uid == "0" {
+        return Err(format!("{} can't be deleted", entry.name));
+    }
+    if std::env::var("PKEXEC_UID").ok().as_deref() == Some(entry.uid.as_str()) {
+        return Err(String::from("You can't delete your own account"));
+    }
+    Ok(entry)
+}
+
+/// Create an account with its password, then set up its home folder when it doesn't follow the defaults. The account
+/// is deleted again when a later step fails, so that nothing is left half done.
+fn create_user(user: &NewUser) -> Result<(), String> {
+    let username = check_name(&user.username)?;
+    let needs_setup = user.skel.is_some() || user.is_encrypted;
+    if let Some(skel) = &user.skel {
+        if !is_skel_dir(skel) {
+            return Err(format!("Invalid template folder: {}", skel.display()));
+        }
+    }
+    // Only a home folder `useradd` creates now may be set up, never one left by an older account.
+    let home = default_home_base()?.join(&username);
+    if needs_setup && std::fs::symlink_metadata(&home).is_ok() {
+        return Err(format!("{} already exists, so it can't be set up for the new account", home.display()));
+    }
+    let mut cmd = vec![String::from("useradd"), String::from("--create-home"), String::from("--comment"), check_field(&user.fullname)?];
+    if let Some(shell) = &user.shell {
+        cmd.extend(vec![String::from("--shell"), check_shell(shell)?]);
+    }
+    let mut groups = user.groups.iter().map(|grp| check_group(grp)).collect::<Result<Vec<String>, String>>()?;
+    if user.is_admin {
+        groups.push(ADMIN_GROUP.to_string());
+    }
+    if !groups.is_empty() {
+        cmd.extend(vec![String::from("--groups"), groups.join(",")]);
+    }
+    cmd.push(username.clone());
+    exec_step(&Step::Run(cmd))?;
+    let res = passwd_entry(&username).and_then(|entry| {
+        set_password(&entry, &user.password)?;
+        if needs_setup {
+            if entry.home != home {
+                return Err(format!("Unexpected home folder: {}", entry.home.display()));
+            }
+            setup_home(&entry, user.skel.as_deref(), if user.is_encrypted { Some(&user.password) } else { None })?;
+        }
+        Ok(())
+    });
+    if res.is_err() {
+        let _ = exec_step(&Step::Run(vec![String::from("userdel"), String::from("--remove"), username]));
+    }
+    res
+}
+
+/// Replace the members of a group and rename it, checking both changes before making either. The old members are
+/// given back when the group can't be renamed.
+fn edit_group(name: &str, members: Option<Vec<String>>, new_name: Option<String>) -> Result<(), String> {
+    let name = check_group(name)?;
+    let members = match members {
+        Some(members) => Some(members.iter().map(|member| passwd_entry(&check_name(member)?).map(|entry| entry.name)).collect::<Result<Vec<String>, String>>()?.join(",")),
+        None => None,
+    };
+    let new_name = match new_name {
+        Some(new_name) if check_group(&new_name).is_ok() => return Err(format!("The group {} already exists", new_name)),
+        Some(new_name) => Some(check_name(&new_name)?),
+        None => None,
+    };
+    let old_members = group_members(&name)?;
+    if let Some(members) = members {
+        exec_step(&Step::Run(vec![String::from("gpasswd"), String::from("--members"), members, name.clone()]))?;
+    }
+    if let Some(new_name) = new_name {
+        if let Err(err) = exec_step(&Step::Run(vec![String::from("groupmod"), String::from("--new-name"), new_name, name.clone()])) {
+            let _ = exec_step(&Step::Run(vec![String::from("gpasswd"), String::from("--members"), old_members, name]));
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// `usermod` command giving an account its new details, which are checked first.
+fn usermod_args(edit: &UserEdit) -> Result<Vec<String>, String> {
+    let entry = passwd_entry(&check_name(&edit.username)?)?;
+    let mut cmd = vec![String::from("usermod")];
+    cmd.extend(vec![String::from("--comment"), check_field(&edit.fullname)?]);
+    cmd.extend(vec![String::from("--shell"), check_shell(&edit.shell)?]);
+    cmd.extend(vec![String::from("--gid"), check_group(&edit.group)?]);
+    if edit.uid.to_string() != entry.uid {
+        cmd.extend(vec![String::from("--uid"), edit.uid.to_string()]);
+    }
+    if edit.home != entry.home {
+        if !edit.home.is_absolute() {
+            return Err(format!("Invalid home folder: {}", edit.home.display()));
+        }
+        cmd.extend(vec![String::from("--home"), edit.home.display().to_string(), String::from("--move-home")]);
+    }
+    if edit.new_username != entry.name {
+        cmd.extend(vec![String::from("--login"), check_name(&edit.new_username)?]);
+    }
+    cmd.push(entry.name);
+    Ok(cmd)
+}
+
+/// Set the password of an account with `chpasswd`, which reads it from its standard input.
+fn set_password(entry: &PasswdEntry, pwd: &str) -> Result<(), String> {
+    if pwd.is_empty() || pwd.contains(&['\n', '\r'][..]) {
+        return Err(String::from("Invalid password"));
+    }
+    run_with_input(&["chpasswd"], &format!("{}:{}\n", entry.name, pwd))
+}
+
+/// Check the current password of a user with the checker of `pam_unix`, which takes it NUL terminated.
+fn verify_password(username: &str, pwd: &str) -> Result<(), String> {
+    run_with_input(&["unix_chkpwd", username, "nullok"], &format!("{}\0", pwd)).map_err(|_| String::from("The current password is incorrect"))
+}
+
+/// Changes that apply the login options of a user: automatic login and guest session through the display manager,
+/// account expiry with `usermod`, login hours with `pam_time` and restricted applications with ACLs. The options are
+/// checked first, so that nothing is changed when one isn't allowed.
+fn login_options_steps(options: &LoginOptions, policy: &mut LoginPolicy) -> Result<Vec<Step>, String> {
+    let username = check_name(&options.username)?;
+    passwd_entry(&username)?;
+    let new = &options.policy;
+    if let Some(hours) = new.login_hours.filter(|hours| !hours.is_valid()) {
+        return Err(format!("Invalid login hours: {}-{}", hours.start, hours.end));
+    }
+    let apps: Vec<PathBuf> = restrictable_apps().into_iter().map(|app| app.exec).collect();
+    if let Some(exec) = new.restricted_apps.iter().find(|exec| !apps.contains(exec)) {
+        return Err(format!("{} is not an application that can be restricted", exec.display()));
+    }
+
+    let mut steps = Vec::new();
+    let prev = policy.user(&username);
+    if let Some(dm) = DisplayManager::detect() {
+        let (path, section, key) = dm.autologin_conf();
+        let content = std::fs::read_to_string(path).unwrap_or_default();
+        let curr_autologin = dm.autologin_user();
+        let mut new_content = content.clone();
+        if options.auto_login {
+            new_content = set_ini_value(&new_content, section, key, Some(&username));
+            match dm {
+                DisplayManager::Gdm => new_content = set_ini_value(&new_content, section, "AutomaticLoginEnable", Some("true")),
+                DisplayManager::LightDm => new_content = set_ini_value(&new_content, section, "autologin-user-timeout", Some("0")),
+                DisplayManager::Sddm => {
+                    if login_policy::ini_value(&new_content, section, "Session").is_none() {
+                        new_content = set_ini_value(&new_content, section, "Session", default_session().as_deref());
+                    }
+                }
+            }
+        } else if curr_autologin.as_deref() == Some(username.as_str()) {
+            new_content = set_ini_value(&new_content, section, key, None);
+            if dm == DisplayManager::Gdm {
+                new_content = set_ini_value(&new_content, section, "AutomaticLoginEnable", Some("false"));
+            }
+        }
+        if dm.supports_guest() && options.allow_guest != dm.is_guest_allowed() {
+            new_content = set_ini_value(&new_content, "Seat:*", "allow-guest", Some(if options.allow_guest { "true" } else { "false" }));
+        }
+        if new_content != content {
+            steps.push(Step::Write { path: path.to_string(), content: new_content });
+        }
+    } else if options.auto_login {
+        return Err(String::from("No supported display manager found"));
+    }
+
+    let run = |args: &[&str]| Step::Run(args.iter().map(ToString::to_string).collect());
+    if new.is_disabled {
+        if !prev.is_disabled {
+            steps.push(run(&["usermod", "--lock", "--expiredate", "1", &username]));
+        }
+    } else {
+        if prev.is_disabled {
+            steps.push(run(&["usermod", "--unlock", &username]));
+        }
+        if prev.is_disabled || prev.expires != new.expires {
+            let expires = new.expires.map(|date| date.format("%Y-%m-%d").to_string()).unwrap_or_else(|| String::from("-1"));
+            steps.push(run(&["chage", "--expiredate", &expires, &username]));
+        }
+    }
+
+    for exec in prev.restricted_apps.iter().filter(|exec| !new.restricted_apps.contains(exec)) {
+        steps.push(run(&["setfacl", "-x", &format!("u:{}", username), &exec.to_string_lossy()]));
+    }
+    for exec in new.restricted_apps.iter().filter(|exec| !prev.restricted_apps.contains(exec)) {
+        steps.push(run(&["setfacl", "-m", &format!("u:{}:---", username), &exec.to_string_lossy()]));
+    }
+
+    let mut new_policy = policy.clone();
+    new_policy.users.insert(username, new.clone());
+    if time_block(&new_policy) != time_block(policy) {
+        let content = std::fs::read_to_string(TIME_CONF).unwrap_or_default();
+        steps.push(Step::Write {
+            path: TIME_CONF.to_string(),
+            content: replace_block(&content, &time_block(&new_policy)),
+        });
+        if let Some(stack) = PAM_ACCOUNT_STACKS.iter().find(|path| Path::new(path).exists()) {
+            let content = std::fs::read_to_string(stack).unwrap_or_default();
+            if !content.contains("pam_time.so") {
+                steps.push(Step::Write {
+                    path: stack.to_string(),
+                    content: format!("{}\n{}\n", content.trim_end(), PAM_TIME_RULE),
+                });
+            }
+        }
+    }
+    if new_policy != *policy {
+        steps.push(Step::Write {
+            path: POLICY_PATH.to_string(),
+            content: serde_json::to_string_pretty(&new_policy).unwrap_or_default(),
+        });
+    }
+    *policy = new_policy;
+    Ok(steps)
+}
+
+/// Restrict the applications of the login policy again. Upgrading a package replaces its executables, which drops
+/// their ACLs, so the pacman hook of System Settings runs this after every transaction. All the rules are tried, so
+/// that one failing doesn't keep the others from being applied.
+fn restore_acls() -> Result<(), String> {
+    let errors: Vec<String> = restore_acl_steps(&LoginPolicy::load()).iter().filter_map(|step| exec_step(step).err()).collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// ACLs of the restricted applications of every user, leaving out users that were deleted and applications that were
+/// uninstalled.
+fn restore_acl_steps(policy: &LoginPolicy) -> Vec<Step> {
+    let mut users: Vec<(&String, &UserPolicy)> = policy.users.iter().filter(|(username, _)| passwd_entry(username).is_ok()).collect();
+    users.sort_by_key(|(username, _)| *username);
+    users
+        .into_iter()
+        .flat_map(|(username, user)| {
+            user.restricted_apps
+                .iter()
+                .filter(|exec| exec.exists())
+                .map(move |exec| Step::Run(vec![String::from("setfacl"), String::from("-m"), format!("u:{}:---", username), exec.to_string_lossy().to_string()]))
+        })
+        .collect()
+}
+
+/// Set or remove (`None`) a key in a section of an INI file, adding the section when it is missing.
+fn set_ini_value(content: &str, section: &str, key: &str, val: Option<&str>) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut curr_section = String::new();
+    let mut is_done = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            if curr_section == section && !is_done {
+                if let Some(val) = val {
+                    lines.push(format!("{}={}", key, val));
+                }
+                is_done = true;
+            }
+            curr_section = trimmed[1..trimmed.len() - 1].to_string();
+        } else if curr_section == section && trimmed.find('=').map(|idx| trimmed[..idx].trim() == key).unwrap_or(false) {
+            if let (Some(val), false) = (val, is_done) {
+                lines.push(format!("{}={}", key, val));
+            }
+            is_done = true;
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+    if !is_done {
+        if let Some(val) = val {
+            if curr_section != section {
+                lines.push(format!("[{}]", section));
+            }
+            lines.push(format!("{}={}", key, val));
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Rule of `pam_time` for all services and terminals, e.g. `*;*;sokha;MoTuWeThFr0800-1700`.
+fn time_rule(hours: &LoginHours, username: &str) -> String {
+    let days: String = DAYS.iter().zip(hours.days.iter()).filter(|(_, is_allowed)| **is_allowed).map(|(day, _)| *day).collect();
+    // A rule without days never matches, which would allow logging in at any time.
+    if days.is_empty() {
+        return format!("*;*;{};!Al0000-2400", username);
+    }
+    format!("*;*;{};{}{:02}00-{:02}00", username, days, hours.start, hours.end.min(24))
+}
+
+/// The managed block of `time.conf` with the login hours of all users.
+fn time_block(policy: &LoginPolicy) -> String {
+    let mut users: Vec<_> = policy.users.iter().filter_map(|(name, user)| user.login_hours.map(|hours| time_rule(&hours, name))).collect();
+    users.sort();
+    let mut block = vec![String::from(TIME_BLOCK_BEGIN)];
+    block.extend(users);
+    block.push(String::from(TIME_BLOCK_END));
+    block.join("\n")
+}
+
+/// Replace the block managed by System Settings in `time.conf`, or append it.
+fn replace_block(content: &str, block: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_block = false;
+    for line in content.lines() {
+        match line.trim() {
+            TIME_BLOCK_BEGIN => in_block = true,
+            TIME_BLOCK_END => in_block = false,
+            _ if !in_block => lines.push(line),
+            _ => {}
+        }
+    }
+    let mut content = lines.join("\n").trim_end().to_string();
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    content + block + "\n"
+}
+
+/// Session started by automatic login with SDDM, preferring Plasma.
+fn default_session() -> Option<String> {
+    let mut sessions: Vec<String> = ["/usr/share/xsessions", "/usr/share/wayland-sessions"]
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.file_name().to_string_lossy().to_string()).collect::<Vec<_>>())
+        .filter(|name| name.ends_with(".desktop"))
+        .collect();
+    sessions.sort_by_key(|name| !name.starts_with("plasma"));
+    sessions.into_iter().next()
+}
+
+/// The fields of an account in `/etc/passwd` needed to handle its home directory.
+struct PasswdEntry {
+    name: String,
+    uid: String,
+    gid: String,
+    home: PathBuf,
+}
+
+fn passwd_entry(username: &str) -> Result<PasswdEntry, String> {
+    let content = std::fs::read_to_string(PASSWD).map_err(|e| format!("{}: {}", PASSWD, e))?;
+    content
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<&str>>())
+        .find(|fields| fields.len() >= 7 && fields[0] == username)
+        .map(|fields| PasswdEntry {
+            name: fields[0].to_string(),
+            uid: fields[2].to_string(),
+            gid: fields[3].to_string(),
+            home: PathBuf::from(fields[5]),
+        })
+        .ok_or_else(|| format!("Unknown user: {}", username))
+}
+
+/// Compress the home directory of an account into a new file only root can read.
+fn archive_home(username: &str, archive: &Path) -> Result<(), String> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let home = passwd_entry(username)?.home;
+    let (dir, name) = match (home.parent(), home.file_name()) {
+        (Some(dir), Some(name)) if home.is_dir() => (dir, name),
+        _ => return Err(format!("{} has no home folder to archive", username)),
+    };
+    if !archive.is_absolute() {
+        return Err(format!("Invalid archive: {}", archive.display()));
+    }
+    if let Some(archive_dir) = archive.parent() {
+        std::fs::create_dir_all(archive_dir).map_err(|e| format!("{}: {}", archive_dir.display(), e))?;
+    }
+    std::fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(archive).map_err(|e| format!("{}: {}", archive.display(), e))?;
+    let mut cmd: Vec<String> = ["tar", "--create", "--gzip", "--file"].iter().map(ToString::to_string).collect();
+    cmd.extend(vec![archive.display().to_string(), String::from("--directory"), dir.display().to_string(), name.to_string_lossy().to_string()]);
+    exec_step(&Step::Run(cmd)).map_err(|err| {
+        let _ = std::fs::remove_file(archive);
+        err
+    })
+}
+
+/// Fill the home directory of a new account again from a template, encrypting it first when asked. `fscrypt` only
+/// encrypts empty directories, so the content `useradd` copied is moved aside meanwhile and put back on failure.
+fn setup_home(entry: &PasswdEntry, skel: Option<&Path>, pwd: Option<&str>) -> Result<(), String> {
+    let home = &entry.home;
+    let skel = match skel {
+        Some(skel) if is_skel_dir(skel) => Some(skel.canonicalize().map_err(|e| format!("{}: {}", skel.display(), e))?),
+        Some(skel) => return Err(format!("Invalid template folder: {}", skel.display())),
+        None => None,
+    };
+    let staged = home.with_extension("system-settings");
+    std::fs::rename(home, &staged).map_err(|e| format!("{}: {}", home.display(), e))?;
+    match fill_home(entry, skel.as_deref().unwrap_or(&staged), pwd) {
+        Ok(()) => std::fs::remove_dir_all(&staged).map_err(|e| format!("{}: {}", staged.display(), e)),
+        Err(err) => {
+            let _ = std::fs::remove_dir_all(home);
+            let _ = std::fs::rename(&staged, home);
+            Err(err)
+        }
+    }
+}
+
+fn fill_home(entry: &PasswdEntry, src: &Path, pwd: Option<&str>) -> Result<(), String> {
+    let home = entry.home.to_string_lossy().to_string();
+    let owner = format!("{}:{}", entry.uid, entry.gid);
+    std::fs::create_dir(&home).map_err(|e| format!("{}: {}", home, e))?;
+    std::fs::set_permissions(&home, std::fs::Permissions::from_mode(0o700)).map_err(|e| format!("{}: {}", home, e))?;
+    exec_step(&Step::Run(vec![String::from("chown"), owner.clone(), home.clone()]))?;
+    if let Some(pwd) = pwd {
+        // The login passphrase protects the key, so that `pam_fscrypt` unlocks the folder at login.
+        run_with_input(&["fscrypt", "encrypt", &home, &format!("--user={}", entry.name), "--source=pam_passphrase", "--quiet"], &format!("{}\n", pwd))?;
+    }
+    exec_step(&Step::Run(vec![String::from("cp"), String::from("--archive"), format!("{}/.", src.display()), home.clone()]))?;
+    exec_step(&Step::Run(vec![String::from("chown"), String::from("--recursive"), owner, home]))
+}
+
+/// Folder `useradd` creates home folders in, from `useradd -D`.
+fn default_home_base() -> Result<PathBuf, String> {
+    let output = Command::new("useradd").arg("-D").output().map_err(|e| format!("Failed to run useradd: {}", e))?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("HOME="))
+        .map(|home| PathBuf::from(home.trim()))
+        .filter(|home| home.is_absolute())
+        .ok_or_else(|| String::from("Failed to read the home folder of new accounts from useradd"))
+}
+
+fn single_arg<'a>(args: &'a [String], usage: &str) -> Result<&'a str, String> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(format!("Usage: {}", usage)),
+    }
+}
+
+/// A user or group name, which must not be taken for an option.
+fn check_name(name: &str) -> Result<String, String> {
+    if name.is_empty() || name.starts_with('-') {
+        return Err(format!("Invalid name: {}", name));
+    }
+    Ok(name.to_string())
+}
+
+/// The name of an existing group.
+fn check_group(name: &str) -> Result<String, String> {
+    let name = check_name(name)?;
+    let content = std::fs::read_to_string(GROUP).map_err(|e| format!("{}: {}", GROUP, e))?;
+    if !content.lines().any(|line| line.split(':').next() == Some(name.as_str())) {
+        return Err(format!("Unknown group: {}", name));
+    }
+    Ok(name)
+}
+
+/// Members of a group, as listed in `/etc/group`.
+fn group_members(name: &str) -> Result<String, String> {
+    let content = std::fs::read_to_string(GROUP).map_err(|e| format!("{}: {}", GROUP, e))?;
+    content
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<&str>>())
+        .find(|fields| fields.len() >= 4 && fields[0] == name)
+        .map(|fields| fields[3].to_string())
+        .ok_or_else(|| format!("Unknown group: {}", name))
+}
+
+/// A login shell listed in `/etc/shells`.
+fn check_shell(shell: &str) -> Result<String, String> {
+    let shells = std::fs::read_to_string(SHELLS).map_err(|e| format!("{}: {}", SHELLS, e))?;
+    if !shells.lines().any(|line| line.trim() == shell) {
+        return Err(format!("{} is not listed in {}", shell, SHELLS));
+    }
+    Ok(shell.to_string())
+}
+
+/// A value of a field of `/etc/passwd`, which must not hold its separator or break its line.
+fn check_field(val: &str) -> Result<String, String> {
+    if val.contains(|c: char| c == ':' || c.is_control()) {
+        return Err(format!("Invalid value: {}", val));
+    }
+    Ok(val.to_string())
+}
+
+fn locale_keyword(key: &str) -> Result<LC_Keywords, String> {
+    Ok(match key {
+        "LANG" => LC_Keywords::LANG,
+        "LANGUAGE" => LC_Keywords::LANGUAGE,
+        "LC_TIME" => LC_Keywords::LC_TIME,
+        "LC_NUMERIC" => LC_Keywords::LC_NUMERIC,
+        "LC_MONETARY" => LC_Keywords::LC_MONETARY,
+        "LC_MEASUREMENT" => LC_Keywords::LC_MEASUREMENT,
+        _ => return Err(format!("Unknown locale category: {}", key)),
+    })
+}
+
+/// Settings given as JSON on the standard input.
+fn read_json<T: serde::de::DeserializeOwned>() -> Result<T, String> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input).map_err(|e| e.to_string())?;
+    serde_json::from_str(&input).map_err(|e| e.to_string())
+}
+
+/// Run a program, giving it secrets on its standard input rather than on its command line.
+fn run_with_input(cmd: &[&str], input: &str) -> Result<(), String> {
+    let mut child = Command::new(cmd[0]).args(&cmd[1..]).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped()).spawn().map_err(|e| format!("{}: {}", cmd[0], e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).map_err(|e| format!("{}: {}", cmd[0], e))?;
+    }
+    let output = child.wait_with_output().map_err(|e| format!("{}: {}", cmd[0], e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("{}: {}", cmd[0], String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+fn exec_step(step: &Step) -> Result<(), String> {
+    match step {
+        Step::Run(cmd) => {
+            let output = Command::new(&cmd[0]).args(&cmd[1..]).output().map_err(|e| format!("{}: {}", cmd[0], e))?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(format!("{}: {}", cmd[0], String::from_utf8_lossy(&output.stderr).trim()))
+            }
+        }
+        Step::Write { path, content } => write_file(Path::new(path), content).map_err(|e| format!("{}: {}", path, e)),
+    }
+}
+
+/// Log out sessions idle for the minutes through a logind drop-in, or stop doing so. logind re-reads its
+/// configuration on SIGHUP without ending running sessions.
+fn idle_logout_steps(mins: Option<u32>) -> Vec<Step> {
+    let conf = match mins {
+        Some(mins) => Step::Write {
+            path: IDLE_LOGOUT_CONF.to_string(),
+            content: format!("# Generated by System Settings\n[Login]\nStopIdleSessionSec={}\n", mins * 60),
+        },
+        None => Step::Run(vec![String::from("rm"), String::from("--force"), IDLE_LOGOUT_CONF.to_string()]),
+    };
+    vec![conf, Step::Run(["systemctl", "kill", "--signal=HUP", "systemd-logind"].iter().map(ToString::to_string).collect())]
+}
+
+/// Replace a file through a temporary one, so that it is never left half written.
+fn write_file(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("system-settings.tmp");
+    std::fs::write(&tmp, content)?;
+    std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o644))?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use login_policy::{ini_value, UserPolicy};
+
+    #[test]
+    fn test_set_ini_value() {
+        let content = "[General]\nNumlock=on\n\n[Autologin]\nUser=dara\nSession=plasma.desktop\n";
+        assert_eq!(set_ini_value(content, "Autologin", "User", Some("sokha")), "[General]\nNumlock=on\n\n[Autologin]\nUser=sokha\nSession=plasma.desktop\n");
+        assert_eq!(set_ini_value(content, "Autologin", "User", None), "[General]\nNumlock=on\n\n[Autologin]\nSession=plasma.desktop\n");
+        assert_eq!(set_ini_value("[General]\nNumlock=on\n", "Autologin", "User", Some("sokha")), "[General]\nNumlock=on\n[Autologin]\nUser=sokha\n");
+        assert_eq!(set_ini_value("[Seat:*]\n[Other]\n", "Seat:*", "allow-guest", Some("true")), "[Seat:*]\nallow-guest=true\n[Other]\n");
+        assert_eq!(ini_value(content, "Autologin", "Session").as_deref(), Some("plasma.desktop"));
+    }
+
+    #[test]
+    fn test_time_rule() {
+        let hours = LoginHours {
+            days: [true, true, true, true, true, false, false],
+            start: 8,
+            end: 17,
+        };
+        assert_eq!(time_rule(&hours, "sokha"), "*;*;sokha;MoTuWeThFr0800-1700");
+        assert_eq!(time_rule(&LoginHours { days: [false; 7], ..hours }, "sokha"), "*;*;sokha;!Al0000-2400");
+        assert!(!LoginHours { start: 17, end: 8, ..hours }.is_valid());
+    }
+
+    #[test]
+    fn test_replace_block() {
+        let mut policy = LoginPolicy::default();
+        policy.users.insert(
+            String::from("sokha"),
+            UserPolicy {
+                login_hours: Some(LoginHours::default()),
+                ..UserPolicy::default()
+            },
+        );
+        let block = time_block(&policy);
+        let content = replace_block("# comment\n", &block);
+        assert_eq!(content, format!("# comment\n{}\n", block));
+        assert_eq!(replace_block(&content, &time_block(&LoginPolicy::default())), format!("# comment\n{}\n{}\n", TIME_BLOCK_BEGIN, TIME_BLOCK_END));
+    }
+
+    #[test]
+    fn test_login_options_checks() {
+        let options = |username: &str| LoginOptions {
+            username: username.to_string(),
+            ..LoginOptions::default()
+        };
+        assert!(login_options_steps(&options("-oProxyCommand"), &mut LoginPolicy::default()).is_err());
+        assert!(login_options_steps(&options("no-such-user-here"), &mut LoginPolicy::default()).is_err());
+        let mut restricted = options("root");
+        restricted.policy.restricted_apps = vec![PathBuf::from("/etc/shadow")];
+        assert!(login_options_steps(&restricted, &mut LoginPolicy::default()).is_err());
+    }
+
+    #[test]
+    fn test_restore_acl_steps() {
+        let exec = std::env::current_exe().unwrap();
+        let mut policy = LoginPolicy::default();
+        for username in ["root", "no-such-user-here"].iter() {
+            policy.users.insert(
+                username.to_string(),
+                UserPolicy {
+                    restricted_apps: vec![exec.clone(), PathBuf::from("/no/such/app")],
+                    ..UserPolicy::default()
+                },
+            );
+        }
+        assert_eq!(
+            restore_acl_steps(&policy),
+            vec![Step::Run(vec![String::from("setfacl"), String::from("-m"), String::from("u:root:---"), exec.to_string_lossy().to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_deletable_user() {
+        assert!(deletable_user("root").is_err());
+        assert!(deletable_user("--remove").is_err());
+        assert!(deletable_user("no-such-user-here").is_err());
+    }
+
+    #[test]
+    fn test_usermod_args() {
+        let edit = UserEdit {
+            username: String::from("root"),
+            new_username: String::from("root"),
+            fullname: String::from("Super User"),
+            uid: 0,
+            group: String::from("root"),
+            shell: String::from("/bin/sh"),
+            home: PathBuf::from("/root"),
+        };
+        let args = |list: &[&str]| list.iter().map(ToString::to_string).collect::<Vec<String>>();
+        assert_eq!(usermod_args(&edit), Ok(args(&["usermod", "--comment", "Super User", "--shell", "/bin/sh", "--gid", "root", "root"])));
+        assert!(usermod_args(&UserEdit {
+            fullname: String::from("root:x:0:0"),
+            ..edit.clone()
+        })
+        .is_err());
+        assert!(usermod_args(&UserEdit {
+            shell: String::from("/tmp/shell"),
+            ..edit.clone()
+        })
+        .is_err());
+        assert!(usermod_args(&UserEdit {
+            group: String::from("no-such-group-here"),
+            ..edit.clone()
+        })
+        .is_err());
+        assert!(usermod_args(&UserEdit {
+            home: PathBuf::from("home/root"),
+            ..edit.clone()
+        })
+        .is_err());
+        assert!(usermod_args(&UserEdit { new_username: String::from("-ohome"), ..edit }).is_err());
+    }
+
+    #[test]
+    fn test_idle_logout_steps() {
+        let steps = idle_logout_steps(Some(30));
+        assert_eq!(
+            steps[0],
+            Step::Write {
+                path: IDLE_LOGOUT_CONF.to_string(),
+                content: String::from("# Generated by System Settings\n[Login]\nStopIdleSessionSec=1800\n"),
+            }
+        );
+        assert_eq!(idle_logout_steps(None)[0], Step::Run(vec![String::from("rm"), String::from("--force"), IDLE_LOGOUT_CONF.to_string()]));
+    }
+}
@@ -0,0 +1,88 @@
+//! Session service of System Settings. It is started at login from `/etc/xdg/autostart`, applies the saved settings
+//! and keeps track of what the settings need while the System Settings window is closed.
+#[path = "../helpers/device_access.rs"]
+#[allow(dead_code)]
+mod device_access;
+#[path = "../helpers/event_sounds.rs"]
+mod event_sounds;
+#[path = "../helpers/kbd_backlight.rs"]
+#[allow(dead_code)]
+mod kbd_backlight;
+
+use device_access::{AccessLog, Device};
+use kbd_backlight::{BacklightSettings, IdleTimer, KbdBacklight};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const TICK: Duration = Duration::from_secs(1);
+/// Ticks between two looks at the processes using a camera. The sound server tells when the microphone is used.
+const CAMERA_SAMPLE_TICKS: u32 = 2;
+/// Wait before connecting again to a sound server that went away, e.g. when it restarts.
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+fn main() {
+    std::thread::spawn(|| loop {
+        if let Err(err) = event_sounds::watch_event_streams() {
+            eprintln!("{}", err);
+        }
+        std::thread::sleep(RECONNECT_DELAY);
+    });
+    let access_log = Arc::new(Mutex::new(AccessLog::load()));
+    let microphone_log = Arc::clone(&access_log);
+    std::thread::spawn(move || loop {
+        if let Err(err) = device_access::watch_source_outputs(|| update_access_log(&microphone_log, Device::Microphone, device_access::microphone_users())) {
+            eprintln!("{}", err);
+        }
+        std::thread::sleep(RECONNECT_DELAY);
+    });
+    let kbd_backlight = KbdBacklight::find();
+    if let Some(kbd_backlight) = &kbd_backlight {
+        let settings = BacklightSettings::load();
+        if let Err(err) = kbd_backlight.set_brightness(kbd_backlight.from_percent(settings.brightness)) {
+            eprintln!("{}", err);
+        }
+    }
+    let mut idle_timer = IdleTimer::default();
+    let mut watches_idle = kbd_backlight.is_some();
+    let mut tick = 0u32;
+    loop {
+        if let (Some(kbd_backlight), true) = (&kbd_backlight, watches_idle) {
+            if let Err(err) = check_idle(kbd_backlight, &mut idle_timer) {
+                // Without an idle time, e.g. outside of X11, the backlight is left as it is for the session.
+                eprintln!("{}", err);
+                watches_idle = false;
+            }
+        }
+        if tick % CAMERA_SAMPLE_TICKS == 0 {
+            let users = if device_access::has_cameras() { device_access::camera_users() } else { Vec::new() };
+            update_access_log(&access_log, Device::Camera, users);
+        }
+        tick = tick.wrapping_add(1);
+        std::thread::sleep(TICK);
+    }
+}
+
+/// Log the applications now using a device.
+fn update_access_log(access_log: &Mutex<AccessLog>, device: Device, users: Vec<String>) {
+    let res = match access_log.lock() {
+        Ok(mut access_log) => access_log.update(device, users),
+        Err(_) => Err(String::from("The access log is unavailable")),
+    };
+    if let Err(err) = res {
+        eprintln!("{}", err);
+    }
+}
+
+/// Turn the backlight off or back on for how long the session has been idle, following the saved settings.
+fn check_idle(kbd_backlight: &KbdBacklight, idle_timer: &mut IdleTimer) -> Result<(), String> {
+    let settings = BacklightSettings::load();
+    let val = if settings.turn_off {
+        idle_timer.tick(kbd_backlight::idle_time()?, Duration::from_secs(settings.turn_off_after), kbd_backlight.brightness())
+    } else {
+        idle_timer.restore()
+    };
+    match val {
+        Some(val) => kbd_backlight.set_brightness(val),
+        None => Ok(()),
+    }
+}
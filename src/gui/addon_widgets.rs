@@ -1,4 +1,5 @@
 use crate::gui::styles::{CustomButton, CustomContainer};
+use crate::helpers::authority;
 use iced::{button, Align, Button, Container, Length, Row, Text};
 use iced_custom_widget::{Icon, Icons};
 pub fn icon_btn<'a, M: 'a + Clone>(state: &'a mut button::State, icon: Icons, text: &str, size: Option<u16>) -> Button<'a, M> {
@@ -23,3 +24,9 @@ pub fn tabbar<'a, M: 'a + Clone, F: Fn(usize) -> M>(tabs: &'a mut Vec<(&'static
    let tabbar_con = Container::new(tabbar).padding(2).center_x().style(CustomContainer::Segment);
    Container::new(tabbar_con).padding(7).width(Length::Fill).center_x()
 }
+
+/// Button asking to unlock the settings which need an administrator, or locking them again.
+pub fn lock_btn<'a, M: 'a + Clone>(state: &'a mut button::State, on_press: M) -> Button<'a, M> {
+   let (icon, text) = if authority::is_unlocked() { (Icons::Unlock, "Lock") } else { (Icons::Key, "Unlock...") };
+   icon_btn(state, icon, text, None).on_press(on_press).style(CustomButton::Default)
+}
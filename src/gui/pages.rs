@@ -25,7 +25,7 @@ use date_time::{DateTimeMessage, DateTimePage};
 use desktop_page::{DesktopMessage, DesktopPage};
 use display_page::{DisplayMessage, DisplayPage};
 use general::{General, GeneralMessage};
-use iced::{Container, Element, Length, Space, Subscription};
+use iced::{Command, Container, Element, Length, Space, Subscription};
 use keyboard::{KeyboardMessage, KeyboardPage};
 use lang_region::{LangRegionMessage, LangRegionPage};
 use mouse_page::{MouseMessage, MousePage};
@@ -125,8 +125,8 @@ impl Pages {
         self.current = idx;
     }
 
-    pub fn update(&mut self, msg: PagesMessage) {
-        self.pages[self.current].update(msg);
+    pub fn update(&mut self, msg: PagesMessage) -> Command<PagesMessage> {
+        self.pages[self.current].update(msg)
     }
 
     pub fn subscription(&self) -> Subscription<PagesMessage> {
@@ -143,7 +143,7 @@ impl Pages {
 }
 
 impl PageModel {
-    fn update(&mut self, msg: PagesMessage) {
+    fn update(&mut self, msg: PagesMessage) -> Command<PagesMessage> {
         use PageModel::*;
         use PagesMessage::*;
         match msg {
@@ -154,7 +154,7 @@ impl PageModel {
             }
             SoundMessage(msg) => {
                 if let SoundPageModel { sound_page } = self {
-                    sound_page.update(msg);
+                    return sound_page.update(msg).map(SoundMessage);
                 }
             }
             GeneralMessage(msg) => {
@@ -204,17 +204,17 @@ impl PageModel {
             }
             UserGroupMsg(msg) => {
                 if let UserGroupPageModel { user_group_page } = self {
-                    user_group_page.update(msg);
+                    return user_group_page.update(msg).map(UserGroupMsg);
                 }
             }
             DateTimeMessage(msg) => {
                 if let DateTimePageModel { datetime_page } = self {
-                    datetime_page.update(msg);
+                    return datetime_page.update(msg).map(DateTimeMessage);
                 }
             }
             LangRegionMessage(msg) => {
                 if let LanguagePageModel { lang_region_page } = self {
-                    lang_region_page.update(msg);
+                    return lang_region_page.update(msg).map(LangRegionMessage);
                 }
             }
             NotifyMsg(msg) => {
@@ -229,12 +229,12 @@ impl PageModel {
             }
             SoftUpdateMsg(msg) => {
                 if let UpdatePageModel { update_page } = self {
-                    update_page.update(msg);
+                    return update_page.update(msg).map(SoftUpdateMsg);
                 }
             }
             PrivacyMessage(msg) => {
                 if let PrivacyPageModel { privacy_page } = self {
-                    privacy_page.update(msg);
+                    return privacy_page.update(msg).map(PrivacyMessage);
                 }
             }
             DesktopMessage(msg) => {
@@ -243,6 +243,7 @@ impl PageModel {
                 }
             }
         }
+        Command::none()
     }
 
     fn subscription(&self) -> Subscription<PagesMessage> {
@@ -250,6 +251,10 @@ impl PageModel {
         match self {
             DateTimePageModel { datetime_page } => datetime_page.subscription().map(PagesMessage::DateTimeMessage),
             UpdatePageModel { update_page } => update_page.subscription().map(PagesMessage::SoftUpdateMsg),
+            NetworkPageModel { network_page } => network_page.subscription().map(PagesMessage::NetMessage),
+            PrivacyPageModel { privacy_page } => privacy_page.subscription().map(PagesMessage::PrivacyMessage),
+            SoundPageModel { sound_page } => sound_page.subscription().map(PagesMessage::SoundMessage),
+            KeyboardPageModel { keyboard_page } => keyboard_page.subscription().map(PagesMessage::KeyboardMessage),
             _ => Subscription::none(),
         }
     }
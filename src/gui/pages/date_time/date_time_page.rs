@@ -1,8 +1,9 @@
 use super::date_time_utils::*;
-use crate::gui::addon_widgets::{icon_btn, tabbar};
+use crate::gui::addon_widgets::{icon_btn, lock_btn, tabbar};
 use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomTextInput};
+use crate::helpers::{authority, Action};
 use chrono::{DateTime, Local};
-use iced::{button, canvas::Canvas, time, Align, Button, Checkbox, Column, Container, Element, Length, Row, Scrollable, Space, Subscription, Text, TextInput};
+use iced::{button, canvas::Canvas, time, Align, Button, Checkbox, Column, Command, Container, Element, Length, Row, Scrollable, Space, Subscription, Text, TextInput};
 use iced_custom_widget::{Icon, Icons};
 use libkoompi::system_settings::{datetime::DateTimeManager, locale::LocaleManager};
 
@@ -18,6 +19,10 @@ pub enum DateTimeMessage {
     DefaultsClicked,
     ResetClicked,
     ApplyClicked,
+    DateTimeSet(Result<(), String>),
+    TimeZoneSet(Result<(), String>),
+    LockClicked,
+    LockToggled(Result<(), String>),
     AutoTZToggled(bool),
     SearchTZChanged(String),
     TZSelected(String),
@@ -36,6 +41,7 @@ pub struct DateTimePage {
     btn_defaults_state: button::State,
     btn_reset_state: button::State,
     btn_ok_state: button::State,
+    btn_lock_state: button::State,
     is_changed: bool,
 }
 
@@ -92,7 +98,7 @@ impl DateTimePage {
         }
     }
 
-    pub fn update(&mut self, msg: DateTimeMessage) {
+    pub fn update(&mut self, msg: DateTimeMessage) -> Command<DateTimeMessage> {
         use DateTimeMessage::*;
         match msg {
             TabChanged(idx) => self.current_tab_idx = idx,
@@ -125,6 +131,7 @@ impl DateTimePage {
                 self.current_tab_idx = current_tab;
             }
             ApplyClicked => {
+                let mut cmd = Command::none();
                 match self.current_tab_idx {
                     0 => {
                         let timezone = self.datetime_tab.clock.now.format(TZ_FMT).to_string();
@@ -135,18 +142,8 @@ impl DateTimePage {
                         ) {
                             Ok(now) => {
                                 self.datetime_tab.clock.now = now.into();
-                                match self.datetime_manager.set_datetime(&self.datetime_tab.clock.now.format(self.locale_manager.time_details().d_t_fmt.as_str()).to_string()) {
-                                    Ok(res) => {
-                                        if res {
-                                            self.datetime_tab.is_date_change = false;
-                                            self.datetime_tab.is_time_change = false;
-                                            println!("Set Datetime success");
-                                        } else {
-                                            eprintln!("Fail to set Datetime");
-                                        }
-                                    }
-                                    Err(err) => eprintln!("{}", err),
-                                }
+                                let datetime = self.datetime_tab.clock.now.format(self.locale_manager.time_details().d_t_fmt.as_str()).to_string();
+                                cmd = Command::perform(authority::exec(Action::SetDateTime, vec![datetime]), DateTimeSet);
                             }
                             Err(err) => eprintln!("{}", err),
                         }
@@ -154,16 +151,7 @@ impl DateTimePage {
                     1 => {
                         if let Some(selected_con) = &self.timezone_tab.selected_continent {
                             if let Some(selected_tz) = &self.selected_tz {
-                                match self.datetime_manager.set_timezone(format!("{}/{}", selected_con, selected_tz).as_str()) {
-                                    Ok(res) => {
-                                        if res {
-                                            println!("Set timezone success");
-                                        } else {
-                                            eprintln!("Fail to set timezone");
-                                        }
-                                    }
-                                    Err(err) => eprintln!("{}", err),
-                                }
+                                cmd = Command::perform(authority::exec(Action::SetTimeZone, vec![format!("{}/{}", selected_con, selected_tz)]), TimeZoneSet);
                             }
                         }
                     }
@@ -171,6 +159,32 @@ impl DateTimePage {
                 }
 
                 self.is_changed = false;
+                return cmd;
+            }
+            DateTimeSet(res) => match res {
+                Ok(()) => {
+                    self.datetime_tab.is_date_change = false;
+                    self.datetime_tab.is_time_change = false;
+                    println!("Set Datetime success");
+                }
+                Err(err) => eprintln!("{}", err),
+            },
+            TimeZoneSet(res) => match res {
+                Ok(()) => {
+                    println!("Set timezone success");
+                    // Read the new time zone back.
+                    match DateTimeManager::new() {
+                        Ok(dt_mn) => self.datetime_manager = dt_mn,
+                        Err(err) => eprintln!("{}", err),
+                    }
+                }
+                Err(err) => eprintln!("{}", err),
+            },
+            LockClicked => return Command::perform(authority::toggle(), LockToggled),
+            LockToggled(res) => {
+                if let Err(err) = res {
+                    eprintln!("{}", err);
+                }
             }
             AutoTZToggled(is_checked) => self.set_ntp(is_checked),
             SearchTZChanged(text) => {
@@ -187,6 +201,7 @@ impl DateTimePage {
                 self.filter_tz();
             }
         }
+        Command::none()
     }
 
     fn filter_tz(&mut self) {
@@ -213,6 +228,7 @@ impl DateTimePage {
             btn_defaults_state,
             btn_reset_state,
             btn_ok_state,
+            btn_lock_state,
             is_changed,
         } = self;
 
@@ -355,10 +371,21 @@ impl DateTimePage {
         let mut btn_reset = icon_btn(btn_reset_state, Icons::Atom, "Reset", None).style(CustomButton::Hovered);
         let mut btn_ok = icon_btn(btn_ok_state, Icons::CheckCircle, "OK", None).style(CustomButton::Primary);
         if *is_changed {
-            btn_ok = btn_ok.on_press(DateTimeMessage::ApplyClicked);
             btn_reset = btn_reset.on_press(DateTimeMessage::ResetClicked);
+            if authority::is_unlocked() {
+                btn_ok = btn_ok.on_press(DateTimeMessage::ApplyClicked);
+            }
         }
-        let bottom_row = Row::new().padding(15).spacing(20).align_items(Align::Center).push(btn_defaults).push(btn_reset).push(Space::with_width(Length::Fill)).push(btn_ok);
+        let btn_lock = lock_btn(btn_lock_state, DateTimeMessage::LockClicked);
+        let bottom_row = Row::new()
+            .padding(15)
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(btn_lock)
+            .push(btn_defaults)
+            .push(btn_reset)
+            .push(Space::with_width(Length::Fill))
+            .push(btn_ok);
         let bottom_section = Container::new(bottom_row).width(Length::Fill).align_x(Align::End);
 
         // មាតិកា
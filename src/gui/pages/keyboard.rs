@@ -1,6 +1,12 @@
 mod add_input_source_sec;
 mod conf_input_source_sec;
+mod global_shortcuts;
+mod input_method;
+mod key_repeat;
 mod keyboard_page;
 mod keyboard_utils;
+mod layout_preview;
+mod modifier_keys;
+mod xkb_registry;
 
 pub use keyboard_page::{KeyboardMessage, KeyboardPage};
@@ -1,3 +1,4 @@
+use super::input_method::{self, Ini};
 use crate::gui::addon_widgets::icon_btn;
 use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomSelect};
 use iced::{button, pick_list, Align, Checkbox, Column, Container, Element, Length, PickList, Row, Space, Text};
@@ -32,13 +33,45 @@ impl ConfigInputSrcSec {
     pub const ls_key_mods: [&'static str; 4] = ["None", "Alt", "Control", "Super"];
 
     pub fn new() -> Self {
-        Self {
+        let mut conf_sec = Self {
             prev_candidate_val: Some(Self::ls_com_keys[0].to_owned()),
             next_candidate_val: Some(Self::ls_com_keys[1].to_owned()),
             enable_emoji: true,
             key_modifier_val: Some(Self::ls_key_mods[1].to_owned()),
             ..Self::default()
+        };
+        conf_sec.load(&input_method::read_ini(input_method::KEYBOARD_CONF));
+        conf_sec
+    }
+
+    /// Take the options of the keyboard input methods of fcitx5, keeping the defaults for the others.
+    fn load(&mut self, conf: &Ini) {
+        if let Some(key) = conf.get("PrevCandidate", "0") {
+            self.prev_candidate_val = Some(input_method::key_label(key));
+        }
+        if let Some(key) = conf.get("NextCandidate", "0") {
+            self.next_candidate_val = Some(input_method::key_label(key));
+        }
+        if let Some(is_enabled) = conf.get_flag("", "EnableEmoji") {
+            self.enable_emoji = is_enabled;
+        }
+        if let Some(modifier) = conf.get("", "ChooseModifier") {
+            self.key_modifier_val = Some(modifier.to_string());
+        }
+    }
+
+    /// Write the options to the config of the keyboard input methods and make fcitx5 use them.
+    pub fn save(&self) -> Result<(), String> {
+        let mut conf = input_method::read_ini(input_method::KEYBOARD_CONF);
+        let keys = |val: &Option<String>| val.iter().map(|label| input_method::fcitx_key(label)).collect::<Vec<String>>();
+        conf.set_list("PrevCandidate", &keys(&self.prev_candidate_val));
+        conf.set_list("NextCandidate", &keys(&self.next_candidate_val));
+        conf.set_flag("", "EnableEmoji", self.enable_emoji);
+        if let Some(modifier) = &self.key_modifier_val {
+            conf.set("", "ChooseModifier", modifier);
         }
+        input_method::write_ini(input_method::KEYBOARD_CONF, &conf)?;
+        input_method::reload()
     }
 
     pub fn update(&mut self, msg: ConfigInputSrcMessage) {
@@ -48,7 +81,7 @@ impl ConfigInputSrcSec {
             NextCandidateChanged(val) => self.next_candidate_val = Some(val),
             EnableEmoji(is_checked) => self.enable_emoji = is_checked,
             KeyModChanged(val) => self.key_modifier_val = Some(val),
-            AddClicked | CancelClicked => println!("Exit Config"),
+            AddClicked | CancelClicked => {}
         }
         self.is_changed = true;
     }
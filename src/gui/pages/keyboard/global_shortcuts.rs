@@ -0,0 +1,556 @@
+use super::input_method::Ini;
+use iced_native::keyboard::{KeyCode, ModifiersState};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Global shortcuts of KDE, saved by kglobalaccel.
+const KGLOBALSHORTCUTSRC: &str = "kglobalshortcutsrc";
+/// Prefix of the desktop files of custom commands, which kglobalaccel launches like applications.
+const CUSTOM_PREFIX: &str = "net.local.";
+/// Action of the components of applications, which launches them.
+const LAUNCH: &str = "_launch";
+const FRIENDLY_NAME: &str = "_k_friendly_name";
+/// Keys of the actions turned off.
+const NONE: &str = "none";
+/// Qt key code of each modifier, added to the code of the key.
+const QT_MODIFIERS: [(&str, i32); 4] = [("Meta", 0x1000_0000), ("Ctrl", 0x0400_0000), ("Alt", 0x0800_0000), ("Shift", 0x0200_0000)];
+/// Qt key code of the keys named by more than one character, besides the function keys.
+const QT_KEYS: [(&str, i32); 19] = [
+    ("Esc", 0x0100_0000),
+    ("Tab", 0x0100_0001),
+    ("Backtab", 0x0100_0002),
+    ("Backspace", 0x0100_0003),
+    ("Return", 0x0100_0004),
+    ("Enter", 0x0100_0005),
+    ("Ins", 0x0100_0006),
+    ("Del", 0x0100_0007),
+    ("Pause", 0x0100_0008),
+    ("Print", 0x0100_0009),
+    ("Home", 0x0100_0010),
+    ("End", 0x0100_0011),
+    ("Left", 0x0100_0012),
+    ("Up", 0x0100_0013),
+    ("Right", 0x0100_0014),
+    ("Down", 0x0100_0015),
+    ("PgUp", 0x0100_0016),
+    ("PgDown", 0x0100_0017),
+    ("Space", 0x20),
+];
+const QT_KEY_F1: i32 = 0x0100_0030;
+pub const CUSTOM_CATEGORY: &str = "Custom";
+
+/// Shortcuts of each category: component, action, title and default keys.
+const CATEGORIES: [(&str, &[(&str, &str, &str, &str)]); 7] = [
+    (
+        "Menu & Dock",
+        &[
+            ("plasmashell", "activate application launcher widget", "Show Application Menu", "Alt+F1"),
+            ("plasmashell", "show dashboard", "Show Desktop Widgets", "Ctrl+F12"),
+            ("plasmashell", "manage activities", "Show Activity Switcher", "Meta+Q"),
+        ],
+    ),
+    (
+        "Workspaces",
+        &[
+            ("kwin", "Switch One Desktop to the Left", "Switch to Workspace on the Left", "Meta+Ctrl+Left"),
+            ("kwin", "Switch One Desktop to the Right", "Switch to Workspace on the Right", "Meta+Ctrl+Right"),
+            ("kwin", "Window One Desktop to the Left", "Move Window to Workspace on the Left", "Meta+Ctrl+Shift+Left"),
+            ("kwin", "Window One Desktop to the Right", "Move Window to Workspace on the Right", "Meta+Ctrl+Shift+Right"),
+            ("kwin", "Expose", "Application Windows", "Ctrl+F9"),
+            ("kwin", "ExposeAll", "All Windows", "Ctrl+F10"),
+            ("kwin", "Show Desktop", "Show Desktop", "Meta+D"),
+        ],
+    ),
+    (
+        "Keyboard",
+        &[
+            ("kwin", "Walk Through Windows", "Move focus to next window", "Alt+Tab"),
+            ("kwin", "Walk Through Windows (Reverse)", "Move focus to previous window", "Alt+Shift+Backtab"),
+            ("kwin", "Window Operations Menu", "Show window menu", "Alt+F3"),
+            ("kwin", "Window Close", "Close window", "Alt+F4"),
+            ("kwin", "Window Maximize", "Maximize window", "Meta+PgUp"),
+            ("kwin", "Window Minimize", "Minimize window", "Meta+PgDown"),
+        ],
+    ),
+    (
+        "Input Sources",
+        &[
+            ("KDE Keyboard Layout Switcher", "Switch to Next Keyboard Layout", "Select next source in Input menu", "Meta+Alt+K"),
+            ("KDE Keyboard Layout Switcher", "Switch to Last-Used Keyboard Layout", "Select the previous source", "Meta+Alt+L"),
+        ],
+    ),
+    (
+        "Screenshots",
+        &[
+            ("org.kde.spectacle.desktop", LAUNCH, "Open screenshot tool", "Print"),
+            ("org.kde.spectacle.desktop", "FullScreenScreenShot", "Capture entire desktop", "Shift+Print"),
+            ("org.kde.spectacle.desktop", "ActiveWindowScreenShot", "Capture active window", "Meta+Print"),
+            ("org.kde.spectacle.desktop", "RectangularRegionScreenShot", "Capture rectangular region", "Meta+Shift+Print"),
+        ],
+    ),
+    (
+        "Services",
+        &[
+            ("ksmserver", "Lock Session", "Lock screen", "Meta+L"),
+            ("ksmserver", "Log Out", "Log out", "Ctrl+Alt+Del"),
+            ("org.kde.dolphin.desktop", LAUNCH, "Open file manager", "Meta+E"),
+            ("org.kde.konsole.desktop", LAUNCH, "Open terminal", "Ctrl+Alt+T"),
+        ],
+    ),
+    ("Spotlight", &[("org.kde.krunner.desktop", LAUNCH, "Show search", "Alt+Space")]),
+];
+
+/// A global shortcut: an action of a component of KDE and its keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Binding {
+    /// Group of the action in kglobalshortcutsrc, like `kwin`.
+    pub component: String,
+    pub action: String,
+    pub title: String,
+    /// Keys in the format of Qt, like `Meta+L`, separated by tabs when there are several.
+    pub keys: String,
+    pub default_keys: String,
+    pub is_enabled: bool,
+    /// Command run by the shortcut, for custom shortcuts.
+    pub command: Option<String>,
+}
+
+impl Binding {
+    fn with_default(component: &str, action: &str, title: &str, default_keys: &str) -> Self {
+        Self {
+            component: component.to_string(),
+            action: action.to_string(),
+            title: title.to_string(),
+            keys: default_keys.to_string(),
+            default_keys: default_keys.to_string(),
+            is_enabled: true,
+            command: None,
+        }
+    }
+
+    /// Shortcut running `command`, in a component not used by the `existing` ones.
+    pub fn custom(title: &str, command: &str, existing: &[&Binding]) -> Self {
+        let slug: String = title.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' }).collect();
+        let slug = slug.trim_matches('-');
+        let slug = if slug.is_empty() { "command" } else { slug };
+        let component = (1..)
+            .map(|nth| if nth == 1 { format!("{}{}.desktop", CUSTOM_PREFIX, slug) } else { format!("{}{}-{}.desktop", CUSTOM_PREFIX, slug, nth) })
+            .find(|component| existing.iter().all(|binding| binding.component != *component))
+            .unwrap_or_default();
+        Self {
+            component,
+            action: String::from(LAUNCH),
+            title: title.to_string(),
+            command: Some(command.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Keys as shown in the list, like `Alt+Space, Alt+F2`.
+    pub fn label(&self) -> String {
+        if self.is_enabled && !self.keys.is_empty() {
+            self.keys.split('\t').collect::<Vec<&str>>().join(", ")
+        } else {
+            String::from("None")
+        }
+    }
+
+    pub fn has_keys(&self, keys: &str) -> bool {
+        self.is_enabled && self.keys.split('\t').any(|key| key.eq_ignore_ascii_case(keys))
+    }
+
+    /// Stop using the keys, turning the shortcut off when it has no keys left.
+    pub fn remove_keys(&mut self, keys: &str) {
+        self.keys = self.keys.split('\t').filter(|key| !key.eq_ignore_ascii_case(keys)).collect::<Vec<&str>>().join("\t");
+        self.is_enabled = !self.keys.is_empty();
+    }
+
+    pub fn restore_default(&mut self) {
+        self.keys = self.default_keys.clone();
+        self.is_enabled = !self.default_keys.is_empty();
+    }
+
+    /// Qt key codes of the keys, as kglobalaccel takes them, empty when the shortcut is turned off.
+    pub fn qt_keys(&self) -> Result<Vec<i32>, String> {
+        if !self.is_enabled {
+            return Ok(Vec::new());
+        }
+        self.keys.split('\t').filter(|keys| !keys.is_empty()).map(|keys| qt_key(keys).ok_or_else(|| format!("Unknown keys: {}", keys))).collect()
+    }
+}
+
+/// Qt key code of keys like `Meta+Ctrl+Left`.
+pub fn qt_key(keys: &str) -> Option<i32> {
+    // The key itself may be `+`, as in `Ctrl++`.
+    let (mods, key) = match keys.strip_suffix("++") {
+        Some(mods) => (mods, "+"),
+        None => match keys.rfind('+') {
+            Some(idx) => (&keys[..idx], &keys[idx + 1..]),
+            None => ("", keys),
+        },
+    };
+    let key_code = if let Some((_, code)) = QT_KEYS.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)) {
+        *code
+    } else if let Some(nth) = key.strip_prefix('F').and_then(|nth| nth.parse::<i32>().ok()).filter(|nth| (1..=35).contains(nth)) {
+        QT_KEY_F1 + nth - 1
+    } else {
+        let mut chars = key.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_graphic() => c.to_ascii_uppercase() as i32,
+            _ => return None,
+        }
+    };
+    mods.split('+')
+        .filter(|name| !name.is_empty())
+        .try_fold(key_code, |code, name| QT_MODIFIERS.iter().find(|(modifier, _)| modifier.eq_ignore_ascii_case(name)).map(|(_, modifier)| code | modifier))
+}
+
+/// Fields of a value of kglobalshortcutsrc, split at the commas and without the escapes of KConfig, like `\t` between
+/// the keys of an action and `\,` for a comma of a field.
+pub fn split_entry(val: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                field.push(c);
+                field.extend(chars.next());
+            }
+            ',' => fields.push(unescape_value(&std::mem::take(&mut field))),
+            _ => field.push(c),
+        }
+    }
+    fields.push(unescape_value(&field));
+    fields
+}
+
+/// Keys of an entry of kglobalshortcutsrc separated by tabs, empty when the action is turned off.
+pub fn parse_entry(val: &str) -> String {
+    split_entry(val).into_iter().next().filter(|keys| keys != NONE).unwrap_or_default()
+}
+
+/// Shortcuts of each category with the keys of the config, followed by the custom shortcuts.
+pub fn parse_categories(config: &Ini) -> Vec<(&'static str, Vec<Binding>)> {
+    let mut categories: Vec<(&'static str, Vec<Binding>)> = CATEGORIES
+        .iter()
+        .map(|(title, actions)| {
+            let bindings = actions
+                .iter()
+                .map(|(component, action, title, default_keys)| {
+                    let mut binding = Binding::with_default(component, action, title, default_keys);
+                    if let Some(val) = config.get(component, action) {
+                        binding.keys = parse_entry(val);
+                        binding.is_enabled = !binding.keys.is_empty();
+                    }
+                    binding
+                })
+                .collect();
+            (*title, bindings)
+        })
+        .collect();
+    let custom = config
+        .section_names()
+        .filter(|component| component.starts_with(CUSTOM_PREFIX))
+        .filter_map(|component| {
+            let keys = parse_entry(config.get(component, LAUNCH)?);
+            Some(Binding {
+                component: component.to_string(),
+                action: String::from(LAUNCH),
+                title: config.get(component, FRIENDLY_NAME).map(|name| split_entry(name).join(",")).unwrap_or_else(|| component.to_string()),
+                is_enabled: !keys.is_empty(),
+                keys,
+                ..Binding::default()
+            })
+        })
+        .collect();
+    categories.push((CUSTOM_CATEGORY, custom));
+    categories
+}
+
+fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(KGLOBALSHORTCUTSRC))
+}
+
+fn desktop_path(component: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("applications").join(component))
+}
+
+pub fn load() -> Vec<(&'static str, Vec<Binding>)> {
+    let config = config_path().and_then(|path| std::fs::read_to_string(path).ok()).map(|content| Ini::parse(&content)).unwrap_or_default();
+    let mut categories = parse_categories(&config);
+    for binding in categories.iter_mut().flat_map(|(_, bindings)| bindings.iter_mut()).filter(|binding| binding.component.starts_with(CUSTOM_PREFIX)) {
+        let desktop = desktop_path(&binding.component).and_then(|path| std::fs::read_to_string(path).ok()).map(|content| Ini::parse(&content)).unwrap_or_default();
+        binding.command = Some(parse_exec(desktop.get("Desktop Entry", "Exec").unwrap_or_default()));
+    }
+    categories
+}
+
+/// Position of the first enabled shortcut other than `skip` using the keys.
+pub fn find_conflict<'a>(bindings: impl IntoIterator<Item = ((usize, usize), &'a Binding)>, keys: &str, skip: (usize, usize)) -> Option<(usize, usize)> {
+    bindings.into_iter().find(|(pos, binding)| *pos != skip && binding.has_keys(keys)).map(|(pos, _)| pos)
+}
+
+/// Keys of a key press in the format of Qt, like `Meta+Ctrl+Left`. Modifier keys alone give `None`.
+pub fn key_sequence(key_code: KeyCode, modifiers: ModifiersState) -> Option<String> {
+    use KeyCode::*;
+    let debug_name = format!("{:?}", key_code);
+    let key = match key_code {
+        LShift | RShift | LControl | RControl | LAlt | RAlt | LWin | RWin => return None,
+        Key1 | Key2 | Key3 | Key4 | Key5 | Key6 | Key7 | Key8 | Key9 | Key0 => debug_name.trim_start_matches("Key"),
+        Left => "Left",
+        Right => "Right",
+        Up => "Up",
+        Down => "Down",
+        PageUp => "PgUp",
+        PageDown => "PgDown",
+        Home => "Home",
+        End => "End",
+        Insert => "Ins",
+        Delete => "Del",
+        Backspace => "Backspace",
+        Tab if modifiers.shift => "Backtab",
+        Tab => "Tab",
+        Enter => "Return",
+        Escape => "Esc",
+        Space => "Space",
+        Snapshot => "Print",
+        Pause => "Pause",
+        Grave => "`",
+        Minus => "-",
+        Equals => "=",
+        LBracket => "[",
+        RBracket => "]",
+        Backslash => "\\",
+        Semicolon => ";",
+        Apostrophe => "'",
+        Comma => ",",
+        Period => ".",
+        Slash => "/",
+        // Letters and function keys are named like their key code.
+        _ if debug_name.len() == 1 || (debug_name.starts_with('F') && debug_name[1..].parse::<u8>().is_ok()) => debug_name.as_str(),
+        _ => return None,
+    };
+    let mods = [(modifiers.logo, "Meta"), (modifiers.control, "Ctrl"), (modifiers.alt, "Alt"), (modifiers.shift, "Shift")];
+    let mut parts: Vec<&str> = mods.iter().filter(|(is_pressed, _)| *is_pressed).map(|(_, name)| *name).collect();
+    parts.push(key);
+    Some(parts.join("+"))
+}
+
+/// Whether a custom shortcut can be saved: desktop files hold one line per key, so control characters are refused.
+pub fn check_custom(title: &str, command: &str) -> Result<(), String> {
+    if title.trim().is_empty() || command.trim().is_empty() {
+        Err(String::from("Please enter a name and a command"))
+    } else if title.chars().chain(command.chars()).any(char::is_control) {
+        Err(String::from("The name and the command can't contain control characters like tabs or line breaks"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Desktop file of a custom shortcut, run by kglobalaccel like an application.
+pub fn desktop_entry(title: &str, command: &str) -> Result<String, String> {
+    check_custom(title, command)?;
+    Ok(format!("[Desktop Entry]\nType=Application\nName={}\nExec={}\nNoDisplay=true\n", escape_value(title), exec_value(command)))
+}
+
+/// `Exec` key running the command with `sh`, as the desktop entry specification asks: the command is quoted between
+/// double quotes with `"`, `` ` ``, `$` and `\\` escaped, `%` is doubled so that it isn't a field code, and the
+/// backslashes are escaped again like in any value.
+fn exec_value(command: &str) -> String {
+    let quoted: String = command
+        .chars()
+        .flat_map(|c| match c {
+            '"' | '`' | '$' | '\\' => vec!['\\', c],
+            '%' => vec!['%', '%'],
+            _ => vec![c],
+        })
+        .collect();
+    escape_value(&format!("sh -c \"{}\"", quoted))
+}
+
+/// The command of an `Exec` key written by `exec_value`, or the whole key for other desktop files.
+pub fn parse_exec(val: &str) -> String {
+    let val = unescape_value(val);
+    let quoted = match val.strip_prefix("sh -c \"").and_then(|val| val.strip_suffix('"')) {
+        Some(quoted) => quoted,
+        None => return val,
+    };
+    let mut command = String::new();
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(_)) | ('%', Some('%')) => command.extend(chars.next()),
+            _ => command.push(c),
+        }
+    }
+    command
+}
+
+fn escape_value(val: &str) -> String {
+    val.replace('\\', "\\\\")
+}
+
+/// A value of a KConfig or desktop file without its escapes, like `\s` for a space.
+fn unescape_value(val: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next() {
+            Some('s') => ' ',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some(other) => other,
+            None => '\\',
+        });
+    }
+    unescaped
+}
+
+/// Call a method of kglobalaccel with its arguments in the text format of GVariant.
+fn kglobalaccel(method: &str, args: &[String]) -> Result<(), String> {
+    let output = Command::new("gdbus")
+        .args(&["call", "--session", "--dest", "org.kde.kglobalaccel", "--object-path", "/kglobalaccel", "--method", &format!("org.kde.KGlobalAccel.{}", method)])
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run gdbus: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("kglobalaccel {}: {}", method, String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+/// A string in the text format of GVariant.
+fn gvariant_str(val: &str) -> String {
+    format!("'{}'", val.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Ids of an action as kglobalaccel takes them: the component and the action, then their names.
+fn action_id(binding: &Binding) -> String {
+    let component_name = if binding.command.is_some() { binding.title.as_str() } else { "" };
+    let ids = [binding.component.as_str(), binding.action.as_str(), component_name, binding.title.as_str()];
+    format!("[{}]", ids.iter().map(|id| gvariant_str(id)).collect::<Vec<String>>().join(", "))
+}
+
+/// Give the shortcuts their keys through kglobalaccel, which saves them, after writing the desktop files of the custom
+/// commands, and delete the `removed` ones.
+pub fn save(bindings: &[&Binding], removed: &[Binding]) -> Result<(), String> {
+    for binding in removed {
+        kglobalaccel("unregister", &[gvariant_str(&binding.component), gvariant_str(&binding.action)])?;
+        if let Some(path) = desktop_path(&binding.component).filter(|path| path.exists()) {
+            std::fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+    }
+    for binding in bindings {
+        if let Some(command) = &binding.command {
+            let desktop = desktop_entry(&binding.title, command)?;
+            let path = desktop_path(&binding.component).ok_or_else(|| String::from("Unknown data directory"))?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+            }
+            std::fs::write(&path, desktop).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        let keys = binding.qt_keys()?;
+        // The empty array needs its type, which GVariant can't tell from the elements.
+        let keys = if keys.is_empty() { String::from("@ai []") } else { format!("{:?}", keys) };
+        let action_id = action_id(binding);
+        kglobalaccel("doRegister", std::slice::from_ref(&action_id))?;
+        kglobalaccel("setForeignShortcut", &[action_id, keys])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_categories() {
+        let config = Ini::parse("[kwin]\nShow Desktop=none,Meta+D,Peek at Desktop\nWindow Close=Alt+F4\\tMeta+Q,Alt+F4,Close Window\n\n[net.local.backup.desktop]\n_k_friendly_name=Backup\\, daily\n_launch=Meta+B,none,Backup\n");
+        let categories = parse_categories(&config);
+        assert_eq!(categories.len(), CATEGORIES.len() + 1);
+        let workspaces = &categories[1].1;
+        let show_desktop = workspaces.iter().find(|binding| binding.action == "Show Desktop").unwrap();
+        assert!(!show_desktop.is_enabled);
+        assert_eq!(show_desktop.qt_keys(), Ok(Vec::new()));
+        let close = categories[2].1.iter().find(|binding| binding.action == "Window Close").unwrap();
+        assert_eq!(close.keys, "Alt+F4\tMeta+Q");
+        assert_eq!(close.label(), "Alt+F4, Meta+Q");
+        assert!(close.has_keys("meta+q"));
+        let (title, custom) = &categories[CATEGORIES.len()];
+        assert_eq!(*title, CUSTOM_CATEGORY);
+        assert_eq!(custom[0].title, "Backup, daily");
+        assert_eq!(custom[0].keys, "Meta+B");
+    }
+
+    #[test]
+    fn test_find_conflict() {
+        let lock = Binding::with_default("ksmserver", "Lock Session", "Lock screen", "Meta+L");
+        let mut layout = Binding::with_default("KDE Keyboard Layout Switcher", "Switch to Last-Used Keyboard Layout", "Previous source", "Meta+Alt+L");
+        let bindings = vec![((0, 0), &lock), ((1, 0), &layout)];
+        assert_eq!(find_conflict(bindings.clone(), "Meta+L", (1, 0)), Some((0, 0)));
+        assert_eq!(find_conflict(bindings, "Meta+L", (0, 0)), None);
+        layout.keys = String::from("Meta+L\tMeta+Alt+L");
+        layout.remove_keys("Meta+L");
+        assert_eq!(layout.keys, "Meta+Alt+L");
+        layout.remove_keys("Meta+Alt+L");
+        assert!(!layout.is_enabled);
+    }
+
+    #[test]
+    fn test_custom() {
+        let first = Binding::custom("Back up home!", "rsync -a ~ /mnt/backup", &[]);
+        assert_eq!(first.component, "net.local.back-up-home.desktop");
+        let second = Binding::custom("Back up home!", "true", &[&first]);
+        assert_eq!(second.component, "net.local.back-up-home-2.desktop");
+        assert_eq!(action_id(&second), "['net.local.back-up-home-2.desktop', '_launch', 'Back up home!', 'Back up home!']");
+    }
+
+    #[test]
+    fn test_split_entry() {
+        assert_eq!(split_entry("Ctrl+\\,\\tMeta+Q,none,Close\\, then quit"), vec!["Ctrl+,\tMeta+Q", "none", "Close, then quit"]);
+        assert_eq!(parse_entry("none,Meta+D,Show Desktop"), "");
+    }
+
+    #[test]
+    fn test_qt_key() {
+        assert_eq!(qt_key("Meta+L"), Some(0x1000_0000 | 0x4c));
+        assert_eq!(qt_key("Meta+Ctrl+Shift+Left"), Some(0x1000_0000 | 0x0400_0000 | 0x0200_0000 | 0x0100_0012));
+        assert_eq!(qt_key("Ctrl+F12"), Some(0x0400_0000 | 0x0100_003b));
+        assert_eq!(qt_key("Ctrl++"), Some(0x0400_0000 | 0x2b));
+        assert_eq!(qt_key("Hyper+L"), None);
+        let mut close = Binding::with_default("kwin", "Window Close", "Close window", "Alt+F4\tPrint");
+        assert_eq!(close.qt_keys(), Ok(vec![0x0800_0000 | 0x0100_0033, 0x0100_0009]));
+        close.is_enabled = false;
+        assert_eq!(close.qt_keys(), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_desktop_entry() {
+        let command = r#"notify-send "Disk $HOME" 100% \o/"#;
+        let desktop = desktop_entry("Disk", command).unwrap();
+        let exec = Ini::parse(&desktop).get("Desktop Entry", "Exec").unwrap().to_string();
+        assert_eq!(exec, r#"sh -c "notify-send \\"Disk \\$HOME\\" 100%% \\\\o/""#);
+        assert_eq!(parse_exec(&exec), command);
+        assert_eq!(parse_exec("firefox"), "firefox");
+        assert!(desktop_entry("Disk", "df\nreboot").is_err());
+        assert!(desktop_entry("Disk\t", "df").is_err());
+    }
+
+    #[test]
+    fn test_key_sequence() {
+        let mods = |logo, control, alt, shift| ModifiersState { shift, control, alt, logo };
+        assert_eq!(key_sequence(KeyCode::L, mods(true, false, false, false)), Some(String::from("Meta+L")));
+        assert_eq!(key_sequence(KeyCode::Left, mods(true, true, false, true)), Some(String::from("Meta+Ctrl+Shift+Left")));
+        assert_eq!(key_sequence(KeyCode::Tab, mods(false, false, true, true)), Some(String::from("Alt+Shift+Backtab")));
+        assert_eq!(key_sequence(KeyCode::F12, mods(false, true, false, false)), Some(String::from("Ctrl+F12")));
+        assert_eq!(key_sequence(KeyCode::Key3, mods(false, true, true, false)), Some(String::from("Ctrl+Alt+3")));
+        assert_eq!(key_sequence(KeyCode::LShift, mods(false, false, false, true)), None);
+    }
+}
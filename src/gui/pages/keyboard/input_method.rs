@@ -0,0 +1,293 @@
+use super::xkb_registry::InputSource;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Where fcitx5 addons install the description of their input methods.
+const INPUT_METHOD_DIRS: [&str; 2] = ["/usr/share/fcitx5/inputmethod", "/usr/local/share/fcitx5/inputmethod"];
+/// Prefix of the input methods of fcitx5 which only type through a keyboard layout.
+const KEYBOARD_PREFIX: &str = "keyboard-";
+/// Group of the profile holding the input sources.
+const GROUP: &str = "Groups/0";
+const DEFAULT_GROUP_NAME: &str = "Default";
+/// Hotkeys and behavior of the framework.
+pub const CONFIG: &str = "config";
+/// Input methods of each group.
+pub const PROFILE: &str = "profile";
+/// Options of the input methods typing through a keyboard layout.
+pub const KEYBOARD_CONF: &str = "conf/keyboard.conf";
+/// Names of keys in the pick lists and in the fcitx5 key strings.
+const KEY_NAMES: [(&str, &str); 3] = [("Left Shift", "Shift_L"), ("Right Shift", "Shift_R"), ("Space", "space")];
+
+/// Configuration file in the format of fcitx5, keeping the order of its sections and keys. Keys before the first
+/// section are in the section named "".
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ini {
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Ini {
+    pub fn parse(content: &str) -> Self {
+        let mut ini = Self::default();
+        let mut section = String::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                section = name.to_string();
+                ini.section_mut(&section);
+            } else if let Some(idx) = line.find('=') {
+                ini.set(&section, line[..idx].trim(), line[idx + 1..].trim());
+            }
+        }
+        ini
+    }
+
+    fn section_mut(&mut self, name: &str) -> &mut Vec<(String, String)> {
+        let idx = match self.sections.iter().position(|(section, _)| section == name) {
+            Some(idx) => idx,
+            None => {
+                self.sections.push((name.to_string(), Vec::new()));
+                self.sections.len() - 1
+            }
+        };
+        &mut self.sections[idx].1
+    }
+
+    pub fn section_names(&self) -> impl Iterator<Item = &str> {
+        self.sections.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections.iter().find(|(name, _)| name == section).and_then(|(_, entries)| entries.iter().find(|(name, _)| name == key)).map(|(_, val)| val.as_str())
+    }
+
+    pub fn set(&mut self, section: &str, key: &str, val: &str) {
+        let entries = self.section_mut(section);
+        match entries.iter_mut().find(|(name, _)| name == key) {
+            Some(entry) => entry.1 = val.to_string(),
+            None => entries.push((key.to_string(), val.to_string())),
+        }
+    }
+
+    /// Replace the section by a list numbered from 0, as fcitx5 saves the keys of a hotkey.
+    pub fn set_list(&mut self, section: &str, values: &[String]) {
+        let entries = self.section_mut(section);
+        entries.clear();
+        entries.extend(values.iter().enumerate().map(|(idx, val)| (idx.to_string(), val.to_owned())));
+    }
+
+    pub fn get_flag(&self, section: &str, key: &str) -> Option<bool> {
+        self.get(section, key).map(|val| val.eq_ignore_ascii_case("true"))
+    }
+
+    pub fn set_flag(&mut self, section: &str, key: &str, flag: bool) {
+        self.set(section, key, if flag { "True" } else { "False" });
+    }
+
+    /// Remove the section and the ones nested in it, like `Groups/0/Items/1` in `Groups/0`.
+    pub fn remove_section(&mut self, name: &str) {
+        let nested = format!("{}/", name);
+        self.sections.retain(|(section, _)| section != name && !section.starts_with(&nested));
+    }
+}
+
+impl std::fmt::Display for Ini {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, entries) in self.sections.iter() {
+            if !name.is_empty() {
+                writeln!(f, "[{}]", name)?;
+            }
+            for (key, val) in entries {
+                writeln!(f, "{}={}", key, val)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn path(name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("fcitx5").join(name))
+}
+
+/// One of the configuration files of fcitx5, empty if it was never saved.
+pub fn read_ini(name: &str) -> Ini {
+    path(name).and_then(|path| std::fs::read_to_string(path).ok()).map(|content| Ini::parse(&content)).unwrap_or_default()
+}
+
+pub fn write_ini(name: &str, ini: &Ini) -> Result<(), String> {
+    let path = path(name).ok_or_else(|| String::from("Unknown config directory"))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    std::fs::write(&path, ini.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn is_installed() -> bool {
+    Command::new("fcitx5").arg("--version").output().map(|output| output.status.success()).unwrap_or(false)
+}
+
+/// Make the running fcitx5 read its configuration again.
+pub fn reload() -> Result<(), String> {
+    match Command::new("fcitx5-remote").arg("-r").status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(_) => Err(String::from("fcitx5 is not running")),
+        Err(e) => Err(format!("Failed to run fcitx5-remote: {}", e)),
+    }
+}
+
+/// Key string of fcitx5 of a hotkey of the pick lists, like `Control+Shift_L` for `Control+Left Shift`.
+pub fn fcitx_key(label: &str) -> String {
+    label.split('+').map(|key| KEY_NAMES.iter().find(|(name, _)| *name == key).map_or(key, |(_, fcitx)| *fcitx)).collect::<Vec<&str>>().join("+")
+}
+
+pub fn key_label(key: &str) -> String {
+    key.split('+').map(|key| KEY_NAMES.iter().find(|(_, fcitx)| *fcitx == key).map_or(key, |(name, _)| *name)).collect::<Vec<&str>>().join("+")
+}
+
+/// Input method described by the `.conf` file named `name`. Its language is the one of `LangCode`, like `zh` for
+/// `zh_CN`.
+pub fn parse_engine(name: &str, content: &str) -> Option<InputSource> {
+    let conf = Ini::parse(content);
+    let description = conf.get("InputMethod", "Name").filter(|desc| !desc.is_empty())?.to_string();
+    let lang = conf.get("InputMethod", "LangCode").and_then(|code| code.split('_').next()).filter(|lang| !lang.is_empty());
+    Some(InputSource {
+        short: conf.get("InputMethod", "Label").unwrap_or(name).to_string(),
+        description,
+        languages: lang.map(String::from).into_iter().collect(),
+        engine: Some(name.to_string()),
+        ..InputSource::default()
+    })
+}
+
+/// Input methods of the installed fcitx5 addons, like Pinyin or Anthy.
+pub fn engines() -> Vec<InputSource> {
+    let mut engines: Vec<InputSource> = INPUT_METHOD_DIRS
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "conf"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().to_string();
+            std::fs::read_to_string(&path).ok().and_then(|content| parse_engine(&name, &content))
+        })
+        .collect();
+    engines.sort_by(|a, b| a.engine.cmp(&b.engine));
+    engines.dedup_by(|a, b| a.engine == b.engine);
+    engines
+}
+
+/// Name of the input method of fcitx5 typing with the source, like `keyboard-us-intl` or `pinyin`.
+pub fn im_name(source: &InputSource) -> String {
+    match &source.engine {
+        Some(engine) => engine.to_owned(),
+        None if source.variant.is_empty() => format!("{}{}", KEYBOARD_PREFIX, source.layout),
+        None => format!("{}{}-{}", KEYBOARD_PREFIX, source.layout, source.variant),
+    }
+}
+
+/// Write the sources to the first group of the profile. The first layout is the layout of the group and the first
+/// input method is the one turned on with the toggle hotkey.
+pub fn set_profile(profile: &mut Ini, sources: &[InputSource]) {
+    let name = profile.get(GROUP, "Name").unwrap_or(DEFAULT_GROUP_NAME).to_string();
+    let layout = sources.iter().find(|src| src.engine.is_none()).map(|src| im_name(src).trim_start_matches(KEYBOARD_PREFIX).to_string()).unwrap_or_default();
+    let default_im = sources.iter().find(|src| src.engine.is_some()).or_else(|| sources.first()).map(im_name).unwrap_or_default();
+    profile.remove_section(GROUP);
+    profile.set(GROUP, "Name", &name);
+    profile.set(GROUP, "Default Layout", &layout);
+    profile.set(GROUP, "DefaultIM", &default_im);
+    for (idx, source) in sources.iter().enumerate() {
+        let item = format!("{}/Items/{}", GROUP, idx);
+        profile.set(&item, "Name", &im_name(source));
+        profile.set(&item, "Layout", "");
+    }
+    if profile.get("GroupOrder", "0").is_none() {
+        profile.set("GroupOrder", "0", &name);
+    }
+}
+
+/// Sources of the first group of the profile, looked up among the known layouts and input methods.
+pub fn parse_profile(profile: &Ini, registry: &[InputSource], engines: &[InputSource]) -> Vec<InputSource> {
+    (0..)
+        .map(|idx| profile.get(&format!("{}/Items/{}", GROUP, idx), "Name"))
+        .take_while(Option::is_some)
+        .flatten()
+        .filter_map(|name| match name.strip_prefix(KEYBOARD_PREFIX) {
+            Some(layout) => {
+                let mut parts = layout.splitn(2, '-');
+                let (layout, variant) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+                Some(registry.iter().find(|src| src.layout == layout && src.variant == variant).cloned().unwrap_or_else(|| InputSource::new(layout, variant)))
+            }
+            None => engines.iter().find(|src| src.engine.as_deref() == Some(name)).cloned(),
+        })
+        .collect()
+}
+
+/// Sources of the fcitx5 profile, empty when fcitx5 is not used.
+pub fn load_profile(registry: &[InputSource], engines: &[InputSource]) -> Vec<InputSource> {
+    if is_installed() {
+        parse_profile(&read_ini(PROFILE), registry, engines)
+    } else {
+        Vec::new()
+    }
+}
+
+pub fn save_profile(sources: &[InputSource]) -> Result<(), String> {
+    let mut profile = read_ini(PROFILE);
+    set_profile(&mut profile, sources);
+    write_ini(PROFILE, &profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ini() {
+        let mut ini = Ini::parse("# Trigger\nPageSize=5\n\n[Hotkey]\n# Enumerate\nEnumerateWithTriggerKeys=True\n\n[Hotkey/TriggerKeys]\n0=Control+space\n");
+        assert_eq!(ini.get("", "PageSize"), Some("5"));
+        assert_eq!(ini.get_flag("Hotkey", "EnumerateWithTriggerKeys"), Some(true));
+        assert_eq!(ini.get("Hotkey/TriggerKeys", "0"), Some("Control+space"));
+        ini.set_list("Hotkey/TriggerKeys", &[String::from("Super+space"), String::from("Shift_L")]);
+        ini.set_flag("Behavior", "ActiveByDefault", false);
+        assert_eq!(
+            ini.to_string(),
+            "PageSize=5\n\n[Hotkey]\nEnumerateWithTriggerKeys=True\n\n[Hotkey/TriggerKeys]\n0=Super+space\n1=Shift_L\n\n[Behavior]\nActiveByDefault=False\n\n"
+        );
+        ini.remove_section("Hotkey");
+        assert_eq!(ini.to_string(), "PageSize=5\n\n[Behavior]\nActiveByDefault=False\n\n");
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(fcitx_key("Control+Left Shift"), "Control+Shift_L");
+        assert_eq!(fcitx_key("Shift+Super+Space"), "Shift+Super+space");
+        assert_eq!(key_label("Control+Shift_R"), "Control+Right Shift");
+        assert_eq!(key_label("Shift+Tab"), "Shift+Tab");
+    }
+
+    #[test]
+    fn test_parse_engine() {
+        let pinyin = parse_engine("pinyin", "[InputMethod]\nName=Pinyin\nName[zh_CN]=拼音\nIcon=fcitx-pinyin\nLabel=拼\nLangCode=zh_CN\nAddon=pinyin\n").unwrap();
+        assert_eq!(pinyin.description, "Pinyin");
+        assert_eq!(pinyin.short, "拼");
+        assert_eq!(pinyin.languages, vec!["zh"]);
+        assert_eq!(pinyin.engine.as_deref(), Some("pinyin"));
+        assert!(parse_engine("broken", "[InputMethod]\nIcon=x\n").is_none());
+    }
+
+    #[test]
+    fn test_profile() {
+        let registry = vec![InputSource::new("us", ""), InputSource::new("kh", "")];
+        let pinyin = parse_engine("pinyin", "[InputMethod]\nName=Pinyin\nLangCode=zh_CN\n").unwrap();
+        let sources = vec![InputSource::new("kh", ""), pinyin.clone(), InputSource::new("us", "intl")];
+        let mut profile = Ini::parse("[Groups/0]\nName=Main\n\n[Groups/0/Items/0]\nName=keyboard-us\n\n[Groups/0/Items/1]\nName=anthy\n\n[GroupOrder]\n0=Main\n");
+        set_profile(&mut profile, &sources);
+        assert_eq!(profile.get("Groups/0", "Name"), Some("Main"));
+        assert_eq!(profile.get("Groups/0", "Default Layout"), Some("kh"));
+        assert_eq!(profile.get("Groups/0", "DefaultIM"), Some("pinyin"));
+        assert_eq!(profile.get("Groups/0/Items/2", "Name"), Some("keyboard-us-intl"));
+        assert_eq!(profile.get("GroupOrder", "0"), Some("Main"));
+        assert_eq!(parse_profile(&profile, &registry, &[pinyin]), sources);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::helpers::kconfig::write_key;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Repeat rates in keys per second of the positions of the Key Repeat slider after "off".
+const RATES: [u32; 7] = [2, 5, 10, 15, 20, 30, 40];
+/// Delays in milliseconds of the positions of the Delay Until Repeat slider, from long to short.
+const DELAYS: [u32; 6] = [1000, 800, 660, 500, 350, 200];
+/// KDE reads the auto-repeat settings from this file when the session starts.
+const KCMINPUTRC: &str = "kcminputrc";
+
+/// Auto-repeat of held keys, applied to the session through the XKB auto-repeat control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRepeat {
+    pub is_enabled: bool,
+    /// Milliseconds a key is held before it repeats.
+    pub delay: u32,
+    /// Repeats per second.
+    pub rate: u32,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self { is_enabled: true, delay: 660, rate: 30 }
+    }
+}
+
+impl KeyRepeat {
+    /// Settings of the positions of the Key Repeat (1 is off) and Delay Until Repeat sliders.
+    pub fn from_sliders(repeat_val: u8, delay_val: u8) -> Self {
+        let nth = |values: &[u32], val: u8| values[(val.max(1) as usize - 1).min(values.len() - 1)];
+        Self {
+            is_enabled: repeat_val > 1,
+            delay: nth(&DELAYS, delay_val),
+            rate: if repeat_val > 1 { nth(&RATES, repeat_val - 1) } else { Self::default().rate },
+        }
+    }
+
+    /// Positions of the sliders closest to the settings.
+    pub fn to_sliders(&self) -> (u8, u8) {
+        let closest = |values: &[u32], val: u32| values.iter().enumerate().min_by_key(|(_, v)| (**v as i64 - val as i64).abs()).map(|(idx, _)| idx as u8 + 1).unwrap_or(1);
+        let repeat_val = if self.is_enabled { closest(&RATES, self.rate) + 1 } else { 1 };
+        (repeat_val, closest(&DELAYS, self.delay))
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("system_settings/key_repeat.json"))
+    }
+
+    pub fn load() -> Self {
+        Self::path().and_then(|path| std::fs::read_to_string(path).ok()).and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+    }
+
+    /// Arguments of `xset`, which sets the auto-repeat controls of the XKB keyboard of the display.
+    pub fn xset_args(&self) -> Vec<String> {
+        if self.is_enabled {
+            vec![String::from("r"), String::from("on"), String::from("r"), String::from("rate"), self.delay.to_string(), self.rate.to_string()]
+        } else {
+            vec![String::from("r"), String::from("off")]
+        }
+    }
+
+    /// Apply the settings to the running session.
+    pub fn apply(&self) -> Result<(), String> {
+        let output = Command::new("xset").args(self.xset_args()).output().map_err(|e| format!("Failed to run xset: {}", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!("xset: {}", String::from_utf8_lossy(&output.stderr).trim()))
+        }
+    }
+
+    /// Save the settings, so that they are applied again at the next login.
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(path) = Self::path() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+            }
+            std::fs::write(&path, serde_json::to_string_pretty(self).unwrap_or_default()).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        write_key(KCMINPUTRC, "Keyboard", "KeyRepeat", Some(if self.is_enabled { "repeat" } else { "nothing" }))?;
+        write_key(KCMINPUTRC, "Keyboard", "RepeatDelay", Some(&self.delay.to_string()))?;
+        write_key(KCMINPUTRC, "Keyboard", "RepeatRate", Some(&self.rate.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliders() {
+        let off = KeyRepeat::from_sliders(1, 3);
+        assert!(!off.is_enabled);
+        assert_eq!(off.to_sliders(), (1, 3));
+        let fast = KeyRepeat::from_sliders(8, 6);
+        assert_eq!(fast, KeyRepeat { is_enabled: true, delay: 200, rate: 40 });
+        assert_eq!(fast.to_sliders(), (8, 6));
+        assert_eq!(KeyRepeat { is_enabled: true, delay: 600, rate: 28 }.to_sliders(), (7, 3));
+    }
+
+    #[test]
+    fn test_xset_args() {
+        assert_eq!(KeyRepeat::default().xset_args(), vec!["r", "on", "r", "rate", "660", "30"]);
+        assert_eq!(KeyRepeat::from_sliders(1, 1).xset_args(), vec!["r", "off"]);
+    }
+}
@@ -1,24 +1,42 @@
 use super::add_input_source_sec::AddInputSrcMessage;
 use super::conf_input_source_sec::ConfigInputSrcMessage;
+use super::global_shortcuts;
+use super::input_method;
+use super::key_repeat::KeyRepeat;
 use super::keyboard_utils::*;
+use super::layout_preview::LayoutPreview;
+use super::modifier_keys::{CapsLock, ComposeKey, ModifierKeys};
+use super::xkb_registry;
 use crate::gui::addon_widgets::{icon_btn, tabbar};
-use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomSelect, CustomSlider};
-use crate::helpers::ROOT_PATH;
-use iced::{button, Align, Button, Checkbox, Column, Container, Element, Length, PickList, Row, Scrollable, Slider, Space, Svg, Text};
+use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomSelect, CustomSlider, CustomTextInput, ERROR};
+use crate::helpers::kbd_backlight::BacklightSettings;
+use iced::{button, Align, Button, Checkbox, Column, Container, Element, Length, PickList, Row, Scrollable, Slider, Space, Subscription, Text, TextInput};
 use iced_custom_widget::{Icon, Icons};
-use vedas_core::svg;
+use iced_native::{keyboard, Event};
 
 #[derive(Debug, Clone)]
 pub enum KeyboardMessage {
    TabChanged(usize),
    KeyRepeatChanged(u8),
    DelayRepeatChanged(u8),
-   AdjustBrightnessToggled(bool),
+   TestTextChanged(String),
+   BrightnessChanged(u8),
    TurnBacklightOffToggled(bool),
    BacklightOffDurationChanged(TurnBacklightOff),
+   CapsLockChanged(CapsLock),
+   SwapAltSuperToggled(bool),
+   ComposeKeyChanged(ComposeKey),
+   NumLockOnBootToggled(bool),
    LeftTabSelected(usize),
    RightPaneSelectedToggled(usize, bool),
+   ShortcutClicked(usize),
+   EventOccurred(Event),
+   ReassignClicked,
    RestoreDefaultClicked,
+   CustomNameChanged(String),
+   CustomCmdChanged(String),
+   AddCustomClicked,
+   RemoveCustomClicked,
    KeyNavToggled(bool),
    InputSourceLeftTabSelected(usize),
    AddClicked,
@@ -74,6 +92,13 @@ impl KeyboardPage {
       }
    }
 
+   /// Go back to the saved settings, staying on the current tab.
+   fn reset(&mut self) {
+      let curr_tab = self.current_tab_idx;
+      *self = Self::new();
+      self.current_tab_idx = curr_tab;
+   }
+
    pub fn update(&mut self, msg: KeyboardMessage) {
       use KeyboardMessage::*;
       let KeyboardPage {
@@ -90,23 +115,73 @@ impl KeyboardPage {
             self.current_tab_idx = idx;
             should_apply = None
          }
-         KeyRepeatChanged(val) => keyboard.key_repeat_val = val,
-         DelayRepeatChanged(val) => keyboard.delay_repeat_val = val,
-         AdjustBrightnessToggled(val) => keyboard.adjust_brightness_low_light = val,
+         KeyRepeatChanged(val) => {
+            keyboard.key_repeat_val = val;
+            apply_key_repeat(keyboard.key_repeat());
+         }
+         DelayRepeatChanged(val) => {
+            keyboard.delay_repeat_val = val;
+            apply_key_repeat(keyboard.key_repeat());
+         }
+         TestTextChanged(val) => {
+            keyboard.test_val = val;
+            should_apply = None
+         }
+         BrightnessChanged(val) => {
+            keyboard.brightness_val = val;
+            if let Err(err) = keyboard.apply_brightness() {
+               eprintln!("{:?}", err);
+            }
+         }
          TurnBacklightOffToggled(val) => keyboard.turn_backlight_off = val,
          BacklightOffDurationChanged(duration) => keyboard.turn_backlight_off_after_val = duration,
+         CapsLockChanged(val) => {
+            keyboard.modifier_keys.caps_lock = val;
+            apply_modifier_keys(keyboard.modifier_keys);
+         }
+         SwapAltSuperToggled(val) => {
+            keyboard.modifier_keys.swap_alt_super = val;
+            apply_modifier_keys(keyboard.modifier_keys);
+         }
+         ComposeKeyChanged(val) => {
+            keyboard.modifier_keys.compose = val;
+            apply_modifier_keys(keyboard.modifier_keys);
+         }
+         NumLockOnBootToggled(val) => keyboard.modifier_keys.numlock_on_boot = val,
          LeftTabSelected(idx) => {
             shortcuts.left_pane_selected = idx;
+            shortcuts.right_pane_selected = 0;
+            shortcuts.capturing = None;
+            shortcuts.conflict = None;
+            should_apply = None
+         }
+         RightPaneSelectedToggled(idx, is_checked) => shortcuts.set_enabled(idx, is_checked),
+         ShortcutClicked(idx) => {
+            shortcuts.start_capture(idx);
+            should_apply = None
+         }
+         EventOccurred(event) => {
+            if let Event::Keyboard(keyboard::Event::KeyPressed { key_code, modifiers }) = event {
+               shortcuts.capture(key_code, modifiers);
+            }
+            should_apply = if shortcuts.is_changed { Some(true) } else { None }
+         }
+         ReassignClicked => shortcuts.reassign(),
+         RestoreDefaultClicked => shortcuts.restore_defaults(),
+         CustomNameChanged(val) => {
+            shortcuts.custom_name_val = val;
             should_apply = None
          }
-         RightPaneSelectedToggled(idx, is_checked) => {
-            shortcuts.right_pane_selected = idx;
-            shortcuts.shortcuts_tab_map.get_mut(shortcuts.left_pane_selected).unwrap().get_mut(idx).unwrap().0 = is_checked;
+         CustomCmdChanged(val) => {
+            shortcuts.custom_cmd_val = val;
+            should_apply = None
          }
-         RestoreDefaultClicked => self.shortcuts = Shortcuts::new(),
+         AddCustomClicked => shortcuts.add_custom(),
+         RemoveCustomClicked => shortcuts.remove_custom(),
          KeyNavToggled(val) => shortcuts.use_keyboard_nav = val,
          InputSourceLeftTabSelected(idx) => {
             input_sources_tab.input_sources_selected = Some(idx);
+            input_sources_tab.preview = input_sources_tab.input_sources.get(idx).and_then(|(src, _)| LayoutPreview::load(src).ok());
             should_apply = None
          }
          AddClicked => {
@@ -120,6 +195,7 @@ impl KeyboardPage {
                }
             }
             input_sources_tab.input_sources_selected = None;
+            input_sources_tab.preview = None;
          }
          UpClicked => {
             if let Some(selected_idx) = input_sources_tab.input_sources_selected {
@@ -151,30 +227,88 @@ impl KeyboardPage {
          SwitchShowInpSrcInfo(is_checked) => behavior_sec.switch_show_inp_src_info = is_checked,
          ChangeFocusShowInpSrcInfo(is_checked) => behavior_sec.change_focus_show_inp_src_info = is_checked,
          AddInputSrcMsg(add_inp_msg) => match add_inp_msg {
-            AddInputSrcMessage::AddClicked(layout) => {
-               input_sources_tab.input_sources.push(('\u{f1ab}', layout, button::State::new()));
+            AddInputSrcMessage::AddClicked(source) => {
+               if !input_sources_tab.input_sources.iter().any(|(src, _)| *src == source) {
+                  input_sources_tab.input_sources.push((source, button::State::new()));
+               }
                input_sources_tab.is_adding = false;
+               input_sources_tab.add_input_source_sec.update(AddInputSrcMessage::CancelClicked);
+            }
+            AddInputSrcMessage::CancelClicked => {
+               input_sources_tab.is_adding = false;
+               input_sources_tab.add_input_source_sec.update(add_inp_msg);
             }
-            AddInputSrcMessage::CancelClicked => input_sources_tab.is_adding = false,
             _ => input_sources_tab.add_input_source_sec.update(add_inp_msg),
          },
          ConfigInputSrcMsg(conf_inp_src_msg) => match conf_inp_src_msg {
-            ConfigInputSrcMessage::AddClicked | ConfigInputSrcMessage::CancelClicked => input_sources_tab.is_config = false,
+            ConfigInputSrcMessage::AddClicked => {
+               if let Err(err) = input_sources_tab.config_input_source_sec.save() {
+                  eprintln!("{:?}", err);
+               }
+               input_sources_tab.is_config = false;
+            }
+            ConfigInputSrcMessage::CancelClicked => input_sources_tab.is_config = false,
             _ => input_sources_tab.config_input_source_sec.update(conf_inp_src_msg),
          },
-         OKClicked => should_apply = Some(false),
-         DefaultsClicked | ResetClicked => {
-            let curr_tab = self.current_tab_idx;
-            *self = Self::new();
-            self.current_tab_idx = curr_tab;
+         OKClicked => {
+            if let Err(err) = keyboard.key_repeat().save() {
+               eprintln!("{:?}", err);
+            }
+            if let Err(err) = keyboard.backlight().save() {
+               eprintln!("{:?}", err);
+            }
+            if let Err(err) = keyboard.modifier_keys.save() {
+               eprintln!("{:?}", err);
+            }
+            if shortcuts.is_changed {
+               if let Err(err) = shortcuts.save() {
+                  eprintln!("{:?}", err);
+               }
+            }
+            let sources = input_sources_tab.sources();
+            if let Err(err) = xkb_registry::apply(&sources) {
+               eprintln!("{:?}", err);
+            }
+            // fcitx5 switches between the layouts and input methods itself when it runs.
+            if input_method::is_installed() {
+               if let Err(err) = input_method::save_profile(&sources).and_then(|_| self.global_opts.save()).and_then(|_| input_method::reload()) {
+                  eprintln!("{:?}", err);
+               }
+            }
+            should_apply = Some(false)
+         }
+         ResetClicked => {
+            self.reset();
+            apply_key_repeat(self.keyboard.key_repeat());
+            apply_brightness(&self.keyboard);
+            apply_modifier_keys(self.keyboard.modifier_keys);
             should_apply = Some(false);
          }
+         // The defaults are only saved once confirmed with OK.
+         DefaultsClicked => {
+            self.reset();
+            self.keyboard = Keyboard::with_settings(KeyRepeat::default(), BacklightSettings::default(), ModifierKeys::default());
+            apply_key_repeat(self.keyboard.key_repeat());
+            apply_brightness(&self.keyboard);
+            apply_modifier_keys(self.keyboard.modifier_keys);
+            should_apply = Some(true);
+         }
       }
       if let Some(should_apply) = should_apply {
          self.is_changed = should_apply;
       }
    }
 
+   /// Key presses are only listened to while a shortcut waits for its keys. The backlight is turned off while idle by
+   /// `system_settings_session`.
+   pub fn subscription(&self) -> Subscription<KeyboardMessage> {
+      if self.shortcuts.capturing.is_some() {
+         iced_native::subscription::events().map(KeyboardMessage::EventOccurred)
+      } else {
+         Subscription::none()
+      }
+   }
+
    pub fn view(&mut self) -> Element<KeyboardMessage> {
       use KeyboardMessage::*;
       let KeyboardPage {
@@ -201,10 +335,18 @@ impl KeyboardPage {
                key_repeat_val,
                delay_repeat_state,
                delay_repeat_val,
-               adjust_brightness_low_light,
+               test_state,
+               test_val,
                turn_backlight_off,
                turn_backlight_off_after_state,
                turn_backlight_off_after_val,
+               kbd_backlight,
+               brightness_state,
+               brightness_val,
+               modifier_keys,
+               caps_lock_state,
+               compose_state,
+               ..
             } = keyboard;
 
             let lb_key_repeat = Text::new("Key Repeat").size(14);
@@ -236,34 +378,66 @@ impl KeyboardPage {
                         .push(Row::new().width(Length::Units(175)).push(Text::new("long").size(12)).push(Space::with_width(Length::Fill)).push(Text::new("short").size(12))),
                   ),
                );
-            let key_repeat_con = Container::new(key_repeat_row).center_x();
+            let txt_test = TextInput::new(test_state, "Type here and hold a key to try the settings", test_val, TestTextChanged)
+               .padding(7)
+               .width(Length::Units(400))
+               .style(CustomTextInput::Default);
+            let key_repeat_con = Container::new(Column::new().spacing(10).align_items(Align::Center).push(key_repeat_row).push(txt_test)).width(Length::Fill).center_x();
 
-            let chk_adjust_brightness = Checkbox::new(*adjust_brightness_low_light, "Adjust keyboard brightness in low light", AdjustBrightnessToggled).spacing(10).style(CustomCheckbox::Default);
             let chk_turn_backlight_off = Checkbox::new(*turn_backlight_off, "Turn keyboard backlight off after", TurnBacklightOffToggled).spacing(10).style(CustomCheckbox::Default);
             let pl_backlight_off_duration = PickList::new(turn_backlight_off_after_state, &TurnBacklightOff::ALL[..], Some(*turn_backlight_off_after_val), BacklightOffDurationChanged).style(CustomSelect::Primary);
             let lb_inactivity = Text::new("of inactivity");
             let keyboard_backligh_off_row = Row::new().spacing(15).align_items(Align::Center).push(chk_turn_backlight_off).push(pl_backlight_off_duration).push(lb_inactivity);
+            let backlight_col = match kbd_backlight {
+               Some(_) => {
+                  let lb_brightness = Text::new("Keyboard Brightness").size(14);
+                  let slider_brightness = Slider::new(brightness_state, 0..=100, *brightness_val, BrightnessChanged).width(Length::Units(250)).style(CustomSlider::Default);
+                  let brightness_row = Row::new().spacing(15).align_items(Align::Center).push(lb_brightness).push(slider_brightness).push(Text::new(format!("{}%", brightness_val)).size(12));
+                  Column::new().spacing(15).push(brightness_row).push(keyboard_backligh_off_row)
+               }
+               None => Column::new().spacing(15).push(Text::new("This keyboard has no backlight.").size(12)),
+            };
 
-            Container::new(
-               Column::new()
-                  .width(Length::Fill)
-                  .spacing(20)
-                  .align_items(Align::Start)
-                  .push(key_repeat_con)
-                  .push(Column::new().spacing(15).push(chk_adjust_brightness).push(keyboard_backligh_off_row)),
-            )
-            .width(Length::Fill)
-            .height(Length::Fill)
+            let lb_modifier_keys = Text::new("Modifier Keys").size(14);
+            let pl_caps_lock = PickList::new(caps_lock_state, &CapsLock::ALL[..], Some(modifier_keys.caps_lock), CapsLockChanged).style(CustomSelect::Primary);
+            let pl_compose = PickList::new(compose_state, &ComposeKey::ALL[..], Some(modifier_keys.compose), ComposeKeyChanged).style(CustomSelect::Primary);
+            let chk_swap_alt_super = Checkbox::new(modifier_keys.swap_alt_super, "Swap Alt and Super keys", SwapAltSuperToggled).spacing(10).style(CustomCheckbox::Default);
+            let chk_numlock_on_boot = Checkbox::new(modifier_keys.numlock_on_boot, "Turn on Num Lock at login", NumLockOnBootToggled).spacing(10).style(CustomCheckbox::Default);
+            let modifier_keys_row = Row::new()
+               .spacing(10)
+               .push(Column::new().spacing(15).align_items(Align::End).push(Text::new("Caps Lock key:")).push(Text::new("Compose key:")))
+               .push(Column::new().spacing(5).push(pl_caps_lock).push(pl_compose));
+            let modifier_keys_col = Column::new().spacing(15).push(lb_modifier_keys).push(modifier_keys_row).push(chk_swap_alt_super).push(chk_numlock_on_boot);
+
+            Container::new(Column::new().width(Length::Fill).spacing(20).align_items(Align::Start).push(key_repeat_con).push(backlight_col).push(modifier_keys_col))
+               .width(Length::Fill)
+               .height(Length::Fill)
          }
          1 => {
+            let is_custom = shortcuts.is_custom();
+            let conflict = shortcuts.conflict.as_ref().map(|(keys, other)| {
+               let category = shortcuts.shortcuts_tab.get(other.0).map(|(_, title, _)| *title).unwrap_or_default();
+               let title = shortcuts.binding(*other).map(|binding| binding.title.clone()).unwrap_or_default();
+               format!("{} is already used by \"{}\" in {}.", keys, title, category)
+            });
             let Shortcuts {
                shortcuts_tab,
                shortcuts_tab_map,
                left_pane_selected,
                right_pane_selected,
+               capturing,
                use_keyboard_nav,
+               custom_name_state,
+               custom_name_val,
+               custom_cmd_state,
+               custom_cmd_val,
+               btn_add_custom_state,
+               btn_remove_custom_state,
+               btn_reassign_state,
+               btn_restore_state,
                left_pane_scroll,
                right_pane_scroll,
+               ..
             } = shortcuts;
 
             let lb_shortcuts = Text::new("To change a shortcut, select it, click key combination, and then type new keys.").size(15);
@@ -283,21 +457,55 @@ impl KeyboardPage {
             let left_pane = Container::new(left_tab_col).width(Length::FillPortion(4)).height(Length::Fill).style(CustomContainer::ForegroundWhite);
 
             // ផ្ទាំងខាងស្ដាំ
-            let right_pane_col = shortcuts_tab_map.get_mut(*left_pane_selected).unwrap().iter_mut().enumerate().fold(
-               Scrollable::new(right_pane_scroll).height(Length::Fill).padding(7).spacing(4).scroller_width(4).scrollbar_width(4),
-               |col, (idx, (is_checked, title, shortcut))| {
+            let capturing = *capturing;
+            let bindings = shortcuts_tab_map.get_mut(*left_pane_selected).map(|bindings| bindings.as_mut_slice()).unwrap_or_default();
+            let has_bindings = !bindings.is_empty();
+            let right_pane_col = bindings
+               .iter_mut()
+               .enumerate()
+               .fold(Scrollable::new(right_pane_scroll).height(Length::Fill).padding(7).spacing(4).scroller_width(4).scrollbar_width(4), |col, (idx, (binding, state))| {
+                  let lb_keys = if capturing == Some(idx) { String::from("Type new keys...") } else { binding.label() };
                   let row = Row::new()
                      .align_items(Align::Center)
                      .padding(4)
-                     .push(Checkbox::new(*is_checked, *title, move |is| RightPaneSelectedToggled(idx, is)).spacing(10).style(CustomCheckbox::Default))
+                     .push(Checkbox::new(binding.is_enabled, binding.title.as_str(), move |is| RightPaneSelectedToggled(idx, is)).spacing(10).style(CustomCheckbox::Default))
                      .push(Space::with_width(Length::Fill))
-                     .push(Text::new(*shortcut))
+                     .push(
+                        Button::new(state, Text::new(lb_keys))
+                           .on_press(ShortcutClicked(idx))
+                           .style(if capturing == Some(idx) { CustomButton::Selected } else { CustomButton::Text }),
+                     )
                      .push(Space::with_width(Length::Units(15)));
 
                   col.push(Container::new(row).width(Length::Fill).style(if *right_pane_selected == idx { CustomContainer::Hovered } else { CustomContainer::ForegroundWhite }))
-               },
-            );
-            let right_pane = Container::new(right_pane_col).width(Length::FillPortion(6)).height(Length::Fill).style(CustomContainer::ForegroundWhite);
+               });
+            let mut right_pane_sec = Column::new().spacing(10).push(Container::new(right_pane_col).height(Length::Fill).style(CustomContainer::ForegroundWhite));
+            if let Some(conflict) = conflict {
+               let btn_reassign = icon_btn(btn_reassign_state, Icons::Upload, "Reassign", None).on_press(ReassignClicked).style(CustomButton::Default);
+               right_pane_sec = right_pane_sec.push(Row::new().spacing(10).align_items(Align::Center).push(Text::new(conflict).size(12).color(ERROR).width(Length::Fill)).push(btn_reassign));
+            }
+            if is_custom {
+               let txt_name = TextInput::new(custom_name_state, "Name", custom_name_val, CustomNameChanged).padding(7).width(Length::FillPortion(2)).style(CustomTextInput::Default);
+               let txt_cmd = TextInput::new(custom_cmd_state, "Command", custom_cmd_val, CustomCmdChanged).padding(7).width(Length::FillPortion(3)).style(CustomTextInput::Default);
+               let mut btn_add_custom = Button::new(btn_add_custom_state, Icon::new(Icons::Ad).size(23)).padding(2).style(CustomButton::Text);
+               let mut btn_remove_custom = Button::new(btn_remove_custom_state, Icon::new(Icons::RemoveUser).size(23)).padding(2).style(CustomButton::Text);
+               let custom_check = global_shortcuts::check_custom(custom_name_val, custom_cmd_val);
+               if custom_check.is_ok() {
+                  btn_add_custom = btn_add_custom.on_press(AddCustomClicked);
+               }
+               if has_bindings {
+                  btn_remove_custom = btn_remove_custom.on_press(RemoveCustomClicked);
+               }
+               right_pane_sec = right_pane_sec.push(Row::new().spacing(10).align_items(Align::Center).push(txt_name).push(txt_cmd).push(btn_add_custom).push(btn_remove_custom));
+               // Empty fields are only waiting to be filled.
+               if let (Err(err), false) = (custom_check, custom_name_val.is_empty() || custom_cmd_val.is_empty()) {
+                  right_pane_sec = right_pane_sec.push(Text::new(err).size(12).color(ERROR));
+               }
+            } else {
+               let btn_restore = icon_btn(btn_restore_state, Icons::Upload, "Restore Defaults", None).on_press(RestoreDefaultClicked).style(CustomButton::Default);
+               right_pane_sec = right_pane_sec.push(Row::new().push(Space::with_width(Length::Fill)).push(btn_restore));
+            }
+            let right_pane = Container::new(right_pane_sec).width(Length::FillPortion(6)).height(Length::Fill);
 
             // ផ្នែកខាងក្រោម
             let chb_keyboard_nav = Checkbox::new(*use_keyboard_nav, "Use keyboard navigations to move focus between controls", KeyNavToggled).spacing(10).style(CustomCheckbox::Default);
@@ -327,6 +535,7 @@ impl KeyboardPage {
                btn_config_state,
                input_sources,
                input_sources_selected,
+               preview,
                auto_switch,
                show_input_menu,
                is_adding,
@@ -344,8 +553,8 @@ impl KeyboardPage {
             let left_tab_col = input_sources
                .iter_mut()
                .enumerate()
-               .fold(Scrollable::new(left_pane_scroll).height(Length::Fill).padding(7).spacing(4).scroller_width(4).scrollbar_width(4), |col, (idx, (icon, title, state))| {
-                  let btn = icon_btn(state, Icons::Download, title, Some(23)).width(Length::Fill).on_press(InputSourceLeftTabSelected(idx));
+               .fold(Scrollable::new(left_pane_scroll).height(Length::Fill).padding(7).spacing(4).scroller_width(4).scrollbar_width(4), |col, (idx, (inp_src, state))| {
+                  let btn = icon_btn(state, Icons::Download, &inp_src.description, Some(23)).width(Length::Fill).on_press(InputSourceLeftTabSelected(idx));
                   col.push(if let Some(selected_idx) = input_sources_selected {
                      btn.style(if *selected_idx == idx { CustomButton::SelectedSidebar } else { CustomButton::Sidebar })
                   } else {
@@ -388,24 +597,8 @@ impl KeyboardPage {
             let right_pane: Element<_> = if *is_config {
                config_input_source_sec.view().map(move |msg| ConfigInputSrcMsg(msg))
             } else if !(*is_adding) {
-               let keyboard_image_con = match input_sources_selected {
-                  Some(idx) => match idx {
-                     0 => {
-                        let en_keyboard = svg!(format!("{}/assets/images/keyboard.svg", ROOT_PATH())).height(Length::Units(250));
-                        Container::new(Row::new().push(Space::with_width(Length::FillPortion(1))).push(en_keyboard).push(Space::with_width(Length::FillPortion(1))))
-                           .width(Length::Fill)
-                           .center_x()
-                           .center_y()
-                     }
-                     1 => {
-                        let kh_keyboard = svg!(format!("{}/assets/images/keyboard.svg", ROOT_PATH())).height(Length::Units(250));
-                        Container::new(Row::new().push(Space::with_width(Length::FillPortion(1))).push(kh_keyboard).push(Space::with_width(Length::FillPortion(1))))
-                           .width(Length::Fill)
-                           .center_x()
-                           .center_y()
-                     }
-                     _ => Container::new(Space::with_width(Length::Fill)),
-                  },
+               let keyboard_image_con = match preview {
+                  Some(preview) => Container::new(preview.view()).width(Length::Fill).center_x().center_y(),
                   None => Container::new(Space::with_width(Length::Fill)),
                };
 
@@ -577,3 +770,23 @@ impl KeyboardPage {
       Container::new(content).width(Length::FillPortion(15)).padding(20).height(Length::Fill).style(CustomContainer::Background).into()
    }
 }
+
+/// Apply the auto-repeat to the session right away, so that it can be tried in the test field.
+fn apply_key_repeat(key_repeat: KeyRepeat) {
+   if let Err(err) = key_repeat.apply() {
+      eprintln!("{:?}", err);
+   }
+}
+
+/// Apply the XKB options to the session right away, so that the keys can be tried.
+fn apply_modifier_keys(modifier_keys: ModifierKeys) {
+   if let Err(err) = modifier_keys.apply() {
+      eprintln!("{:?}", err);
+   }
+}
+
+fn apply_brightness(keyboard: &Keyboard) {
+   if let Err(err) = keyboard.apply_brightness() {
+      eprintln!("{:?}", err);
+   }
+}
@@ -1,7 +1,15 @@
 use super::add_input_source_sec::AddInputSrcSec;
 use super::conf_input_source_sec::ConfigInputSrcSec;
-use iced::{button, pick_list, scrollable, slider};
+use super::global_shortcuts::{self, Binding};
+use super::input_method::{self, Ini};
+use super::key_repeat::KeyRepeat;
+use super::layout_preview::LayoutPreview;
+use super::modifier_keys::{CapsLock, ComposeKey, ModifierKeys};
+use super::xkb_registry::{self, InputSource};
+use crate::helpers::kbd_backlight::{BacklightSettings, KbdBacklight};
+use iced::{button, pick_list, scrollable, slider, text_input};
 use iced_custom_widget::Icons;
+use iced_native::keyboard::{KeyCode, ModifiersState};
 use smart_default::SmartDefault;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, SmartDefault)]
 pub enum TurnBacklightOff {
@@ -15,6 +23,21 @@ pub enum TurnBacklightOff {
 
 impl TurnBacklightOff {
     pub const ALL: [TurnBacklightOff; 5] = [TurnBacklightOff::_5s, TurnBacklightOff::_10s, TurnBacklightOff::_30s, TurnBacklightOff::_1m, TurnBacklightOff::_5m];
+
+    pub fn secs(&self) -> u64 {
+        match self {
+            TurnBacklightOff::_5s => 5,
+            TurnBacklightOff::_10s => 10,
+            TurnBacklightOff::_30s => 30,
+            TurnBacklightOff::_1m => 60,
+            TurnBacklightOff::_5m => 300,
+        }
+    }
+
+    /// The longest duration that is not longer than the seconds.
+    pub fn from_secs(secs: u64) -> Self {
+        Self::ALL.iter().rev().find(|duration| duration.secs() <= secs).copied().unwrap_or_default()
+    }
 }
 
 impl std::fmt::Display for TurnBacklightOff {
@@ -39,89 +62,216 @@ pub struct Keyboard {
     pub key_repeat_val: u8,
     pub delay_repeat_state: slider::State,
     pub delay_repeat_val: u8,
-    pub adjust_brightness_low_light: bool,
+    pub test_state: text_input::State,
+    pub test_val: String,
     pub turn_backlight_off: bool,
     pub turn_backlight_off_after_state: pick_list::State<TurnBacklightOff>,
     pub turn_backlight_off_after_val: TurnBacklightOff,
+    /// `None` when the keyboard has no backlight.
+    pub kbd_backlight: Option<KbdBacklight>,
+    pub brightness_state: slider::State,
+    pub brightness_val: u8,
+    pub modifier_keys: ModifierKeys,
+    pub caps_lock_state: pick_list::State<CapsLock>,
+    pub compose_state: pick_list::State<ComposeKey>,
 }
 
 impl Keyboard {
     pub fn new() -> Self {
+        Self::with_settings(KeyRepeat::load(), BacklightSettings::load(), ModifierKeys::load())
+    }
+
+    pub fn with_settings(key_repeat: KeyRepeat, backlight: BacklightSettings, modifier_keys: ModifierKeys) -> Self {
+        let (key_repeat_val, delay_repeat_val) = key_repeat.to_sliders();
         Self {
-            key_repeat_val: 7,
-            delay_repeat_val: 3,
-            adjust_brightness_low_light: true,
-            turn_backlight_off: false,
+            key_repeat_val,
+            delay_repeat_val,
+            turn_backlight_off: backlight.turn_off,
+            turn_backlight_off_after_val: TurnBacklightOff::from_secs(backlight.turn_off_after),
+            kbd_backlight: KbdBacklight::find(),
+            brightness_val: backlight.brightness,
+            modifier_keys,
             ..Default::default()
         }
     }
+
+    pub fn key_repeat(&self) -> KeyRepeat {
+        KeyRepeat::from_sliders(self.key_repeat_val, self.delay_repeat_val)
+    }
+
+    pub fn backlight(&self) -> BacklightSettings {
+        BacklightSettings {
+            brightness: self.brightness_val,
+            turn_off: self.turn_backlight_off,
+            turn_off_after: self.turn_backlight_off_after_val.secs(),
+        }
+    }
+
+    /// Light the keyboard with the brightness of the slider.
+    pub fn apply_brightness(&self) -> Result<(), String> {
+        match &self.kbd_backlight {
+            Some(kbd_backlight) => kbd_backlight.set_brightness(kbd_backlight.from_percent(self.brightness_val)),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Shortcuts {
     pub shortcuts_tab: Vec<(Icons, &'static str, button::State)>,
-    pub shortcuts_tab_map: Vec<Vec<(bool, &'static str, &'static str)>>,
+    /// Shortcuts of each category of the left pane.
+    pub shortcuts_tab_map: Vec<Vec<(Binding, button::State)>>,
+    /// Custom shortcuts removed since they were saved.
+    pub removed: Vec<Binding>,
     pub left_pane_selected: usize,
     pub right_pane_selected: usize,
+    /// Shortcut of the selected category waiting for its keys.
+    pub capturing: Option<usize>,
+    /// Keys pressed for the selected shortcut and the position of the shortcut already using them.
+    pub conflict: Option<(String, (usize, usize))>,
     pub use_keyboard_nav: bool,
+    pub custom_name_state: text_input::State,
+    pub custom_name_val: String,
+    pub custom_cmd_state: text_input::State,
+    pub custom_cmd_val: String,
+    pub btn_add_custom_state: button::State,
+    pub btn_remove_custom_state: button::State,
+    pub btn_reassign_state: button::State,
+    pub btn_restore_state: button::State,
+    pub is_changed: bool,
     pub left_pane_scroll: scrollable::State,
     pub right_pane_scroll: scrollable::State,
 }
 
 impl Shortcuts {
     pub fn new() -> Self {
+        let categories = global_shortcuts::load();
         Self {
-            shortcuts_tab: vec![
-                (Icons::User, "Menu & Dock", button::State::new()),
-                (Icons::User, "Workspaces", button::State::new()),
-                (Icons::User, "Keyboard", button::State::new()),
-                (Icons::User, "Input Sources", button::State::new()),
-                (Icons::User, "Screenshots", button::State::new()),
-                (Icons::User, "Services", button::State::new()),
-                (Icons::User, "Spotlight", button::State::new()),
-            ],
-            shortcuts_tab_map: vec![
-                vec![(true, "Turn Dock Hiding On/Off", "shift+ctrl+D"), (false, "Show Menu", "")],
-                vec![
-                    (true, "Workspaces", "shift+UP"),
-                    (false, "Show Notification Center", ""),
-                    (true, "Turn Do Not Disturb On/Off", ""),
-                    (true, "Application Windows", "shift+DOWN"),
-                    (true, "Show Desktop", "F11"),
-                ],
-                vec![
-                    (true, "Change the way Tab moves focus", "shift+F7"),
-                    (true, "Turn keyboard access On/Off", "shift+F1"),
-                    (true, "Move focus to menu bar", "shift+F2"),
-                    (true, "Move focus to Dock", "shift+F3"),
-                    (true, "Move focus to Window toolbar", "shift+F4"),
-                    (true, "Move focus to next Window", "ctrl+`"),
-                    (true, "Move focus to window drawer", "ctrl+shift+`"),
-                    (true, "Move focus to status menus", "shift+F5"),
-                ],
-                vec![(false, "Select the previous source", "shift+Space"), (false, "Select next source in Input menu", "ctrl+shift+Space")],
-                vec![
-                    (true, "Workspaces", "shift+UP"),
-                    (false, "Show Notification Center", ""),
-                    (true, "Turn Do Not Disturb On/Off", ""),
-                    (true, "Application Windows", "shift+DOWN"),
-                    (true, "Show Desktop", "F11"),
-                ],
-                vec![
-                    (true, "Change the way Tab moves focus", "shift+F7"),
-                    (true, "Turn keyboard access On/Off", "shift+F1"),
-                    (true, "Move focus to menu bar", "shift+F2"),
-                    (true, "Move focus to Dock", "shift+F3"),
-                    (true, "Move focus to Window toolbar", "shift+F4"),
-                    (true, "Move focus to next Window", "ctrl+`"),
-                    (true, "Move focus to window drawer", "ctrl+shift+`"),
-                    (true, "Move focus to status menus", "shift+F5"),
-                ],
-                vec![(false, "Select the previous source", "shift+Space"), (false, "Select next source in Input menu", "ctrl+shift+Space")],
-            ],
+            shortcuts_tab: categories.iter().map(|(title, _)| (Icons::User, *title, button::State::new())).collect(),
+            shortcuts_tab_map: categories.into_iter().map(|(_, bindings)| bindings.into_iter().map(|binding| (binding, button::State::new())).collect()).collect(),
             ..Default::default()
         }
     }
+
+    pub fn is_custom(&self) -> bool {
+        self.shortcuts_tab.get(self.left_pane_selected).map_or(false, |(_, title, _)| *title == global_shortcuts::CUSTOM_CATEGORY)
+    }
+
+    fn binding_mut(&mut self, (category, idx): (usize, usize)) -> Option<&mut Binding> {
+        self.shortcuts_tab_map.get_mut(category).and_then(|bindings| bindings.get_mut(idx)).map(|(binding, _)| binding)
+    }
+
+    pub fn binding(&self, (category, idx): (usize, usize)) -> Option<&Binding> {
+        self.shortcuts_tab_map.get(category).and_then(|bindings| bindings.get(idx)).map(|(binding, _)| binding)
+    }
+
+    pub fn bindings(&self) -> Vec<&Binding> {
+        self.shortcuts_tab_map.iter().flatten().map(|(binding, _)| binding).collect()
+    }
+
+    pub fn set_enabled(&mut self, idx: usize, is_enabled: bool) {
+        self.right_pane_selected = idx;
+        if let Some(binding) = self.binding_mut((self.left_pane_selected, idx)) {
+            binding.is_enabled = is_enabled;
+            self.is_changed = true;
+        }
+    }
+
+    pub fn start_capture(&mut self, idx: usize) {
+        self.right_pane_selected = idx;
+        self.capturing = Some(idx);
+        self.conflict = None;
+    }
+
+    /// Take the keys pressed for the shortcut waiting for them. Escape alone cancels and Backspace alone turns the
+    /// shortcut off. Keys used by another shortcut are only taken once reassigned.
+    pub fn capture(&mut self, key_code: KeyCode, modifiers: ModifiersState) {
+        let target = match self.capturing {
+            Some(idx) => (self.left_pane_selected, idx),
+            None => return,
+        };
+        let is_alone = !(modifiers.shift || modifiers.control || modifiers.alt || modifiers.logo);
+        if is_alone && key_code == KeyCode::Escape {
+            self.capturing = None;
+            return;
+        }
+        if is_alone && key_code == KeyCode::Backspace {
+            self.capturing = None;
+            self.set_enabled(target.1, false);
+            return;
+        }
+        let keys = match global_shortcuts::key_sequence(key_code, modifiers) {
+            Some(keys) => keys,
+            None => return,
+        };
+        self.capturing = None;
+        let positions = self
+            .shortcuts_tab_map
+            .iter()
+            .enumerate()
+            .flat_map(|(category, bindings)| bindings.iter().enumerate().map(move |(idx, (binding, _))| ((category, idx), binding)));
+        match global_shortcuts::find_conflict(positions, &keys, target) {
+            Some(other) => self.conflict = Some((keys, other)),
+            None => self.assign(target, keys),
+        }
+    }
+
+    fn assign(&mut self, target: (usize, usize), keys: String) {
+        if let Some(binding) = self.binding_mut(target) {
+            binding.keys = keys;
+            binding.is_enabled = true;
+            self.is_changed = true;
+        }
+    }
+
+    /// Give the keys of the conflict to the selected shortcut, taking them from the other one.
+    pub fn reassign(&mut self) {
+        if let Some((keys, other)) = self.conflict.take() {
+            if let Some(binding) = self.binding_mut(other) {
+                binding.remove_keys(&keys);
+            }
+            self.assign((self.left_pane_selected, self.right_pane_selected), keys);
+        }
+    }
+
+    pub fn restore_defaults(&mut self) {
+        if let Some(bindings) = self.shortcuts_tab_map.get_mut(self.left_pane_selected) {
+            bindings.iter_mut().for_each(|(binding, _)| binding.restore_default());
+            self.conflict = None;
+            self.is_changed = true;
+        }
+    }
+
+    pub fn add_custom(&mut self) {
+        let custom = Binding::custom(self.custom_name_val.trim(), self.custom_cmd_val.trim(), &self.bindings());
+        if let Some(bindings) = self.shortcuts_tab_map.get_mut(self.left_pane_selected) {
+            bindings.push((custom, button::State::new()));
+            let idx = bindings.len() - 1;
+            self.start_capture(idx);
+            self.custom_name_val.clear();
+            self.custom_cmd_val.clear();
+            self.is_changed = true;
+        }
+    }
+
+    pub fn remove_custom(&mut self) {
+        let idx = self.right_pane_selected;
+        if let Some(bindings) = self.shortcuts_tab_map.get_mut(self.left_pane_selected).filter(|bindings| idx < bindings.len()) {
+            let (binding, _) = bindings.remove(idx);
+            self.removed.push(binding);
+            self.right_pane_selected = 0;
+            self.capturing = None;
+            self.conflict = None;
+            self.is_changed = true;
+        }
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        global_shortcuts::save(&self.bindings(), &self.removed)?;
+        self.removed.clear();
+        self.is_changed = false;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -131,8 +281,10 @@ pub struct InputSources {
     pub btn_up_state: button::State,
     pub btn_down_state: button::State,
     pub btn_config_state: button::State,
-    pub input_sources: Vec<(char, String, button::State)>,
+    /// Sources of the session, the first one being the default.
+    pub input_sources: Vec<(InputSource, button::State)>,
     pub input_sources_selected: Option<usize>,
+    pub preview: Option<LayoutPreview>,
     pub show_input_menu: bool,
     pub auto_switch: bool,
     // add input source
@@ -147,15 +299,25 @@ pub struct InputSources {
 
 impl InputSources {
     pub fn new() -> Self {
+        let registry = xkb_registry::load_registry();
+        let engines = input_method::engines();
+        let mut input_sources = input_method::load_profile(&registry, &engines);
+        if input_sources.is_empty() {
+            input_sources = xkb_registry::current(&registry);
+        }
         Self {
-            input_sources: vec![('\u{f57e}', "Khmer".to_string(), button::State::new()), ('\u{f0ac}', "English".to_string(), button::State::new())],
-            add_input_source_sec: AddInputSrcSec::new(),
+            input_sources: input_sources.into_iter().map(|src| (src, button::State::new())).collect(),
+            add_input_source_sec: AddInputSrcSec::new(registry.into_iter().chain(engines).collect()),
             config_input_source_sec: ConfigInputSrcSec::new(),
             show_input_menu: true,
             auto_switch: false,
             ..Default::default()
         }
     }
+
+    pub fn sources(&self) -> Vec<InputSource> {
+        self.input_sources.iter().map(|(src, _)| src.clone()).collect()
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -195,9 +357,11 @@ pub struct Behavior {
 impl GlobalOptions {
     pub const hotkey_opts: [&'static str; 6] = ["Control+Left Shift", "Control+Right Shift", "Super+Space", "Shift+Super+Space", "Shift+Tab", "Control+Alt+Space"];
     pub const share_inp_state_opt: [&'static str; 3] = ["All", "Application", "No"];
+    /// Values of `ShareInputState` in the fcitx5 config for the share input state options.
+    const share_inp_state_vals: [&'static str; 3] = ["All", "Program", "No"];
 
     pub fn new() -> Self {
-        Self {
+        let mut global_opts = Self {
             hotkey_sec: HotKey {
                 toggle_inp_src_val: Some(Self::hotkey_opts[0].to_owned()),
                 show_press_toggle_repeat: true,
@@ -215,6 +379,66 @@ impl GlobalOptions {
                 change_focus_show_inp_src_info: false,
                 ..Behavior::default()
             },
+        };
+        global_opts.load(&input_method::read_ini(input_method::CONFIG));
+        global_opts
+    }
+
+    /// Hotkey values with the section of the fcitx5 config they are saved in.
+    fn hotkeys_mut(&mut self) -> Vec<(&'static str, &mut Option<String>)> {
+        let hotkey = &mut self.hotkey_sec;
+        vec![
+            ("Hotkey/TriggerKeys", &mut hotkey.toggle_inp_src_val),
+            ("Hotkey/AltTriggerKeys", &mut hotkey.temp_switch_first_n_cur_inp_src_val),
+            ("Hotkey/EnumerateForwardKeys", &mut hotkey.switch_inp_src_fw_val),
+            ("Hotkey/EnumerateBackwardKeys", &mut hotkey.switch_inp_src_bw_val),
+            ("Hotkey/ActivateKeys", &mut hotkey.act_inp_src_val),
+            ("Hotkey/DeactivateKeys", &mut hotkey.deact_inp_src_val),
+        ]
+    }
+
+    /// Checkboxes with their section and key in the fcitx5 config.
+    fn flags_mut(&mut self) -> Vec<(&'static str, &'static str, &mut bool)> {
+        let (hotkey, behavior) = (&mut self.hotkey_sec, &mut self.behavior_sec);
+        vec![
+            ("Hotkey", "EnumerateWithTriggerKeys", &mut hotkey.show_press_toggle_repeat),
+            ("Hotkey", "EnumerateSkipFirst", &mut hotkey.skip_first_inp_src_switch),
+            ("Behavior", "ActiveByDefault", &mut behavior.act_by_def),
+            ("Behavior", "ShowInputMethodInformation", &mut behavior.switch_show_inp_src_info),
+            ("Behavior", "showInputMethodInformationWhenFocusIn", &mut behavior.change_focus_show_inp_src_info),
+        ]
+    }
+
+    /// Take the options set in the fcitx5 config, keeping the defaults for the others.
+    fn load(&mut self, config: &Ini) {
+        for (section, val) in self.hotkeys_mut() {
+            if let Some(key) = config.get(section, "0") {
+                *val = Some(input_method::key_label(key));
+            }
+        }
+        for (section, key, flag) in self.flags_mut() {
+            if let Some(is_set) = config.get_flag(section, key) {
+                *flag = is_set;
+            }
+        }
+        if let Some(idx) = config.get("Behavior", "ShareInputState").and_then(|val| Self::share_inp_state_vals.iter().position(|share| *share == val)) {
+            self.behavior_sec.share_inp_state_val = Some(Self::share_inp_state_opt[idx].to_owned());
+        }
+    }
+
+    /// Write the options to the fcitx5 config, keeping its other settings.
+    pub fn save(&mut self) -> Result<(), String> {
+        let mut config = input_method::read_ini(input_method::CONFIG);
+        for (section, val) in self.hotkeys_mut() {
+            let keys: Vec<String> = val.iter().map(|label| input_method::fcitx_key(label)).collect();
+            config.set_list(section, &keys);
+        }
+        for (section, key, flag) in self.flags_mut() {
+            config.set_flag(section, key, *flag);
+        }
+        if let Some(idx) = self.behavior_sec.share_inp_state_val.as_ref().and_then(|val| Self::share_inp_state_opt.iter().position(|share| *share == val.as_str())) {
+            config.set("Behavior", "ShareInputState", Self::share_inp_state_vals[idx]);
         }
+        input_method::write_ini(input_method::CONFIG, &config)
     }
 }
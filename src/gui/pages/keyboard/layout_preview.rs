@@ -0,0 +1,200 @@
+use super::xkb_registry::InputSource;
+use crate::gui::styles::CustomContainer;
+use iced::{Column, Container, Element, Length, Row, Space, Text};
+use std::collections::HashMap;
+use std::process::Command;
+
+/// Keys of the main block of a PC keyboard, row by row, with the indent of each row in the drawing.
+const ROWS: [(u16, &[&str]); 4] = [
+    (0, &["TLDE", "AE01", "AE02", "AE03", "AE04", "AE05", "AE06", "AE07", "AE08", "AE09", "AE10", "AE11", "AE12"]),
+    (20, &["AD01", "AD02", "AD03", "AD04", "AD05", "AD06", "AD07", "AD08", "AD09", "AD10", "AD11", "AD12", "BKSL"]),
+    (30, &["AC01", "AC02", "AC03", "AC04", "AC05", "AC06", "AC07", "AC08", "AC09", "AC10", "AC11"]),
+    (45, &["AB01", "AB02", "AB03", "AB04", "AB05", "AB06", "AB07", "AB08", "AB09", "AB10"]),
+];
+/// The keysyms of `X11/keysymdef.h` that give a Unicode character in their comments, shipped so the header isn't needed at runtime.
+const KEYSYMDEF: &str = include_str!("../../../../assets/keysymdef.h");
+const KEY_SIZE: u16 = 36;
+
+thread_local! {
+    /// Parsed on the first preview only.
+    static KEYSYM_CHARS: HashMap<String, char> = parse_keysymdef(KEYSYMDEF);
+}
+
+/// The characters typed by a key, without and with Shift.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyCap {
+    pub base: String,
+    pub shifted: String,
+}
+
+/// Drawing of the main keys of a layout.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutPreview {
+    rows: Vec<(u16, Vec<KeyCap>)>,
+}
+
+/// Keysyms of the first group of each key of the `xkb_symbols` section of a compiled keymap.
+pub fn parse_symbols(keymap: &str) -> HashMap<String, Vec<String>> {
+    let mut symbols = HashMap::new();
+    let section = match keymap.find("xkb_symbols") {
+        Some(start) => &keymap[start..],
+        None => return symbols,
+    };
+    for block in section.split("key <").skip(1) {
+        let name = match block.find('>') {
+            Some(end) => &block[..end],
+            None => continue,
+        };
+        let body = block.split("};").next().unwrap_or_default();
+        let body = body.find("symbols[Group1]").map(|start| &body[start + "symbols[Group1]".len()..]).unwrap_or(body);
+        let group = body.find('[').and_then(|start| body[start + 1..].find(']').map(|end| &body[start + 1..start + 1 + end]));
+        if let Some(group) = group {
+            symbols.insert(name.to_string(), group.split(',').map(|sym| sym.trim().to_string()).collect());
+        }
+    }
+    symbols
+}
+
+/// Characters of the named keysyms, read from the `/* U+0430 CYRILLIC SMALL LETTER A */` comments.
+pub fn parse_keysymdef(content: &str) -> HashMap<String, char> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.strip_prefix("#define XK_")?.split_whitespace();
+            let name = words.next()?;
+            let code = line.split("/* U+").nth(1)?.split_whitespace().next()?;
+            let ch = u32::from_str_radix(code, 16).ok().and_then(std::char::from_u32)?;
+            Some((name.to_string(), ch))
+        })
+        .collect()
+}
+
+/// Text shown on a key for a keysym. Keys without a printable character, like dead keys, are left blank.
+pub fn keysym_label(keysym: &str, chars: &HashMap<String, char>) -> String {
+    if keysym.chars().count() == 1 {
+        return keysym.to_string();
+    }
+    let unicode = keysym.strip_prefix('U').filter(|code| code.len() >= 4).and_then(|code| u32::from_str_radix(code, 16).ok()).and_then(std::char::from_u32);
+    match unicode.or_else(|| chars.get(keysym).copied()) {
+        Some(ch) if !ch.is_control() => ch.to_string(),
+        _ => String::new(),
+    }
+}
+
+impl LayoutPreview {
+    pub fn from_keymap(keymap: &str, chars: &HashMap<String, char>) -> Self {
+        let symbols = parse_symbols(keymap);
+        let label = |syms: Option<&Vec<String>>, level: usize| syms.and_then(|syms| syms.get(level)).map(|sym| keysym_label(sym, chars)).unwrap_or_default();
+        Self {
+            rows: ROWS
+                .iter()
+                .map(|(indent, keys)| {
+                    let caps = keys
+                        .iter()
+                        .map(|key| {
+                            let syms = symbols.get(*key);
+                            let (base, shifted) = (label(syms, 0), label(syms, 1));
+                            // Letters only show their capital, like on the printed keys.
+                            if base.to_uppercase() == shifted {
+                                KeyCap { base: String::new(), shifted }
+                            } else {
+                                KeyCap { base, shifted }
+                            }
+                        })
+                        .collect();
+                    (*indent, caps)
+                })
+                .collect(),
+        }
+    }
+
+    /// Compile the keymap of the source with `xkbcli`, without changing the one of the session.
+    pub fn load(source: &InputSource) -> Result<Self, String> {
+        if source.engine.is_some() {
+            return Err(String::from("Input methods have no layout of their own"));
+        }
+        let mut cmd = Command::new("xkbcli");
+        cmd.args(&["compile-keymap", "--layout", &source.layout]);
+        if !source.variant.is_empty() {
+            cmd.args(&["--variant", &source.variant]);
+        }
+        let output = cmd.output().map_err(|e| format!("Failed to run xkbcli: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("xkbcli: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(KEYSYM_CHARS.with(|chars| Self::from_keymap(&String::from_utf8_lossy(&output.stdout), chars)))
+    }
+
+    pub fn view<'a, M: 'a>(&self) -> Element<'a, M> {
+        self.rows
+            .iter()
+            .fold(Column::new().spacing(4).padding(10), |col, (indent, caps)| {
+                let row = caps.iter().fold(Row::new().spacing(4).push(Space::with_width(Length::Units(*indent))), |row, cap| {
+                    let key = Column::new().push(Text::new(cap.shifted.as_str()).size(14)).push(Text::new(cap.base.as_str()).size(14));
+                    row.push(Container::new(key).width(Length::Units(KEY_SIZE)).height(Length::Units(KEY_SIZE)).padding(3).style(CustomContainer::Segment))
+                });
+                col.push(row)
+            })
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYMAP: &str = r#"xkb_keymap {
+xkb_keycodes "(unnamed)" {
+	<AD01>               = 24;
+};
+xkb_symbols "(unnamed)" {
+	name[Group1]="Russian";
+
+	key <TLDE>               {	[   Cyrillic_io,   Cyrillic_IO ] };
+	key <AE01>               {	[             1,        exclam ] };
+	key <AD01>               {
+		type= "ALPHABETIC",
+		symbols[Group1]= [ Cyrillic_shorti, Cyrillic_SHORTI ]
+	};
+	key <AC10>               {	[    dead_acute,     U00E9 ] };
+};
+};"#;
+
+    fn cap(base: &str, shifted: &str) -> KeyCap {
+        KeyCap {
+            base: base.to_string(),
+            shifted: shifted.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_symbols() {
+        let symbols = parse_symbols(KEYMAP);
+        assert_eq!(symbols.len(), 4);
+        assert_eq!(symbols["AD01"], vec!["Cyrillic_shorti", "Cyrillic_SHORTI"]);
+        assert_eq!(symbols["AE01"], vec!["1", "exclam"]);
+    }
+
+    #[test]
+    fn test_keysym_label() {
+        let chars =
+            parse_keysymdef("#define XK_exclam                        0x0021  /* U+0021 EXCLAMATION MARK */\n#define XK_Cyrillic_io                   0x06a3  /* U+0451 CYRILLIC SMALL LETTER IO */\n#define XK_dead_acute                    0xfe51\n");
+        assert_eq!(chars.len(), 2);
+        assert_eq!(keysym_label("q", &chars), "q");
+        assert_eq!(keysym_label("exclam", &chars), "!");
+        assert_eq!(keysym_label("U00E9", &chars), "é");
+        assert_eq!(keysym_label("dead_acute", &chars), "");
+        assert_eq!(keysym_label("NoSymbol", &chars), "");
+    }
+
+    #[test]
+    fn test_from_keymap() {
+        let chars = parse_keysymdef("#define XK_Cyrillic_io 0x06a3 /* U+0451 CYRILLIC SMALL LETTER IO */\n#define XK_Cyrillic_IO 0x06b3 /* U+0401 CYRILLIC CAPITAL LETTER IO */\n#define XK_exclam 0x0021 /* U+0021 EXCLAMATION MARK */\n");
+        let preview = LayoutPreview::from_keymap(KEYMAP, &chars);
+        assert_eq!(preview.rows.len(), 4);
+        let first_row = &preview.rows[0].1;
+        assert_eq!(first_row[0], cap("", "Ё"));
+        assert_eq!(first_row[1], cap("1", "!"));
+        assert_eq!(first_row[2], KeyCap::default());
+    }
+}
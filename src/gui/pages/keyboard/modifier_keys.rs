@@ -0,0 +1,233 @@
+use crate::helpers::kconfig::{read_key, write_key};
+use std::process::Command;
+
+/// KDE applies the XKB options of this file when the session starts.
+const KXKBRC: &str = "kxkbrc";
+/// KDE sets the Num Lock of this file when the session starts.
+const KCMINPUTRC: &str = "kcminputrc";
+/// Values of `NumLock` of kcminputrc: turn it on, or leave it as the firmware set it.
+const NUMLOCK_ON: &str = "0";
+const NUMLOCK_UNCHANGED: &str = "2";
+const SWAP_ALT_WIN: &str = "altwin:swap_alt_win";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapsLock {
+    CapsLock,
+    Control,
+    SwapControl,
+    Escape,
+    Backspace,
+    Disabled,
+}
+
+impl Default for CapsLock {
+    fn default() -> Self {
+        CapsLock::CapsLock
+    }
+}
+
+impl CapsLock {
+    pub const ALL: [CapsLock; 6] = [CapsLock::CapsLock, CapsLock::Control, CapsLock::SwapControl, CapsLock::Escape, CapsLock::Backspace, CapsLock::Disabled];
+
+    pub fn option(&self) -> Option<&'static str> {
+        match self {
+            CapsLock::CapsLock => None,
+            CapsLock::Control => Some("ctrl:nocaps"),
+            CapsLock::SwapControl => Some("ctrl:swapcaps"),
+            CapsLock::Escape => Some("caps:escape"),
+            CapsLock::Backspace => Some("caps:backspace"),
+            CapsLock::Disabled => Some("caps:none"),
+        }
+    }
+}
+
+impl std::fmt::Display for CapsLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CapsLock::CapsLock => "Caps Lock",
+                CapsLock::Control => "Control",
+                CapsLock::SwapControl => "Swap with Control",
+                CapsLock::Escape => "Escape",
+                CapsLock::Backspace => "Backspace",
+                CapsLock::Disabled => "No Action",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComposeKey {
+    None,
+    RightAlt,
+    RightSuper,
+    RightControl,
+    Menu,
+    ScrollLock,
+}
+
+impl Default for ComposeKey {
+    fn default() -> Self {
+        ComposeKey::None
+    }
+}
+
+impl ComposeKey {
+    pub const ALL: [ComposeKey; 6] = [ComposeKey::None, ComposeKey::RightAlt, ComposeKey::RightSuper, ComposeKey::RightControl, ComposeKey::Menu, ComposeKey::ScrollLock];
+
+    pub fn option(&self) -> Option<&'static str> {
+        match self {
+            ComposeKey::None => None,
+            ComposeKey::RightAlt => Some("compose:ralt"),
+            ComposeKey::RightSuper => Some("compose:rwin"),
+            ComposeKey::RightControl => Some("compose:rctrl"),
+            ComposeKey::Menu => Some("compose:menu"),
+            ComposeKey::ScrollLock => Some("compose:sclk"),
+        }
+    }
+}
+
+impl std::fmt::Display for ComposeKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ComposeKey::None => "None",
+                ComposeKey::RightAlt => "Right Alt",
+                ComposeKey::RightSuper => "Right Super",
+                ComposeKey::RightControl => "Right Control",
+                ComposeKey::Menu => "Menu",
+                ComposeKey::ScrollLock => "Scroll Lock",
+            }
+        )
+    }
+}
+
+/// Behavior of the modifier keys, set with XKB options next to the ones of other tools, which are kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModifierKeys {
+    pub caps_lock: CapsLock,
+    pub swap_alt_super: bool,
+    pub compose: ComposeKey,
+    pub numlock_on_boot: bool,
+}
+
+/// Whether the option is one of those set by the Modifier Keys section.
+fn is_managed(option: &str) -> bool {
+    option == SWAP_ALT_WIN || CapsLock::ALL.iter().any(|caps| caps.option() == Some(option)) || ComposeKey::ALL.iter().any(|compose| compose.option() == Some(option))
+}
+
+/// XKB options of the `options:` line of `setxkbmap -query`.
+pub fn parse_options(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("options:"))
+        .map(|options| options.trim().split(',').filter(|option| !option.is_empty()).map(ToString::to_string).collect())
+        .unwrap_or_default()
+}
+
+impl ModifierKeys {
+    /// Settings saved in the KDE config, or those of the session when there are none.
+    pub fn load() -> Self {
+        let options = match read_key(KXKBRC, "Layout", "Options") {
+            Some(options) => options.split(',').map(ToString::to_string).collect(),
+            None => {
+                let output = Command::new("setxkbmap").arg("-query").output().map(|output| String::from_utf8_lossy(&output.stdout).to_string()).unwrap_or_default();
+                parse_options(&output)
+            }
+        };
+        Self {
+            numlock_on_boot: read_key(KCMINPUTRC, "Keyboard", "NumLock").as_deref() == Some(NUMLOCK_ON),
+            ..Self::from_options(&options)
+        }
+    }
+
+    pub fn from_options(options: &[String]) -> Self {
+        let has = |option: Option<&str>| option.map_or(false, |option| options.iter().any(|opt| opt == option));
+        Self {
+            caps_lock: CapsLock::ALL.iter().copied().find(|caps| has(caps.option())).unwrap_or_default(),
+            swap_alt_super: has(Some(SWAP_ALT_WIN)),
+            compose: ComposeKey::ALL.iter().copied().find(|compose| has(compose.option())).unwrap_or_default(),
+            numlock_on_boot: false,
+        }
+    }
+
+    /// The options of the session with those of the settings in place of the managed ones.
+    pub fn merge_options(&self, current: &[String]) -> Vec<String> {
+        let mut options: Vec<String> = current.iter().filter(|option| !is_managed(option)).cloned().collect();
+        let swap_alt_super = if self.swap_alt_super { Some(SWAP_ALT_WIN) } else { None };
+        options.extend(self.caps_lock.option().into_iter().chain(swap_alt_super).chain(self.compose.option()).map(ToString::to_string));
+        options
+    }
+
+    /// Options of the running session, with the settings applied.
+    fn session_options(&self) -> Result<Vec<String>, String> {
+        let output = Command::new("setxkbmap").arg("-query").output().map_err(|e| format!("Failed to run setxkbmap: {}", e))?;
+        Ok(self.merge_options(&parse_options(&String::from_utf8_lossy(&output.stdout))))
+    }
+
+    /// Apply the XKB options to the running session.
+    pub fn apply(&self) -> Result<(), String> {
+        let options = self.session_options()?;
+        // An empty option first clears the old ones, which would otherwise be kept.
+        let output = Command::new("setxkbmap").args(&["-option", "", "-option", &options.join(",")]).output().map_err(|e| format!("Failed to run setxkbmap: {}", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!("setxkbmap: {}", String::from_utf8_lossy(&output.stderr).trim()))
+        }
+    }
+
+    /// Save the settings, so that they are applied again at the next login.
+    pub fn save(&self) -> Result<(), String> {
+        let options = self.session_options()?;
+        write_key(KXKBRC, "Layout", "ResetOldOptions", Some("true"))?;
+        write_key(KXKBRC, "Layout", "Options", Some(&options.join(",")))?;
+        write_key(KCMINPUTRC, "Keyboard", "NumLock", Some(if self.numlock_on_boot { NUMLOCK_ON } else { NUMLOCK_UNCHANGED }))?;
+        // Makes KWin load the options again.
+        match Command::new("dbus-send").args(&["--session", "--type=signal", "/Layouts", "org.kde.keyboard.reloadConfig"]).status() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("Failed to run dbus-send: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(list: &[&str]) -> Vec<String> {
+        list.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let output = "rules:      evdev\nmodel:      pc105\nlayout:     us,kh\noptions:    grp:alt_shift_toggle,ctrl:nocaps\n";
+        assert_eq!(parse_options(output), options(&["grp:alt_shift_toggle", "ctrl:nocaps"]));
+        assert!(parse_options("layout:     us\n").is_empty());
+    }
+
+    #[test]
+    fn test_from_options() {
+        let keys = ModifierKeys::from_options(&options(&["grp:alt_shift_toggle", "ctrl:swapcaps", "compose:menu"]));
+        assert_eq!(keys.caps_lock, CapsLock::SwapControl);
+        assert_eq!(keys.compose, ComposeKey::Menu);
+        assert!(!keys.swap_alt_super);
+        assert_eq!(ModifierKeys::from_options(&[]), ModifierKeys::default());
+    }
+
+    #[test]
+    fn test_merge_options() {
+        let keys = ModifierKeys {
+            caps_lock: CapsLock::Escape,
+            swap_alt_super: true,
+            ..ModifierKeys::default()
+        };
+        let current = options(&["grp:alt_shift_toggle", "ctrl:nocaps", "compose:ralt", "ctrl:swap_lalt_lctl"]);
+        assert_eq!(keys.merge_options(&current), options(&["grp:alt_shift_toggle", "ctrl:swap_lalt_lctl", "caps:escape", "altwin:swap_alt_win"]));
+        assert_eq!(ModifierKeys::default().merge_options(&current), options(&["grp:alt_shift_toggle", "ctrl:swap_lalt_lctl"]));
+    }
+}
@@ -0,0 +1,292 @@
+use crate::helpers::kconfig::write_key;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::Command;
+
+/// Registry of the layouts, where xkeyboard-config installs it.
+const REGISTRIES: [&str; 2] = ["/usr/share/X11/xkb/rules/evdev.xml", "/usr/share/xkeyboard-config-2/rules/evdev.xml"];
+/// Names of the languages, from the iso-codes package.
+const ISO_639: &str = "/usr/share/iso-codes/json/iso_639-2.json";
+/// KDE keeps the layouts of the session in this file, read by KWin on Wayland too.
+const KXKBRC: &str = "kxkbrc";
+const FALLBACK_LAYOUT: &str = "us";
+
+/// A keyboard layout or one of its variants.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputSource {
+    pub layout: String,
+    /// Empty for the layout itself.
+    pub variant: String,
+    /// Label of the input menu, like `en`.
+    pub short: String,
+    pub description: String,
+    /// ISO 639 codes of the languages typed with it.
+    pub languages: Vec<String>,
+    /// Input method of fcitx5 typing through the first layout, for sources which are not layouts.
+    pub engine: Option<String>,
+}
+
+impl InputSource {
+    pub fn new(layout: &str, variant: &str) -> Self {
+        Self {
+            layout: layout.to_string(),
+            variant: variant.to_string(),
+            short: layout.to_string(),
+            description: if variant.is_empty() { layout.to_string() } else { format!("{} ({})", layout, variant) },
+            languages: Vec::new(),
+            engine: None,
+        }
+    }
+
+    /// Whether the description or the names contain the lowercase `query`.
+    pub fn matches(&self, query: &str) -> bool {
+        [&self.description, &self.layout, &self.variant].iter().any(|val| val.to_lowercase().contains(query))
+    }
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn child_text(node: Option<roxmltree::Node>, name: &str) -> String {
+    node.and_then(|node| child(node, name)).and_then(|child| child.text()).unwrap_or_default().trim().to_string()
+}
+
+fn languages(config: Option<roxmltree::Node>) -> Vec<String> {
+    config
+        .and_then(|config| child(config, "languageList"))
+        .map(|list| list.children().filter(|node| node.has_tag_name("iso639Id")).filter_map(|node| node.text()).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Read the layouts and their variants from the XKB registry. Variants without their own languages or label take
+/// those of their layout.
+pub fn parse_registry(xml: &str) -> Result<Vec<InputSource>, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let mut sources = Vec::new();
+    for layout in doc.descendants().filter(|node| node.has_tag_name("layout")) {
+        let config = child(layout, "configItem");
+        let base = InputSource {
+            layout: child_text(config, "name"),
+            variant: String::new(),
+            short: child_text(config, "shortDescription"),
+            description: child_text(config, "description"),
+            languages: languages(config),
+            engine: None,
+        };
+        if base.layout.is_empty() {
+            continue;
+        }
+        let variants: Vec<InputSource> = child(layout, "variantList")
+            .map(|list| {
+                list.children()
+                    .filter(|node| node.has_tag_name("variant"))
+                    .map(|variant| {
+                        let config = child(variant, "configItem");
+                        let (short, languages) = (child_text(config, "shortDescription"), languages(config));
+                        InputSource {
+                            layout: base.layout.clone(),
+                            variant: child_text(config, "name"),
+                            short: if short.is_empty() { base.short.clone() } else { short },
+                            description: child_text(config, "description"),
+                            languages: if languages.is_empty() { base.languages.clone() } else { languages },
+                            engine: None,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        sources.push(base);
+        sources.extend(variants);
+    }
+    Ok(sources)
+}
+
+/// All the layouts and variants known to the system.
+pub fn load_registry() -> Vec<InputSource> {
+    REGISTRIES.iter().find_map(|path| std::fs::read_to_string(path).ok()).and_then(|xml| parse_registry(&xml).ok()).unwrap_or_default()
+}
+
+#[derive(Deserialize)]
+struct IsoCodes {
+    #[serde(rename = "639-2")]
+    languages: Vec<IsoLanguage>,
+}
+
+#[derive(Deserialize)]
+struct IsoLanguage {
+    alpha_2: Option<String>,
+    alpha_3: String,
+    bibliographic: Option<String>,
+    name: String,
+}
+
+/// Names of the languages by their three letter codes, including the bibliographic ones like `ger` used by XKB, and
+/// by their two letter codes used by input methods.
+pub fn parse_language_names(json: &str) -> HashMap<String, String> {
+    let codes: IsoCodes = match serde_json::from_str(json) {
+        Ok(codes) => codes,
+        Err(_) => return HashMap::new(),
+    };
+    let mut names = HashMap::new();
+    for lang in codes.languages {
+        for code in lang.alpha_2.into_iter().chain(lang.bibliographic) {
+            names.insert(code, lang.name.clone());
+        }
+        names.insert(lang.alpha_3, lang.name);
+    }
+    names
+}
+
+pub fn language_names() -> HashMap<String, String> {
+    std::fs::read_to_string(ISO_639).map(|json| parse_language_names(&json)).unwrap_or_default()
+}
+
+/// The sources with the name of their first language, sorted by language and then by description. Sources without
+/// language are named after the start of their description, like `English` for `English (US)`.
+pub fn group_by_language(sources: Vec<InputSource>, names: &HashMap<String, String>) -> Vec<(String, InputSource)> {
+    let mut grouped: Vec<(String, InputSource)> = sources
+        .into_iter()
+        .map(|source| {
+            let lang = source.languages.first().and_then(|code| names.get(code)).cloned();
+            let lang = lang.unwrap_or_else(|| source.description.split(" (").next().unwrap_or_default().to_string());
+            (lang, source)
+        })
+        .collect();
+    grouped.sort_by(|(lang_a, src_a), (lang_b, src_b)| lang_a.cmp(lang_b).then_with(|| src_a.variant.is_empty().cmp(&src_b.variant.is_empty()).reverse()).then_with(|| src_a.description.cmp(&src_b.description)));
+    grouped
+}
+
+/// Layouts and variants of the output of `setxkbmap -query`.
+pub fn parse_query(output: &str) -> Vec<(String, String)> {
+    let field = |name: &str| output.lines().find_map(|line| line.strip_prefix(name)).map(|val| val.trim().split(',').map(String::from).collect::<Vec<String>>()).unwrap_or_default();
+    let variants = field("variant:");
+    field("layout:")
+        .into_iter()
+        .enumerate()
+        .filter(|(_, layout)| !layout.is_empty())
+        .map(|(idx, layout)| (layout, variants.get(idx).cloned().unwrap_or_default()))
+        .collect()
+}
+
+/// The sources of the session, in order.
+pub fn current(registry: &[InputSource]) -> Vec<InputSource> {
+    let output = Command::new("setxkbmap").arg("-query").output().map(|output| String::from_utf8_lossy(&output.stdout).to_string()).unwrap_or_default();
+    let mut pairs = parse_query(&output);
+    if pairs.is_empty() {
+        pairs.push((String::from(FALLBACK_LAYOUT), String::new()));
+    }
+    pairs
+        .iter()
+        .map(|(layout, variant)| registry.iter().find(|src| src.layout == *layout && src.variant == *variant).cloned().unwrap_or_else(|| InputSource::new(layout, variant)))
+        .collect()
+}
+
+/// Arguments of `setxkbmap` for the layouts among the sources.
+pub fn setxkbmap_args(sources: &[InputSource]) -> Vec<String> {
+    let join = |field: fn(&InputSource) -> &str| sources.iter().filter(|src| src.engine.is_none()).map(field).collect::<Vec<&str>>().join(",");
+    vec![String::from("-layout"), join(|src| &src.layout), String::from("-variant"), join(|src| &src.variant)]
+}
+
+/// Switch the session to the layouts among the sources, the first one being the default, and save them for the next
+/// logins. Input methods are left to fcitx5.
+pub fn apply(sources: &[InputSource]) -> Result<(), String> {
+    let sources: Vec<InputSource> = sources.iter().filter(|src| src.engine.is_none()).cloned().collect();
+    if sources.is_empty() {
+        return Err(String::from("At least one keyboard layout is needed"));
+    }
+    let output = Command::new("setxkbmap").args(setxkbmap_args(&sources)).output().map_err(|e| format!("Failed to run setxkbmap: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("setxkbmap: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let list = |field: fn(&InputSource) -> &str| sources.iter().map(field).collect::<Vec<&str>>().join(",");
+    write_key(KXKBRC, "Layout", "Use", Some("true"))?;
+    write_key(KXKBRC, "Layout", "LayoutList", Some(&list(|src| &src.layout)))?;
+    write_key(KXKBRC, "Layout", "VariantList", Some(&list(|src| &src.variant)))?;
+    write_key(KXKBRC, "Layout", "DisplayNames", Some(&list(|src| &src.short)))?;
+    // Makes KWin load the layouts again.
+    match Command::new("dbus-send").args(&["--session", "--type=signal", "/Layouts", "org.kde.keyboard.reloadConfig"]).status() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Failed to run dbus-send: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGISTRY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xkbConfigRegistry version="1.1">
+  <layoutList>
+    <layout>
+      <configItem>
+        <name>us</name>
+        <shortDescription>en</shortDescription>
+        <description>English (US)</description>
+        <languageList><iso639Id>eng</iso639Id></languageList>
+      </configItem>
+      <variantList>
+        <variant>
+          <configItem>
+            <name>intl</name>
+            <description>English (US, intl., with dead keys)</description>
+          </configItem>
+        </variant>
+        <variant>
+          <configItem>
+            <name>haw</name>
+            <shortDescription>haw</shortDescription>
+            <description>Hawaiian</description>
+            <languageList><iso639Id>haw</iso639Id></languageList>
+          </configItem>
+        </variant>
+      </variantList>
+    </layout>
+    <layout>
+      <configItem>
+        <name>kh</name>
+        <shortDescription>km</shortDescription>
+        <description>Khmer (Cambodia)</description>
+        <languageList><iso639Id>khm</iso639Id></languageList>
+      </configItem>
+    </layout>
+  </layoutList>
+</xkbConfigRegistry>"#;
+
+    #[test]
+    fn test_parse_registry() {
+        let sources = parse_registry(REGISTRY).unwrap();
+        assert_eq!(sources.iter().map(|src| (src.layout.as_str(), src.variant.as_str())).collect::<Vec<_>>(), vec![("us", ""), ("us", "intl"), ("us", "haw"), ("kh", "")]);
+        assert_eq!(sources[1].short, "en");
+        assert_eq!(sources[1].languages, vec!["eng"]);
+        assert_eq!(sources[2].short, "haw");
+        assert_eq!(sources[2].languages, vec!["haw"]);
+    }
+
+    #[test]
+    fn test_group_by_language() {
+        let names = parse_language_names(r#"{"639-2": [{"alpha_3": "eng", "name": "English"}, {"alpha_2": "km", "alpha_3": "khm", "name": "Central Khmer"}, {"alpha_3": "deu", "bibliographic": "ger", "name": "German"}]}"#);
+        assert_eq!(names.get("ger").map(String::as_str), Some("German"));
+        assert_eq!(names.get("km").map(String::as_str), Some("Central Khmer"));
+        let grouped = group_by_language(parse_registry(REGISTRY).unwrap(), &names);
+        let langs: Vec<(&str, &str)> = grouped.iter().map(|(lang, src)| (lang.as_str(), src.variant.as_str())).collect();
+        assert_eq!(langs, vec![("Central Khmer", ""), ("English", ""), ("English", "intl"), ("Hawaiian", "haw")]);
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let output = "rules:      evdev\nmodel:      pc105\nlayout:     us,kh\nvariant:    intl,\noptions:    grp:alt_shift_toggle\n";
+        assert_eq!(parse_query(output), vec![(String::from("us"), String::from("intl")), (String::from("kh"), String::new())]);
+        assert_eq!(parse_query("layout:     us\n"), vec![(String::from("us"), String::new())]);
+    }
+
+    #[test]
+    fn test_setxkbmap_args() {
+        let pinyin = InputSource {
+            engine: Some(String::from("pinyin")),
+            ..InputSource::default()
+        };
+        let sources = vec![InputSource::new("kh", ""), pinyin, InputSource::new("us", "intl")];
+        assert_eq!(setxkbmap_args(&sources), vec!["-layout", "kh,us", "-variant", ",intl"]);
+    }
+}
@@ -1,8 +1,8 @@
 use super::lang_region_utils::*;
-use crate::gui::addon_widgets::{icon_btn, tabbar};
+use crate::gui::addon_widgets::{icon_btn, lock_btn, tabbar};
 use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomSelect, CustomTextInput, HOVERED};
-use crate::helpers::ROOT_PATH;
-use iced::{button, pick_list, Align, Button, Checkbox, Column, Container, Element, Length, PickList, Row, Scrollable, Space, Svg, Text, TextInput};
+use crate::helpers::{authority, Action, ROOT_PATH};
+use iced::{button, pick_list, Align, Button, Checkbox, Column, Command, Container, Element, Length, PickList, Row, Scrollable, Space, Svg, Text, TextInput};
 use iced_custom_widget::{Icon, IconBrand, IconBrands, Icons};
 use libkoompi::system_settings::locale::{LC_Keywords, LocaleManager, LS_MEASURE_UNITS};
 use num_format::{Buffer, CustomFormat, Grouping};
@@ -30,7 +30,10 @@ pub enum LangRegionMessage {
    AppLangChanged(String),
    DefaultsClicked,
    OKClicked,
+   LocaleWritten(Result<(), String>),
    ResetClicked,
+   LockClicked,
+   LockToggled(Result<(), String>),
    AddLangMsg(AddLangMessage),
 }
 
@@ -52,6 +55,7 @@ pub struct LangRegionPage {
    btn_defaults_state: button::State,
    btn_reset_state: button::State,
    btn_ok_state: button::State,
+   btn_lock_state: button::State,
    is_changed: bool,
 }
 
@@ -125,7 +129,7 @@ impl LangRegionPage {
       }
    }
 
-   pub fn update(&mut self, msg: LangRegionMessage) {
+   pub fn update(&mut self, msg: LangRegionMessage) -> Command<LangRegionMessage> {
       use LangRegionMessage::*;
       match msg {
          TabChanged(idx) => self.current_tab_idx = idx,
@@ -234,24 +238,31 @@ impl LangRegionPage {
             }
          }
          OKClicked => {
-            match self.locale_mn.write_conf() {
-               Ok(()) => {
-                  DialogBuilder::new()
-                     .message("These changes will take effect after the next login.")
-                     .title("Note")
-                     .style(DialogStyle::Info)
-                     .buttons(DialogButtons::Ok)
-                     .build()
-                     .show();
-               }
-               Err(err) => {
-                  let res = DialogBuilder::new().message(format!("{}", err).as_str()).title("Error").style(DialogStyle::Error).buttons(DialogButtons::Quit).build().show();
-                  if res == DialogSelection::Quit {
-                     std::process::exit(1);
-                  }
+            self.is_changed = false;
+            return Command::perform(authority::exec(Action::WriteLocale, self.locale_args()), LocaleWritten);
+         }
+         LocaleWritten(res) => match res {
+            Ok(()) => {
+               DialogBuilder::new()
+                  .message("These changes will take effect after the next login.")
+                  .title("Note")
+                  .style(DialogStyle::Info)
+                  .buttons(DialogButtons::Ok)
+                  .build()
+                  .show();
+            }
+            Err(err) => {
+               let res = DialogBuilder::new().message(format!("{}", err).as_str()).title("Error").style(DialogStyle::Error).buttons(DialogButtons::Quit).build().show();
+               if res == DialogSelection::Quit {
+                  std::process::exit(1);
                }
             }
-            self.is_changed = false;
+         },
+         LockClicked => return Command::perform(authority::toggle(), LockToggled),
+         LockToggled(res) => {
+            if let Err(err) = res {
+               eprintln!("{}", err);
+            }
          }
          ResetClicked | DefaultsClicked => {
             // match self.current_tab_idx {
@@ -288,6 +299,7 @@ impl LangRegionPage {
             }
          },
       }
+      Command::none()
    }
 
    pub fn view(&mut self) -> Element<LangRegionMessage> {
@@ -301,6 +313,7 @@ impl LangRegionPage {
          btn_defaults_state,
          btn_reset_state,
          btn_ok_state,
+         btn_lock_state,
          is_changed,
          // formats_tab,
       } = self;
@@ -616,13 +629,26 @@ impl LangRegionPage {
       let mut btn_reset = icon_btn(btn_reset_state, Icons::RegisteredTrademark, "Reset", None).style(CustomButton::Hovered);
       let mut btn_ok = icon_btn(btn_ok_state, Icons::CheckCircle, "OK", None).style(CustomButton::Primary);
       if *is_changed {
-         btn_ok = btn_ok.on_press(OKClicked);
          btn_reset = btn_reset.on_press(ResetClicked);
+         if authority::is_unlocked() {
+            btn_ok = btn_ok.on_press(OKClicked);
+         }
       }
+      let btn_lock = lock_btn(btn_lock_state, LockClicked);
 
-      let bottom_sec = Container::new(Row::new().padding(15).spacing(10).align_items(Align::Center).push(btn_defaults).push(btn_reset).push(Space::with_width(Length::Fill)).push(btn_ok))
-         .width(Length::Fill)
-         .align_x(Align::End);
+      let bottom_sec = Container::new(
+         Row::new()
+            .padding(15)
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(btn_lock)
+            .push(btn_defaults)
+            .push(btn_reset)
+            .push(Space::with_width(Length::Fill))
+            .push(btn_ok),
+      )
+      .width(Length::Fill)
+      .align_x(Align::End);
 
       // មាតិកា
       let content = Column::new()
@@ -637,6 +663,21 @@ impl LangRegionPage {
 }
 
 impl LangRegionPage {
+   /// The locale categories as `KEY=VALUE`, written to the system configuration by the helper.
+   fn locale_args(&self) -> Vec<String> {
+      let general_tab = &self.general_tab;
+      let mut args = vec![format!("LANGUAGE={}", general_tab.get_formatted_prefered_lang())];
+      let selected = [
+         ("LANG", &general_tab.selected_region),
+         ("LC_TIME", &general_tab.selected_time_format),
+         ("LC_NUMERIC", &general_tab.selected_num_format),
+         ("LC_MONETARY", &general_tab.selected_currency_format),
+         ("LC_MEASUREMENT", &general_tab.selected_measure_format),
+      ];
+      args.extend(selected.iter().filter_map(|(key, val)| val.as_ref().map(|val| format!("{}={}", key, val.key))));
+      args
+   }
+
    fn get_first_day(locale_mn: &LocaleManager) -> String {
       locale_mn.time_details().list_days().get((locale_mn.time_details().first_weekday - 1) as usize).unwrap_or(&String::from("")).clone()
    }
@@ -1,6 +1,8 @@
 mod common;
+mod diagnostics;
 mod netsettings;
 mod networkpage;
+mod proxy;
 mod wire;
 mod wireless;
 pub use networkpage::{NetMessage, NetworkPage};
@@ -0,0 +1,521 @@
+use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle, picklist::PickListStyle, rules::RuleStyle, textinput::InputStyle, ERROR, PRIMARY, SUCCESS};
+use iced::{
+    button,
+    canvas::{self, Canvas, Cursor, Frame, Geometry, Path, Stroke},
+    pick_list, scrollable, text_input, Align, Button, Color, Column, Container, Element, Length, PickList, Point, Rectangle, Row, Rule, Scrollable, Space, Text, TextInput,
+};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+const HISTORY_LEN: usize = 60;
+
+#[derive(Default, Debug, Clone)]
+pub struct Diagnostics {
+    interfaces: Vec<(IfaceStats, button::State)>,
+    selected_iface: Option<String>,
+    last_sample: Option<Instant>,
+    graph: ThroughputGraph,
+    tool: DiagTool,
+    pick_tool: pick_list::State<DiagTool>,
+    host: text_input::State,
+    host_val: String,
+    run_btn: button::State,
+    report: Arc<Mutex<DiagReport>>,
+    snapshot: DiagReport,
+    scroll: scrollable::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum DiagnosticsMsg {
+    Tick(Instant),
+    IfaceSelected(String),
+    ToolChanged(DiagTool),
+    HostChanged(String),
+    OnRun,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagTool {
+    Ping,
+    Lookup,
+    Route,
+    Traceroute,
+}
+impl DiagTool {
+    const ALL: [DiagTool; 4] = [DiagTool::Ping, DiagTool::Lookup, DiagTool::Route, DiagTool::Traceroute];
+}
+impl Default for DiagTool {
+    fn default() -> Self {
+        DiagTool::Ping
+    }
+}
+impl fmt::Display for DiagTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DiagTool::Ping => "Ping",
+                DiagTool::Lookup => "DNS Lookup",
+                DiagTool::Route => "Route",
+                DiagTool::Traceroute => "Traceroute",
+            }
+        )
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct DiagReport {
+    title: String,
+    running: bool,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    error: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct IfaceStats {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errs: u64,
+    pub rx_drop: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errs: u64,
+    pub tx_drop: u64,
+}
+
+impl IfaceStats {
+    fn error_rate(errs: u64, packets: u64) -> String {
+        if packets == 0 {
+            String::from("0.00%")
+        } else {
+            format!("{:.2}%", errs as f64 * 100.0 / packets as f64)
+        }
+    }
+}
+
+/// Parse the content of `/proc/net/dev` into per-interface counters.
+pub fn parse_proc_net_dev(content: &str) -> Vec<IfaceStats> {
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let idx = line.find(':')?;
+            let fields: Vec<u64> = line[idx + 1..].split_whitespace().filter_map(|field| field.parse().ok()).collect();
+            if fields.len() < 16 {
+                return None;
+            }
+            Some(IfaceStats {
+                name: line[..idx].trim().to_string(),
+                rx_bytes: fields[0],
+                rx_packets: fields[1],
+                rx_errs: fields[2],
+                rx_drop: fields[3],
+                tx_bytes: fields[8],
+                tx_packets: fields[9],
+                tx_errs: fields[10],
+                tx_drop: fields[11],
+            })
+        })
+        .collect()
+}
+
+fn read_proc_net_dev() -> Vec<IfaceStats> {
+    match std::fs::read_to_string("/proc/net/dev") {
+        Ok(content) => parse_proc_net_dev(&content),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            Vec::new()
+        }
+    }
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut val = bytes;
+    let mut unit = 0;
+    while val >= 1024.0 && unit < UNITS.len() - 1 {
+        val /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", val, UNITS[unit])
+}
+
+fn table_cell(text: &str, portion: u16) -> Text {
+    Text::new(text).size(14).width(Length::FillPortion(portion))
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        let interfaces: Vec<(IfaceStats, button::State)> = read_proc_net_dev().into_iter().map(|stats| (stats, button::State::new())).collect();
+        Self {
+            selected_iface: interfaces.iter().map(|(stats, _)| stats.name.clone()).find(|name| name != "lo"),
+            interfaces,
+            last_sample: Some(Instant::now()),
+            ..Self::default()
+        }
+    }
+
+    pub fn update(&mut self, msg: DiagnosticsMsg) {
+        match msg {
+            DiagnosticsMsg::Tick(now) => {
+                let current = read_proc_net_dev();
+                let elapsed = self.last_sample.map(|last| now.duration_since(last).as_secs_f32()).unwrap_or_default();
+                if let Some(name) = &self.selected_iface {
+                    let prev = self.interfaces.iter().find(|(stats, _)| &stats.name == name).map(|(stats, _)| stats.clone());
+                    let curr = current.iter().find(|stats| &stats.name == name);
+                    if let (Some(prev), Some(curr), true) = (prev, curr, elapsed > 0.0) {
+                        let rx = curr.rx_bytes.saturating_sub(prev.rx_bytes) as f32 / elapsed;
+                        let tx = curr.tx_bytes.saturating_sub(prev.tx_bytes) as f32 / elapsed;
+                        self.graph.push(rx, tx);
+                    }
+                }
+                self.interfaces = current.into_iter().map(|stats| (stats, button::State::new())).collect();
+                self.last_sample = Some(now);
+                if let Ok(report) = self.report.lock() {
+                    self.snapshot = report.clone();
+                }
+            }
+            DiagnosticsMsg::IfaceSelected(name) => {
+                self.selected_iface = Some(name);
+                self.graph = ThroughputGraph::default();
+            }
+            DiagnosticsMsg::ToolChanged(tool) => self.tool = tool,
+            DiagnosticsMsg::HostChanged(host) => self.host_val = host,
+            DiagnosticsMsg::OnRun => {
+                let host = self.host_val.trim().to_string();
+                if self.snapshot.running {
+                    return;
+                }
+                let is_valid = !host.is_empty() && !host.starts_with('-') && !host.contains(char::is_whitespace);
+                let tool = self.tool;
+                let report = Arc::clone(&self.report);
+                if let Ok(mut report) = report.lock() {
+                    *report = DiagReport {
+                        title: format!("{} {}", tool, host),
+                        running: is_valid,
+                        error: if is_valid { None } else { Some(format!("'{}' is not a valid host name or address", host)) },
+                        ..DiagReport::default()
+                    };
+                    self.snapshot = report.clone();
+                }
+                if !is_valid {
+                    return;
+                }
+                std::thread::spawn(move || {
+                    let result = tools::run(tool, &host);
+                    if let Ok(mut report) = report.lock() {
+                        report.running = false;
+                        match result {
+                            Ok((header, rows)) => {
+                                report.header = header;
+                                report.rows = rows;
+                            }
+                            Err(e) => report.error = Some(e),
+                        }
+                    }
+                });
+            }
+        }
+    }
+
+    pub fn view(&mut self) -> Element<DiagnosticsMsg> {
+        let Diagnostics {
+            interfaces,
+            selected_iface,
+            graph,
+            tool,
+            pick_tool,
+            host,
+            host_val,
+            run_btn,
+            snapshot,
+            scroll,
+            ..
+        } = self;
+
+        let header = Row::new()
+            .spacing(10)
+            .push(table_cell("Interface", 2))
+            .push(table_cell("Received", 2))
+            .push(table_cell("Sent", 2))
+            .push(table_cell("RX Errors", 2))
+            .push(table_cell("TX Errors", 2))
+            .push(table_cell("Dropped", 1));
+        let iface_table = interfaces.iter_mut().fold(Column::new().spacing(6).push(header).push(Rule::horizontal(4).style(RuleStyle {})), |col, (stats, state)| {
+            let is_selected = selected_iface.as_ref() == Some(&stats.name);
+            let row = Row::new()
+                .spacing(10)
+                .push(table_cell(&stats.name, 2))
+                .push(table_cell(&format_bytes(stats.rx_bytes as f64), 2))
+                .push(table_cell(&format_bytes(stats.tx_bytes as f64), 2))
+                .push(table_cell(&format!("{} ({})", stats.rx_errs, IfaceStats::error_rate(stats.rx_errs, stats.rx_packets)), 2))
+                .push(table_cell(&format!("{} ({})", stats.tx_errs, IfaceStats::error_rate(stats.tx_errs, stats.tx_packets)), 2))
+                .push(table_cell(&(stats.rx_drop + stats.tx_drop).to_string(), 1));
+            col.push(
+                Button::new(state, row)
+                    .width(Length::Fill)
+                    .style(if is_selected { ButtonStyle::CircleRadius(215, 219, 221, 1.0, 6.0, Color::BLACK) } else { ButtonStyle::Transparent })
+                    .on_press(DiagnosticsMsg::IfaceSelected(stats.name.clone())),
+            )
+        });
+
+        let (rx_now, tx_now) = graph.history.back().cloned().unwrap_or_default();
+        let graph_sec = Column::new()
+            .spacing(6)
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .push(Text::new(format!("Throughput: {}", selected_iface.as_deref().unwrap_or("none"))).size(18))
+                    .push(Space::with_width(Length::Fill))
+                    .push(Text::new(format!("↓ {}/s", format_bytes(rx_now as f64))).size(14).color(SUCCESS))
+                    .push(Text::new(format!("↑ {}/s", format_bytes(tx_now as f64))).size(14).color(PRIMARY)),
+            )
+            .push(Canvas::new(graph).width(Length::Fill).height(Length::Units(120)));
+
+        let tool_sec = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(
+                PickList::new(pick_tool, &DiagTool::ALL[..], Some(*tool), DiagnosticsMsg::ToolChanged)
+                    .text_size(16)
+                    .style(PickListStyle {})
+                    .padding(6)
+                    .width(Length::FillPortion(1)),
+            )
+            .push(
+                TextInput::new(host, "Host name or IP address", host_val, DiagnosticsMsg::HostChanged)
+                    .padding(6)
+                    .style(InputStyle::InkBorder)
+                    .width(Length::FillPortion(2))
+                    .on_submit(DiagnosticsMsg::OnRun),
+            )
+            .push(
+                Button::new(run_btn, Text::new(if snapshot.running { "Running..." } else { "Run" }))
+                    .padding(6)
+                    .style(ButtonStyle::Circular(86, 101, 115, 1.0))
+                    .on_press(DiagnosticsMsg::OnRun),
+            );
+
+        let result_sec: Element<_> = if let Some(error) = &snapshot.error {
+            Text::new(error.as_str()).size(14).color(ERROR).into()
+        } else if snapshot.header.is_empty() {
+            Text::new(if snapshot.running { "Waiting for results..." } else { "" }).size(14).into()
+        } else {
+            let portion = |idx: usize| if idx == 0 { 1 } else { 2 };
+            let header = snapshot.header.iter().enumerate().fold(Row::new().spacing(10), |row, (idx, cell)| row.push(table_cell(cell, portion(idx))));
+            snapshot
+                .rows
+                .iter()
+                .fold(Column::new().spacing(6).push(Text::new(snapshot.title.as_str()).size(16)).push(header).push(Rule::horizontal(4).style(RuleStyle {})), |col, cells| {
+                    col.push(cells.iter().enumerate().fold(Row::new().spacing(10), |row, (idx, cell)| row.push(table_cell(cell, portion(idx)))))
+                })
+                .into()
+        };
+
+        let content = Column::new()
+            .spacing(10)
+            .push(Text::new("Network Details").size(24))
+            .push(Container::new(iface_table).padding(10).width(Length::Fill).style(ContainerStyle::LightGrayCircle))
+            .push(Container::new(graph_sec).padding(10).width(Length::Fill).style(ContainerStyle::LightGrayCircle))
+            .push(Text::new("Diagnostics").size(24))
+            .push(Container::new(Column::new().spacing(10).push(tool_sec).push(result_sec)).padding(10).width(Length::Fill).style(ContainerStyle::LightGrayCircle));
+        Scrollable::new(scroll).height(Length::Fill).padding(10).scrollbar_width(4).scroller_width(4).push(content).into()
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+struct ThroughputGraph {
+    history: VecDeque<(f32, f32)>,
+}
+
+impl ThroughputGraph {
+    fn push(&mut self, rx: f32, tx: f32) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back((rx, tx));
+    }
+}
+
+impl canvas::Program<DiagnosticsMsg> for ThroughputGraph {
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+        frame.fill(&Path::rectangle(Point::ORIGIN, bounds.size()), Color::WHITE);
+        let peak = self.history.iter().fold(1024.0_f32, |peak, (rx, tx)| peak.max(*rx).max(*tx));
+        let step = frame.width() / (HISTORY_LEN - 1) as f32;
+        let height = frame.height();
+        let offset = HISTORY_LEN - self.history.len();
+        let line = |select: &dyn Fn(&(f32, f32)) -> f32| {
+            Path::new(|builder| {
+                for (idx, sample) in self.history.iter().enumerate() {
+                    let point = Point::new((idx + offset) as f32 * step, height - select(sample) / peak * (height - 4.0));
+                    if idx == 0 {
+                        builder.move_to(point);
+                    } else {
+                        builder.line_to(point);
+                    }
+                }
+            })
+        };
+        frame.stroke(&line(&|sample| sample.0), Stroke { width: 2.0, color: SUCCESS, ..Stroke::default() });
+        frame.stroke(&line(&|sample| sample.1), Stroke { width: 2.0, color: PRIMARY, ..Stroke::default() });
+        vec![frame.into_geometry()]
+    }
+}
+
+mod tools {
+    use super::DiagTool;
+    use std::net::{IpAddr, ToSocketAddrs};
+    use std::process::Command;
+
+    type Table = (Vec<String>, Vec<Vec<String>>);
+
+    pub fn run(tool: DiagTool, host: &str) -> Result<Table, String> {
+        match tool {
+            DiagTool::Ping => ping(host),
+            DiagTool::Lookup => lookup(host),
+            DiagTool::Route => route(host),
+            DiagTool::Traceroute => traceroute(host),
+        }
+    }
+
+    fn command_output(program: &str, args: &[&str]) -> Result<String, String> {
+        let output = Command::new(program).args(args).output().map_err(|e| format!("Failed to run {}: {}", program, e))?;
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        if stdout.trim().is_empty() && !output.status.success() {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        } else {
+            Ok(stdout)
+        }
+    }
+
+    fn strings(vals: &[&str]) -> Vec<String> {
+        vals.iter().map(|val| val.to_string()).collect()
+    }
+
+    fn ping(host: &str) -> Result<Table, String> {
+        let output = command_output("ping", &["-c", "4", "-W", "2", host])?;
+        Ok((strings(&["Seq", "TTL", "Time"]), parse_ping(&output)))
+    }
+
+    /// Rows of the replies and of the summary printed by `ping`.
+    pub fn parse_ping(output: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        for line in output.lines() {
+            if line.contains("icmp_seq=") {
+                let field = |key: &str| line.split_whitespace().find_map(|part| part.strip_prefix(key)).unwrap_or("-").to_string();
+                rows.push(vec![field("icmp_seq="), field("ttl="), format!("{} ms", field("time="))]);
+            } else if line.contains("packets transmitted") {
+                rows.push(vec![String::from("summary"), String::new(), line.trim().to_string()]);
+            } else if line.starts_with("rtt") || line.starts_with("round-trip") {
+                rows.push(vec![String::from("min/avg/max"), String::new(), line.splitn(2, '=').nth(1).unwrap_or("").trim().to_string()]);
+            }
+        }
+        rows
+    }
+
+    fn lookup(host: &str) -> Result<Table, String> {
+        let addrs = (host, 0).to_socket_addrs().map_err(|e| format!("Could not resolve {}: {}", host, e))?;
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for addr in addrs {
+            let ip = addr.ip();
+            let row = vec![String::from(if ip.is_ipv4() { "A" } else { "AAAA" }), ip.to_string()];
+            if !rows.contains(&row) {
+                rows.push(row);
+            }
+        }
+        Ok((strings(&["Type", "Address"]), rows))
+    }
+
+    fn route(host: &str) -> Result<Table, String> {
+        let addr = match host.parse::<IpAddr>() {
+            Ok(addr) => addr,
+            Err(_) => (host, 0).to_socket_addrs().map_err(|e| format!("Could not resolve {}: {}", host, e))?.next().ok_or_else(|| format!("No address found for {}", host))?.ip(),
+        };
+        let output = command_output("ip", &["route", "get", &addr.to_string()])?;
+        let words: Vec<&str> = output.split_whitespace().collect();
+        let field = |key: &str| words.iter().position(|word| *word == key).and_then(|idx| words.get(idx + 1)).map(|val| val.to_string()).unwrap_or_else(|| String::from("-"));
+        let rows = vec![
+            vec![String::from("Destination"), addr.to_string()],
+            vec![String::from("Gateway"), field("via")],
+            vec![String::from("Interface"), field("dev")],
+            vec![String::from("Source"), field("src")],
+        ];
+        Ok((strings(&["Field", "Value"]), rows))
+    }
+
+    fn traceroute(host: &str) -> Result<Table, String> {
+        let output = command_output("tracepath", &["-n", "-m", "20", host])?;
+        Ok((strings(&["Hop", "Address", "Time"]), parse_tracepath(&output)))
+    }
+
+    /// Rows of the hops printed by `tracepath -n`.
+    pub fn parse_tracepath(output: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        for line in output.lines() {
+            let mut parts = line.split_whitespace();
+            let hop = match parts.next() {
+                Some(hop) if hop.ends_with(':') && hop.trim_end_matches(':').chars().all(|c| c.is_ascii_digit() || c == '?') => hop.trim_end_matches(':'),
+                _ => continue,
+            };
+            let rest: Vec<&str> = parts.collect();
+            let addr = rest.first().cloned().unwrap_or("-");
+            let time = rest.iter().find(|part| part.ends_with("ms")).cloned().unwrap_or("-");
+            rows.push(vec![hop.to_string(), addr.to_string(), time.to_string()]);
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tools::{parse_ping, parse_tracepath};
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_net_dev() {
+        let content = "Inter-|   Receive                                                |  Transmit\n face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n    lo:   52340     612    0    0    0     0          0         0    52340     612    0    0    0     0       0          0\n wlan0: 98765432   80123    2    5    0     0          0       120  1234567   45678    1    3    0     0       0          0\n";
+        let stats = parse_proc_net_dev(content);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats[1],
+            IfaceStats {
+                name: String::from("wlan0"),
+                rx_bytes: 98765432,
+                rx_packets: 80123,
+                rx_errs: 2,
+                rx_drop: 5,
+                tx_bytes: 1234567,
+                tx_packets: 45678,
+                tx_errs: 1,
+                tx_drop: 3,
+            }
+        );
+        assert!(parse_proc_net_dev("header\nheader\n  eth0: 1 2 3\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_ping() {
+        let output = "PING koompi.com (104.21.2.3) 56(84) bytes of data.\n64 bytes from 104.21.2.3: icmp_seq=1 ttl=57 time=24.1 ms\n64 bytes from 104.21.2.3: icmp_seq=2 ttl=57 time=23.8 ms\n\n--- koompi.com ping statistics ---\n2 packets transmitted, 2 received, 0% packet loss, time 1001ms\nrtt min/avg/max/mdev = 23.812/23.956/24.100/0.144 ms\n";
+        let rows = parse_ping(output);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], vec!["1", "57", "24.1 ms"]);
+        assert_eq!(rows[2], vec!["summary", "", "2 packets transmitted, 2 received, 0% packet loss, time 1001ms"]);
+        assert_eq!(rows[3], vec!["min/avg/max", "", "23.812/23.956/24.100/0.144 ms"]);
+    }
+
+    #[test]
+    fn test_parse_tracepath() {
+        let output = " 1?: [LOCALHOST]     pmtu 1500\n 1:  192.168.1.1     0.736ms \n 2:  10.10.0.1       12.504ms asymm  4\n     Resume: pmtu 1500\n";
+        let rows = parse_tracepath(output);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], vec!["1?", "[LOCALHOST]", "-"]);
+        assert_eq!(rows[1], vec!["1", "192.168.1.1", "0.736ms"]);
+        assert_eq!(rows[2], vec!["2", "10.10.0.1", "12.504ms"]);
+    }
+}
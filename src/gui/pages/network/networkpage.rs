@@ -1,8 +1,10 @@
+use super::diagnostics::{Diagnostics, DiagnosticsMsg};
 use super::netsettings::{NetSettings, NetSettingsMsg};
+use super::proxy::{Proxy, ProxyMsg};
 use super::wire::{Wire, WireMsg};
 use super::wireless::{Wireless, WirelessMsg};
 use crate::gui::styles::containers::ContainerStyle;
-use iced::{scrollable, Align, Column, Container, Element, Length, Row, Rule, Scrollable, Text};
+use iced::{scrollable, time, Align, Column, Container, Element, Length, Row, Rule, Scrollable, Subscription, Text};
 use iced_custom_widget as icw;
 use icw::components::Tab;
 use icw::components::{Icon, Icons};
@@ -28,6 +30,8 @@ pub struct NetworkPage {
     wireless: Wireless,
     wire: Wire,
     network: NetSettings,
+    proxy: Proxy,
+    diagnostics: Diagnostics,
     is_active: bool,
     scroll_content: scrollable::State,
 }
@@ -39,12 +43,16 @@ pub enum NetMessage {
     WireMsg(WireMsg),
     ToggleChange(bool),
     NetSettingsMsg(NetSettingsMsg),
+    ProxyMsg(ProxyMsg),
+    DiagnosticsMsg(DiagnosticsMsg),
 }
 impl NetworkPage {
     pub fn new() -> Self {
         Self {
             network: NetSettings::new(),
             wireless: Wireless::new(),
+            proxy: Proxy::new(),
+            diagnostics: Diagnostics::new(),
             ..Default::default()
         }
     }
@@ -66,6 +74,18 @@ impl NetworkPage {
             NetMessage::NetSettingsMsg(msg) => {
                 self.network.update(msg);
             }
+            NetMessage::ProxyMsg(msg) => {
+                self.proxy.update(msg);
+            }
+            NetMessage::DiagnosticsMsg(msg) => {
+                self.diagnostics.update(msg);
+            }
+        }
+    }
+    pub fn subscription(&self) -> Subscription<NetMessage> {
+        match self.choice {
+            Choice::H => time::every(std::time::Duration::from_millis(1000)).map(|now| NetMessage::DiagnosticsMsg(DiagnosticsMsg::Tick(now))),
+            _ => Subscription::none(),
         }
     }
     pub fn view(&mut self) -> Element<NetMessage> {
@@ -92,8 +112,8 @@ impl NetworkPage {
             Choice::D => Text::new("Content D").into(),
             Choice::F => Text::new("Content F").into(),
             Choice::G => Text::new("Content G").into(),
-            Choice::E => Text::new("Content E").into(),
-            Choice::H => Text::new("Content H").into(),
+            Choice::E => self.proxy.view().map(move |msg| NetMessage::ProxyMsg(msg)),
+            Choice::H => self.diagnostics.view().map(move |msg| NetMessage::DiagnosticsMsg(msg)),
         });
         let netsidebar_scroll = Scrollable::new(&mut self.scroll_content).push(row).padding(10).scrollbar_width(4).scroller_width(4);
         let whole_content: Element<_> = Row::new()
@@ -0,0 +1,627 @@
+use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle, picklist::PickListStyle, rules::RuleStyle, textinput::InputStyle};
+use iced::{button, pick_list, scrollable, text_input, Align, Button, Column, Container, Element, Length, PickList, Row, Rule, Scrollable, Space, Text, TextInput};
+use std::fmt;
+
+#[derive(Default, Debug, Clone)]
+pub struct Proxy {
+    mode: ProxyMode,
+    pick_mode: pick_list::State<ProxyMode>,
+    entries: Vec<(ProxyProtocol, ProxyEntry)>,
+    pac_url: text_input::State,
+    pac_url_val: String,
+    ignore_hosts: text_input::State,
+    ignore_hosts_val: String,
+    apply_btn: button::State,
+    revert_btn: button::State,
+    scroll: scrollable::State,
+    status: Option<Result<String, String>>,
+    is_changed: bool,
+}
+
+#[derive(Default, Debug, Clone)]
+struct ProxyEntry {
+    host: text_input::State,
+    host_val: String,
+    port: text_input::State,
+    port_val: String,
+    username: text_input::State,
+    username_val: String,
+    password: text_input::State,
+    password_val: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum ProxyMsg {
+    ModeChanged(ProxyMode),
+    HostChanged(usize, String),
+    PortChanged(usize, String),
+    UsernameChanged(usize, String),
+    PasswordChanged(usize, String),
+    PacUrlChanged(String),
+    IgnoreHostsChanged(String),
+    OnApply,
+    OnRevert,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyMode {
+    Disabled,
+    Manual,
+    AutoConfig,
+    AutoDetect,
+}
+impl ProxyMode {
+    const ALL: [ProxyMode; 4] = [ProxyMode::Disabled, ProxyMode::Manual, ProxyMode::AutoConfig, ProxyMode::AutoDetect];
+}
+impl Default for ProxyMode {
+    fn default() -> Self {
+        ProxyMode::Disabled
+    }
+}
+impl fmt::Display for ProxyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ProxyMode::Disabled => "None",
+                ProxyMode::Manual => "Manual",
+                ProxyMode::AutoConfig => "Automatic (PAC URL)",
+                ProxyMode::AutoDetect => "Auto Discovery (WPAD)",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyProtocol {
+    Http,
+    Https,
+    Socks,
+}
+impl ProxyProtocol {
+    const ALL: [ProxyProtocol; 3] = [ProxyProtocol::Http, ProxyProtocol::Https, ProxyProtocol::Socks];
+
+    fn env_key(&self) -> &'static str {
+        match self {
+            ProxyProtocol::Http => "http_proxy",
+            ProxyProtocol::Https => "https_proxy",
+            ProxyProtocol::Socks => "all_proxy",
+        }
+    }
+    fn scheme(&self) -> &'static str {
+        match self {
+            ProxyProtocol::Http | ProxyProtocol::Https => "http",
+            ProxyProtocol::Socks => "socks5",
+        }
+    }
+    fn kde_key(&self) -> &'static str {
+        match self {
+            ProxyProtocol::Http => "httpProxy",
+            ProxyProtocol::Https => "httpsProxy",
+            ProxyProtocol::Socks => "socksProxy",
+        }
+    }
+}
+impl fmt::Display for ProxyProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ProxyProtocol::Http => "HTTP Proxy",
+                ProxyProtocol::Https => "HTTPS Proxy",
+                ProxyProtocol::Socks => "SOCKS Proxy",
+            }
+        )
+    }
+}
+
+fn default_text(text: &str) -> Text {
+    Text::new(text).size(16)
+}
+
+impl Proxy {
+    pub fn new() -> Self {
+        let mut proxy = Self {
+            entries: ProxyProtocol::ALL.iter().map(|protocol| (*protocol, ProxyEntry::default())).collect(),
+            ..Self::default()
+        };
+        proxy.load();
+        proxy
+    }
+
+    fn load(&mut self) {
+        let conf = backend::ProxyConf::load();
+        self.mode = conf.mode;
+        self.pac_url_val = conf.pac_url;
+        self.ignore_hosts_val = conf.ignore_hosts.join(", ");
+        for (protocol, entry) in self.entries.iter_mut() {
+            *entry = ProxyEntry::default();
+            if let Some(server) = conf.servers.iter().find(|server| server.protocol == *protocol) {
+                entry.host_val = server.host.clone();
+                entry.port_val = server.port.to_string();
+                entry.username_val = server.username.clone();
+                entry.password_val = server.password.clone();
+            }
+        }
+        self.is_changed = false;
+    }
+
+    fn to_conf(&self) -> Result<backend::ProxyConf, String> {
+        let mut servers = Vec::new();
+        if self.mode == ProxyMode::Manual {
+            for (protocol, entry) in self.entries.iter() {
+                if entry.host_val.trim().is_empty() {
+                    continue;
+                }
+                let host = entry.host_val.trim();
+                validate::host(host).map_err(|e| format!("{}: {}", protocol, e))?;
+                let port = validate::port(&entry.port_val).map_err(|e| format!("{}: {}", protocol, e))?;
+                servers.push(backend::ProxyServer {
+                    protocol: *protocol,
+                    host: host.to_string(),
+                    port,
+                    username: entry.username_val.trim().to_string(),
+                    password: entry.password_val.clone(),
+                });
+            }
+            if servers.is_empty() {
+                return Err(String::from("Manual mode requires at least one proxy server"));
+            }
+        }
+        if self.mode == ProxyMode::AutoConfig {
+            validate::pac_url(self.pac_url_val.trim())?;
+        }
+        let ignore_hosts: Vec<String> = self.ignore_hosts_val.split(|c| c == ',' || c == ' ').map(str::trim).filter(|h| !h.is_empty()).map(String::from).collect();
+        for host in ignore_hosts.iter() {
+            validate::ignore_host(host)?;
+        }
+        Ok(backend::ProxyConf {
+            mode: self.mode,
+            servers,
+            pac_url: self.pac_url_val.trim().to_string(),
+            ignore_hosts,
+        })
+    }
+
+    pub fn update(&mut self, msg: ProxyMsg) {
+        match msg {
+            ProxyMsg::ModeChanged(mode) => self.mode = mode,
+            ProxyMsg::HostChanged(idx, val) => self.entries[idx].1.host_val = val,
+            ProxyMsg::PortChanged(idx, val) => {
+                if val.is_empty() || (val.len() <= 5 && val.chars().all(|c| c.is_ascii_digit())) {
+                    self.entries[idx].1.port_val = val;
+                }
+            }
+            ProxyMsg::UsernameChanged(idx, val) => self.entries[idx].1.username_val = val,
+            ProxyMsg::PasswordChanged(idx, val) => self.entries[idx].1.password_val = val,
+            ProxyMsg::PacUrlChanged(val) => self.pac_url_val = val,
+            ProxyMsg::IgnoreHostsChanged(val) => self.ignore_hosts_val = val,
+            ProxyMsg::OnApply => {
+                self.status = Some(match self.to_conf() {
+                    Ok(conf) => match conf.save() {
+                        Ok(()) => {
+                            self.is_changed = false;
+                            Ok(String::from("Proxy settings applied. Applications started from now on will use them."))
+                        }
+                        Err(e) => Err(format!("Failed to save proxy settings: {}", e)),
+                    },
+                    Err(e) => Err(e),
+                });
+                return;
+            }
+            ProxyMsg::OnRevert => {
+                self.load();
+                self.status = None;
+                return;
+            }
+        }
+        self.is_changed = true;
+        self.status = None;
+    }
+
+    pub fn view(&mut self) -> Element<ProxyMsg> {
+        let Proxy {
+            mode,
+            pick_mode,
+            entries,
+            pac_url,
+            pac_url_val,
+            ignore_hosts,
+            ignore_hosts_val,
+            apply_btn,
+            revert_btn,
+            scroll,
+            status,
+            is_changed,
+        } = self;
+
+        let mode_sec = Container::new(
+            Row::new().align_items(Align::Center).spacing(10).push(default_text("Proxy").width(Length::FillPortion(1))).push(
+                PickList::new(pick_mode, &ProxyMode::ALL[..], Some(*mode), ProxyMsg::ModeChanged)
+                    .text_size(16)
+                    .style(PickListStyle {})
+                    .padding(6)
+                    .width(Length::FillPortion(2)),
+            ),
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(ContainerStyle::LightGrayCircle);
+
+        let detail_sec: Element<_> = match mode {
+            ProxyMode::Disabled => Text::new("Applications connect to the internet directly.").size(14).into(),
+            ProxyMode::AutoDetect => Text::new("The proxy configuration will be discovered from the network (WPAD).").size(14).into(),
+            ProxyMode::AutoConfig => Container::new(
+                Row::new().align_items(Align::Center).spacing(10).push(default_text("Configuration URL").width(Length::FillPortion(1))).push(
+                    TextInput::new(pac_url, "http://example.com/proxy.pac", pac_url_val, ProxyMsg::PacUrlChanged)
+                        .padding(6)
+                        .style(InputStyle::InkBorder)
+                        .width(Length::FillPortion(2)),
+                ),
+            )
+            .padding(10)
+            .width(Length::Fill)
+            .style(ContainerStyle::LightGrayCircle)
+            .into(),
+            ProxyMode::Manual => entries
+                .iter_mut()
+                .enumerate()
+                .fold(Column::new().spacing(10), |col, (idx, (protocol, entry))| {
+                    col.push(Text::new(protocol.to_string()).size(18)).push(
+                        Container::new(
+                            Column::new()
+                                .spacing(10)
+                                .push(
+                                    Row::new()
+                                        .align_items(Align::Center)
+                                        .spacing(10)
+                                        .push(
+                                            TextInput::new(&mut entry.host, "Host", &entry.host_val, move |val| ProxyMsg::HostChanged(idx, val))
+                                                .padding(6)
+                                                .style(InputStyle::InkBorder)
+                                                .width(Length::FillPortion(3)),
+                                        )
+                                        .push(
+                                            TextInput::new(&mut entry.port, "Port", &entry.port_val, move |val| ProxyMsg::PortChanged(idx, val))
+                                                .padding(6)
+                                                .style(InputStyle::InkBorder)
+                                                .width(Length::FillPortion(1)),
+                                        ),
+                                )
+                                .push(Rule::horizontal(4).style(RuleStyle {}))
+                                .push(
+                                    Row::new()
+                                        .align_items(Align::Center)
+                                        .spacing(10)
+                                        .push(
+                                            TextInput::new(&mut entry.username, "Username (optional)", &entry.username_val, move |val| ProxyMsg::UsernameChanged(idx, val))
+                                                .padding(6)
+                                                .style(InputStyle::InkBorder)
+                                                .width(Length::FillPortion(1)),
+                                        )
+                                        .push(
+                                            TextInput::new(&mut entry.password, "Password (optional)", &entry.password_val, move |val| ProxyMsg::PasswordChanged(idx, val))
+                                                .password()
+                                                .padding(6)
+                                                .style(InputStyle::InkBorder)
+                                                .width(Length::FillPortion(1)),
+                                        ),
+                                ),
+                        )
+                        .padding(10)
+                        .width(Length::Fill)
+                        .style(ContainerStyle::LightGrayCircle),
+                    )
+                })
+                .into(),
+        };
+
+        let ignore_sec = Column::new().spacing(10).push(Text::new("Ignore Hosts").size(18)).push(
+            Container::new(
+                Column::new()
+                    .spacing(6)
+                    .push(
+                        TextInput::new(ignore_hosts, "localhost, 127.0.0.0/8, *.local", ignore_hosts_val, ProxyMsg::IgnoreHostsChanged)
+                            .padding(6)
+                            .style(InputStyle::InkBorder)
+                            .width(Length::Fill),
+                    )
+                    .push(Text::new("Separate hosts with commas. Wildcard domains and CIDR ranges are allowed.").size(12)),
+            )
+            .padding(10)
+            .width(Length::Fill)
+            .style(ContainerStyle::LightGrayCircle),
+        );
+
+        let mut revert = Button::new(revert_btn, Text::new("Revert")).padding(8).style(ButtonStyle::Circular(86, 101, 115, 1.0));
+        let mut apply = Button::new(apply_btn, Text::new("Apply")).padding(8).style(ButtonStyle::Circular(65, 203, 126, 1.0));
+        if *is_changed {
+            revert = revert.on_press(ProxyMsg::OnRevert);
+            apply = apply.on_press(ProxyMsg::OnApply);
+        }
+        let status_sec: Element<_> = match status {
+            Some(Ok(msg)) => Text::new(msg.as_str()).size(14).color(crate::gui::styles::SUCCESS).into(),
+            Some(Err(msg)) => Text::new(msg.as_str()).size(14).color(crate::gui::styles::ERROR).into(),
+            None => Space::with_height(Length::Shrink).into(),
+        };
+        let mut content = Column::new().spacing(10).push(Text::new("System Proxy").size(24)).push(mode_sec).push(detail_sec);
+        if *mode != ProxyMode::Disabled {
+            content = content.push(ignore_sec);
+        }
+        Column::new()
+            .spacing(10)
+            .push(Scrollable::new(scroll).height(Length::Fill).padding(10).scrollbar_width(4).scroller_width(4).push(content))
+            .push(status_sec)
+            .push(Row::new().spacing(10).push(Space::with_width(Length::Fill)).push(revert).push(apply))
+            .into()
+    }
+}
+
+mod validate {
+    use std::net::IpAddr;
+
+    pub fn host(host: &str) -> Result<(), String> {
+        if host.is_empty() {
+            return Err(String::from("host is empty"));
+        }
+        let bare = host.trim_start_matches('[').trim_end_matches(']');
+        if bare.parse::<IpAddr>().is_ok() {
+            return Ok(());
+        }
+        if host.len() > 253 {
+            return Err(format!("host name '{}' is too long", host));
+        }
+        for label in host.trim_end_matches('.').split('.') {
+            if label.is_empty() || label.len() > 63 {
+                return Err(format!("'{}' is not a valid host name", host));
+            }
+            if label.starts_with('-') || label.ends_with('-') || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                return Err(format!("'{}' is not a valid host name", host));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn port(port: &str) -> Result<u16, String> {
+        match port.trim().parse::<u16>() {
+            Ok(0) | Err(_) => Err(format!("'{}' is not a valid port (1-65535)", port.trim())),
+            Ok(port) => Ok(port),
+        }
+    }
+
+    pub fn pac_url(url: &str) -> Result<(), String> {
+        if ["http://", "https://", "file://"].iter().any(|scheme| url.starts_with(scheme) && url.len() > scheme.len()) {
+            Ok(())
+        } else {
+            Err(String::from("Configuration URL must start with http://, https:// or file://"))
+        }
+    }
+
+    pub fn ignore_host(entry: &str) -> Result<(), String> {
+        let (addr, prefix) = match entry.find('/') {
+            Some(idx) => (&entry[..idx], Some(&entry[idx + 1..])),
+            None => (entry, None),
+        };
+        if let Some(prefix) = prefix {
+            let max = match addr.parse::<IpAddr>() {
+                Ok(IpAddr::V4(_)) => 32,
+                Ok(IpAddr::V6(_)) => 128,
+                Err(_) => return Err(format!("'{}' is not a valid network range", entry)),
+            };
+            return match prefix.parse::<u8>() {
+                Ok(len) if len <= max => Ok(()),
+                _ => Err(format!("'{}' is not a valid network range", entry)),
+            };
+        }
+        host(addr.trim_start_matches("*.").trim_start_matches('.')).map_err(|_| format!("'{}' is not a valid host to ignore", entry))
+    }
+}
+
+mod backend {
+    use super::{ProxyMode, ProxyProtocol};
+    use crate::helpers::kconfig;
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    const ENV_FILE: &str = "environment.d/60-proxy.conf";
+    const KIOSLAVERC: &str = "kioslaverc";
+    const KDE_GROUP: &str = "Proxy Settings";
+
+    #[derive(Debug, Clone)]
+    pub struct ProxyServer {
+        pub protocol: ProxyProtocol,
+        pub host: String,
+        pub port: u16,
+        pub username: String,
+        pub password: String,
+    }
+
+    impl ProxyServer {
+        fn url(&self) -> String {
+            let auth = if self.username.is_empty() {
+                String::new()
+            } else if self.password.is_empty() {
+                format!("{}@", encode(&self.username))
+            } else {
+                format!("{}:{}@", encode(&self.username), encode(&self.password))
+            };
+            let host = if self.host.contains(':') && !self.host.starts_with('[') { format!("[{}]", self.host) } else { self.host.clone() };
+            format!("{}://{}{}:{}/", self.protocol.scheme(), auth, host, self.port)
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct ProxyConf {
+        pub mode: ProxyMode,
+        pub servers: Vec<ProxyServer>,
+        pub pac_url: String,
+        pub ignore_hosts: Vec<String>,
+    }
+
+    impl ProxyConf {
+        /// Load the proxy configuration from the desktop settings, falling back to the environment file.
+        pub fn load() -> Self {
+            let mut conf = Self::default();
+            let env_file = env_file_path().and_then(|path| std::fs::read_to_string(path).ok()).unwrap_or_default();
+            for line in env_file.lines() {
+                let (key, val) = match line.find('=') {
+                    Some(idx) => (line[..idx].trim(), line[idx + 1..].trim().trim_matches('"')),
+                    None => continue,
+                };
+                if key == "no_proxy" {
+                    conf.ignore_hosts = val.split(',').map(str::trim).filter(|h| !h.is_empty()).map(String::from).collect();
+                } else if let Some(protocol) = ProxyProtocol::ALL.iter().find(|protocol| protocol.env_key() == key) {
+                    if let Some(server) = parse_url(*protocol, val) {
+                        conf.mode = ProxyMode::Manual;
+                        conf.servers.push(server);
+                    }
+                }
+            }
+            match read_kde_key("ProxyType").as_deref() {
+                Some("2") => {
+                    conf.mode = ProxyMode::AutoConfig;
+                    conf.pac_url = read_kde_key("Proxy Config Script").unwrap_or_default();
+                }
+                Some("3") => conf.mode = ProxyMode::AutoDetect,
+                _ => {}
+            }
+            conf
+        }
+
+        /// Persist the configuration to `environment.d` for new sessions and to the desktop proxy settings.
+        pub fn save(&self) -> Result<(), std::io::Error> {
+            let path = env_file_path().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "config directory not found"))?;
+            if self.mode == ProxyMode::Manual {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                // The servers may hold a user name and password, which other users must not read.
+                let mut file = std::fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(&path)?;
+                file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+                writeln!(file, "# Generated by System Settings")?;
+                for server in self.servers.iter() {
+                    let url = server.url();
+                    writeln!(file, "{}=\"{}\"", server.protocol.env_key(), url)?;
+                    writeln!(file, "{}=\"{}\"", server.protocol.env_key().to_uppercase(), url)?;
+                }
+                if !self.ignore_hosts.is_empty() {
+                    let no_proxy = self.ignore_hosts.join(",");
+                    writeln!(file, "no_proxy=\"{}\"", no_proxy)?;
+                    writeln!(file, "NO_PROXY=\"{}\"", no_proxy)?;
+                }
+            } else if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+
+            let proxy_type = match self.mode {
+                ProxyMode::Disabled => "0",
+                ProxyMode::Manual => "1",
+                ProxyMode::AutoConfig => "2",
+                ProxyMode::AutoDetect => "3",
+            };
+            write_kde_key("ProxyType", Some(proxy_type))?;
+            for protocol in ProxyProtocol::ALL.iter() {
+                let val = self.servers.iter().find(|server| server.protocol == *protocol).map(|server| format!("{}://{} {}", server.protocol.scheme(), server.host, server.port));
+                write_kde_key(protocol.kde_key(), val.as_deref())?;
+            }
+            write_kde_key("Proxy Config Script", Some(self.pac_url.as_str()).filter(|url| !url.is_empty()))?;
+            write_kde_key("NoProxyFor", Some(self.ignore_hosts.join(",")).filter(|hosts| !hosts.is_empty()).as_deref())?;
+            // Ask running KIO workers to reload their proxy configuration.
+            if let Err(e) = Command::new("dbus-send").args(&["--type=signal", "/KIO/Scheduler", "org.kde.KIO.Scheduler.reparseSlaveConfiguration", "string:"]).status() {
+                eprintln!("Failed to reload the proxy configuration of KIO: {}", e);
+            }
+            Ok(())
+        }
+    }
+
+    fn env_file_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(ENV_FILE))
+    }
+
+    fn read_kde_key(key: &str) -> Option<String> {
+        kconfig::read_key(KIOSLAVERC, KDE_GROUP, key)
+    }
+
+    fn write_kde_key(key: &str, val: Option<&str>) -> Result<(), std::io::Error> {
+        kconfig::write_key(KIOSLAVERC, KDE_GROUP, key, val).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    }
+
+    fn parse_url(protocol: ProxyProtocol, url: &str) -> Option<ProxyServer> {
+        let rest = url.splitn(2, "://").nth(1)?.trim_end_matches('/');
+        let (auth, host_port) = match rest.rfind('@') {
+            Some(idx) => (&rest[..idx], &rest[idx + 1..]),
+            None => ("", rest),
+        };
+        let (username, password) = match auth.find(':') {
+            Some(idx) => (decode(&auth[..idx]), decode(&auth[idx + 1..])),
+            None => (decode(auth), String::new()),
+        };
+        let idx = host_port.rfind(':')?;
+        let host = host_port[..idx].trim_start_matches('[').trim_end_matches(']').to_string();
+        let port = host_port[idx + 1..].parse().ok()?;
+        Some(ProxyServer { protocol, host, port, username, password })
+    }
+
+    fn encode(val: &str) -> String {
+        val.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect()
+    }
+
+    pub(super) fn decode(val: &str) -> String {
+        let hex = |b: u8| (b as char).to_digit(16).map(|digit| digit as u8);
+        let bytes = val.as_bytes();
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut idx = 0;
+        while idx < bytes.len() {
+            if let (b'%', Some(hi), Some(lo)) = (bytes[idx], bytes.get(idx + 1).copied().and_then(hex), bytes.get(idx + 2).copied().and_then(hex)) {
+                out.push(hi << 4 | lo);
+                idx += 3;
+            } else {
+                out.push(bytes[idx]);
+                idx += 1;
+            }
+        }
+        String::from_utf8_lossy(&out).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backend, validate};
+
+    #[test]
+    fn test_validate_host_port() {
+        assert!(validate::host("proxy.koompi.com").is_ok());
+        assert!(validate::host("10.0.0.1").is_ok());
+        assert!(validate::host("[::1]").is_ok());
+        assert!(validate::host("-bad.example").is_err());
+        assert!(validate::host("bad host").is_err());
+        assert_eq!(validate::port("3128"), Ok(3128));
+        assert!(validate::port("0").is_err());
+        assert!(validate::port("70000").is_err());
+    }
+
+    #[test]
+    fn test_validate_ignore_host() {
+        assert!(validate::ignore_host("*.local").is_ok());
+        assert!(validate::ignore_host(".koompi.org").is_ok());
+        assert!(validate::ignore_host("192.168.0.0/16").is_ok());
+        assert!(validate::ignore_host("192.168.0.0/33").is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(backend::decode("user%40koompi"), "user@koompi");
+        assert_eq!(backend::decode("p%3Ass%41"), "p:ssA");
+        assert_eq!(backend::decode("%E1%9E%80%"), "\u{1780}%");
+        assert_eq!(backend::decode("%\u{1780}%4"), "%\u{1780}%4");
+    }
+}
@@ -1,7 +1,16 @@
+mod lock_policy;
+mod permission_store;
+
 use crate::gui::addon_widgets::tabbar;
-use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomSelect};
-use iced::{button, pick_list, scrollable, Align, Button, Checkbox, Column, Container, Element, Length, PickList, Row, Scrollable, Space, Text};
-use iced_custom_widget::{number_input, Icon, IconBrand, IconBrands, Icons, NumberInput};
+use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomSelect, CustomTextInput, ERROR};
+use crate::helpers::device_access::{AccessLog, Device};
+use crate::helpers::firewall::{app_profiles, AppProfile, FirewallChange, FirewallConf, FirewallKind, FwAction, FwProtocol, FwRule, FwTarget, FwTargetKind};
+use crate::helpers::{authority, Action};
+use chrono::prelude::*;
+use iced::{button, pick_list, scrollable, text_input, time, Align, Button, Checkbox, Column, Command, Container, Element, Image, Length, PickList, Row, Scrollable, Space, Subscription, Svg, Text, TextInput};
+use iced_custom_widget::{number_input, Icon, Icons, NumberInput};
+use lock_policy::LockPolicy;
+use permission_store::{installed_apps, DesktopApp, PermissionKind};
 use smart_default::SmartDefault;
 
 #[derive(Debug, Clone)]
@@ -10,18 +19,32 @@ pub enum PrivacyMessage {
    ChangePW(bool),
    ReqPWToggled(bool),
    ReqPWDurChanged(ReqPWDuration),
-   ShowMsgScreenLockToggled(bool),
-   ShowMsgScreenLock,
    LogoutAfterToggled(bool),
    LogoutAfterDurChanged(u8),
+   LogoutOKClicked,
+   IdleLogoutApplied(Result<(), String>),
    ReqAdminPWToggled(bool),
    PrivacyTabSelected(usize),
-   EnablePrivacyToggled(bool),
    AppsPrivacyToggled(usize, bool),
+   AccessLogRefreshed,
    TurnFirewallClicked,
+   /// The helper is done applying the rules.
+   FwApplied(FirewallConf, Result<(), String>),
    BlockAllInConToggled(bool),
    AutoAllowBuiltInToggled(bool),
    AutoAllowDownSignedToggled(bool),
+   FwRuleActionChanged(FwAction),
+   FwRuleProtocolChanged(FwProtocol),
+   FwRuleTargetKindChanged(FwTargetKind),
+   FwRuleTargetChanged(String),
+   FwRuleSourceChanged(String),
+   FwRuleAddClicked,
+   FwRuleSelected(usize),
+   FwRuleRemoveClicked,
+   FwShowRulesetToggled(bool),
+   FwRulesetLoaded(Result<String, String>),
+   FwImportClicked,
+   FwExportClicked,
    AdvancedToggled(bool),
 }
 
@@ -49,35 +72,156 @@ impl PrivacyPage {
       }
    }
 
-   pub fn update(&mut self, msg: PrivacyMessage) {
+   pub fn subscription(&self) -> Subscription<PrivacyMessage> {
+      if self.current_tab_idx == 1 && self.privacy_tab.device().is_some() {
+         time::every(std::time::Duration::from_secs(2)).map(|_| PrivacyMessage::AccessLogRefreshed)
+      } else {
+         Subscription::none()
+      }
+   }
+
+   pub fn update(&mut self, msg: PrivacyMessage) -> Command<PrivacyMessage> {
       use PrivacyMessage::*;
       match msg {
          TabChanged(idx) => self.current_tab_idx = idx,
          ChangePW(is_checked) => self.general_tab.is_change_pw = is_checked,
-         ReqPWToggled(is_checked) => self.general_tab.req_pw = is_checked,
-         ReqPWDurChanged(val) => self.general_tab.req_pw_dur_val = val,
-         ShowMsgScreenLockToggled(is_checked) => self.general_tab.show_msg_screen_lock = is_checked,
-         ShowMsgScreenLock => {}
+         ReqPWToggled(is_checked) => {
+            self.general_tab.req_pw = is_checked;
+            self.general_tab.apply_screen_lock();
+         }
+         ReqPWDurChanged(val) => {
+            self.general_tab.req_pw_dur_val = val;
+            self.general_tab.apply_screen_lock();
+         }
          LogoutAfterToggled(is_checked) => self.general_tab.logout_after = is_checked,
          LogoutAfterDurChanged(val) => self.general_tab.logout_after_dur_state.dur_val = val,
-         ReqAdminPWToggled(is_checked) => self.general_tab.req_admin_pw_sys_pref = is_checked,
-         PrivacyTabSelected(idx) => self.privacy_tab.selected_privacy_tab = idx,
-         EnablePrivacyToggled(is_checked) => {
-            let (_, enable, _) = self.privacy_tab.privacy_tab_map.get_mut(self.privacy_tab.selected_privacy_tab).unwrap();
-            if let Some(enable) = enable {
-               enable.0 = is_checked;
+         // logind is configured for every user, so the helper only writes it once the change is confirmed.
+         LogoutOKClicked => return Command::perform(authority::exec(Action::IdleLogout, self.general_tab.policy().idle_logout_args()), IdleLogoutApplied),
+         IdleLogoutApplied(res) => match res {
+            Ok(()) => {
+               let general_tab = &mut self.general_tab;
+               general_tab.applied_logout = (general_tab.logout_after, general_tab.logout_after_dur_state.dur_val);
+               general_tab.status = None;
             }
+            Err(err) => self.general_tab.status = Some(err),
+         },
+         ReqAdminPWToggled(is_checked) => self.general_tab.req_admin_pw_sys_pref = is_checked,
+         PrivacyTabSelected(idx) => {
+            self.privacy_tab.selected_privacy_tab = idx;
+            self.privacy_tab.selected_app_privacy = 0;
+            self.privacy_tab.load_apps();
          }
          AppsPrivacyToggled(idx, is_checked) => {
             self.privacy_tab.selected_app_privacy = idx;
-            self.privacy_tab.privacy_tab_map.get_mut(self.privacy_tab.selected_privacy_tab).unwrap().2.get_mut(idx).unwrap().0 = is_checked;
+            let (_, kind, ls_apps) = self.privacy_tab.privacy_tab_map.get_mut(self.privacy_tab.selected_privacy_tab).unwrap();
+            if let Some((is_allowed, app)) = ls_apps.get_mut(idx) {
+               match kind.set(app, is_checked) {
+                  Ok(()) => {
+                     *is_allowed = is_checked;
+                     self.privacy_tab.status = None;
+                  }
+                  Err(err) => self.privacy_tab.status = Some(err),
+               }
+            }
+         }
+         AccessLogRefreshed => self.privacy_tab.access_log = AccessLog::load(),
+         TurnFirewallClicked => {
+            self.firewall_tab.is_turn_on_firewall = !self.firewall_tab.is_turn_on_firewall;
+            return self.firewall_tab.apply();
+         }
+         FwApplied(conf, res) => {
+            let fw = &mut self.firewall_tab;
+            match res {
+               Ok(()) => {
+                  fw.status = conf.save().err();
+                  fw.applied = conf;
+                  if fw.show_ruleset {
+                     return FirewallTab::load_ruleset();
+                  }
+               }
+               // Show the rules the firewall still follows.
+               Err(err) => {
+                  fw.reset();
+                  fw.status = Some(err);
+               }
+            }
+         }
+         BlockAllInConToggled(is_checked) => {
+            self.firewall_tab.block_all_in_con = is_checked;
+            return self.firewall_tab.apply();
          }
-         TurnFirewallClicked => self.firewall_tab.is_turn_on_firewall = !self.firewall_tab.is_turn_on_firewall,
-         BlockAllInConToggled(is_checked) => self.firewall_tab.block_all_in_con = is_checked,
          AutoAllowBuiltInToggled(is_checked) => self.firewall_tab.auto_allow_built_in_software = is_checked,
          AutoAllowDownSignedToggled(is_checked) => self.firewall_tab.auto_allow_down_signed_software = is_checked,
+         FwRuleActionChanged(val) => self.firewall_tab.rule_action = val,
+         FwRuleProtocolChanged(val) => self.firewall_tab.rule_protocol = val,
+         FwRuleTargetKindChanged(val) => {
+            self.firewall_tab.rule_target_kind = val;
+            self.firewall_tab.rule_target.clear();
+         }
+         FwRuleTargetChanged(val) => self.firewall_tab.rule_target = val,
+         FwRuleSourceChanged(val) => self.firewall_tab.rule_source = val,
+         FwRuleAddClicked => {
+            let fw = &mut self.firewall_tab;
+            let rule = FwRule {
+               action: fw.rule_action,
+               protocol: fw.rule_protocol,
+               target: match fw.rule_target_kind {
+                  FwTargetKind::Port => FwTarget::Port(fw.rule_target.trim().to_string()),
+                  FwTargetKind::Application => FwTarget::App(fw.rule_target.trim().to_string()),
+               },
+               source: fw.rule_source.trim().to_string(),
+            };
+            match rule.validate() {
+               Ok(()) => {
+                  fw.ls_rules.push((rule, button::State::new()));
+                  fw.rule_target.clear();
+                  fw.rule_source.clear();
+                  return fw.apply();
+               }
+               Err(err) => fw.status = Some(err),
+            }
+         }
+         FwRuleSelected(idx) => self.firewall_tab.selected_rule = Some(idx),
+         FwRuleRemoveClicked => {
+            let fw = &mut self.firewall_tab;
+            if let Some(idx) = fw.selected_rule.take() {
+               if idx < fw.ls_rules.len() {
+                  fw.ls_rules.remove(idx);
+                  return fw.apply();
+               }
+            }
+         }
+         FwShowRulesetToggled(is_checked) => {
+            let fw = &mut self.firewall_tab;
+            fw.show_ruleset = is_checked;
+            if is_checked {
+               return FirewallTab::load_ruleset();
+            }
+         }
+         FwRulesetLoaded(res) => self.firewall_tab.ruleset = res.unwrap_or_else(|err| err),
+         FwImportClicked => {
+            if let Ok(nfd2::Response::Okay(path)) = nfd2::open_file_dialog(Some("json"), None) {
+               let fw = &mut self.firewall_tab;
+               match FirewallConf::import(&path) {
+                  Ok(conf) => {
+                     fw.ls_rules = conf.rules.into_iter().map(|rule| (rule, button::State::new())).collect();
+                     fw.block_all_in_con = conf.block_all_incoming;
+                     fw.selected_rule = None;
+                     return fw.apply();
+                  }
+                  Err(err) => fw.status = Some(err),
+               }
+            }
+         }
+         FwExportClicked => {
+            if let Ok(nfd2::Response::Okay(path)) = nfd2::open_save_dialog(Some("json"), None) {
+               let fw = &mut self.firewall_tab;
+               fw.status = fw.conf().export(&path).err();
+            }
+         }
          AdvancedToggled(is_checked) => self.is_advanced = is_checked,
       }
+      Command::none()
    }
 
    pub fn view(&mut self) -> Element<PrivacyMessage> {
@@ -104,11 +248,12 @@ impl PrivacyPage {
                req_pw,
                req_pw_dur_state,
                req_pw_dur_val,
-               show_msg_screen_lock,
-               set_lock_msg_state,
                logout_after,
                logout_after_dur_state: DurationState { dur_state, dur_val },
+               applied_logout,
+               logout_ok_state,
                req_admin_pw_sys_pref,
+               status,
             } = general_tab;
 
             let lb_login_pw = Text::new("A login password has been set for this user");
@@ -118,16 +263,14 @@ impl PrivacyPage {
             let pl_req_pw_dur = PickList::new(req_pw_dur_state, &ReqPWDuration::ALL[..], Some(*req_pw_dur_val), ReqPWDurChanged).style(CustomSelect::Primary);
             let lb_after_sleep = Text::new("after sleep or screen saver begin.");
             let req_pw_sec = Container::new(Row::new().spacing(10).align_items(Align::Center).push(chb_req_pw).push(pl_req_pw_dur).push(lb_after_sleep));
-            let chb_show_msg_screen_lock = Checkbox::new(*show_msg_screen_lock, "Show a message when the screen is locked", ShowMsgScreenLockToggled).spacing(10).style(CustomCheckbox::Default);
-            let mut btn_set_lock_msg = Button::new(set_lock_msg_state, Text::new("  Set Lock Message  ")).style(CustomButton::Default);
-            if *show_msg_screen_lock {
-               btn_set_lock_msg = btn_set_lock_msg.on_press(ShowMsgScreenLock);
-            }
-            let show_msg_sec = Container::new(Row::new().spacing(10).align_items(Align::Center).push(chb_show_msg_screen_lock).push(btn_set_lock_msg));
             let chb_logout_after = Checkbox::new(*logout_after, "Log out after", LogoutAfterToggled).spacing(10).style(CustomCheckbox::Default);
             let pl_logout_after_dur = NumberInput::new(dur_state, *dur_val, 100, LogoutAfterDurChanged).width(Length::Units(50));
             let lb_min_inactivity = Text::new("minutes of inactivity");
-            let logout_after_dur_sec = Container::new(Row::new().spacing(10).align_items(Align::Center).push(chb_logout_after).push(pl_logout_after_dur).push(lb_min_inactivity));
+            let mut btn_logout_ok = Button::new(logout_ok_state, Text::new("  OK  ")).style(CustomButton::Primary);
+            if (*logout_after, *dur_val) != *applied_logout {
+               btn_logout_ok = btn_logout_ok.on_press(LogoutOKClicked);
+            }
+            let logout_after_dur_sec = Container::new(Row::new().spacing(10).align_items(Align::Center).push(chb_logout_after).push(pl_logout_after_dur).push(lb_min_inactivity).push(btn_logout_ok));
             let chb_req_admin = Checkbox::new(*req_admin_pw_sys_pref, "Require an administrator password to access system preferences", ReqAdminPWToggled)
                .spacing(10)
                .style(CustomCheckbox::Default);
@@ -138,9 +281,9 @@ impl PrivacyPage {
                   .align_items(Align::Start)
                   .push(change_pw_sec)
                   .push(req_pw_sec)
-                  .push(show_msg_sec)
                   .push(logout_after_dur_sec)
-                  .push(chb_req_admin),
+                  .push(chb_req_admin)
+                  .push(Text::new(status.as_deref().unwrap_or("")).size(12).color(ERROR)),
             )
             .width(Length::Fill)
             .height(Length::Fill)
@@ -153,6 +296,9 @@ impl PrivacyPage {
                selected_app_privacy,
                left_pane_scroll,
                right_pane_scroll,
+               access_log,
+               status,
+               ..
             } = privacy_tab;
 
             // ផ្ទាំងខាងឆ្វេង
@@ -169,28 +315,41 @@ impl PrivacyPage {
             let left_pane = Container::new(left_tab_col).width(Length::FillPortion(4)).height(Length::Fill).style(CustomContainer::ForegroundWhite);
 
             // ផ្ទាំងខាងស្ដាំ
-            let (hint, enable, ls_apps) = privacy_tab_map.get_mut(*selected_privacy_tab).unwrap();
-            let mut main_view_col = Column::new().spacing(10);
-            if let Some((is_checked, title)) = enable {
-               main_view_col = main_view_col.push(Checkbox::new(*is_checked, *title, EnablePrivacyToggled));
-            }
-            main_view_col = main_view_col.push(Text::new(*hint));
+            let (hint, _, ls_apps) = privacy_tab_map.get_mut(*selected_privacy_tab).unwrap();
+            let mut main_view_col = Column::new().spacing(10).push(Text::new(*hint));
             let apps_list_view = ls_apps.iter_mut().enumerate().fold(
                Scrollable::new(right_pane_scroll).width(Length::Fill).height(Length::Fill).padding(7).spacing(4).scroller_width(4).scrollbar_width(4),
-               |col, (idx, (is_checked, icon, title))| {
+               |col, (idx, (is_checked, app))| {
+                  let icon: Element<_> = match &app.icon {
+                     Some(path) if path.extension().map(|ext| ext == "svg").unwrap_or(false) => Svg::from_path(path).width(Length::Units(30)).height(Length::Units(30)).into(),
+                     Some(path) => Image::new(path).width(Length::Units(30)).height(Length::Units(30)).into(),
+                     None => Icon::new(Icons::Cube).size(30).into(),
+                  };
                   let row = Row::new()
                      .width(Length::Fill)
+                     .spacing(10)
                      .align_items(Align::Center)
                      .padding(4)
-                     .push(Checkbox::new(*is_checked, *title, move |is| AppsPrivacyToggled(idx, is)).spacing(10).style(CustomCheckbox::Default))
-                     // .push(Space::with_width(Length::Fill))
-                     .push(IconBrand::new(*icon).size(30));
+                     .push(Checkbox::new(*is_checked, "", move |is| AppsPrivacyToggled(idx, is)).style(CustomCheckbox::Default))
+                     .push(icon)
+                     .push(Text::new(&app.name));
 
                   col.push(Container::new(row).width(Length::Fill).style(if *selected_app_privacy == idx { CustomContainer::Hovered } else { CustomContainer::ForegroundWhite }))
                },
             );
-            let app_view_sec = Container::new(apps_list_view).height(Length::Fill).style(CustomContainer::ForegroundWhite);
+            let apps_list_view: Element<_> = if ls_apps.is_empty() { Text::new("No applications have requested access.").size(12).into() } else { apps_list_view.into() };
+            let app_view_sec = Container::new(apps_list_view).width(Length::Fill).height(Length::Fill).style(CustomContainer::ForegroundWhite);
             main_view_col = main_view_col.push(app_view_sec);
+            if let Some(status) = status {
+               main_view_col = main_view_col.push(Text::new(status.as_str()).size(12).color(ERROR));
+            }
+            if let Some(device) = PrivacyTab::DEVICES.get(*selected_privacy_tab).copied().flatten() {
+               let recent_col = access_log.recent(device).take(5).fold(Column::new().spacing(4).push(Text::new("Recently used").size(14)), |col, record| {
+                  let last_used = Local.timestamp(record.last_used, 0).format("%d %b %Y, %H:%M").to_string();
+                  col.push(Row::new().spacing(10).push(Text::new(&record.app).width(Length::Fill)).push(Text::new(last_used).size(12)))
+               });
+               main_view_col = main_view_col.push(Container::new(recent_col).width(Length::Fill).padding(7).style(CustomContainer::ForegroundWhite));
+            }
             let main_view_sec = Container::new(main_view_col).width(Length::FillPortion(6)).height(Length::Fill).style(CustomContainer::ForegroundGray);
 
             Container::new(Row::new().spacing(10).push(left_pane).push(main_view_sec)).width(Length::Fill).height(Length::Fill)
@@ -202,6 +361,30 @@ impl PrivacyPage {
                block_all_in_con,
                auto_allow_built_in_software,
                auto_allow_down_signed_software,
+               kind,
+               ls_rules,
+               selected_rule,
+               rules_scroll,
+               app_profiles,
+               rule_action_state,
+               rule_action,
+               rule_protocol_state,
+               rule_protocol,
+               rule_target_kind_state,
+               rule_target_kind,
+               rule_app_state,
+               rule_target_state,
+               rule_target,
+               rule_source_state,
+               rule_source,
+               add_rule_state,
+               remove_rule_state,
+               import_state,
+               export_state,
+               show_ruleset,
+               ruleset,
+               ruleset_scroll,
+               status,
             } = firewall_tab;
 
             let firewall_state = |is_on| if is_on { "On" } else { "Off" };
@@ -222,6 +405,68 @@ impl PrivacyPage {
                .spacing(10)
                .style(CustomCheckbox::Default);
 
+            // ផ្នែកច្បាប់
+            let txt_backend = Text::new(format!("Rules ({})", if *kind == FirewallKind::Ufw { "ufw" } else { "nftables" })).size(14);
+            let scrollable_rules = ls_rules
+               .iter_mut()
+               .enumerate()
+               .fold(Scrollable::new(rules_scroll).height(Length::Units(120)).padding(7).spacing(4).scroller_width(4).scrollbar_width(4), |scrollable, (idx, (rule, state))| {
+                  let btn = Button::new(state, Text::new(rule.to_string()).size(14))
+                     .width(Length::Fill)
+                     .on_press(FwRuleSelected(idx))
+                     .style(if *selected_rule == Some(idx) { CustomButton::Selected } else { CustomButton::Text });
+                  scrollable.push(btn)
+               });
+            let mut btn_remove_rule = Button::new(remove_rule_state, Icon::new(Icons::Minus).size(23)).padding(2).style(CustomButton::Text);
+            if selected_rule.is_some() {
+               btn_remove_rule = btn_remove_rule.on_press(FwRuleRemoveClicked);
+            }
+            let btn_import = Button::new(import_state, Text::new("  Import...  ")).on_press(FwImportClicked).style(CustomButton::Default);
+            let btn_export = Button::new(export_state, Text::new("  Export...  ")).on_press(FwExportClicked).style(CustomButton::Default);
+            let rules_sec = Container::new(
+               Column::new().push(Container::new(txt_backend).width(Length::Fill).padding(7).style(CustomContainer::Header)).push(scrollable_rules).push(
+                  Container::new(Row::new().spacing(10).align_items(Align::Center).push(btn_remove_rule).push(Space::with_width(Length::Fill)).push(btn_import).push(btn_export))
+                     .width(Length::Fill)
+                     .style(CustomContainer::Header),
+               ),
+            )
+            .width(Length::Fill)
+            .style(CustomContainer::ForegroundWhite);
+
+            let pl_action = PickList::new(rule_action_state, &FwAction::ALL[..], Some(*rule_action), FwRuleActionChanged).style(CustomSelect::Primary);
+            let pl_protocol = PickList::new(rule_protocol_state, &FwProtocol::ALL[..], Some(*rule_protocol), FwRuleProtocolChanged).style(CustomSelect::Primary);
+            let pl_target_kind = PickList::new(rule_target_kind_state, &FwTargetKind::ALL[..], Some(*rule_target_kind), FwRuleTargetKindChanged).style(CustomSelect::Primary);
+            let target_input: Element<_> = match rule_target_kind {
+               FwTargetKind::Port => TextInput::new(rule_target_state, "Port or range, e.g. 22", rule_target, FwRuleTargetChanged)
+                  .padding(7)
+                  .width(Length::FillPortion(2))
+                  .style(CustomTextInput::Default)
+                  .into(),
+               FwTargetKind::Application => {
+                  let selected_app = app_profiles.iter().find(|app| &app.name == rule_target).map(|app| app.name.clone());
+                  PickList::new(rule_app_state, app_profiles.iter().map(|app| app.name.clone()).collect::<Vec<String>>(), selected_app, FwRuleTargetChanged)
+                     .width(Length::FillPortion(2))
+                     .style(CustomSelect::Primary)
+                     .into()
+               }
+            };
+            let txt_source = TextInput::new(rule_source_state, "From (any)", rule_source, FwRuleSourceChanged).padding(7).width(Length::FillPortion(2)).style(CustomTextInput::Default);
+            let btn_add_rule = Button::new(add_rule_state, Icon::new(Icons::Ad).size(23)).padding(2).on_press(FwRuleAddClicked).style(CustomButton::Text);
+            let rule_editor_sec = Row::new().spacing(7).align_items(Align::Center).push(pl_action).push(pl_target_kind).push(target_input);
+            let rule_editor_sec = if let FwTargetKind::Port = rule_target_kind { rule_editor_sec.push(pl_protocol) } else { rule_editor_sec };
+            let rule_editor_sec = rule_editor_sec.push(txt_source).push(btn_add_rule);
+
+            let status_sec = Text::new(status.as_deref().unwrap_or("")).size(12).color(ERROR);
+            let chb_show_ruleset = Checkbox::new(*show_ruleset, "Show active ruleset", FwShowRulesetToggled).spacing(10).style(CustomCheckbox::Default);
+            let mut ruleset_sec = Column::new().spacing(7).push(chb_show_ruleset);
+            if *show_ruleset {
+               ruleset_sec = ruleset_sec.push(
+                  Container::new(Scrollable::new(ruleset_scroll).height(Length::Units(150)).padding(7).scroller_width(4).scrollbar_width(4).push(Text::new(ruleset.as_str()).size(12)))
+                     .width(Length::Fill)
+                     .style(CustomContainer::ForegroundWhite),
+               );
+            }
+
             Container::new(
                Column::new()
                   .spacing(10)
@@ -229,7 +474,11 @@ impl PrivacyPage {
                   .push(txt_firewall_hint)
                   .push(Column::new().push(chb_block_all_in_con).push(Row::new().push(Space::with_width(Length::Units(30))).push(txt_block_all_hint)))
                   .push(chb_auto_allow_built_in_software)
-                  .push(chb_auto_allow_down_signed_software),
+                  .push(chb_auto_allow_down_signed_software)
+                  .push(rules_sec)
+                  .push(rule_editor_sec)
+                  .push(status_sec)
+                  .push(ruleset_sec),
             )
             .width(Length::Fill)
             .height(Length::Fill)
@@ -265,16 +514,42 @@ pub(self) struct GeneralTab {
    req_pw: bool,
    req_pw_dur_state: pick_list::State<ReqPWDuration>,
    req_pw_dur_val: ReqPWDuration,
-   show_msg_screen_lock: bool,
-   set_lock_msg_state: button::State,
    logout_after: bool,
    logout_after_dur_state: DurationState,
+   /// Idle logout and its minutes as written for logind.
+   applied_logout: (bool, u8),
+   logout_ok_state: button::State,
    req_admin_pw_sys_pref: bool,
+   status: Option<String>,
 }
 
 impl GeneralTab {
    pub fn new() -> Self {
-      Self { req_pw: true, ..Default::default() }
+      let policy = LockPolicy::load();
+      Self {
+         req_pw: policy.require_password,
+         req_pw_dur_val: ReqPWDuration::from_secs(policy.grace_secs),
+         logout_after: policy.logout_after,
+         logout_after_dur_state: DurationState {
+            dur_val: policy.logout_mins,
+            ..Default::default()
+         },
+         applied_logout: (policy.logout_after, policy.logout_mins),
+         ..Default::default()
+      }
+   }
+
+   fn policy(&self) -> LockPolicy {
+      LockPolicy {
+         require_password: self.req_pw,
+         grace_secs: self.req_pw_dur_val.secs(),
+         logout_after: self.logout_after,
+         logout_mins: self.logout_after_dur_state.dur_val,
+      }
+   }
+
+   fn apply_screen_lock(&mut self) {
+      self.status = self.policy().apply_screen_lock().err();
    }
 }
 
@@ -282,15 +557,20 @@ impl GeneralTab {
 pub(self) struct PrivacyTab {
    privacy_tabs: Vec<(Icons, &'static str, button::State)>,
    selected_privacy_tab: usize,
-   privacy_tab_map: Vec<(&'static str, Option<(bool, &'static str)>, Vec<(bool, IconBrands, &'static str)>)>,
+   privacy_tab_map: Vec<(&'static str, PermissionKind, Vec<(bool, DesktopApp)>)>,
    selected_app_privacy: usize,
    left_pane_scroll: scrollable::State,
    right_pane_scroll: scrollable::State,
+   installed_apps: Vec<DesktopApp>,
+   access_log: AccessLog,
+   status: Option<String>,
 }
 
 impl PrivacyTab {
+   const DEVICES: [Option<Device>; 8] = [None, None, None, None, Some(Device::Camera), Some(Device::Microphone), None, None];
+
    pub fn new() -> Self {
-      Self {
+      let mut privacy_tab = Self {
          privacy_tabs: vec![
             (Icons::LocationArrow, "Location & Services", button::State::new()),
             (Icons::FileContract, "Contacts", button::State::new()),
@@ -304,40 +584,190 @@ impl PrivacyTab {
          privacy_tab_map: vec![
             (
                "Allow the apps and services below to determine your location.",
-               Some((true, "Enable Location Services")),
-               vec![(true, IconBrands::CloudscaleCh, "Weather"), (false, IconBrands::GoogleLogo, "Google Chrome"), (true, IconBrands::Discord, "Discord")],
+               PermissionKind::Store {
+                  table: "location",
+                  id: "location",
+                  allow: "EXACT",
+                  deny: "NONE",
+                  // The time of the last request, which the portal requires.
+                  data: &["0"],
+               },
+               Vec::new(),
+            ),
+            (
+               "Allow the apps below to access your contacts.",
+               PermissionKind::Flatpak {
+                  allow: "--talk-name=org.gnome.evolution.dataserver.AddressBook10",
+                  deny: "--no-talk-name=org.gnome.evolution.dataserver.AddressBook10",
+               },
+               Vec::new(),
+            ),
+            (
+               "Allow the apps below to access your calendars.",
+               PermissionKind::Flatpak {
+                  allow: "--talk-name=org.gnome.evolution.dataserver.Calendar8",
+                  deny: "--no-talk-name=org.gnome.evolution.dataserver.Calendar8",
+               },
+               Vec::new(),
+            ),
+            (
+               "Allow the apps below to access your photos.",
+               PermissionKind::Flatpak {
+                  allow: "--filesystem=xdg-pictures",
+                  deny: "--nofilesystem=xdg-pictures",
+               },
+               Vec::new(),
+            ),
+            (
+               "Allow the apps below to access your camera.",
+               PermissionKind::Store {
+                  table: "devices",
+                  id: "camera",
+                  allow: "yes",
+                  deny: "no",
+                  data: &[],
+               },
+               Vec::new(),
+            ),
+            (
+               "Allow the apps below to access your microphone.",
+               PermissionKind::Store {
+                  table: "devices",
+                  id: "microphone",
+                  allow: "yes",
+                  deny: "no",
+                  data: &[],
+               },
+               Vec::new(),
+            ),
+            (
+               "Allow the apps below to control your computer.",
+               PermissionKind::Flatpak {
+                  allow: "--talk-name=org.a11y.Bus",
+                  deny: "--no-talk-name=org.a11y.Bus",
+               },
+               Vec::new(),
+            ),
+            (
+               "Allow the apps below to use Bluetooth.",
+               PermissionKind::Flatpak {
+                  allow: "--allow=bluetooth",
+                  deny: "--disallow=bluetooth",
+               },
+               Vec::new(),
             ),
-            ("Allow the apps below to access your contacts.", None, vec![(true, IconBrands::GoodreadsG, "Terminal")]),
-            ("Allow the apps below to access your calendars.", None, vec![(true, IconBrands::GooglePlay, "Terminal")]),
-            ("Allow the apps below to access your photos.", None, vec![(true, IconBrands::GoogleWallet, "Terminal")]),
-            ("Allow the apps below to access your camera.", None, vec![(true, IconBrands::GoogleLogo, "Google Chrome")]),
-            ("Allow the apps below to access your microphone.", None, vec![(true, IconBrands::GoogleLogo, "Google Chrome"), (true, IconBrands::Telegram, "Telegram")]),
-            ("Allow the apps below to control your computer.", None, Vec::new()),
-            ("Allow the apps below to use Bluetooth.", None, Vec::new()),
          ],
+         installed_apps: installed_apps(),
+         access_log: AccessLog::load(),
          ..Default::default()
+      };
+      privacy_tab.load_apps();
+      privacy_tab
+   }
+
+   fn device(&self) -> Option<Device> {
+      Self::DEVICES.get(self.selected_privacy_tab).copied().flatten()
+   }
+
+   /// Reload the grants of the selected category. Portal permissions list the apps that asked for access,
+   /// sandbox overrides list every Flatpak app. Apps the portal hasn't asked about yet are not allowed.
+   fn load_apps(&mut self) {
+      let installed_apps = &self.installed_apps;
+      if let Some((_, kind, ls_apps)) = self.privacy_tab_map.get_mut(self.selected_privacy_tab) {
+         let grants = kind.grants(installed_apps);
+         *ls_apps = installed_apps
+            .iter()
+            .filter(|app| if kind.is_sandbox_only() { app.is_flatpak } else { app.is_flatpak || grants.contains_key(&app.app_id) })
+            .map(|app| (grants.get(&app.app_id).copied().unwrap_or(false), app.clone()))
+            .collect();
       }
+      self.status = None;
    }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, SmartDefault)]
 pub(self) struct FirewallTab {
    turn_firewall_state: button::State,
    is_turn_on_firewall: bool,
    block_all_in_con: bool,
    auto_allow_built_in_software: bool,
    auto_allow_down_signed_software: bool,
+   #[default(FirewallKind::Ufw)]
+   kind: FirewallKind,
+   ls_rules: Vec<(FwRule, button::State)>,
+   /// Rules the firewall follows now.
+   applied: FirewallConf,
+   selected_rule: Option<usize>,
+   rules_scroll: scrollable::State,
+   app_profiles: Vec<AppProfile>,
+   rule_action_state: pick_list::State<FwAction>,
+   rule_action: FwAction,
+   rule_protocol_state: pick_list::State<FwProtocol>,
+   rule_protocol: FwProtocol,
+   rule_target_kind_state: pick_list::State<FwTargetKind>,
+   rule_target_kind: FwTargetKind,
+   rule_app_state: pick_list::State<String>,
+   rule_target_state: text_input::State,
+   rule_target: String,
+   rule_source_state: text_input::State,
+   rule_source: String,
+   add_rule_state: button::State,
+   remove_rule_state: button::State,
+   import_state: button::State,
+   export_state: button::State,
+   show_ruleset: bool,
+   ruleset: String,
+   ruleset_scroll: scrollable::State,
+   status: Option<String>,
 }
 
 impl FirewallTab {
    pub fn new() -> Self {
+      let kind = FirewallKind::detect();
+      let mut conf = FirewallConf::load().unwrap_or_default();
+      // The firewall may have been turned on or off by another tool since the rules were saved.
+      conf.enabled = kind.is_enabled().unwrap_or(conf.enabled);
       Self {
-         is_turn_on_firewall: true,
+         is_turn_on_firewall: conf.enabled,
+         block_all_in_con: conf.block_all_incoming,
          auto_allow_built_in_software: true,
          auto_allow_down_signed_software: true,
+         kind,
+         ls_rules: conf.rules.iter().map(|rule| (rule.clone(), button::State::new())).collect(),
+         applied: conf,
+         app_profiles: app_profiles(),
          ..Default::default()
       }
    }
+
+   fn conf(&self) -> FirewallConf {
+      FirewallConf {
+         enabled: self.is_turn_on_firewall,
+         block_all_incoming: self.block_all_in_con,
+         rules: self.ls_rules.iter().map(|(rule, _)| rule.clone()).collect(),
+      }
+   }
+
+   /// Have the helper push the changes of the rules to the firewall. They are remembered for the next session once
+   /// applied.
+   fn apply(&mut self) -> Command<PrivacyMessage> {
+      let change = FirewallChange { prev: self.applied.clone(), conf: self.conf() };
+      let conf = change.conf.clone();
+      Command::perform(authority::exec_json(Action::Firewall, &change), move |res| PrivacyMessage::FwApplied(conf.clone(), res))
+   }
+
+   /// Go back to the rules the firewall follows.
+   fn reset(&mut self) {
+      self.is_turn_on_firewall = self.applied.enabled;
+      self.block_all_in_con = self.applied.block_all_incoming;
+      self.ls_rules = self.applied.rules.iter().map(|rule| (rule.clone(), button::State::new())).collect();
+      self.selected_rule = None;
+   }
+
+   /// Only root may list the rules, so the helper reports them.
+   fn load_ruleset() -> Command<PrivacyMessage> {
+      Command::perform(authority::exec_output(Action::Firewall, vec![String::from("--ruleset")]), PrivacyMessage::FwRulesetLoaded)
+   }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, SmartDefault)]
@@ -353,6 +783,22 @@ pub enum ReqPWDuration {
 
 impl ReqPWDuration {
    const ALL: [ReqPWDuration; 6] = [ReqPWDuration::Immediately, ReqPWDuration::_5s, ReqPWDuration::_1m, ReqPWDuration::_5m, ReqPWDuration::_15m, ReqPWDuration::_1h];
+
+   fn secs(&self) -> u32 {
+      use ReqPWDuration::*;
+      match self {
+         Immediately => 0,
+         _5s => 5,
+         _1m => 60,
+         _5m => 300,
+         _15m => 900,
+         _1h => 3600,
+      }
+   }
+
+   fn from_secs(secs: u32) -> Self {
+      Self::ALL.iter().rev().find(|dur| dur.secs() <= secs).copied().unwrap_or_default()
+   }
 }
 
 impl std::fmt::Display for ReqPWDuration {
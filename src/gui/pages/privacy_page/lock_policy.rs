@@ -0,0 +1,87 @@
+use crate::helpers::kconfig::{read_key, write_key};
+use crate::helpers::IDLE_LOGOUT_CONF;
+use std::process::Command;
+
+const LOCKER_RC: &str = "kscreenlockerrc";
+
+/// Session lock and idle logout policy shown on the General tab of Security & Privacy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockPolicy {
+   pub require_password: bool,
+   pub grace_secs: u32,
+   pub logout_after: bool,
+   pub logout_mins: u8,
+}
+
+impl LockPolicy {
+   pub fn load() -> Self {
+      let logout_secs = std::fs::read_to_string(IDLE_LOGOUT_CONF).ok().and_then(|content| parse_logout_secs(&content));
+      Self {
+         require_password: read_key(LOCKER_RC, "Daemon", "LockOnResume").map(|val| val != "false").unwrap_or(true),
+         grace_secs: read_key(LOCKER_RC, "Daemon", "LockGrace").and_then(|val| val.parse().ok()).unwrap_or(5),
+         logout_after: logout_secs.is_some(),
+         logout_mins: logout_secs.map(|secs| (secs / 60).min(u8::MAX as u32) as u8).unwrap_or(60),
+      }
+   }
+
+   /// Apply the screen locker part of the policy to the current user's session.
+   pub fn apply_screen_lock(&self) -> Result<(), String> {
+      for (group, key, val) in self.screen_lock_keys().iter() {
+         write_key(LOCKER_RC, group, key, val.as_deref())?;
+      }
+      // Ask the running screen locker to reload its configuration.
+      Command::new("dbus-send")
+         .args(&["--session", "--type=method_call", "--dest=org.freedesktop.ScreenSaver", "/ScreenSaver", "org.kde.screensaver.configure"])
+         .status()
+         .map(|_| ())
+         .map_err(|e| e.to_string())
+   }
+
+   /// Arguments of the `idle-logout` action of the helper, which applies the idle logout part of the policy
+   /// through systemd-logind.
+   pub fn idle_logout_args(&self) -> Vec<String> {
+      if self.logout_after {
+         vec![self.logout_mins.to_string()]
+      } else {
+         Vec::new()
+      }
+   }
+
+   /// Values of the `kscreenlockerrc` keys for the screen locker part of the policy.
+   fn screen_lock_keys(&self) -> [(&'static str, &'static str, Option<String>); 2] {
+      [("Daemon", "LockOnResume", Some(self.require_password.to_string())), ("Daemon", "LockGrace", Some(self.grace_secs.to_string()))]
+   }
+}
+
+/// Seconds of the idle logout in the logind drop-in of System Settings.
+fn parse_logout_secs(content: &str) -> Option<u32> {
+   content.lines().find_map(|line| line.trim().strip_prefix("StopIdleSessionSec=").and_then(|val| val.trim().parse::<u32>().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_screen_lock_keys() {
+      let policy = LockPolicy {
+         require_password: false,
+         grace_secs: 60,
+         ..LockPolicy::default()
+      };
+      assert_eq!(policy.screen_lock_keys(), [("Daemon", "LockOnResume", Some(String::from("false"))), ("Daemon", "LockGrace", Some(String::from("60")))]);
+   }
+
+   #[test]
+   fn test_idle_logout() {
+      let policy = LockPolicy {
+         logout_after: true,
+         logout_mins: 30,
+         ..LockPolicy::default()
+      };
+      assert_eq!(policy.idle_logout_args(), vec![String::from("30")]);
+      assert!(LockPolicy { logout_after: false, ..policy }.idle_logout_args().is_empty());
+      assert_eq!(parse_logout_secs("# Generated by System Settings\n[Login]\nStopIdleSessionSec=1800\n"), Some(1800));
+      assert_eq!(parse_logout_secs("[Login]\n"), None);
+   }
+}
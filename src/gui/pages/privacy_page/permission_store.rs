@@ -0,0 +1,216 @@
+use crate::helpers::resolve_icon;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Where a privacy permission is stored for an application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionKind {
+   /// An entry of the xdg-desktop-portal permission store, e.g. `devices`/`camera`. `data` are the values the portal
+   /// expects after the permission, like the time of the last request of the location entry.
+   Store {
+      table: &'static str,
+      id: &'static str,
+      allow: &'static str,
+      deny: &'static str,
+      data: &'static [&'static str],
+   },
+   /// A static Flatpak sandbox override, only available to Flatpak applications.
+   Flatpak { allow: &'static str, deny: &'static str },
+}
+
+impl PermissionKind {
+   pub fn is_sandbox_only(&self) -> bool {
+      matches!(self, PermissionKind::Flatpak { .. })
+   }
+
+   /// Read the current grants of every application for this permission.
+   pub fn grants(&self, apps: &[DesktopApp]) -> HashMap<String, bool> {
+      let mut grants = HashMap::new();
+      match self {
+         PermissionKind::Store { table, id, allow, .. } => {
+            if let Ok(output) = Command::new("flatpak").args(&["permissions", *table, *id]).output() {
+               // Columns: Table Object App Permissions Data
+               for line in String::from_utf8_lossy(&output.stdout).lines() {
+                  let cols: Vec<&str> = line.split('\t').map(str::trim).collect();
+                  if cols.len() >= 4 && cols[0] == *table && cols[1] == *id && !cols[2].is_empty() {
+                     grants.insert(cols[2].to_string(), cols[3].split(',').any(|perm| perm.trim() == *allow));
+                  }
+               }
+            }
+         }
+         PermissionKind::Flatpak { allow, .. } => {
+            // Without an override of the user, the permissions from the manifest of the app apply.
+            let lookups: [&[&str]; 2] = [&["override", "--user", "--show"], &["info", "--show-permissions"]];
+            for app in apps.iter().filter(|app| app.is_flatpak) {
+               let state = lookups.iter().find_map(|args| {
+                  let output = Command::new("flatpak").args(*args).arg(&app.app_id).output().ok()?;
+                  override_state(&String::from_utf8_lossy(&output.stdout), allow)
+               });
+               grants.insert(app.app_id.clone(), state.unwrap_or(false));
+            }
+         }
+      }
+      grants
+   }
+
+   /// Arguments of `flatpak` that allow or deny the permission to the application.
+   fn set_args(&self, app: &DesktopApp, is_allowed: bool) -> Result<Vec<String>, String> {
+      Ok(match self {
+         PermissionKind::Store { table, id, allow, deny, data } => {
+            let mut args: Vec<String> = vec!["permission-set", *table, *id, app.app_id.as_str(), if is_allowed { *allow } else { *deny }].into_iter().map(String::from).collect();
+            args.extend(data.iter().map(|val| val.to_string()));
+            args
+         }
+         PermissionKind::Flatpak { allow, deny } => {
+            if !app.is_flatpak {
+               return Err(format!("{} is not sandboxed, so this permission cannot be changed", app.name));
+            }
+            vec![String::from("override"), String::from("--user"), (if is_allowed { allow } else { deny }).to_string(), app.app_id.clone()]
+         }
+      })
+   }
+
+   pub fn set(&self, app: &DesktopApp, is_allowed: bool) -> Result<(), String> {
+      let args = self.set_args(app, is_allowed)?;
+      let output = Command::new("flatpak").args(&args).output().map_err(|e| format!("Failed to run flatpak: {}", e))?;
+      if output.status.success() {
+         Ok(())
+      } else {
+         Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+      }
+   }
+}
+
+/// Find the state of an override such as `--allow=bluetooth` in the output of `flatpak override --show` or
+/// `flatpak info --show-permissions`.
+fn override_state(content: &str, allow: &str) -> Option<bool> {
+   let arg = allow.trim_start_matches("--");
+   let (name, val) = match arg.find('=') {
+      Some(idx) => (&arg[..idx], &arg[idx + 1..]),
+      None => (arg, ""),
+   };
+   if name == "talk-name" {
+      return content.lines().find_map(|line| match line.trim().strip_prefix(val).and_then(|rest| rest.strip_prefix('=')) {
+         Some("none") => Some(false),
+         Some(_) => Some(true),
+         None => None,
+      });
+   }
+   let key = match name {
+      "allow" => "features",
+      "device" => "devices",
+      "filesystem" => "filesystems",
+      _ => name,
+   };
+   let vals: Vec<&str> = content.lines().find_map(|line| line.trim().strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))?.split(';').collect();
+   if vals.contains(&val) {
+      Some(true)
+   } else if vals.iter().any(|v| v.strip_prefix('!') == Some(val)) {
+      Some(false)
+   } else {
+      None
+   }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DesktopApp {
+   pub app_id: String,
+   pub name: String,
+   pub icon: Option<PathBuf>,
+   pub is_flatpak: bool,
+}
+
+fn application_dirs() -> Vec<PathBuf> {
+   let mut dirs = vec![PathBuf::from("/usr/share/applications"), PathBuf::from("/var/lib/flatpak/exports/share/applications")];
+   if let Some(data_dir) = dirs::data_dir() {
+      dirs.push(data_dir.join("applications"));
+      dirs.push(data_dir.join("flatpak/exports/share/applications"));
+   }
+   dirs
+}
+
+/// Parse the `[Desktop Entry]` group of a `.desktop` file.
+pub fn parse_desktop_entry(desktop_id: &str, content: &str) -> Option<DesktopApp> {
+   let mut in_entry = false;
+   let mut fields: HashMap<&str, &str> = HashMap::new();
+   for line in content.lines().map(str::trim) {
+      if line.starts_with('[') {
+         in_entry = line == "[Desktop Entry]";
+      } else if let (true, Some(idx)) = (in_entry, line.find('=')) {
+         fields.entry(line[..idx].trim()).or_insert_with(|| line[idx + 1..].trim());
+      }
+   }
+   if fields.get("Type").copied() != Some("Application") || fields.get("NoDisplay").copied() == Some("true") || fields.get("Hidden").copied() == Some("true") {
+      return None;
+   }
+   let flatpak_id = fields.get("X-Flatpak").map(|id| id.to_string());
+   Some(DesktopApp {
+      app_id: flatpak_id.clone().unwrap_or_else(|| desktop_id.to_string()),
+      name: fields.get("Name")?.to_string(),
+      icon: fields.get("Icon").and_then(|icon| resolve_icon(icon)),
+      is_flatpak: flatpak_id.is_some(),
+   })
+}
+
+/// List the installed graphical applications. Entries in later directories override earlier ones.
+pub fn installed_apps() -> Vec<DesktopApp> {
+   let mut apps: HashMap<String, DesktopApp> = HashMap::new();
+   for dir in application_dirs() {
+      if let Ok(entries) = std::fs::read_dir(&dir) {
+         for entry in entries.flatten() {
+            let path = entry.path();
+            let desktop_id = match (path.extension().and_then(|ext| ext.to_str()), path.file_stem().and_then(|stem| stem.to_str())) {
+               (Some("desktop"), Some(stem)) => stem.to_string(),
+               _ => continue,
+            };
+            if let Some(app) = std::fs::read_to_string(&path).ok().and_then(|content| parse_desktop_entry(&desktop_id, &content)) {
+               apps.insert(app.app_id.clone(), app);
+            }
+         }
+      }
+   }
+   let mut apps: Vec<DesktopApp> = apps.into_iter().map(|(_, app)| app).collect();
+   apps.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+   apps
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn app(is_flatpak: bool) -> DesktopApp {
+      DesktopApp {
+         app_id: String::from("org.gnome.Maps"),
+         name: String::from("Maps"),
+         icon: None,
+         is_flatpak,
+      }
+   }
+
+   #[test]
+   fn test_set_args() {
+      let location = PermissionKind::Store {
+         table: "location",
+         id: "location",
+         allow: "EXACT",
+         deny: "NONE",
+         data: &["0"],
+      };
+      assert_eq!(location.set_args(&app(true), true).unwrap(), vec!["permission-set", "location", "location", "org.gnome.Maps", "EXACT", "0"]);
+      assert_eq!(location.set_args(&app(true), false).unwrap(), vec!["permission-set", "location", "location", "org.gnome.Maps", "NONE", "0"]);
+      let camera = PermissionKind::Store {
+         table: "devices",
+         id: "camera",
+         allow: "yes",
+         deny: "no",
+         data: &[],
+      };
+      assert_eq!(camera.set_args(&app(false), true).unwrap(), vec!["permission-set", "devices", "camera", "org.gnome.Maps", "yes"]);
+      let bluetooth = PermissionKind::Flatpak {
+         allow: "--allow=bluetooth",
+         deny: "--disallow=bluetooth",
+      };
+      assert!(bluetooth.set_args(&app(false), true).is_err());
+   }
+}
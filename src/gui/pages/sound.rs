@@ -1,5 +1,12 @@
 mod audio;
 mod configure;
+mod equalizer;
+mod filter_chain;
+mod meter;
+mod mixer;
+mod player;
+mod server;
+mod sound_theme;
 mod soundeffect;
 mod soundpage;
 pub use soundpage::{SoundMessage, SoundPage};
@@ -1,8 +1,16 @@
-use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle, picklist::PickListStyle, sliders::SliderStyle};
-use iced::{button, pick_list, slider, Align, Button, Column, Container, Element, Font, HorizontalAlignment, Length, PickList, Row, Slider, Space, Text};
+use super::meter::LevelMeter;
+use super::mixer::{Mixer, MixerMsg};
+use super::server::{self, CardChange, DeviceClass, EventKind, Facility, ServerCard, ServerEvent, ServerPort, ServerWatcher};
+use super::soundeffect::playback;
+use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle, picklist::PickListStyle, sliders::SliderStyle, CustomProgressBar};
+use iced::{button, pick_list, slider, Align, Button, Column, Container, Element, Font, HorizontalAlignment, Length, PickList, ProgressBar, Row, Slider, Space, Text};
 use libkoompi::system_settings::sounds::controllers::{AppControl, DeviceControl, SinkController, SourceController};
 use libkoompi::system_settings::SoundCard;
 use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How long the sinks and sources of a card that was just plugged in are waited for.
+const NEW_CARD_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Default)]
 pub struct AudioTab {
@@ -28,127 +36,115 @@ pub struct AudioTab {
     list_source_ports: Vec<InputPort>,
     list_sinks: Vec<(String, String)>,
     list_sources: Vec<(String, String)>,
+    mixer: Mixer,
+    watcher: ServerWatcher,
+    cards: Vec<ServerCard>,
+    /// Cards that were just plugged in and when, whose sinks and sources become the default ones.
+    new_cards: Vec<(u32, Instant)>,
+    output_device: (String, String),
+    input_device: (String, String),
+    output_channels: Vec<(String, button::State)>,
+    meter: LevelMeter,
+    test_input: button::State,
+    input_level: f32,
+    status: Option<String>,
 }
 // pub fn list_devices(dev: &Vec::<>
 impl AudioTab {
     pub fn new() -> Self {
         let mut sink_obj = SinkController::create();
         let mut source_obj = SourceController::create();
-        let list_sink_dev = sink_obj.list_devices();
-        let list_source_dev = source_obj.list_devices();
-        let mut sinks = Vec::new();
-        let mut sources = Vec::new();
-        let mut list_src_ports = Vec::new();
-        let mut list_ports_device = Vec::new();
-        let current_sinks = sink_obj.get_volume();
-        let current_source = source_obj.get_volume();
-        println!("Current Source Volume: {:?}", current_source);
-        println!("Current Sink Volume: {:?}", current_sinks);
-
-        // get all sinks and sources device description and name
-        match list_sink_dev {
-            Ok(devices) => {
-                for dev in devices {
-                    sinks.push((
-                        match dev.name {
-                            Some(dev_name) => dev_name,
-                            None => String::from(""),
-                        },
-                        match dev.description {
-                            Some(descr) => descr,
-                            None => String::from(""),
-                        },
-                    ))
-                }
+        let mixer = Mixer::new(&mut sink_obj, &mut source_obj);
+        let mut audio_tab = Self {
+            mixer,
+            watcher: ServerWatcher::spawn(),
+            cards: server::list_cards(),
+            sink_input: sink_obj,
+            source_output: source_obj,
+            ..AudioTab::default()
+        };
+        audio_tab.sync_devices();
+        audio_tab
+    }
+    /// Read the devices, default sink/source, ports and volumes from the sound server.
+    fn sync_devices(&mut self) {
+        let sinks = DeviceClass::Sink.list();
+        let sources = DeviceClass::Source.list();
+        self.list_sinks = sinks.iter().map(|dev| (dev.name.clone(), dev.description.clone())).collect();
+        self.list_sources = sources.iter().map(|dev| (dev.name.clone(), dev.description.clone())).collect();
+        let default_sink = DeviceClass::Sink.default_name().and_then(|name| sinks.into_iter().find(|dev| dev.name == name));
+        let default_source = DeviceClass::Source.default_name().and_then(|name| sources.into_iter().find(|dev| dev.name == name));
+        if let Some(sink) = default_sink {
+            // A port that was just plugged in (e.g. headphones) is preferred over the active one.
+            let is_plugged = |port: &&ServerPort| port.available == Some(true) && self.list_ports.iter().any(|old| old.name == port.name && old.available == Some(false));
+            let active_priority = sink.active_port().map(|port| port.priority).unwrap_or_default();
+            let plugged_port = if self.output_device.0 == sink.name {
+                sink.ports.iter().filter(is_plugged).find(|port| port.priority >= active_priority)
+            } else {
+                None
+            };
+            self.select_output_pick = match plugged_port {
+                Some(port) => match DeviceClass::Sink.set_port(&sink.name, &port.name) {
+                    Ok(()) => OutputPort::from(port),
+                    Err(e) => {
+                        eprintln!("Error: {:?}", e);
+                        sink.active_port().map(OutputPort::from).unwrap_or_default()
+                    }
+                },
+                None => sink.active_port().map(OutputPort::from).unwrap_or_default(),
+            };
+            self.list_ports = sink.ports.iter().map(OutputPort::from).collect();
+            self.output_val = sink.volume;
+            self.is_speak_mute = sink.is_muted;
+            if self.output_channels.iter().map(|(position, _)| position).ne(sink.channel_map.iter()) {
+                self.output_channels = sink.channel_map.iter().map(|position| (position.clone(), button::State::new())).collect();
             }
-            Err(e) => println!("Error: {:?}", e),
+            self.output_device = (sink.name, sink.description);
         }
-        match list_source_dev {
-            Ok(devices) => {
-                for dev in devices {
-                    sources.push((
-                        match dev.name {
-                            Some(dev_name) => dev_name,
-                            None => String::from(""),
-                        },
-                        match dev.description {
-                            Some(descr) => descr,
-                            None => String::from(""),
-                        },
-                    ));
-                }
+        if let Some(source) = default_source {
+            self.select_input_pick = source.active_port().map(InputPort::from).unwrap_or_default();
+            self.list_source_ports = source.ports.iter().map(InputPort::from).collect();
+            self.input_val = source.volume;
+            self.is_headset_mute = source.is_muted;
+            if self.meter.is_running() && self.input_device.0 != source.name {
+                self.status = self.meter.start(&source.name).err();
             }
-            Err(e) => eprintln!("Error: {:?}", e),
+            self.input_device = (source.name, source.description);
         }
-        match sink_obj.get_card_info_list() {
-            Ok(list_cards) => {
-                for data in list_cards {
-                    for ports in data.ports {
-                        list_ports_device.push(match ports.description {
-                            Some(ref port_name) => OutputPort { port: port_name.to_string() },
-                            None => OutputPort { port: "".to_string() },
-                        });
-                        list_src_ports.push(match ports.description {
-                            Some(port_name) => InputPort { port: port_name },
-                            None => InputPort { port: "".to_string() },
-                        });
-                    }
+    }
+    /// Make sound cards that were just plugged in, e.g. a USB or Bluetooth headset, and ports that became available
+    /// the default sink or source. Sinks recreated by a change of profile, and virtual ones such as that of the
+    /// equalizer, are left alone.
+    fn switch_to_new_devices(&mut self, events: &[ServerEvent]) {
+        if events.iter().any(|event| event.facility == Facility::Card) {
+            let cards = server::list_cards();
+            for change in server::card_changes(&self.cards, &cards) {
+                match change {
+                    CardChange::Added(card) => self.new_cards.push((card, Instant::now())),
+                    CardChange::Plugged(card, port) => switch_to_port(card, &port),
                 }
             }
-            Err(e) => println!("Error: {:?}", e),
+            self.cards = cards;
         }
-        let first_port: String = match list_ports_device.get(2) {
-            Some(d) => d.port.clone(),
-            None => String::from("Port Unavailable"),
-        };
-        let second_port: String = match list_src_ports.get(0) {
-            Some(d) => d.port.clone(),
-            None => String::from("Port Unavailable"),
-        };
-        Self {
-            list_sinks: sinks,
-            list_sources: sources,
-            list_ports: list_ports_device,
-            list_source_ports: list_src_ports,
-            output_val: match current_sinks {
-                Ok(mut vec_vol) => match vec_vol.pop() {
-                    Some(val) => match val.parse() {
-                        Ok(d) => d,
-                        Err(e) => {
-                            eprintln!("Error: {:?}", e);
-                            50.0
-                        }
-                    },
-                    None => 50.0,
-                },
-                Err(e) => {
-                    println!("Error: {:?}", e);
-                    50.0
+        self.new_cards.retain(|(_, added)| added.elapsed() < NEW_CARD_TIMEOUT);
+        for event in events.iter().filter(|event| event.kind == EventKind::New) {
+            let class = match event.facility {
+                Facility::Sink => DeviceClass::Sink,
+                Facility::Source => DeviceClass::Source,
+                _ => continue,
+            };
+            let is_new_card = |card: Option<u32>| self.new_cards.iter().any(|(new_card, _)| Some(*new_card) == card);
+            if let Some(device) = class.list().into_iter().find(|dev| Some(dev.index) == event.index && is_new_card(dev.card)) {
+                if let Err(e) = class.set_default(&device.name) {
+                    eprintln!("Error: {:?}", e);
                 }
-            },
-            input_val: match current_source {
-                Ok(mut vec_vol) => match vec_vol.pop() {
-                    Some(val) => match val.parse() {
-                        Ok(d) => d,
-                        Err(e) => {
-                            println!("Error: {:?}", e);
-                            50.0
-                        }
-                    },
-                    None => 50.0,
-                },
-                Err(e) => {
-                    println!("Error: {:?}", e);
-                    50.0
-                }
-            },
-            sink_input: sink_obj,
-            source_output: source_obj,
-            select_input_pick: InputPort { port: second_port },
-            select_output_pick: OutputPort { port: first_port },
-            ..AudioTab::default()
+            }
         }
     }
+    pub fn stop_input_test(&mut self) {
+        self.meter.stop();
+        self.input_level = 0.0;
+    }
     pub fn update(&mut self, msg: AudioTabMsg) {
         match msg {
             AudioTabMsg::InputChanged(val) => {
@@ -174,8 +170,14 @@ impl AudioTab {
                 self.output_val = val;
             }
             AudioTabMsg::NotifyChanged(val) => self.notify_val = val,
-            AudioTabMsg::OutputPortChanged(port) => self.select_output_pick = port,
-            AudioTabMsg::InputPortChanged(port) => self.select_input_pick = port,
+            AudioTabMsg::OutputPortChanged(port) => match DeviceClass::Sink.set_port(&self.output_device.0, &port.name) {
+                Ok(()) => self.select_output_pick = port,
+                Err(e) => eprintln!("Error: {:?}", e),
+            },
+            AudioTabMsg::InputPortChanged(port) => match DeviceClass::Source.set_port(&self.input_device.0, &port.name) {
+                Ok(()) => self.select_input_pick = port,
+                Err(e) => eprintln!("Error: {:?}", e),
+            },
             AudioTabMsg::SpeakerMute => {
                 self.is_speak_mute = !self.is_speak_mute;
                 for dev in &self.list_sinks {
@@ -197,25 +199,72 @@ impl AudioTab {
             AudioTabMsg::NotificationMute => {
                 self.is_notification_mute = !self.is_notification_mute;
             }
+            AudioTabMsg::MixerMsg(msg) => self.mixer.update(msg, &mut self.sink_input, &mut self.source_output),
+            AudioTabMsg::ServerTick => {
+                let events = self.watcher.drain();
+                self.switch_to_new_devices(&events);
+                if events.iter().any(|event| matches!(event.facility, Facility::Sink | Facility::Source | Facility::Card | Facility::Server)) {
+                    self.sync_devices();
+                }
+                // Streams need a refresh when they change, or when a device they can be moved to comes or goes.
+                let streams_changed = events.iter().any(|event| match event.facility {
+                    Facility::SinkInput | Facility::SourceOutput => true,
+                    Facility::Sink | Facility::Source => event.kind != EventKind::Change,
+                    _ => false,
+                });
+                if streams_changed {
+                    self.mixer.refresh(&mut self.sink_input, &mut self.source_output);
+                }
+                self.input_level = self.meter.peak();
+            }
+            AudioTabMsg::TestInputToggled => {
+                if self.meter.is_running() {
+                    self.stop_input_test();
+                } else {
+                    self.status = self.meter.start(&self.input_device.0).err();
+                }
+            }
+            AudioTabMsg::SpeakerTest(idx) => {
+                let channel_map: Vec<String> = self.output_channels.iter().map(|(position, _)| position.clone()).collect();
+                let device = self.output_device.0.clone();
+                // The test plays for a moment, keep the page responsive meanwhile.
+                std::thread::spawn(move || {
+                    if let Err(e) = playback::tone(&device, &channel_map, idx) {
+                        eprintln!("Error: {:?}", e);
+                    }
+                });
+            }
         }
     }
     pub fn view(&mut self) -> Element<AudioTabMsg> {
+        let speaker_test = self
+            .output_channels
+            .iter_mut()
+            .enumerate()
+            .fold(Row::new().spacing(10).align_items(Align::Center).push(Text::new("Test")), |row, (idx, (position, state))| {
+                row.push(Button::new(state, Text::new(channel_label(position)).size(14)).on_press(AudioTabMsg::SpeakerTest(idx)).style(ButtonStyle::Default))
+            });
+        let input_test = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(
+                Button::new(&mut self.test_input, Text::new(if self.meter.is_running() { "Stop" } else { "Test" }).size(14))
+                    .on_press(AudioTabMsg::TestInputToggled)
+                    .style(ButtonStyle::Default),
+            )
+            .push(ProgressBar::new(0.0..=1.0, self.input_level).height(Length::Units(10)).style(CustomProgressBar::ForegroundGrey));
         let output_view = Container::new(
             Column::new()
                 .spacing(10)
                 .align_items(Align::Center)
                 .push(
-                    Row::new()
-                        .align_items(Align::Center)
-                        .push(Text::new("Headphones (Built-in Audio Analog stereo").width(Length::FillPortion(6)))
-                        .push(Space::with_width(Length::Fill))
-                        .push(
-                            Row::new().align_items(Align::Center).spacing(4).width(Length::FillPortion(3)).push(Text::new("Port")).push(
-                                PickList::new(&mut self.output_pick, &self.list_ports, Some(self.select_output_pick.clone()), AudioTabMsg::OutputPortChanged)
-                                    .style(PickListStyle {})
-                                    .width(Length::Fill),
-                            ),
+                    Row::new().align_items(Align::Center).push(Text::new(&self.output_device.1).width(Length::FillPortion(6))).push(Space::with_width(Length::Fill)).push(
+                        Row::new().align_items(Align::Center).spacing(4).width(Length::FillPortion(3)).push(Text::new("Port")).push(
+                            PickList::new(&mut self.output_pick, &self.list_ports, Some(self.select_output_pick.clone()), AudioTabMsg::OutputPortChanged)
+                                .style(PickListStyle {})
+                                .width(Length::Fill),
                         ),
+                    ),
                 )
                 .push(
                     Row::new()
@@ -240,7 +289,8 @@ impl AudioTab {
                                     .push(Text::new("%")),
                             ),
                         ),
-                ),
+                )
+                .push(speaker_test),
         )
         .style(ContainerStyle::LightGrayCircle)
         .width(Length::Fill)
@@ -251,17 +301,13 @@ impl AudioTab {
                 .spacing(10)
                 .align_items(Align::Center)
                 .push(
-                    Row::new()
-                        .align_items(Align::Center)
-                        .push(Text::new("Headset Microphone  (Built-in Audio Analog stereo").width(Length::FillPortion(6)))
-                        .push(Space::with_width(Length::Fill))
-                        .push(
-                            Row::new().align_items(Align::Center).spacing(4).width(Length::FillPortion(3)).push(Text::new("Port")).push(
-                                PickList::new(&mut self.input_pick, &self.list_source_ports, Some(self.select_input_pick.clone()), AudioTabMsg::InputPortChanged)
-                                    .style(PickListStyle {})
-                                    .width(Length::Fill),
-                            ),
+                    Row::new().align_items(Align::Center).push(Text::new(&self.input_device.1).width(Length::FillPortion(6))).push(Space::with_width(Length::Fill)).push(
+                        Row::new().align_items(Align::Center).spacing(4).width(Length::FillPortion(3)).push(Text::new("Port")).push(
+                            PickList::new(&mut self.input_pick, &self.list_source_ports, Some(self.select_input_pick.clone()), AudioTabMsg::InputPortChanged)
+                                .style(PickListStyle {})
+                                .width(Length::Fill),
                         ),
+                    ),
                 )
                 .push(
                     Row::new()
@@ -279,7 +325,8 @@ impl AudioTab {
                                 .push(Text::new(&self.input_val.to_string()).horizontal_alignment(HorizontalAlignment::Center).width(Length::Units(20)))
                                 .push(Text::new("%")),
                         ),
-                ),
+                )
+                .push(input_test),
         )
         .style(ContainerStyle::LightGrayCircle)
         .width(Length::Fill)
@@ -324,8 +371,11 @@ impl AudioTab {
                 .push(output_view)
                 .push(header("Microphone"))
                 .push(input_view)
+                .push(Text::new(self.status.as_deref().unwrap_or("")).size(12))
                 .push(header("Notification Audio"))
-                .push(notify_view),
+                .push(notify_view)
+                .push(header("Applications"))
+                .push(self.mixer.view().map(AudioTabMsg::MixerMsg)),
         )
         .width(Length::Fill)
         .into()
@@ -334,6 +384,35 @@ impl AudioTab {
 fn header(title: &str) -> Text {
     Text::new(title).size(18)
 }
+/// Make the sink or source with a port that was just plugged in the default one. The port of the default sink is
+/// picked by `sync_devices`, which weighs it against the active one.
+fn switch_to_port(card: u32, port: &str) {
+    for class in [DeviceClass::Sink, DeviceClass::Source].iter() {
+        let device = class.list().into_iter().find(|dev| dev.card == Some(card) && dev.ports.iter().any(|dev_port| dev_port.name == port));
+        if let Some(device) = device {
+            if class.default_name().as_deref() != Some(device.name.as_str()) {
+                if let Err(e) = class.set_default(&device.name).and_then(|()| class.set_port(&device.name, port)) {
+                    eprintln!("Error: {:?}", e);
+                }
+            }
+        }
+    }
+}
+
+fn channel_label(position: &str) -> &str {
+    match position {
+        "mono" => "Mono",
+        "front-left" => "Front Left",
+        "front-right" => "Front Right",
+        "front-center" => "Center",
+        "lfe" => "Subwoofer",
+        "rear-left" => "Rear Left",
+        "rear-right" => "Rear Right",
+        "side-left" => "Side Left",
+        "side-right" => "Side Right",
+        _ => position,
+    }
+}
 #[derive(Debug, Clone)]
 pub enum AudioTabMsg {
     InputChanged(f64),
@@ -344,25 +423,51 @@ pub enum AudioTabMsg {
     SpeakerMute,
     MicrophoneMute,
     NotificationMute,
+    MixerMsg(MixerMsg),
+    ServerTick,
+    TestInputToggled,
+    SpeakerTest(usize),
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct OutputPort {
     pub port: String,
+    pub name: String,
+    pub available: Option<bool>,
 }
 impl fmt::Display for OutputPort {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.port)
+        write!(f, "{}{}", self.port, if self.available == Some(false) { " (unplugged)" } else { "" })
+    }
+}
+impl From<&ServerPort> for OutputPort {
+    fn from(port: &ServerPort) -> Self {
+        Self {
+            port: port.description.clone(),
+            name: port.name.clone(),
+            available: port.available,
+        }
     }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct InputPort {
     pub port: String,
+    pub name: String,
+    pub available: Option<bool>,
 }
 impl fmt::Display for InputPort {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.port)
+        write!(f, "{}{}", self.port, if self.available == Some(false) { " (unplugged)" } else { "" })
+    }
+}
+impl From<&ServerPort> for InputPort {
+    fn from(port: &ServerPort) -> Self {
+        Self {
+            port: port.description.clone(),
+            name: port.name.clone(),
+            available: port.available,
+        }
     }
 }
 
@@ -375,16 +480,16 @@ fn icon(unicode: char) -> Text {
     Text::new(&unicode.to_string()).font(ICONS).width(Length::Units(20)).horizontal_alignment(HorizontalAlignment::Center).size(20)
 }
 
-fn speaker_icon() -> Text {
+pub(super) fn speaker_icon() -> Text {
     icon('\u{f028}')
 }
-fn speaker_icon_mute() -> Text {
+pub(super) fn speaker_icon_mute() -> Text {
     icon('\u{f6a9}')
 }
-fn microphone_icon() -> Text {
+pub(super) fn microphone_icon() -> Text {
     icon('\u{f130}')
 }
-fn microphone_icon_mute() -> Text {
+pub(super) fn microphone_icon_mute() -> Text {
     icon('\u{f131}')
 }
 
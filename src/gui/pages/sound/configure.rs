@@ -1,91 +1,241 @@
-use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle, picklist::PickListStyle};
-use iced::{pick_list, Align, Column, Container, Element, Font, HorizontalAlignment, Length, PickList, Row, Text};
-use libkoompi::system_settings::sounds::controllers::{DeviceControl, SinkController};
-use libkoompi::system_settings::SoundCard;
+use super::equalizer::{Equalizer, EqualizerMsg};
+use super::server::{self, DeviceClass, ServerCard, ServerDevice};
+use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle, picklist::PickListStyle, sliders::SliderStyle};
+use iced::{button, pick_list, slider, Align, Button, Column, Command, Container, Element, Font, HorizontalAlignment, Length, PickList, Row, Slider, Text};
 use std::fmt;
+
+/// A sound card with the widget state of its own profile picker.
 #[derive(Default)]
-pub struct ConfigureAudio {
+struct Card {
+    info: ServerCard,
+    profiles: Vec<ProfileList>,
+    selected: Option<ProfileList>,
     pick_profiles: pick_list::State<ProfileList>,
-    select_pick: ProfileList,
-    sink_control: SinkController,
-    profile_data: Vec<ProfileList>,
+    apply: button::State,
+}
+
+impl Card {
+    fn new(info: ServerCard) -> Self {
+        let profiles: Vec<ProfileList> = info.profiles.iter().map(ProfileList::from).collect();
+        let selected = profiles.iter().find(|profile| Some(&profile.name) == info.active_profile.as_ref()).cloned();
+        Self { info, profiles, selected, ..Self::default() }
+    }
+
+    fn is_changed(&self) -> bool {
+        self.selected.as_ref().map(|profile| Some(&profile.name) != self.info.active_profile.as_ref()).unwrap_or(false)
+    }
+}
+
+/// An output device of a card with the widget state of its balance slider.
+#[derive(Default)]
+struct Output {
+    info: ServerDevice,
+    balance: f64,
+    balance_state: slider::State,
+}
+
+#[derive(Default)]
+pub struct ConfigureAudio {
+    cards: Vec<Card>,
+    outputs: Vec<Output>,
+    equalizer: Equalizer,
+    status: Option<String>,
 }
 #[derive(Debug, Clone)]
 pub enum ConfigureAudioMsg {
-    ProfileChanged(ProfileList),
+    ProfileChanged(usize, ProfileList),
+    ApplyProfile(usize),
+    BalanceChanged(usize, f64),
+    EqualizerMsg(EqualizerMsg),
 }
 impl ConfigureAudio {
     pub fn new() -> Self {
-        let mut list_profiles = Vec::new();
-        let mut first_select = ProfileList::default();
-        match SinkController::create().get_card_info_list() {
-            Ok(vec_of_dev) => {
-                for dev in vec_of_dev {
-                    for profile_list in dev.profiles {
-                        list_profiles.push(match profile_list.description {
-                            Some(descr) => ProfileList { profile: descr },
-                            None => ProfileList { profile: String::from("") },
-                        });
+        let mut configure = Self { equalizer: Equalizer::new(), ..Self::default() };
+        configure.refresh();
+        configure
+    }
+
+    /// Re-read the cards and their outputs, e.g. after a profile change which replaces the sinks of a card.
+    fn refresh(&mut self) {
+        self.cards = server::list_cards().into_iter().map(Card::new).collect();
+        let sinks = DeviceClass::Sink.list();
+        self.equalizer.set_outputs(&sinks);
+        self.outputs = sinks
+            .into_iter()
+            .map(|info| Output {
+                balance: balance(&info.channel_map, &info.channel_volumes),
+                info,
+                ..Output::default()
+            })
+            .collect();
+    }
+
+    pub fn update(&mut self, msg: ConfigureAudioMsg) -> Command<ConfigureAudioMsg> {
+        match msg {
+            ConfigureAudioMsg::ProfileChanged(idx, profile) => {
+                if let Some(card) = self.cards.get_mut(idx) {
+                    card.selected = Some(profile);
+                }
+            }
+            ConfigureAudioMsg::ApplyProfile(idx) => {
+                if let Some(card) = self.cards.get(idx) {
+                    if let Some(profile) = &card.selected {
+                        self.status = match server::set_card_profile(&card.info.name, &profile.name) {
+                            Ok(_) => None,
+                            Err(e) => Some(format!("Failed to apply {} to {}: {}", profile, card.info.description, e)),
+                        };
+                        self.refresh();
                     }
                 }
             }
-            Err(e) => {
-                println!("{:?}", e)
+            ConfigureAudioMsg::EqualizerMsg(msg) => return self.equalizer.update(msg).map(ConfigureAudioMsg::EqualizerMsg),
+            ConfigureAudioMsg::BalanceChanged(idx, val) => {
+                if let Some(output) = self.outputs.get_mut(idx) {
+                    let volumes = balanced_volumes(&output.info.channel_map, &output.info.channel_volumes, val);
+                    match DeviceClass::Sink.set_channel_volumes(&output.info.name, &volumes) {
+                        Ok(_) => {
+                            output.balance = val;
+                            output.info.channel_volumes = volumes;
+                            self.status = None;
+                        }
+                        Err(e) => self.status = Some(e),
+                    }
+                }
             }
         }
-        match list_profiles.get(2) {
-            Some(name) => first_select = name.clone(),
-            None => {}
+        Command::none()
+    }
+    pub fn view(&mut self) -> Element<ConfigureAudioMsg> {
+        let ConfigureAudio { cards, outputs, equalizer, status } = self;
+        let content_view = Container::new(Text::new("Device Profile")).width(Length::Fill);
+        let mut col = Column::new().spacing(10).push(content_view.center_x());
+        if let Some(status) = status {
+            col = col.push(Text::new(status.as_str()).size(12));
         }
-
-        Self {
-            sink_control: SinkController::create(),
-            profile_data: list_profiles,
-            select_pick: first_select,
-            ..Self::default()
+        if cards.is_empty() {
+            col = col.push(Text::new("No sound card found"));
         }
-    }
-    pub fn update(&mut self, msg: ConfigureAudioMsg) {
-        match msg {
-            ConfigureAudioMsg::ProfileChanged(profie) => {
-                self.select_pick = profie;
+        // Hand out the outputs to their cards up front, as the views borrow them mutably.
+        let mut card_outputs: Vec<Vec<(usize, &mut Output)>> = cards.iter().map(|_| Vec::new()).collect();
+        for (idx, output) in outputs.iter_mut().enumerate() {
+            if let Some(pos) = cards.iter().position(|card| Some(card.info.index) == output.info.card) {
+                card_outputs[pos].push((idx, output));
             }
         }
-    }
-    pub fn view(&mut self) -> Element<ConfigureAudioMsg> {
-        let content_view = Container::new(Text::new("Device Profile")).width(Length::Fill);
-        Container::new(
-            Column::new()
+        for ((idx, card), outputs) in cards.iter_mut().enumerate().zip(card_outputs) {
+            let is_changed = card.is_changed();
+            let details = format!("Driver: {}    Bus: {}", card.info.driver, if card.info.bus.is_empty() { "unknown" } else { card.info.bus.as_str() });
+            let mut apply = Button::new(&mut card.apply, Text::new("Apply")).style(ButtonStyle::Default);
+            if is_changed {
+                apply = apply.on_press(ConfigureAudioMsg::ApplyProfile(idx));
+            }
+            let ports = card.info.ports.iter().fold(Column::new().spacing(5), |col, port| {
+                let availability = match port.available {
+                    Some(true) => "plugged in",
+                    Some(false) => "unplugged",
+                    None => "availability unknown",
+                };
+                col.push(Row::new().spacing(10).push(Text::new(&port.description).width(Length::Fill)).push(Text::new(availability).size(12)))
+            });
+            let card_outputs = outputs.into_iter().fold(Column::new().spacing(10), |col, (idx, output)| {
+                let mut output_col = Column::new().spacing(5).push(Text::new(&output.info.description)).push(Text::new(format!("Sample format: {}", output.info.sample_spec)).size(12));
+                // Balance only makes sense with separate left and right channels.
+                if output.info.channel_map.iter().any(|position| position.contains("left")) && output.info.channel_map.iter().any(|position| position.contains("right")) {
+                    output_col = output_col.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Align::Center)
+                            .push(Text::new("Left"))
+                            .push(
+                                Slider::new(&mut output.balance_state, -100.0..=100.0, output.balance, move |val| ConfigureAudioMsg::BalanceChanged(idx, val))
+                                    .step(1.0)
+                                    .style(SliderStyle::Circle(10.0))
+                                    .width(Length::Fill),
+                            )
+                            .push(Text::new("Right")),
+                    );
+                }
+                col.push(output_col)
+            });
+            let card_view = Column::new()
                 .spacing(10)
-                .push(content_view.center_x())
-                .push(Row::new().align_items(Align::Center).push(card_icon()).push(Text::new("Built-in Audio Card")))
+                .push(Row::new().spacing(10).align_items(Align::Center).push(card_icon()).push(Text::new(&card.info.description)))
+                .push(Text::new(details).size(12))
                 .push(
                     Row::new()
                         .spacing(10)
                         .align_items(Align::Center)
                         .push(Text::new("Profile"))
-                        // .style(ButtonStyle::Transparent)
                         .push(
-                            PickList::new(&mut self.pick_profiles, &self.profile_data, Some(self.select_pick.clone()), ConfigureAudioMsg::ProfileChanged)
+                            PickList::new(&mut card.pick_profiles, &card.profiles, card.selected.clone(), move |profile| ConfigureAudioMsg::ProfileChanged(idx, profile))
                                 .width(Length::Fill)
                                 .padding(10)
                                 .style(PickListStyle {}),
-                        ),
-                ),
-        )
-        .style(ContainerStyle::LightGrayCircle)
-        .padding(10)
-        .width(Length::Fill)
-        .into()
+                        )
+                        .push(apply),
+                )
+                .push(Text::new("Ports"))
+                .push(ports)
+                .push(Text::new("Outputs"))
+                .push(card_outputs);
+            col = col.push(Container::new(card_view).style(ContainerStyle::LightGrayCircle).padding(10).width(Length::Fill));
+        }
+        col.push(equalizer.view().map(ConfigureAudioMsg::EqualizerMsg)).into()
+    }
+}
+
+/// Balance of the left and right channels from -100 (left only) to 100 (right only).
+fn balance(channel_map: &[String], volumes: &[f64]) -> f64 {
+    let side = |side: &str| channel_map.iter().zip(volumes).filter(|(position, _)| position.contains(side)).map(|(_, volume)| *volume).fold(0.0, f64::max);
+    let (left, right) = (side("left"), side("right"));
+    if left == right || left.max(right) == 0.0 {
+        0.0
+    } else if left < right {
+        ((1.0 - left / right) * 100.0).round()
+    } else {
+        ((right / left - 1.0) * 100.0).round()
     }
 }
+
+/// Channel volumes for a balance, keeping the loudest channel at its volume.
+fn balanced_volumes(channel_map: &[String], volumes: &[f64], balance: f64) -> Vec<f64> {
+    let max = volumes.iter().cloned().fold(0.0, f64::max);
+    channel_map
+        .iter()
+        .map(|position| {
+            if position.contains("left") {
+                max * (1.0 - balance.max(0.0) / 100.0)
+            } else if position.contains("right") {
+                max * (1.0 + balance.min(0.0) / 100.0)
+            } else {
+                max
+            }
+        })
+        .collect()
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct ProfileList {
+    pub name: String,
     pub profile: String,
+    pub available: bool,
+}
+impl From<&server::CardProfile> for ProfileList {
+    fn from(profile: &server::CardProfile) -> Self {
+        Self {
+            name: profile.name.clone(),
+            profile: profile.description.clone(),
+            available: profile.available,
+        }
+    }
 }
 impl fmt::Display for ProfileList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.profile)
+        if self.available {
+            write!(f, "{}", self.profile)
+        } else {
+            write!(f, "{} (unavailable)", self.profile)
+        }
     }
 }
 const ICONS: Font = Font::External {
@@ -100,3 +250,18 @@ fn icon(unicode: char) -> Text {
 fn card_icon() -> Text {
     icon('\u{f7c2}')
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balance() {
+        let channel_map = vec![String::from("front-left"), String::from("front-right")];
+        assert_eq!(balance(&channel_map, &[80.0, 80.0]), 0.0);
+        assert_eq!(balance(&channel_map, &[40.0, 80.0]), 50.0);
+        assert_eq!(balance(&channel_map, &[80.0, 20.0]), -75.0);
+        assert_eq!(balanced_volumes(&channel_map, &[40.0, 80.0], -50.0), vec![80.0, 40.0]);
+        assert_eq!(balanced_volumes(&channel_map, &[80.0, 80.0], 0.0), vec![80.0, 80.0]);
+    }
+}
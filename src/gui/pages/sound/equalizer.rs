@@ -0,0 +1,248 @@
+use super::filter_chain::{DeviceEq, EqualizerConf, Preset, BANDS, MAX_GAIN};
+use super::server::ServerDevice;
+use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle, picklist::PickListStyle, sliders::SliderStyle, textinput::InputStyle};
+use crate::helpers::authority;
+use iced::{button, pick_list, slider, text_input, Align, Button, Column, Command, Container, Element, HorizontalAlignment, Length, PickList, Row, Slider, Text, TextInput};
+use iced_custom_widget as icw;
+use icw::components::Toggler;
+use std::collections::HashMap;
+use std::fmt;
+
+/// An output device the equalizer can be put in front of.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EqOutput {
+    pub name: String,
+    pub description: String,
+}
+impl fmt::Display for EqOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", if self.description.is_empty() { &self.name } else { &self.description })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum EqualizerMsg {
+    OutputChanged(EqOutput),
+    EnableToggled(bool),
+    PresetChanged(Preset),
+    GainChanged(usize, f64),
+    LoudnessToggled(bool),
+    PresetNameChanged(String),
+    SavePreset,
+    DeletePreset,
+    Apply,
+    Applied(Result<(), String>),
+}
+
+#[derive(Default)]
+pub struct Equalizer {
+    conf: EqualizerConf,
+    outputs: Vec<EqOutput>,
+    selected: Option<EqOutput>,
+    /// Settings of the selected output, saved to `conf` when applied.
+    current: DeviceEq,
+    presets: Vec<Preset>,
+    preset_name: String,
+    is_changed: bool,
+    is_applying: bool,
+    status: Option<String>,
+    output_pick: pick_list::State<EqOutput>,
+    preset_pick: pick_list::State<Preset>,
+    band_states: [slider::State; 10],
+    preset_name_state: text_input::State,
+    save_state: button::State,
+    delete_state: button::State,
+    apply_state: button::State,
+}
+
+impl Equalizer {
+    pub fn new() -> Self {
+        let conf = EqualizerConf::load();
+        Self {
+            presets: conf.all_presets(),
+            conf,
+            ..Self::default()
+        }
+    }
+
+    /// Update the output devices, keeping the selection when the device still exists.
+    pub fn set_outputs(&mut self, devices: &[ServerDevice]) {
+        self.outputs = devices
+            .iter()
+            .filter(|dev| dev.card.is_some())
+            .map(|dev| EqOutput {
+                name: dev.name.clone(),
+                description: dev.description.clone(),
+            })
+            .collect();
+        let selected = self.selected.as_ref().and_then(|selected| self.outputs.iter().find(|output| output.name == selected.name)).or_else(|| self.outputs.first()).cloned();
+        if selected != self.selected {
+            self.select(selected);
+        }
+    }
+
+    fn select(&mut self, output: Option<EqOutput>) {
+        self.current = output.as_ref().map(|output| self.conf.device(&output.name)).unwrap_or_default();
+        self.selected = output;
+        self.is_changed = false;
+    }
+
+    pub fn update(&mut self, msg: EqualizerMsg) -> Command<EqualizerMsg> {
+        match msg {
+            EqualizerMsg::OutputChanged(output) => self.select(Some(output)),
+            EqualizerMsg::EnableToggled(is_enabled) => {
+                self.current.is_enabled = is_enabled;
+                self.is_changed = true;
+            }
+            EqualizerMsg::PresetChanged(preset) => {
+                self.current.gains = preset.gains.clone();
+                self.current.preset = preset.name.clone();
+                self.preset_name = preset.name;
+                self.is_changed = true;
+            }
+            EqualizerMsg::GainChanged(idx, gain) => {
+                if let Some(val) = self.current.gains.get_mut(idx) {
+                    *val = gain;
+                    self.current.preset.clear();
+                    self.is_changed = true;
+                }
+            }
+            EqualizerMsg::LoudnessToggled(loudness) => {
+                self.current.loudness = loudness;
+                self.is_changed = true;
+            }
+            EqualizerMsg::PresetNameChanged(name) => self.preset_name = name,
+            EqualizerMsg::SavePreset => {
+                self.status = match self.conf.save_preset(&self.preset_name, &self.current.gains) {
+                    Ok(_) => {
+                        self.current.preset = self.preset_name.trim().to_string();
+                        self.presets = self.conf.all_presets();
+                        self.is_changed = true;
+                        None
+                    }
+                    Err(e) => Some(e),
+                };
+            }
+            EqualizerMsg::DeletePreset => {
+                self.conf.delete_preset(&self.current.preset);
+                self.presets = self.conf.all_presets();
+                self.current.preset.clear();
+                self.is_changed = true;
+            }
+            EqualizerMsg::Apply => {
+                if let Some(output) = &self.selected {
+                    self.conf.devices.insert(output.name.clone(), self.current.clone());
+                    let descriptions: HashMap<String, String> = self.outputs.iter().map(|output| (output.name.clone(), output.to_string())).collect();
+                    // Restarting the filter chains and waiting for their sinks takes a moment.
+                    let conf = self.conf.clone();
+                    self.is_applying = true;
+                    self.is_changed = false;
+                    return Command::perform(authority::in_background(move || conf.apply(&descriptions)), EqualizerMsg::Applied);
+                }
+            }
+            EqualizerMsg::Applied(res) => {
+                self.is_applying = false;
+                self.status = match res {
+                    Ok(_) => None,
+                    Err(e) => {
+                        self.is_changed = true;
+                        Some(format!("Failed to apply the equalizer: {}", e))
+                    }
+                };
+            }
+        }
+        Command::none()
+    }
+
+    pub fn view(&mut self) -> Element<EqualizerMsg> {
+        let Equalizer {
+            outputs,
+            selected,
+            current,
+            presets,
+            preset_name,
+            is_changed,
+            is_applying,
+            status,
+            output_pick,
+            preset_pick,
+            band_states,
+            preset_name_state,
+            save_state,
+            delete_state,
+            apply_state,
+            ..
+        } = self;
+        let selected_preset = presets.iter().find(|preset| preset.name == current.preset).cloned();
+        let is_custom_preset = !current.preset.is_empty() && !super::filter_chain::builtin_presets().iter().any(|preset| preset.name == current.preset);
+        let mut col = Column::new()
+            .spacing(10)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new("Equalizer").width(Length::Fill))
+                    .push(Toggler::new(current.is_enabled, String::from(""), EqualizerMsg::EnableToggled)),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new("Output").width(Length::Units(80)))
+                    .push(PickList::new(output_pick, &*outputs, selected.clone(), EqualizerMsg::OutputChanged).style(PickListStyle {}).width(Length::Fill)),
+            );
+        let mut preset_row = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(Text::new("Preset").width(Length::Units(80)))
+            .push(PickList::new(preset_pick, &*presets, selected_preset, EqualizerMsg::PresetChanged).style(PickListStyle {}).width(Length::Fill));
+        let mut delete = Button::new(delete_state, Text::new("Delete")).style(ButtonStyle::Default);
+        if is_custom_preset {
+            delete = delete.on_press(EqualizerMsg::DeletePreset);
+        }
+        preset_row = preset_row.push(delete);
+        col = col.push(preset_row);
+        let bands = band_states.iter_mut().zip(current.gains.iter()).enumerate().fold(Column::new().spacing(5), |col, (idx, (state, gain))| {
+            let freq = BANDS[idx];
+            let label = if freq >= 1000.0 { format!("{} kHz", freq / 1000.0) } else { format!("{} Hz", freq) };
+            col.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(label).size(12).width(Length::Units(60)))
+                    .push(
+                        Slider::new(state, -MAX_GAIN..=MAX_GAIN, *gain, move |val| EqualizerMsg::GainChanged(idx, val))
+                            .step(0.5)
+                            .style(SliderStyle::Circle(10.0))
+                            .width(Length::Fill),
+                    )
+                    .push(Text::new(format!("{:+.1} dB", gain)).size(12).horizontal_alignment(HorizontalAlignment::Right).width(Length::Units(60))),
+            )
+        });
+        let mut apply = Button::new(apply_state, Text::new("Apply")).style(ButtonStyle::Default);
+        if *is_changed && !*is_applying && selected.is_some() {
+            apply = apply.on_press(EqualizerMsg::Apply);
+        }
+        col = col
+            .push(bands)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new("Loudness normalization").width(Length::Fill))
+                    .push(Toggler::new(current.loudness, String::from(""), EqualizerMsg::LoudnessToggled)),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(TextInput::new(preset_name_state, "Preset name", preset_name, EqualizerMsg::PresetNameChanged).padding(6).style(InputStyle::InkBorder).width(Length::Fill))
+                    .push(Button::new(save_state, Text::new("Save Preset")).on_press(EqualizerMsg::SavePreset).style(ButtonStyle::Default))
+                    .push(apply),
+            );
+        if let Some(status) = status {
+            col = col.push(Text::new(status.as_str()).size(12));
+        }
+        Container::new(col).style(ContainerStyle::LightGrayCircle).padding(10).width(Length::Fill).into()
+    }
+}
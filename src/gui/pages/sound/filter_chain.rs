@@ -0,0 +1,442 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// Center frequencies of the equalizer bands in Hz.
+pub const BANDS: [f64; 10] = [31.0, 63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0];
+/// Largest boost or cut of a band in dB.
+pub const MAX_GAIN: f64 = 12.0;
+/// Prefix of the names of the sinks created in front of the output devices.
+const SINK_PREFIX: &str = "system_settings_eq";
+/// Bands of the `mbeq` LADSPA plugin used with PulseAudio.
+const MBEQ_BANDS: [f64; 15] = [50.0, 100.0, 156.0, 220.0, 311.0, 440.0, 622.0, 880.0, 1250.0, 1750.0, 2500.0, 3500.0, 5000.0, 10000.0, 20000.0];
+/// Controls of the `sc4m` LADSPA compressor used for loudness: RMS/peak, attack (ms), release (ms), threshold (dB),
+/// ratio, knee (dB) and makeup gain (dB).
+const LOUDNESS: [(&str, f64); 7] = [
+    ("RMS/peak", 0.5),
+    ("Attack time (ms)", 10.0),
+    ("Release time (ms)", 200.0),
+    ("Threshold level (dB)", -20.0),
+    ("Ratio (1:n)", 3.0),
+    ("Knee radius (dB)", 6.0),
+    ("Makeup gain (dB)", 8.0),
+];
+const PULSE_BLOCK_BEGIN: &str = "### system-settings equalizer begin";
+const PULSE_BLOCK_END: &str = "### system-settings equalizer end";
+
+/// Gains of the equalizer bands in dB.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub gains: Vec<f64>,
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub fn builtin_presets() -> Vec<Preset> {
+    vec![
+        Preset {
+            name: String::from("Flat"),
+            gains: vec![0.0; BANDS.len()],
+        },
+        Preset {
+            name: String::from("Voice"),
+            gains: vec![-6.0, -4.0, -2.0, 0.0, 2.0, 4.0, 5.0, 4.0, 2.0, 0.0],
+        },
+        Preset {
+            name: String::from("Bass Boost"),
+            gains: vec![7.0, 6.0, 5.0, 3.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        },
+    ]
+}
+
+/// Equalizer settings of an output device.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceEq {
+    pub is_enabled: bool,
+    /// Name of the preset the gains come from, empty once the gains were changed by hand.
+    pub preset: String,
+    pub gains: Vec<f64>,
+    pub loudness: bool,
+}
+
+impl Default for DeviceEq {
+    fn default() -> Self {
+        Self {
+            is_enabled: false,
+            preset: String::from("Flat"),
+            gains: vec![0.0; BANDS.len()],
+            loudness: false,
+        }
+    }
+}
+
+/// Equalizer settings of the user, persisted by System Settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EqualizerConf {
+    /// Settings by name of the output device.
+    pub devices: HashMap<String, DeviceEq>,
+    /// Presets saved by the user.
+    pub presets: Vec<Preset>,
+}
+
+impl EqualizerConf {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("system_settings/equalizer.json"))
+    }
+
+    pub fn load() -> Self {
+        Self::path().and_then(|path| std::fs::read_to_string(path).ok()).and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+    }
+
+    pub fn device(&self, name: &str) -> DeviceEq {
+        self.devices.get(name).cloned().unwrap_or_default()
+    }
+
+    /// The built-in presets followed by the presets of the user.
+    pub fn all_presets(&self) -> Vec<Preset> {
+        let mut presets = builtin_presets();
+        presets.extend(self.presets.iter().cloned());
+        presets
+    }
+
+    /// Save the gains as a preset of the user, replacing a preset of the same name.
+    pub fn save_preset(&mut self, name: &str, gains: &[f64]) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(String::from("Please enter a name for the preset"));
+        }
+        if builtin_presets().iter().any(|preset| preset.name == name) {
+            return Err(format!("{} is a built-in preset", name));
+        }
+        let preset = Preset { name: name.to_string(), gains: gains.to_vec() };
+        match self.presets.iter_mut().find(|preset| preset.name == name) {
+            Some(saved) => *saved = preset,
+            None => self.presets.push(preset),
+        }
+        Ok(())
+    }
+
+    pub fn delete_preset(&mut self, name: &str) {
+        self.presets.retain(|preset| preset.name != name);
+    }
+
+    fn enabled(&self) -> Vec<(&String, &DeviceEq)> {
+        let mut devices: Vec<_> = self.devices.iter().filter(|(_, eq)| eq.is_enabled).collect();
+        devices.sort_by(|a, b| a.0.cmp(b.0));
+        devices
+    }
+
+    /// Save the settings and load the filters into the sound server. `descriptions` maps the names of the output
+    /// devices to the names shown to the user.
+    pub fn apply(&self, descriptions: &HashMap<String, String>) -> Result<(), String> {
+        if let Some(path) = Self::path() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+            }
+            std::fs::write(&path, serde_json::to_string_pretty(self).unwrap_or_default()).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        if is_pipewire() {
+            self.apply_pipewire(descriptions)?;
+        } else {
+            self.apply_pulseaudio(descriptions)?;
+        }
+        self.route_default_sink();
+        Ok(())
+    }
+
+    /// PipeWire runs the filter chains in the `filter-chain` user service, which is enabled so that they are loaded
+    /// again after a reboot.
+    fn apply_pipewire(&self, descriptions: &HashMap<String, String>) -> Result<(), String> {
+        let dir = dirs::config_dir().ok_or_else(|| String::from("Unknown config directory"))?.join("pipewire/filter-chain.conf.d");
+        let path = dir.join("system-settings-equalizer.conf");
+        if self.enabled().is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+        } else {
+            std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+            std::fs::write(&path, self.pipewire_conf(descriptions)).map_err(|e| format!("{}: {}", path.display(), e))?;
+            systemctl(&["enable", "filter-chain.service"])?;
+        }
+        systemctl(&["restart", "filter-chain.service"])
+    }
+
+    /// Configuration of the `filter-chain` service with a chain of biquad filters per enabled device.
+    pub fn pipewire_conf(&self, descriptions: &HashMap<String, String>) -> String {
+        let mut conf = String::from("# Written by System Settings, changes will be overwritten.\ncontext.modules = [\n");
+        for (device, eq) in self.enabled() {
+            let mut nodes = Vec::new();
+            for (idx, (freq, gain)) in BANDS.iter().zip(eq.gains.iter()).enumerate() {
+                let label = match idx {
+                    0 => "bq_lowshelf",
+                    idx if idx == BANDS.len() - 1 => "bq_highshelf",
+                    _ => "bq_peaking",
+                };
+                nodes.push((
+                    format!("band{}", idx + 1),
+                    format!("{{ type = builtin name = band{} label = {} control = {{ \"Freq\" = {:.1} \"Q\" = 1.0 \"Gain\" = {:.1} }} }}", idx + 1, label, freq, gain),
+                    ("In", "Out"),
+                ));
+            }
+            if eq.loudness {
+                let controls: Vec<String> = LOUDNESS.iter().map(|(name, val)| format!("\"{}\" = {:.1}", name, val)).collect();
+                nodes.push((
+                    String::from("loudness"),
+                    format!("{{ type = ladspa name = loudness plugin = sc4m_1916 label = sc4m control = {{ {} }} }}", controls.join(" ")),
+                    ("Input", "Output"),
+                ));
+            }
+            let links: Vec<String> = nodes.windows(2).map(|pair| format!("{{ output = \"{}:{}\" input = \"{}:{}\" }}", pair[0].0, (pair[0].2).1, pair[1].0, (pair[1].2).0)).collect();
+            let description = descriptions.get(device).unwrap_or(device);
+            conf.push_str("  { name = libpipewire-module-filter-chain\n    args = {\n");
+            conf.push_str(&format!("      node.description = \"{} (Equalizer)\"\n", description.replace('\\', "\\\\").replace('"', "\\\"")));
+            conf.push_str("      filter.graph = {\n        nodes = [\n");
+            for (_, node, _) in nodes.iter() {
+                conf.push_str(&format!("          {}\n", node));
+            }
+            conf.push_str("        ]\n        links = [\n");
+            for link in links.iter() {
+                conf.push_str(&format!("          {}\n", link));
+            }
+            conf.push_str("        ]\n      }\n      audio.channels = 2\n      audio.position = [ FL FR ]\n");
+            conf.push_str(&format!("      capture.props = {{ node.name = \"{}\" media.class = Audio/Sink }}\n", sink_name(device)));
+            conf.push_str(&format!("      playback.props = {{ node.name = \"{}.output\" node.passive = true target.object = \"{}\" }}\n", sink_name(device), device));
+            conf.push_str("    }\n  }\n");
+        }
+        conf.push_str("]\n");
+        conf
+    }
+
+    /// PulseAudio loads a LADSPA sink per filter. The modules are loaded right away and written to the user's
+    /// `default.pa` to be loaded again after a reboot.
+    fn apply_pulseaudio(&self, descriptions: &HashMap<String, String>) -> Result<(), String> {
+        let output = Command::new("pactl").args(&["list", "short", "modules"]).output().map_err(|e| e.to_string())?;
+        // Unload the filters in front of the others, as a sink can't be unloaded while it is the master of another.
+        let mut loaded: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| line.contains(&format!("sink_name={}.", SINK_PREFIX)))
+            .filter_map(|line| line.split('\t').next().map(String::from))
+            .collect();
+        loaded.reverse();
+        for module in loaded {
+            pactl(&["unload-module", &module])?;
+        }
+        let modules = self.pulse_modules(descriptions);
+        for args in modules.iter() {
+            let mut cmd = vec!["load-module"];
+            cmd.extend(args.iter().map(String::as_str));
+            pactl(&cmd)?;
+        }
+        let path = dirs::config_dir().ok_or_else(|| String::from("Unknown config directory"))?.join("pulse/default.pa");
+        let content = std::fs::read_to_string(&path).unwrap_or_else(|_| String::from(".include /etc/pulse/default.pa\n"));
+        let mut lines: Vec<String> = Vec::new();
+        let mut in_block = false;
+        for line in content.lines() {
+            match line {
+                PULSE_BLOCK_BEGIN => in_block = true,
+                PULSE_BLOCK_END => in_block = false,
+                line if !in_block => lines.push(line.to_string()),
+                _ => {}
+            }
+        }
+        if !modules.is_empty() {
+            lines.push(String::from(PULSE_BLOCK_BEGIN));
+            lines.extend(modules.iter().map(|args| format!("load-module {}", args.join(" "))));
+            lines.push(String::from(PULSE_BLOCK_END));
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&path, lines.join("\n") + "\n").map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Arguments of the `module-ladspa-sink` modules: the `mbeq` equalizer, followed by the `sc4m` compressor in
+    /// front of it when loudness is enabled.
+    pub fn pulse_modules(&self, descriptions: &HashMap<String, String>) -> Vec<Vec<String>> {
+        let mut modules = Vec::new();
+        for (device, eq) in self.enabled() {
+            let description = descriptions.get(device).unwrap_or(device);
+            let eq_sink = if eq.loudness { format!("{}.bands", sink_name(device)) } else { sink_name(device) };
+            let gains: Vec<String> = MBEQ_BANDS.iter().map(|freq| format!("{:.1}", interpolate(&eq.gains, *freq))).collect();
+            modules.push(vec![
+                String::from("module-ladspa-sink"),
+                format!("sink_name={}", eq_sink),
+                format!("sink_master={}", device),
+                sink_properties(description),
+                String::from("plugin=mbeq_1197"),
+                String::from("label=mbeq"),
+                format!("control={}", gains.join(",")),
+            ]);
+            if eq.loudness {
+                let controls: Vec<String> = LOUDNESS.iter().map(|(_, val)| val.to_string()).collect();
+                modules.push(vec![
+                    String::from("module-ladspa-sink"),
+                    format!("sink_name={}", sink_name(device)),
+                    format!("sink_master={}", eq_sink),
+                    sink_properties(description),
+                    String::from("plugin=sc4m_1916"),
+                    String::from("label=sc4m"),
+                    format!("control={}", controls.join(",")),
+                ]);
+            }
+        }
+        modules
+    }
+
+    /// Send the sound of the default output through its equalizer, or back to the device once it is disabled.
+    fn route_default_sink(&self) {
+        let default = match Command::new("pactl").arg("get-default-sink").output() {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            Err(_) => return,
+        };
+        let device = default.strip_prefix(&format!("{}.", SINK_PREFIX)).map(|device| device.trim_end_matches(".bands").to_string()).unwrap_or_else(|| default.clone());
+        let target = if self.device(&device).is_enabled { sink_name(&device) } else { device };
+        if target == default {
+            return;
+        }
+        // The filter-chain service needs a moment to create the sinks after a restart.
+        for _ in 0..10 {
+            if pactl(&["set-default-sink", &target]).is_ok() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(200));
+        }
+        eprintln!("{} did not appear", target);
+    }
+}
+
+/// Name of the sink in front of an output device.
+pub fn sink_name(device: &str) -> String {
+    format!("{}.{}", SINK_PREFIX, device)
+}
+
+/// `sink_properties` argument naming a sink after its device. The value is unescaped twice, first as a module
+/// argument in double quotes, then as a property in single quotes.
+fn sink_properties(description: &str) -> String {
+    let mut value = String::new();
+    for c in format!("{} (Equalizer)", description).chars() {
+        match c {
+            '\\' => value.push_str(r"\\\\"),
+            '\'' => value.push_str(r"\\'"),
+            '"' => value.push_str(r#"\""#),
+            c => value.push(c),
+        }
+    }
+    format!("sink_properties=\"device.description='{}'\"", value)
+}
+
+/// Gain at a frequency, interpolated on a logarithmic scale between the bands.
+fn interpolate(gains: &[f64], freq: f64) -> f64 {
+    let points: Vec<(f64, f64)> = BANDS.iter().map(|freq| freq.log2()).zip(gains.iter().cloned()).collect();
+    let x = freq.log2();
+    match points.iter().position(|(band, _)| *band >= x) {
+        Some(0) => points[0].1,
+        Some(idx) => {
+            let ((x0, y0), (x1, y1)) = (points[idx - 1], points[idx]);
+            y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+        }
+        None => points.last().map(|(_, gain)| *gain).unwrap_or_default(),
+    }
+}
+
+fn is_pipewire() -> bool {
+    Command::new("pactl")
+        .arg("info")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).lines().any(|line| line.starts_with("Server Name:") && line.contains("PipeWire")))
+        .unwrap_or(false)
+}
+
+fn pactl(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("pactl").args(args).output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn systemctl(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("systemctl").arg("--user").args(args).output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conf(loudness: bool) -> EqualizerConf {
+        let mut conf = EqualizerConf::default();
+        conf.devices.insert(
+            String::from("alsa_output.analog-stereo"),
+            DeviceEq {
+                is_enabled: true,
+                preset: String::from("Bass Boost"),
+                gains: builtin_presets()[2].gains.clone(),
+                loudness,
+            },
+        );
+        conf.devices.insert(String::from("alsa_output.hdmi-stereo"), DeviceEq::default());
+        conf
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let gains = vec![10.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -10.0];
+        assert_eq!(interpolate(&gains, 20.0), 10.0);
+        assert_eq!(interpolate(&gains, 20000.0), -10.0);
+        assert_eq!(interpolate(&gains, 1000.0), 0.0);
+        let mid = interpolate(&gains, 44.0);
+        assert!(mid > 0.0 && mid < 10.0);
+    }
+
+    #[test]
+    fn test_pipewire_conf() {
+        let content = conf(true).pipewire_conf(&HashMap::new());
+        assert_eq!(content.matches("libpipewire-module-filter-chain").count(), 1);
+        assert!(content.contains("label = bq_lowshelf control = { \"Freq\" = 31.0 \"Q\" = 1.0 \"Gain\" = 7.0 }"));
+        assert!(content.contains("{ output = \"band10:Out\" input = \"loudness:Input\" }"));
+        assert!(content.contains("node.name = \"system_settings_eq.alsa_output.analog-stereo\" media.class = Audio/Sink"));
+        assert!(content.contains("target.object = \"alsa_output.analog-stereo\""));
+        assert!(!conf(false).pipewire_conf(&HashMap::new()).contains("loudness"));
+        let descriptions: HashMap<String, String> = [(String::from("alsa_output.analog-stereo"), String::from(r#"The "USB" Speaker"#))].iter().cloned().collect();
+        assert!(conf(false).pipewire_conf(&descriptions).contains(r#"node.description = "The \"USB\" Speaker (Equalizer)""#));
+    }
+
+    #[test]
+    fn test_pulse_modules() {
+        let modules = conf(true).pulse_modules(&HashMap::new());
+        assert_eq!(modules.len(), 2);
+        assert!(modules[0].contains(&String::from("sink_name=system_settings_eq.alsa_output.analog-stereo.bands")));
+        assert!(modules[1].contains(&String::from("sink_master=system_settings_eq.alsa_output.analog-stereo.bands")));
+        assert_eq!(conf(false).pulse_modules(&HashMap::new()).len(), 1);
+        let descriptions: HashMap<String, String> = [(String::from("alsa_output.analog-stereo"), String::from(r#"Sokha's "USB" Speaker"#))].iter().cloned().collect();
+        let modules = conf(false).pulse_modules(&descriptions);
+        assert!(modules[0].contains(&String::from(r#"sink_properties="device.description='Sokha\\'s \"USB\" Speaker (Equalizer)'""#)));
+    }
+
+    #[test]
+    fn test_save_preset() {
+        let mut conf = EqualizerConf::default();
+        assert!(conf.save_preset("Flat", &[0.0; 10]).is_err());
+        assert!(conf.save_preset(" ", &[0.0; 10]).is_err());
+        conf.save_preset("Classroom", &[1.0; 10]).unwrap();
+        conf.save_preset("Classroom", &[2.0; 10]).unwrap();
+        assert_eq!(conf.presets.len(), 1);
+        assert_eq!(conf.all_presets().last().map(|preset| preset.gains[0]), Some(2.0));
+    }
+}
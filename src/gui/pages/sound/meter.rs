@@ -0,0 +1,106 @@
+use crate::helpers::device_access::METER_CLIENT_NAME;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const RATE: usize = 8000;
+// 50ms of mono signed 16 bit samples.
+const CHUNK_BYTES: usize = RATE / 20 * 2;
+/// The page reads the peak while its subscription runs. Without a read for this long, the recording is stopped.
+const UNREAD_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// State shared with the thread reading the recording stream.
+#[derive(Debug)]
+struct MeterState {
+    peak: f32,
+    last_read: Instant,
+    is_stopped: bool,
+}
+
+/// Peak level of a source, read from a recording stream of the sound server in a background thread.
+#[derive(Debug, Default)]
+pub struct LevelMeter {
+    state: Option<Arc<Mutex<MeterState>>>,
+}
+
+impl LevelMeter {
+    pub fn is_running(&self) -> bool {
+        self.state.as_ref().and_then(|state| state.lock().ok().map(|state| !state.is_stopped)).unwrap_or(false)
+    }
+
+    pub fn start(&mut self, source: &str) -> Result<(), String> {
+        self.stop();
+        let mut child = Command::new("parec")
+            .args(&[
+                &format!("--device={}", source),
+                &format!("--client-name={}", METER_CLIENT_NAME),
+                "--raw",
+                "--format=s16le",
+                "--channels=1",
+                &format!("--rate={}", RATE),
+                "--latency-msec=50",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run parec: {}", e))?;
+        let mut stdout = child.stdout.take().ok_or_else(|| String::from("Failed to read the recording stream"))?;
+        let state = Arc::new(Mutex::new(MeterState {
+            peak: 0.0,
+            last_read: Instant::now(),
+            is_stopped: false,
+        }));
+        let thread_state = Arc::clone(&state);
+        std::thread::spawn(move || {
+            let mut buf = [0u8; CHUNK_BYTES];
+            while stdout.read_exact(&mut buf).is_ok() {
+                let chunk_peak = buf.chunks_exact(2).map(|bytes| (i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32).abs()).fold(0.0, f32::max);
+                let mut state = match thread_state.lock() {
+                    Ok(state) => state,
+                    Err(_) => break,
+                };
+                // Stopped by the page, or the page went away without stopping it.
+                if state.is_stopped || state.last_read.elapsed() > UNREAD_TIMEOUT {
+                    break;
+                }
+                // Rise immediately, fall slowly so short peaks stay visible.
+                state.peak = chunk_peak.max(state.peak * 0.7);
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+            if let Ok(mut state) = thread_state.lock() {
+                state.is_stopped = true;
+                state.peak = 0.0;
+            }
+        });
+        self.state = Some(state);
+        Ok(())
+    }
+
+    /// Ask the reading thread to end the recording, which it does after the next chunk.
+    pub fn stop(&mut self) {
+        if let Some(state) = self.state.take() {
+            if let Ok(mut state) = state.lock() {
+                state.is_stopped = true;
+            }
+        }
+    }
+
+    /// The current peak between 0 and 1.
+    pub fn peak(&self) -> f32 {
+        match self.state.as_ref().map(|state| state.lock()) {
+            Some(Ok(mut state)) => {
+                state.last_read = Instant::now();
+                state.peak
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+impl Drop for LevelMeter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
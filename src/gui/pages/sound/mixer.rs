@@ -0,0 +1,266 @@
+use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle, picklist::PickListStyle, sliders::SliderStyle};
+use crate::helpers::device_access::METER_CLIENT_NAME;
+use crate::helpers::resolve_icon;
+use iced::{button, pick_list, slider, Align, Button, Column, Container, Element, HorizontalAlignment, Image, Length, PickList, Row, Slider, Svg, Text};
+use libkoompi::system_settings::sounds::controllers::{AppControl, DeviceControl, SinkController, SourceController};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Volume of a stream at 100% as used by the sound server (`PA_VOLUME_NORM`).
+const VOLUME_NORM: f64 = 65536.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    Playback,
+    Recording,
+}
+
+/// A sink or source a stream can be moved to.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StreamDevice {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+}
+impl fmt::Display for StreamDevice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", if self.description.is_empty() { &self.name } else { &self.description })
+    }
+}
+
+/// A sink-input (playback) or source-output (recording) of an application.
+#[derive(Default)]
+pub struct Stream {
+    index: u32,
+    app_name: String,
+    icon: Option<PathBuf>,
+    volume: f64,
+    is_muted: bool,
+    device_index: u32,
+    mute_state: button::State,
+    volume_state: slider::State,
+    device_state: pick_list::State<StreamDevice>,
+}
+
+#[derive(Debug, Clone)]
+pub enum MixerMsg {
+    VolumeChanged(StreamKind, u32, f64),
+    MuteToggled(StreamKind, u32),
+    DeviceChanged(StreamKind, u32, StreamDevice),
+}
+
+#[derive(Default)]
+pub struct Mixer {
+    playback: Vec<Stream>,
+    recording: Vec<Stream>,
+    sinks: Vec<StreamDevice>,
+    sources: Vec<StreamDevice>,
+}
+
+impl Mixer {
+    pub fn new(sink: &mut SinkController, source: &mut SourceController) -> Self {
+        let mut mixer = Self::default();
+        mixer.refresh(sink, source);
+        mixer
+    }
+
+    /// Re-read the streams from the sound server, keeping the widget state of the streams that still exist.
+    pub fn refresh(&mut self, sink: &mut SinkController, source: &mut SourceController) {
+        match sink.list_devices() {
+            Ok(devices) => {
+                self.sinks = devices
+                    .into_iter()
+                    .map(|dev| StreamDevice {
+                        index: dev.index,
+                        name: dev.name.unwrap_or_default(),
+                        description: dev.description.unwrap_or_default(),
+                    })
+                    .collect()
+            }
+            Err(e) => eprintln!("Error: {:?}", e),
+        }
+        match source.list_devices() {
+            Ok(devices) => {
+                self.sources = devices
+                    .into_iter()
+                    .map(|dev| StreamDevice {
+                        index: dev.index,
+                        name: dev.name.unwrap_or_default(),
+                        description: dev.description.unwrap_or_default(),
+                    })
+                    .collect()
+            }
+            Err(e) => eprintln!("Error: {:?}", e),
+        }
+        match sink.list_applications() {
+            Ok(apps) => merge_streams(
+                &mut self.playback,
+                apps.into_iter()
+                    .map(|app| {
+                        (
+                            app.index,
+                            app.proplist.get_str("application.name").or(app.name),
+                            app.proplist.get_str("application.icon_name"),
+                            app.volume.avg().0,
+                            app.mute,
+                            app.connection_id,
+                        )
+                    })
+                    .collect(),
+            ),
+            Err(e) => eprintln!("Error: {:?}", e),
+        }
+        match source.list_applications() {
+            Ok(apps) => merge_streams(
+                &mut self.recording,
+                apps.into_iter()
+                    .filter(|app| app.proplist.get_str("application.name").as_deref() != Some(METER_CLIENT_NAME))
+                    .map(|app| {
+                        (
+                            app.index,
+                            app.proplist.get_str("application.name").or(app.name),
+                            app.proplist.get_str("application.icon_name"),
+                            app.volume.avg().0,
+                            app.mute,
+                            app.connection_id,
+                        )
+                    })
+                    .collect(),
+            ),
+            Err(e) => eprintln!("Error: {:?}", e),
+        }
+    }
+
+    pub fn update(&mut self, msg: MixerMsg, sink: &mut SinkController, source: &mut SourceController) {
+        match msg {
+            MixerMsg::VolumeChanged(kind, index, val) => {
+                if let Some(stream) = self.stream_mut(kind, index) {
+                    // AppControl only adjusts stream volumes relatively.
+                    let delta = (val - stream.volume) / 100.0;
+                    match (kind, delta > 0.0) {
+                        (StreamKind::Playback, true) => sink.increase_app_volume_by_percent(index, delta),
+                        (StreamKind::Playback, false) => sink.decrease_app_volume_by_percent(index, -delta),
+                        (StreamKind::Recording, true) => source.increase_app_volume_by_percent(index, delta),
+                        (StreamKind::Recording, false) => source.decrease_app_volume_by_percent(index, -delta),
+                    }
+                    stream.volume = val;
+                }
+            }
+            MixerMsg::MuteToggled(kind, index) => {
+                if let Some(stream) = self.stream_mut(kind, index) {
+                    let is_muted = !stream.is_muted;
+                    let result = match kind {
+                        StreamKind::Playback => sink.set_app_mute(index, is_muted),
+                        StreamKind::Recording => source.set_app_mute(index, is_muted),
+                    };
+                    match result {
+                        Ok(_) => stream.is_muted = is_muted,
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                }
+            }
+            MixerMsg::DeviceChanged(kind, index, device) => {
+                let result = match kind {
+                    StreamKind::Playback => sink.move_app_by_index(index, device.index),
+                    StreamKind::Recording => source.move_app_by_index(index, device.index),
+                };
+                match result {
+                    Ok(_) => {
+                        if let Some(stream) = self.stream_mut(kind, index) {
+                            stream.device_index = device.index;
+                        }
+                    }
+                    Err(e) => eprintln!("Error: {:?}", e),
+                }
+            }
+        }
+    }
+
+    fn stream_mut(&mut self, kind: StreamKind, index: u32) -> Option<&mut Stream> {
+        match kind {
+            StreamKind::Playback => self.playback.iter_mut().find(|stream| stream.index == index),
+            StreamKind::Recording => self.recording.iter_mut().find(|stream| stream.index == index),
+        }
+    }
+
+    pub fn view(&mut self) -> Element<MixerMsg> {
+        let Mixer { playback, recording, sinks, sources } = self;
+        let playback_col = streams_view(StreamKind::Playback, playback, sinks, "No application is playing sound");
+        let recording_col = streams_view(StreamKind::Recording, recording, sources, "No application is recording");
+        Column::new().spacing(10).push(Text::new("Playback").size(14)).push(playback_col).push(Text::new("Recording").size(14)).push(recording_col).into()
+    }
+}
+
+fn merge_streams(streams: &mut Vec<Stream>, infos: Vec<(u32, Option<String>, Option<String>, u32, bool, u32)>) {
+    streams.retain(|stream| infos.iter().any(|info| info.0 == stream.index));
+    for (index, app_name, icon_name, volume, is_muted, device_index) in infos {
+        let pos = match streams.iter().position(|stream| stream.index == index) {
+            Some(pos) => pos,
+            None => {
+                streams.push(Stream {
+                    index,
+                    icon: icon_name.as_deref().and_then(resolve_icon),
+                    ..Stream::default()
+                });
+                streams.len() - 1
+            }
+        };
+        let stream = &mut streams[pos];
+        stream.app_name = app_name.unwrap_or_else(|| String::from("Unknown application"));
+        stream.volume = (volume as f64 / VOLUME_NORM * 100.0).round();
+        stream.is_muted = is_muted;
+        stream.device_index = device_index;
+    }
+}
+
+fn streams_view<'a>(kind: StreamKind, streams: &'a mut Vec<Stream>, devices: &'a [StreamDevice], empty_hint: &str) -> Element<'a, MixerMsg> {
+    if streams.is_empty() {
+        return Container::new(Text::new(empty_hint).size(12)).style(ContainerStyle::LightGrayCircle).width(Length::Fill).padding(10).into();
+    }
+    streams
+        .iter_mut()
+        .fold(Column::new().spacing(10), |col, stream| {
+            let index = stream.index;
+            let icon: Element<_> = match &stream.icon {
+                Some(path) if path.extension().map(|ext| ext == "svg").unwrap_or(false) => Svg::from_path(path).width(Length::Units(24)).height(Length::Units(24)).into(),
+                Some(path) => Image::new(path).width(Length::Units(24)).height(Length::Units(24)).into(),
+                None => Text::new("").width(Length::Units(24)).into(),
+            };
+            let selected_device = devices.iter().find(|dev| dev.index == stream.device_index).cloned();
+            let mute_icon = match (kind, stream.is_muted) {
+                (StreamKind::Playback, false) => super::audio::speaker_icon(),
+                (StreamKind::Playback, true) => super::audio::speaker_icon_mute(),
+                (StreamKind::Recording, false) => super::audio::microphone_icon(),
+                (StreamKind::Recording, true) => super::audio::microphone_icon_mute(),
+            };
+            let stream_view = Column::new()
+                .spacing(10)
+                .push(
+                    Row::new().spacing(10).align_items(Align::Center).push(icon).push(Text::new(&stream.app_name).width(Length::FillPortion(6))).push(
+                        PickList::new(&mut stream.device_state, devices, selected_device, move |dev| MixerMsg::DeviceChanged(kind, index, dev))
+                            .style(PickListStyle {})
+                            .width(Length::FillPortion(3)),
+                    ),
+                )
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(Button::new(&mut stream.mute_state, mute_icon).on_press(MixerMsg::MuteToggled(kind, index)).style(ButtonStyle::Transparent))
+                        .push(
+                            Slider::new(&mut stream.volume_state, 0.0..=150.0, stream.volume, move |val| MixerMsg::VolumeChanged(kind, index, val))
+                                .step(1.0)
+                                .style(SliderStyle::Circle(10.0))
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            Row::new()
+                                .align_items(Align::Center)
+                                .push(Text::new(&stream.volume.to_string()).horizontal_alignment(HorizontalAlignment::Center).width(Length::Units(20)))
+                                .push(Text::new("%")),
+                        ),
+                );
+            col.push(Container::new(stream_view).style(ContainerStyle::LightGrayCircle).width(Length::Fill).padding(10))
+        })
+        .into()
+}
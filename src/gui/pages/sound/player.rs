@@ -0,0 +1,281 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Interleaved 16 bit PCM of a decoded sound file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Decoded {
+    pub rate: u32,
+    pub channels: u16,
+    pub samples: Vec<i16>,
+}
+
+impl Decoded {
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Wav,
+    Ogg,
+    Flac,
+}
+
+fn detect_format(path: &Path) -> Result<Format, String> {
+    let mut magic = [0u8; 4];
+    File::open(path).and_then(|mut file| file.read_exact(&mut magic)).map_err(|e| format!("{}: {}", path.display(), e))?;
+    match &magic {
+        b"RIFF" => Ok(Format::Wav),
+        b"OggS" => Ok(Format::Ogg),
+        b"fLaC" => Ok(Format::Flac),
+        _ => Err(format!("{}: unsupported audio format", path.display())),
+    }
+}
+
+/// Scale an integer sample of `bits` bits to 16 bits.
+fn to_i16(sample: i32, bits: u16) -> i16 {
+    if bits > 16 {
+        (sample >> (bits - 16)) as i16
+    } else {
+        (sample << (16 - bits)) as i16
+    }
+}
+
+/// Decode a WAV, Ogg Vorbis or FLAC file.
+pub fn decode(path: &Path) -> Result<Decoded, String> {
+    let err = |e: String| format!("{}: {}", path.display(), e);
+    match detect_format(path)? {
+        Format::Wav => {
+            let mut reader = hound::WavReader::open(path).map_err(|e| err(e.to_string()))?;
+            let spec = reader.spec();
+            let samples = match spec.sample_format {
+                hound::SampleFormat::Int => reader.samples::<i32>().map(|sample| sample.map(|sample| to_i16(sample, spec.bits_per_sample))).collect::<Result<Vec<_>, _>>(),
+                hound::SampleFormat::Float => reader.samples::<f32>().map(|sample| sample.map(|sample| (sample.max(-1.0).min(1.0) * i16::MAX as f32) as i16)).collect::<Result<Vec<_>, _>>(),
+            }
+            .map_err(|e| err(e.to_string()))?;
+            Ok(Decoded {
+                rate: spec.sample_rate,
+                channels: spec.channels,
+                samples,
+            })
+        }
+        Format::Ogg => {
+            let mut reader = lewton::inside_ogg::OggStreamReader::new(File::open(path).map_err(|e| err(e.to_string()))?).map_err(|e| err(e.to_string()))?;
+            let mut samples = Vec::new();
+            while let Some(packet) = reader.read_dec_packet_itl().map_err(|e| err(e.to_string()))? {
+                samples.extend(packet);
+            }
+            Ok(Decoded {
+                rate: reader.ident_hdr.audio_sample_rate,
+                channels: reader.ident_hdr.audio_channels as u16,
+                samples,
+            })
+        }
+        Format::Flac => {
+            let mut reader = claxon::FlacReader::open(path).map_err(|e| err(e.to_string()))?;
+            let info = reader.streaminfo();
+            let bits = info.bits_per_sample as u16;
+            let samples = reader.samples().map(|sample| sample.map(|sample| to_i16(sample, bits))).collect::<Result<Vec<_>, _>>().map_err(|e| err(e.to_string()))?;
+            Ok(Decoded {
+                rate: info.sample_rate,
+                channels: info.channels as u16,
+                samples,
+            })
+        }
+    }
+}
+
+/// Read `out_frames` frames starting at frame position `pos`, stepping `speed` frames of the source per output frame
+/// with linear interpolation, and scaled by `volume`. Returns the new position.
+pub fn render(decoded: &Decoded, pos: f64, speed: f64, volume: f32, out_frames: usize, out: &mut Vec<i16>) -> f64 {
+    let channels = decoded.channels.max(1) as usize;
+    let frames = decoded.frames();
+    let mut pos = pos;
+    for _ in 0..out_frames {
+        let idx = pos as usize;
+        if idx >= frames {
+            break;
+        }
+        let next = (idx + 1).min(frames - 1);
+        let frac = (pos - idx as f64) as f32;
+        for channel in 0..channels {
+            let a = decoded.samples[idx * channels + channel] as f32;
+            let b = decoded.samples[next * channels + channel] as f32;
+            let sample = (a + (b - a) * frac) * volume;
+            out.push(sample.max(i16::MIN as f32).min(i16::MAX as f32) as i16);
+        }
+        pos += speed;
+    }
+    pos
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Controls {
+    volume: f32,
+    speed: f64,
+    is_paused: bool,
+    is_stopped: bool,
+    is_finished: bool,
+}
+
+/// Handle to a sound played in a background thread through the sound server.
+#[derive(Debug, Clone)]
+pub struct Player {
+    controls: Arc<Mutex<Controls>>,
+}
+
+impl Player {
+    /// Samples are written in small chunks so that volume, speed and pause take effect quickly.
+    const CHUNK: Duration = Duration::from_millis(50);
+
+    /// Start playing a decoded sound. `volume` is a factor (1.0 is unchanged) and `speed` the playback rate. The
+    /// stream has no `event` media role, so that `system_settings_session` doesn't apply the alert volume to it again.
+    pub fn start(decoded: Decoded, volume: f32, speed: f64) -> Result<Self, String> {
+        let mut child = Command::new("pacat")
+            .args(&[
+                String::from("--raw"),
+                String::from("--format=s16le"),
+                format!("--rate={}", decoded.rate),
+                format!("--channels={}", decoded.channels),
+                String::from("--latency-msec=100"),
+            ])
+            .stdin(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run pacat: {}", e))?;
+        let mut stdin = child.stdin.take().ok_or_else(|| String::from("Failed to open the playback stream"))?;
+        let controls = Arc::new(Mutex::new(Controls {
+            volume,
+            speed,
+            is_paused: false,
+            is_stopped: false,
+            is_finished: false,
+        }));
+        let thread_controls = Arc::clone(&controls);
+        std::thread::spawn(move || {
+            let chunk_frames = (decoded.rate as u128 * Self::CHUNK.as_millis() / 1000) as usize;
+            let mut pos = 0.0;
+            let mut out = Vec::with_capacity(chunk_frames * decoded.channels as usize);
+            loop {
+                let controls = match thread_controls.lock() {
+                    Ok(controls) => *controls,
+                    Err(_) => break,
+                };
+                if controls.is_stopped || pos as usize >= decoded.frames() {
+                    break;
+                }
+                if controls.is_paused {
+                    std::thread::sleep(Self::CHUNK);
+                    continue;
+                }
+                out.clear();
+                pos = render(&decoded, pos, controls.speed, controls.volume, chunk_frames, &mut out);
+                let bytes: Vec<u8> = out.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+                if stdin.write_all(&bytes).is_err() {
+                    break;
+                }
+            }
+            let is_stopped = thread_controls.lock().map(|controls| controls.is_stopped).unwrap_or(true);
+            if is_stopped {
+                let _ = child.kill();
+            } else {
+                // Let the sound server play what is still buffered.
+                drop(stdin);
+            }
+            let _ = child.wait();
+            if let Ok(mut controls) = thread_controls.lock() {
+                controls.is_finished = true;
+            }
+        });
+        Ok(Self { controls })
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Controls)) {
+        if let Ok(mut controls) = self.controls.lock() {
+            f(&mut controls);
+        }
+    }
+
+    pub fn set_paused(&self, is_paused: bool) {
+        self.update(|controls| controls.is_paused = is_paused);
+    }
+
+    pub fn stop(&self) {
+        self.update(|controls| controls.is_stopped = true);
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.update(|controls| controls.volume = volume);
+    }
+
+    pub fn set_speed(&self, speed: f64) {
+        self.update(|controls| controls.speed = speed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.controls.lock().map(|controls| controls.is_paused).unwrap_or_default()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.controls.lock().map(|controls| controls.is_finished).unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn bundled(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/sounds").join(name)
+    }
+
+    #[test]
+    fn test_decode_bundled_sounds() {
+        for (name, channels, rate, frames) in [
+            ("empty_trash.wav", 2, 44100, 242550),
+            ("error.wav", 1, 48000, 47574),
+            ("notifications.wav", 2, 48000, 156000),
+            ("shutdown.wav", 2, 96000, 573431),
+            ("startup.wav", 2, 44100, 49733),
+        ]
+        .iter()
+        {
+            let decoded = decode(&bundled(name)).unwrap();
+            assert_eq!(decoded.channels, *channels, "{}", name);
+            assert_eq!(decoded.rate, *rate, "{}", name);
+            assert_eq!(decoded.frames(), *frames, "{}", name);
+            assert!(decoded.samples.iter().any(|sample| *sample != 0), "{} is silent", name);
+        }
+    }
+
+    #[test]
+    fn test_decode_unsupported() {
+        assert!(decode(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")).is_err());
+    }
+
+    #[test]
+    fn test_render_volume_and_speed() {
+        let decoded = Decoded {
+            rate: 8000,
+            channels: 1,
+            samples: vec![0, 1000, 2000, 3000],
+        };
+        let mut out = Vec::new();
+        let pos = render(&decoded, 0.0, 1.0, 0.5, 4, &mut out);
+        assert_eq!(out, vec![0, 500, 1000, 1500]);
+        assert_eq!(pos, 4.0);
+        out.clear();
+        let pos = render(&decoded, 0.0, 0.5, 1.0, 3, &mut out);
+        assert_eq!(out, vec![0, 500, 1000]);
+        assert_eq!(pos, 1.5);
+        out.clear();
+        render(&decoded, 0.0, 2.0, 1.0, 4, &mut out);
+        assert_eq!(out, vec![0, 2000]);
+    }
+}
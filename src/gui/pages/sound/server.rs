@@ -0,0 +1,527 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    New,
+    Change,
+    Remove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facility {
+    Sink,
+    Source,
+    SinkInput,
+    SourceOutput,
+    Card,
+    Server,
+    Other,
+}
+
+/// An event reported by `pactl subscribe`, e.g. `Event 'change' on sink #0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerEvent {
+    pub kind: EventKind,
+    pub facility: Facility,
+    /// Index of the sink, stream or card, `None` for the server.
+    pub index: Option<u32>,
+}
+
+pub fn parse_event(line: &str) -> Option<ServerEvent> {
+    let rest = line.trim().strip_prefix("Event '")?;
+    let (kind, rest) = rest.split_at(rest.find('\'')?);
+    let kind = match kind {
+        "new" => EventKind::New,
+        "change" => EventKind::Change,
+        "remove" => EventKind::Remove,
+        _ => return None,
+    };
+    let target = rest.strip_prefix("' on ")?;
+    let (target, index) = match target.find(" #") {
+        Some(idx) => (&target[..idx], target[idx + 2..].parse().ok()),
+        None => (target, None),
+    };
+    let facility = match target {
+        "sink" => Facility::Sink,
+        "source" => Facility::Source,
+        "sink-input" => Facility::SinkInput,
+        "source-output" => Facility::SourceOutput,
+        "card" => Facility::Card,
+        "server" => Facility::Server,
+        _ => Facility::Other,
+    };
+    Some(ServerEvent { kind, facility, index })
+}
+
+/// State shared with the thread following `pactl subscribe`.
+#[derive(Debug, Default)]
+struct WatcherState {
+    events: Vec<ServerEvent>,
+    /// The running `pactl subscribe`, which only prints on events, so it is killed to end the thread.
+    child: Option<Child>,
+    is_stopped: bool,
+}
+
+/// Collects sound server events in a background thread until they are drained by the page. Repeated events are
+/// kept once, so that a burst of changes, e.g. while a volume slider is dragged, doesn't pile up.
+#[derive(Debug, Default)]
+pub struct ServerWatcher {
+    state: Arc<Mutex<WatcherState>>,
+}
+
+impl ServerWatcher {
+    pub fn spawn() -> Self {
+        let watcher = Self::default();
+        let state = Arc::clone(&watcher.state);
+        std::thread::spawn(move || loop {
+            match pactl_command().arg("subscribe").stdout(Stdio::piped()).stderr(Stdio::null()).spawn() {
+                Ok(mut child) => {
+                    let stdout = child.stdout.take();
+                    match state.lock() {
+                        Ok(mut state) if !state.is_stopped => state.child = Some(child),
+                        _ => {
+                            let _ = child.kill();
+                            let _ = child.wait();
+                            break;
+                        }
+                    }
+                    if let Some(stdout) = stdout {
+                        for line in BufReader::new(stdout).lines().flatten() {
+                            if let Some(event) = parse_event(&line) {
+                                if let Ok(mut state) = state.lock() {
+                                    if !state.events.contains(&event) {
+                                        state.events.push(event);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    if let Some(mut child) = state.lock().ok().and_then(|mut state| state.child.take()) {
+                        let _ = child.wait();
+                    }
+                }
+                Err(e) => eprintln!("Error: {:?}", e),
+            }
+            if state.lock().map(|state| state.is_stopped).unwrap_or(true) {
+                break;
+            }
+            // The sound server went away (e.g. restarted), reconnect after a while.
+            std::thread::sleep(Duration::from_secs(3));
+        });
+        watcher
+    }
+
+    pub fn drain(&self) -> Vec<ServerEvent> {
+        self.state.lock().map(|mut state| state.events.drain(..).collect()).unwrap_or_default()
+    }
+
+    /// End the background thread by killing `pactl subscribe`.
+    pub fn stop(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.is_stopped = true;
+            if let Some(child) = state.child.as_mut() {
+                let _ = child.kill();
+            }
+        }
+    }
+}
+
+impl Drop for ServerWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerPort {
+    pub name: String,
+    pub description: String,
+    pub priority: u32,
+    /// `None` when the card cannot detect whether something is plugged into the jack.
+    pub available: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerDevice {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    pub volume: f64,
+    pub is_muted: bool,
+    pub ports: Vec<ServerPort>,
+    pub active_port: Option<String>,
+    /// Channel positions such as `front-left`.
+    pub channel_map: Vec<String>,
+    /// Volume of each channel of `channel_map` in percent.
+    pub channel_volumes: Vec<f64>,
+    /// Sample format, channels and rate, e.g. `s16le 2ch 44100Hz`.
+    pub sample_spec: String,
+    pub card: Option<u32>,
+}
+
+impl ServerDevice {
+    pub fn active_port(&self) -> Option<&ServerPort> {
+        self.active_port.as_ref().and_then(|active| self.ports.iter().find(|port| &port.name == active))
+    }
+}
+
+/// Whether a line is an entry of a list such as `Ports:`, which are indented by two tabs.
+fn is_entry(line: &str) -> bool {
+    line.starts_with("\t\t") && !line.starts_with("\t\t\t")
+}
+
+/// Parse a port entry, e.g. `analog-output-speaker: Speakers (type: Speaker, priority: 10000, availability unknown)`.
+fn parse_port(line: &str) -> Option<ServerPort> {
+    let idx = line.find(": ")?;
+    let (name, rest) = (&line[..idx], &line[idx + 2..]);
+    let (description, details) = match rest.rfind(" (") {
+        Some(idx) => (&rest[..idx], &rest[idx + 2..]),
+        None => (rest, ""),
+    };
+    let priority = details.split(", ").find_map(|detail| detail.strip_prefix("priority: ")).and_then(|val| val.parse().ok()).unwrap_or_default();
+    let available = if details.contains("not available") {
+        Some(false)
+    } else if details.contains("availability unknown") {
+        None
+    } else if details.contains("available") {
+        Some(true)
+    } else {
+        None
+    };
+    Some(ServerPort {
+        name: name.to_string(),
+        description: description.to_string(),
+        priority,
+        available,
+    })
+}
+
+/// Parse the output of `pactl list sinks` or `pactl list sources`.
+pub fn parse_devices(content: &str) -> Vec<ServerDevice> {
+    let mut devices: Vec<ServerDevice> = Vec::new();
+    let mut in_ports = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(index) = trimmed.strip_prefix("Sink #").or_else(|| trimmed.strip_prefix("Source #")) {
+            devices.push(ServerDevice {
+                index: index.parse().unwrap_or_default(),
+                ..ServerDevice::default()
+            });
+            in_ports = false;
+            continue;
+        }
+        let device = match devices.last_mut() {
+            Some(device) => device,
+            None => continue,
+        };
+        // Port entries are indented one level deeper than the properties.
+        if in_ports && is_entry(line) {
+            device.ports.extend(parse_port(trimmed));
+            continue;
+        }
+        in_ports = false;
+        if let Some(name) = trimmed.strip_prefix("Name: ") {
+            device.name = name.to_string();
+        } else if let Some(description) = trimmed.strip_prefix("Description: ") {
+            device.description = description.to_string();
+        } else if let Some(mute) = trimmed.strip_prefix("Mute: ") {
+            device.is_muted = mute == "yes";
+        } else if let Some(volume) = trimmed.strip_prefix("Volume: ") {
+            // front-left: 32768 /  50% / -18.06 dB,   front-right: 32768 /  50% / -18.06 dB
+            let percents: Vec<f64> = volume.split('/').filter_map(|part| part.trim().strip_suffix('%')).filter_map(|val| val.trim().parse().ok()).collect();
+            if !percents.is_empty() {
+                device.volume = (percents.iter().sum::<f64>() / percents.len() as f64).round();
+            }
+            device.channel_volumes = percents;
+        } else if let Some(channel_map) = trimmed.strip_prefix("Channel Map: ") {
+            device.channel_map = channel_map.split(',').map(|position| position.trim().to_string()).collect();
+        } else if let Some(sample_spec) = trimmed.strip_prefix("Sample Specification: ") {
+            device.sample_spec = sample_spec.to_string();
+        } else if let Some(card) = trimmed.strip_prefix("Card: ") {
+            device.card = card.parse().ok();
+        } else if trimmed == "Ports:" {
+            in_ports = true;
+        } else if let Some(port) = trimmed.strip_prefix("Active Port: ") {
+            device.active_port = Some(port.to_string());
+        }
+    }
+    devices
+}
+
+/// Whether a sink or source, used to pick the `pactl` sub-commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceClass {
+    Sink,
+    Source,
+}
+
+impl DeviceClass {
+    fn name(&self) -> &'static str {
+        match self {
+            DeviceClass::Sink => "sink",
+            DeviceClass::Source => "source",
+        }
+    }
+
+    pub fn list(&self) -> Vec<ServerDevice> {
+        let devices = pactl_command().args(&["list", &format!("{}s", self.name())]).output().map(|output| parse_devices(&String::from_utf8_lossy(&output.stdout)));
+        match devices {
+            // Monitors of sinks are listed as sources, but are not microphones.
+            Ok(devices) => devices.into_iter().filter(|dev| !dev.name.ends_with(".monitor")).collect(),
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn default_name(&self) -> Option<String> {
+        let output = pactl_command().arg("info").output().ok()?;
+        let prefix = match self {
+            DeviceClass::Sink => "Default Sink: ",
+            DeviceClass::Source => "Default Source: ",
+        };
+        String::from_utf8_lossy(&output.stdout).lines().find_map(|line| line.strip_prefix(prefix).map(str::to_string))
+    }
+
+    pub fn set_default(&self, device: &str) -> Result<(), String> {
+        pactl(&[&format!("set-default-{}", self.name()), device])
+    }
+
+    pub fn set_port(&self, device: &str, port: &str) -> Result<(), String> {
+        pactl(&[&format!("set-{}-port", self.name()), device, port])
+    }
+
+    /// Set the volume of each channel in percent.
+    pub fn set_channel_volumes(&self, device: &str, volumes: &[f64]) -> Result<(), String> {
+        let volumes: Vec<String> = volumes.iter().map(|volume| format!("{}%", volume.round())).collect();
+        let mut args = vec![format!("set-{}-volume", self.name()), device.to_string()];
+        args.extend(volumes);
+        pactl(&args.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CardProfile {
+    pub name: String,
+    pub description: String,
+    pub available: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerCard {
+    pub index: u32,
+    pub name: String,
+    pub driver: String,
+    pub description: String,
+    pub bus: String,
+    pub profiles: Vec<CardProfile>,
+    pub active_profile: Option<String>,
+    pub ports: Vec<ServerPort>,
+}
+
+/// Parse the output of `pactl list cards`.
+pub fn parse_cards(content: &str) -> Vec<ServerCard> {
+    let mut cards: Vec<ServerCard> = Vec::new();
+    let mut section = "";
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(index) = trimmed.strip_prefix("Card #") {
+            cards.push(ServerCard {
+                index: index.parse().unwrap_or_default(),
+                ..ServerCard::default()
+            });
+            section = "";
+            continue;
+        }
+        let card = match cards.last_mut() {
+            Some(card) => card,
+            None => continue,
+        };
+        if is_entry(line) {
+            match section {
+                "Properties:" => {
+                    if let Some(idx) = trimmed.find(" = ") {
+                        let val = trimmed[idx + 3..].trim_matches('"').to_string();
+                        match &trimmed[..idx] {
+                            "device.description" => card.description = val,
+                            "device.bus" => card.bus = val,
+                            _ => {}
+                        }
+                    }
+                }
+                // input:analog-stereo: Analog Stereo Input (sinks: 0, sources: 1, priority: 65, available: yes)
+                "Profiles:" => {
+                    if let Some(idx) = trimmed.find(": ") {
+                        let rest = &trimmed[idx + 2..];
+                        card.profiles.push(CardProfile {
+                            name: trimmed[..idx].to_string(),
+                            description: rest.rfind(" (").map(|end| &rest[..end]).unwrap_or(rest).to_string(),
+                            available: !rest.contains("available: no"),
+                        });
+                    }
+                }
+                "Ports:" => card.ports.extend(parse_port(trimmed)),
+                _ => {}
+            }
+            continue;
+        }
+        if line.starts_with("\t\t") {
+            continue;
+        }
+        section = "";
+        if let Some(name) = trimmed.strip_prefix("Name: ") {
+            card.name = name.to_string();
+        } else if let Some(driver) = trimmed.strip_prefix("Driver: ") {
+            card.driver = driver.to_string();
+        } else if let Some(profile) = trimmed.strip_prefix("Active Profile: ") {
+            card.active_profile = Some(profile.to_string());
+        } else if trimmed.ends_with(':') {
+            section = match trimmed {
+                "Properties:" => "Properties:",
+                "Profiles:" => "Profiles:",
+                "Ports:" => "Ports:",
+                _ => "",
+            };
+        }
+    }
+    cards
+}
+
+/// A reason to switch to a card, found by comparing two listings of the cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardChange {
+    /// The card was plugged in.
+    Added(u32),
+    /// A port of the card became available, e.g. headphones were plugged into its jack.
+    Plugged(u32, String),
+}
+
+/// Cards and ports that were plugged in since the cards were listed as `old`. A change of profile recreates the sinks
+/// and sources of a card, but isn't reported, as nothing was plugged in.
+pub fn card_changes(old: &[ServerCard], new: &[ServerCard]) -> Vec<CardChange> {
+    let mut changes = Vec::new();
+    for card in new {
+        match old.iter().find(|old_card| old_card.index == card.index) {
+            Some(old_card) => {
+                let is_plugged = |port: &&ServerPort| port.available == Some(true) && old_card.ports.iter().any(|old| old.name == port.name && old.available == Some(false));
+                changes.extend(card.ports.iter().filter(is_plugged).map(|port| CardChange::Plugged(card.index, port.name.clone())));
+            }
+            None => changes.push(CardChange::Added(card.index)),
+        }
+    }
+    changes
+}
+
+pub fn list_cards() -> Vec<ServerCard> {
+    match pactl_command().args(&["list", "cards"]).output() {
+        Ok(output) => parse_cards(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            Vec::new()
+        }
+    }
+}
+
+pub fn set_card_profile(card: &str, profile: &str) -> Result<(), String> {
+    pactl(&["set-card-profile", card, profile])
+}
+
+/// `pactl` with untranslated output, which is parsed by the labels of its fields.
+fn pactl_command() -> Command {
+    let mut cmd = Command::new("pactl");
+    cmd.env("LC_ALL", "C");
+    cmd
+}
+
+fn pactl(args: &[&str]) -> Result<(), String> {
+    let output = pactl_command().args(args).output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_event() {
+        assert_eq!(
+            parse_event("Event 'change' on sink-input #12"),
+            Some(ServerEvent {
+                kind: EventKind::Change,
+                facility: Facility::SinkInput,
+                index: Some(12),
+            })
+        );
+        assert_eq!(
+            parse_event("Event 'change' on server"),
+            Some(ServerEvent {
+                kind: EventKind::Change,
+                facility: Facility::Server,
+                index: None,
+            })
+        );
+        assert_eq!(parse_event("Event 'new' on source #7").and_then(|event| event.index), Some(7));
+        assert_eq!(parse_event("Event 'unknown' on sink #0"), None);
+    }
+
+    #[test]
+    fn test_parse_devices() {
+        let content = "Sink #3\n\tState: RUNNING\n\tName: alsa_output.pci-0000_00_1f.3.analog-stereo\n\tDescription: Built-in Audio Analog Stereo\n\tMute: no\n\tChannel Map: front-left,front-right\n\tVolume: front-left: 32768 /  50% / -18.06 dB,   front-right: 39322 /  60% / -13.31 dB\n\t        balance 0.09\n\tPorts:\n\t\tanalog-output-speaker: Speakers (type: Speaker, priority: 10000, availability unknown)\n\t\tanalog-output-headphones: Headphones (type: Headphones, priority: 9900, not available)\n\tActive Port: analog-output-speaker\n\tFormats:\n\t\tpcm\n";
+        let devices = parse_devices(content);
+        assert_eq!(devices.len(), 1);
+        let device = &devices[0];
+        assert_eq!(device.index, 3);
+        assert_eq!(device.description, "Built-in Audio Analog Stereo");
+        assert_eq!(device.volume, 55.0);
+        assert_eq!(device.channel_map, vec!["front-left", "front-right"]);
+        assert_eq!(device.ports.len(), 2);
+        assert_eq!(device.ports[1].available, Some(false));
+        assert_eq!(device.ports[1].priority, 9900);
+        assert_eq!(device.active_port().map(|port| port.description.as_str()), Some("Speakers"));
+        assert_eq!(device.channel_volumes, vec![50.0, 60.0]);
+    }
+
+    #[test]
+    fn test_parse_cards() {
+        let content = "Card #0\n\tName: alsa_card.pci-0000_00_1f.3\n\tDriver: module-alsa-card.c\n\tProperties:\n\t\tdevice.bus = \"pci\"\n\t\tdevice.description = \"Built-in Audio\"\n\tProfiles:\n\t\toutput:analog-stereo: Analog Stereo Output (sinks: 1, sources: 0, priority: 6500, available: yes)\n\t\toutput:hdmi-stereo: Digital Stereo (HDMI) Output (sinks: 1, sources: 0, priority: 5900, available: no)\n\tActive Profile: output:analog-stereo\n\tPorts:\n\t\tanalog-output-headphones: Headphones (type: Headphones, priority: 9900, latency offset: 0 usec, availability group: Legacy 2, not available)\n\t\t\tProperties:\n\t\t\t\tport.type = \"headphones\"\n\t\t\tPart of profile(s): output:analog-stereo\n";
+        let cards = parse_cards(content);
+        assert_eq!(cards.len(), 1);
+        let card = &cards[0];
+        assert_eq!(card.driver, "module-alsa-card.c");
+        assert_eq!(card.description, "Built-in Audio");
+        assert_eq!(card.bus, "pci");
+        assert_eq!(card.profiles.len(), 2);
+        assert_eq!(card.profiles[1].description, "Digital Stereo (HDMI) Output");
+        assert!(!card.profiles[1].available);
+        assert_eq!(card.active_profile.as_deref(), Some("output:analog-stereo"));
+        assert_eq!(card.ports.len(), 1);
+        assert_eq!(card.ports[0].available, Some(false));
+    }
+
+    #[test]
+    fn test_card_changes() {
+        let card = |index: u32, profile: &str, available: Option<bool>| ServerCard {
+            index,
+            active_profile: Some(profile.to_string()),
+            ports: vec![ServerPort {
+                name: String::from("analog-output-headphones"),
+                available,
+                ..ServerPort::default()
+            }],
+            ..ServerCard::default()
+        };
+        let old = vec![card(0, "output:analog-stereo", Some(false))];
+        assert!(card_changes(&old, &[card(0, "output:hdmi-stereo", Some(false))]).is_empty());
+        assert_eq!(card_changes(&old, &[card(0, "output:analog-stereo", Some(true))]), vec![CardChange::Plugged(0, String::from("analog-output-headphones"))]);
+        assert_eq!(card_changes(&old, &[old[0].clone(), card(4, "a2dp-sink", None)]), vec![CardChange::Added(4)]);
+        assert!(card_changes(&[card(0, "output:analog-stereo", None)], &[card(0, "output:analog-stereo", Some(true))]).is_empty());
+    }
+}
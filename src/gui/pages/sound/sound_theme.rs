@@ -0,0 +1,224 @@
+use crate::helpers::event_sounds;
+use crate::helpers::kconfig::write_key;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Name of the theme written by System Settings to apply custom and disabled sounds on top of the selected theme.
+pub const OVERLAY_THEME: &str = "system-settings";
+const FALLBACK_THEME: &str = "freedesktop";
+const EXTENSIONS: [&str; 4] = ["oga", "ogg", "wav", "flac"];
+
+/// A sound theme as described by the freedesktop sound theme specification.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SoundTheme {
+    pub id: String,
+    pub name: String,
+    pub inherits: Vec<String>,
+    pub directories: Vec<String>,
+}
+
+impl fmt::Display for SoundTheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", if self.name.is_empty() { &self.id } else { &self.name })
+    }
+}
+
+/// Parse the `index.theme` of a sound theme. Stereo directories are preferred over other output profiles.
+pub fn parse_index_theme(id: &str, content: &str) -> SoundTheme {
+    let mut theme = SoundTheme { id: id.to_string(), ..SoundTheme::default() };
+    let mut group = String::new();
+    let mut profiles: HashMap<String, String> = HashMap::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') && line.ends_with(']') {
+            group = line[1..line.len() - 1].to_string();
+        } else if let Some(idx) = line.find('=') {
+            let (key, val) = (line[..idx].trim(), line[idx + 1..].trim());
+            let list = || val.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect::<Vec<_>>();
+            match (group.as_str(), key) {
+                ("Sound Theme", "Name") => theme.name = val.to_string(),
+                ("Sound Theme", "Inherits") => theme.inherits = list(),
+                ("Sound Theme", "Directories") => theme.directories = list(),
+                (dir, "OutputProfile") => {
+                    profiles.insert(dir.to_string(), val.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+    theme.directories.sort_by_key(|dir| profiles.get(dir).map(|profile| profile != "stereo").unwrap_or(true));
+    theme
+}
+
+/// Base directories of sound themes, most important first.
+fn sound_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(data_dir) = dirs::data_dir() {
+        dirs.push(data_dir.join("sounds"));
+    }
+    let data_dirs = std::env::var("XDG_DATA_DIRS").unwrap_or_default();
+    let data_dirs = if data_dirs.is_empty() { "/usr/local/share:/usr/share" } else { data_dirs.as_str() };
+    dirs.extend(data_dirs.split(':').map(|dir| Path::new(dir).join("sounds")));
+    dirs
+}
+
+pub fn load_theme(id: &str) -> Option<SoundTheme> {
+    sound_dirs().into_iter().find_map(|dir| std::fs::read_to_string(dir.join(id).join("index.theme")).ok()).map(|content| parse_index_theme(id, &content))
+}
+
+/// List the installed sound themes, except the overlay theme.
+pub fn list_themes() -> Vec<SoundTheme> {
+    let mut themes: Vec<SoundTheme> = Vec::new();
+    for dir in sound_dirs() {
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let id = entry.file_name().to_string_lossy().to_string();
+                if id == OVERLAY_THEME || themes.iter().any(|theme| theme.id == id) {
+                    continue;
+                }
+                if let Ok(content) = std::fs::read_to_string(entry.path().join("index.theme")) {
+                    themes.push(parse_index_theme(&id, &content));
+                }
+            }
+        }
+    }
+    themes.sort_by(|a, b| a.to_string().to_lowercase().cmp(&b.to_string().to_lowercase()));
+    themes
+}
+
+/// The theme followed by the themes it inherits from, ending with the `freedesktop` fallback theme.
+fn theme_chain(id: &str) -> Vec<SoundTheme> {
+    let mut chain: Vec<SoundTheme> = Vec::new();
+    let mut pending = vec![id.to_string()];
+    while let Some(id) = pending.pop() {
+        if chain.iter().any(|theme| theme.id == id) {
+            continue;
+        }
+        if let Some(theme) = load_theme(&id) {
+            pending.extend(theme.inherits.iter().rev().cloned());
+            chain.push(theme);
+        }
+    }
+    if !chain.iter().any(|theme| theme.id == FALLBACK_THEME) {
+        chain.extend(load_theme(FALLBACK_THEME));
+    }
+    chain
+}
+
+/// Find the sound file of an event. When nothing matches, the event ID is shortened at its last dash and looked
+/// up again, so `power-plug` falls back to `power`.
+pub fn lookup(theme: &str, event: &str) -> Option<PathBuf> {
+    let chain = theme_chain(theme);
+    let bases = sound_dirs();
+    let mut name = event;
+    loop {
+        for theme in chain.iter() {
+            for base in bases.iter() {
+                let theme_dir = base.join(&theme.id);
+                for dir in theme.directories.iter() {
+                    for ext in EXTENSIONS.iter() {
+                        let path = theme_dir.join(dir).join(format!("{}.{}", name, ext));
+                        if path.exists() {
+                            return Some(path);
+                        }
+                    }
+                }
+            }
+        }
+        match name.rfind('-') {
+            Some(idx) => name = &name[..idx],
+            None => return None,
+        }
+    }
+}
+
+/// Sound effect settings of the user, persisted by System Settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundEffectConf {
+    pub is_enabled: bool,
+    pub theme: String,
+    /// Volume of alert sounds in percent, applied to their streams by `system_settings_session`.
+    pub alert_volume: u8,
+    pub disabled: Vec<String>,
+    pub custom: HashMap<String, PathBuf>,
+}
+
+impl Default for SoundEffectConf {
+    fn default() -> Self {
+        Self {
+            is_enabled: true,
+            theme: String::from(FALLBACK_THEME),
+            alert_volume: 100,
+            disabled: Vec::new(),
+            custom: HashMap::new(),
+        }
+    }
+}
+
+impl SoundEffectConf {
+    fn path() -> Option<PathBuf> {
+        event_sounds::conf_path()
+    }
+
+    pub fn load() -> Self {
+        Self::path().and_then(|path| std::fs::read_to_string(path).ok()).and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+    }
+
+    pub fn is_event_enabled(&self, event: &str) -> bool {
+        self.is_enabled && !self.disabled.iter().any(|id| id == event)
+    }
+
+    /// The file played for an event: the custom file if any, otherwise the file of the theme.
+    pub fn sound_file(&self, event: &str) -> Option<PathBuf> {
+        self.custom.get(event).cloned().or_else(|| lookup(&self.theme, event))
+    }
+
+    /// Save the settings and apply them to the desktop through an overlay theme inheriting the selected one.
+    pub fn apply(&self) -> Result<(), String> {
+        if let Some(path) = Self::path() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+            }
+            std::fs::write(&path, serde_json::to_string_pretty(self).unwrap_or_default()).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        let overlay_dir = dirs::data_dir().ok_or_else(|| String::from("Unknown data directory"))?.join("sounds").join(OVERLAY_THEME);
+        let stereo_dir = overlay_dir.join("stereo");
+        if overlay_dir.exists() {
+            std::fs::remove_dir_all(&overlay_dir).map_err(|e| format!("{}: {}", overlay_dir.display(), e))?;
+        }
+        std::fs::create_dir_all(&stereo_dir).map_err(|e| format!("{}: {}", stereo_dir.display(), e))?;
+        let index = format!("[Sound Theme]\nName=Custom\nInherits={}\nDirectories=stereo\n\n[stereo]\nOutputProfile=stereo\n", self.theme);
+        std::fs::write(overlay_dir.join("index.theme"), index).map_err(|e| e.to_string())?;
+        for (event, file) in self.custom.iter() {
+            let ext = file.extension().and_then(|ext| ext.to_str()).unwrap_or("wav");
+            std::os::unix::fs::symlink(file, stereo_dir.join(format!("{}.{}", event, ext))).map_err(|e| format!("{}: {}", file.display(), e))?;
+        }
+        // libcanberra skips events that have a `.disabled` file in the theme.
+        for event in self.disabled.iter() {
+            std::fs::write(stereo_dir.join(format!("{}.disabled", event)), "").map_err(|e| e.to_string())?;
+        }
+        let flag = if self.is_enabled { "true" } else { "false" };
+        let gtk_settings = dirs::config_dir().map(|dir| dir.join("gtk-3.0/settings.ini")).unwrap_or_default();
+        let gtk_settings = gtk_settings.to_string_lossy();
+        write_key(&gtk_settings, "Settings", "gtk-sound-theme-name", Some(OVERLAY_THEME))?;
+        write_key(&gtk_settings, "Settings", "gtk-enable-event-sounds", Some(flag))?;
+        write_key("kdeglobals", "Sounds", "Theme", Some(OVERLAY_THEME))?;
+        write_key("kdeglobals", "Sounds", "Enable", Some(flag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_index_theme() {
+        let content = "[Sound Theme]\nName=Ocean\nInherits=freedesktop\nDirectories=5.1,stereo\n\n[5.1]\nOutputProfile=5.1\n\n[stereo]\nOutputProfile=stereo\n";
+        let theme = parse_index_theme("ocean", content);
+        assert_eq!(theme.name, "Ocean");
+        assert_eq!(theme.inherits, vec!["freedesktop"]);
+        assert_eq!(theme.directories, vec!["stereo", "5.1"]);
+    }
+}
@@ -1,21 +1,37 @@
 #![allow(unused_variables)]
 #![allow(dead_code)]
-use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle};
-use iced::{button, Align, Button, Column, Container, Element, Length, Row, Space, Text};
+use super::player::{self, Player};
+use super::sound_theme::{list_themes, SoundEffectConf, SoundTheme};
+use crate::gui::styles::{buttons::ButtonStyle, containers::ContainerStyle, picklist::PickListStyle, sliders::SliderStyle};
+use iced::{button, pick_list, slider, Align, Button, Column, Container, Element, HorizontalAlignment, Length, PickList, Row, Slider, Space, Text};
 use iced_custom_widget as icw;
 use icw::components::Toggler;
 use icw::components::{Icon, Icons};
 use std::fmt;
 use std::path::PathBuf;
-#[derive(Default, Debug, Clone)]
+#[derive(Default)]
 pub struct SndEffect {
     is_sound_effect: bool,
-    sample_effects: Vec<(button::State, button::State, String)>,
-    effect_tick: usize,
-    enable_sound_effect: bool,
+    sample_effects: Vec<(SoundEffectType, EffectState)>,
+    themes: Vec<SoundTheme>,
+    theme_pick: pick_list::State<SoundTheme>,
+    volume_slider: slider::State,
+    conf: SoundEffectConf,
+    status: Option<String>,
+    preview_pause: button::State,
+    preview_stop: button::State,
+    speed_slider: slider::State,
     sound_effecs: SettingsSoundEffect,
 }
 
+#[derive(Debug, Default, Clone)]
+pub struct EffectState {
+    play: button::State,
+    enable: button::State,
+    replace: button::State,
+    reset: button::State,
+}
+
 pub type SoundEffectErorr = Result<bool, std::io::Error>;
 
 pub trait SoundEffect {
@@ -28,74 +44,128 @@ pub trait SoundEffect {
 #[derive(Debug, Clone)]
 pub enum SndEffectMsg {
     SoundEffect(bool),
+    ThemeChanged(SoundTheme),
+    AlertVolumeChanged(f64),
+    AlertVolumeReleased,
     TestSoundEffect(usize),
     EnableEffect(usize),
+    ReplaceEffect(usize),
+    ResetEffect(usize),
+    PreviewPauseToggled,
+    PreviewStopped,
+    PreviewSpeedChanged(f64),
+    PreviewTick,
 }
 impl SndEffect {
     pub fn new() -> Self {
-        let str_con = |f: &str| -> String { f.to_string() };
-        let mut vec_sounds: Vec<String> = vec![
-            str_con("Bootup"),
-            str_con("Log out"),
-            str_con("Shutdown"),
-            str_con("Volume +/-"),
-            str_con("Wake Up"),
-            // str_con("Notifications"),
-            // str_con("Low battery"),
-            // str_con("Send icon in Launcher to Desktop"),
-            // str_con("Empty Trash"),
-            // str_con("Plug in"),
-            // str_con("Plug out"),
-            // str_con("Removeable device connected"),
-            // str_con("Removable device removed"),
-            // str_con("Error"),
-        ];
-        let mut vec_tuple: Vec<(button::State, button::State, String)> = Vec::new();
-        vec_sounds.iter_mut().for_each(|name| vec_tuple.push((button::State::new(), button::State::new(), name.clone())));
-        let mut sound_effect_hash: Vec<(SoundEffectType, PathBuf)> = Vec::new();
-
-        match playback::read_directory(if cfg!(debug_assertions) {
-            print!("run debug");
-            std::path::PathBuf::new().join(&format!("{}/assets/sounds", env!("CARGO_MANIFEST_DIR")))
-        } else {
-            std::path::PathBuf::new().join(standart_path::sys_data_dir().unwrap().join("syssettings/sounds"))
-        }) {
-            Ok(mut path) =>
-            {
-                #[allow(const_item_mutation)]
-                for (i, j) in SoundEffectType::ALL[..].iter_mut().zip(path.iter_mut()) {
-                    sound_effect_hash.push((*i, j.to_path_buf()));
-                }
-            }
-            Err(e) => println!("Error: {}", e),
-        }
+        let conf = SoundEffectConf::load();
         Self {
-            sound_effecs: SettingsSoundEffect {
-                hash_sounds: sound_effect_hash,
-                ..Default::default()
-            },
-            enable_sound_effect: true,
-            sample_effects: vec_tuple,
+            is_sound_effect: conf.is_enabled,
+            sample_effects: SoundEffectType::ALL.iter().map(|effect| (*effect, EffectState::default())).collect(),
+            themes: list_themes(),
+            conf,
+            sound_effecs: SettingsSoundEffect::new(),
             ..Default::default()
         }
     }
+    /// The file played for an effect, falling back to the sounds bundled with System Settings.
+    fn sound_file(&self, effect: SoundEffectType) -> Option<PathBuf> {
+        self.conf
+            .sound_file(effect.event_id())
+            .or_else(|| effect.bundled_sound().map(|name| standart_path::bundled_sounds_dir().join(name)).filter(|path| path.exists()))
+    }
+    pub fn is_previewing(&self) -> bool {
+        self.sound_effecs.is_playing()
+    }
+    fn apply(&mut self) {
+        self.status = self.conf.apply().err();
+    }
     pub fn update(&mut self, msg: SndEffectMsg) {
         match msg {
             SndEffectMsg::TestSoundEffect(idx) => {
-                let _key = SoundEffectType::ALL[idx];
-                let value = &self.sound_effecs.hash_sounds[idx].1;
-                match playback::run(&value) {
-                    Ok(()) => println!("sucesss"),
-                    Err(e) => println!("Error: {}", e),
+                let effect = self.sample_effects[idx].0;
+                match self.sound_file(effect) {
+                    Some(path) => {
+                        self.sound_effecs.set_volume(self.conf.alert_volume as u32);
+                        self.status = self.sound_effecs.play(path).err().map(|e| e.to_string());
+                    }
+                    None => self.status = Some(format!("The theme has no sound for \"{}\"", effect)),
+                }
+            }
+            SndEffectMsg::PreviewPauseToggled => {
+                let file = self.sound_effecs.file.clone();
+                let result = if self.sound_effecs.is_paused() { self.sound_effecs.play(file) } else { self.sound_effecs.pause(file) };
+                self.status = result.err().map(|e| e.to_string());
+            }
+            SndEffectMsg::PreviewStopped => {
+                let file = self.sound_effecs.file.clone();
+                self.status = self.sound_effecs.stop(file).err().map(|e| e.to_string());
+            }
+            SndEffectMsg::PreviewSpeedChanged(val) => self.sound_effecs.set_speed(val as u32),
+            SndEffectMsg::PreviewTick => {}
+            SndEffectMsg::SoundEffect(is_effect) => {
+                self.is_sound_effect = is_effect;
+                self.conf.is_enabled = is_effect;
+                self.apply();
+            }
+            SndEffectMsg::ThemeChanged(theme) => {
+                self.conf.theme = theme.id;
+                self.apply();
+            }
+            SndEffectMsg::AlertVolumeChanged(val) => {
+                self.conf.alert_volume = val as u8;
+                self.sound_effecs.set_volume(val as u32);
+            }
+            // Saving rewrites the overlay theme, so it waits until the slider is let go.
+            SndEffectMsg::AlertVolumeReleased => self.apply(),
+            SndEffectMsg::EnableEffect(idx) => {
+                let event = self.sample_effects[idx].0.event_id();
+                if self.conf.is_event_enabled(event) {
+                    self.conf.disabled.push(event.to_string());
+                } else {
+                    self.conf.disabled.retain(|id| id != event);
+                }
+                self.apply();
+            }
+            SndEffectMsg::ReplaceEffect(idx) => {
+                let event = self.sample_effects[idx].0.event_id();
+                if let Ok(nfd2::Response::Okay(path)) = nfd2::open_file_dialog(Some("oga,ogg,wav,flac"), None) {
+                    self.conf.custom.insert(event.to_string(), path);
+                    self.apply();
                 }
             }
-            SndEffectMsg::SoundEffect(is_effect) => self.is_sound_effect = is_effect,
-            SndEffectMsg::EnableEffect(idx) => self.effect_tick = idx,
+            SndEffectMsg::ResetEffect(idx) => {
+                self.conf.custom.remove(self.sample_effects[idx].0.event_id());
+                self.apply();
+            }
         }
     }
     pub fn view(&mut self) -> Element<SndEffectMsg> {
-        let effect_enable = self.enable_sound_effect;
-        let current_tick = self.effect_tick;
+        let conf = &self.conf;
+        let selected_theme = self.themes.iter().find(|theme| theme.id == conf.theme).cloned();
+        let theme_row = Row::new()
+            .align_items(Align::Center)
+            .spacing(10)
+            .push(Text::new("Theme"))
+            .push(Space::with_width(Length::Fill))
+            .push(PickList::new(&mut self.theme_pick, &self.themes, selected_theme, SndEffectMsg::ThemeChanged).style(PickListStyle {}).width(Length::FillPortion(1)));
+        let volume_row = Row::new()
+            .align_items(Align::Center)
+            .spacing(10)
+            .push(Text::new("Alert volume"))
+            .push(
+                Slider::new(&mut self.volume_slider, 0.0..=100.0, conf.alert_volume as f64, SndEffectMsg::AlertVolumeChanged)
+                    .on_release(SndEffectMsg::AlertVolumeReleased)
+                    .step(1.0)
+                    .style(SliderStyle::Circle(10.0))
+                    .width(Length::Fill),
+            )
+            .push(
+                Row::new()
+                    .align_items(Align::Center)
+                    .push(Text::new(conf.alert_volume.to_string()).horizontal_alignment(HorizontalAlignment::Center).width(Length::Units(20)))
+                    .push(Text::new("%")),
+            );
         let sound_effects = Column::new()
             .spacing(10)
             .push(
@@ -111,41 +181,81 @@ impl SndEffect {
                 .style(ContainerStyle::LightGrayCircle),
             )
             .push(if self.is_sound_effect {
-                self.sample_effects.iter_mut().enumerate().fold(Column::new().spacing(10).align_items(Align::Center), |col_sound, (idx, (enable_state, state, name))| {
-                    col_sound.push(
-                        Row::new()
-                            .align_items(Align::Center)
-                            .spacing(10)
-                            .width(Length::Fill)
-                            .push(
-                                Button::new(enable_state, Row::new().push(Text::new(name.as_str())))
-                                    .width(Length::Fill)
-                                    .style(ButtonStyle::Transparent)
-                                    .on_press(SndEffectMsg::TestSoundEffect(idx)),
+                let settings = Container::new(Column::new().spacing(10).push(theme_row).push(volume_row)).padding(10).style(ContainerStyle::LightGrayCircle);
+                self.sample_effects.iter_mut().enumerate().fold(Column::new().spacing(10).align_items(Align::Center).push(settings), |col_sound, (idx, (effect, state))| {
+                    let event = effect.event_id();
+                    let custom = conf.custom.get(event);
+                    let mut row = Row::new()
+                        .align_items(Align::Center)
+                        .spacing(10)
+                        .width(Length::Fill)
+                        .push(
+                            Button::new(
+                                &mut state.play,
+                                Row::new().spacing(10).align_items(Align::Center).push(Icon::new(Icons::AudioFile)).push(Text::new(effect.to_string())).push(
+                                    Text::new(match custom {
+                                        Some(path) => path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+                                        None => String::new(),
+                                    })
+                                    .size(12),
+                                ),
                             )
-                            // .push(Space::with_width(Length::Fill))
-                            .push(
-                                Button::new(state, Icon::new(if effect_enable && current_tick == idx { Icons::CheckCircle } else { Icons::Check }))
-                                    .padding(4)
-                                    .style(ButtonStyle::Transparent)
-                                    .on_press(SndEffectMsg::EnableEffect(idx)),
-                            ),
+                            .width(Length::Fill)
+                            .style(ButtonStyle::Transparent)
+                            .on_press(SndEffectMsg::TestSoundEffect(idx)),
+                        )
+                        .push(Button::new(&mut state.replace, Text::new("Replace...").size(14)).style(ButtonStyle::Default).on_press(SndEffectMsg::ReplaceEffect(idx)));
+                    if custom.is_some() {
+                        row = row.push(Button::new(&mut state.reset, Text::new("Reset").size(14)).style(ButtonStyle::Default).on_press(SndEffectMsg::ResetEffect(idx)));
+                    }
+                    col_sound.push(
+                        row.push(
+                            Button::new(&mut state.enable, Icon::new(if conf.is_event_enabled(event) { Icons::CheckCircle } else { Icons::Circle }))
+                                .padding(4)
+                                .style(ButtonStyle::Transparent)
+                                .on_press(SndEffectMsg::EnableEffect(idx)),
+                        ),
                     )
                 })
             } else {
                 Column::new()
-            });
+            })
+            .push(Text::new(self.status.as_deref().unwrap_or("")).size(12));
+        let sound_effects = if self.sound_effecs.is_playing() {
+            let file_name = self.sound_effecs.file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            let preview = Row::new()
+                .spacing(10)
+                .align_items(Align::Center)
+                .push(Text::new(format!("Playing {}", file_name)).width(Length::FillPortion(2)))
+                .push(
+                    Button::new(&mut self.preview_pause, Text::new(if self.sound_effecs.is_paused() { "Resume" } else { "Pause" }).size(14))
+                        .style(ButtonStyle::Default)
+                        .on_press(SndEffectMsg::PreviewPauseToggled),
+                )
+                .push(Button::new(&mut self.preview_stop, Text::new("Stop").size(14)).style(ButtonStyle::Default).on_press(SndEffectMsg::PreviewStopped))
+                .push(Text::new("Speed"))
+                .push(
+                    Slider::new(&mut self.speed_slider, 50.0..=200.0, self.sound_effecs.speed() as f64, SndEffectMsg::PreviewSpeedChanged)
+                        .step(10.0)
+                        .style(SliderStyle::Circle(10.0))
+                        .width(Length::FillPortion(1)),
+                );
+            sound_effects.push(Container::new(preview).padding(10).style(ContainerStyle::LightGrayCircle))
+        } else {
+            sound_effects
+        };
         sound_effects.into()
     }
 }
 
+/// Player of sound effect previews.
 #[derive(Debug, Default, Clone)]
 pub struct SettingsSoundEffect {
     file: std::path::PathBuf,
-    hash_sounds: Vec<(SoundEffectType, PathBuf)>,
     effect_type: SoundEffectType,
     volume: u32,
     speed: u32,
+    player: Option<Player>,
 }
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum SoundEffectType {
@@ -154,33 +264,66 @@ pub enum SoundEffectType {
     Logout,
     Wakeup,
     VolumnUpDown,
-    // Notification,
-    // LowBattery,
-    // SendIconLauncher,
-    // EmptyTrash,
-    // Plugin,
-    // Plugout,
-    // RemoveDevConnected,
-    // RemovableDevRemoved,
-    // ErrorSound,
+    Bell,
+    Complete,
+    Notification,
+    LowBattery,
+    EmptyTrash,
+    Plugin,
+    Plugout,
+    RemoveDevConnected,
+    RemovableDevRemoved,
+    ErrorSound,
 }
 impl SoundEffectType {
-    const ALL: [SoundEffectType; 5] = [
+    const ALL: [SoundEffectType; 15] = [
         SoundEffectType::Bootup,
         SoundEffectType::ShutDown,
         SoundEffectType::Logout,
         SoundEffectType::Wakeup,
         SoundEffectType::VolumnUpDown,
-        // SoundEffectType::Notification,
-        // SoundEffectType::LowBattery,
-        // SoundEffectType::SendIconLauncher,
-        // SoundEffectType::EmptyTrash,
-        // SoundEffectType::Plugin,
-        // SoundEffectType::Plugout,
-        // SoundEffectType::RemoveDevConnected,
-        // SoundEffectType::RemovableDevRemoved,
-        // SoundEffectType::RemovableDevRemoved,
+        SoundEffectType::Bell,
+        SoundEffectType::Complete,
+        SoundEffectType::Notification,
+        SoundEffectType::LowBattery,
+        SoundEffectType::EmptyTrash,
+        SoundEffectType::Plugin,
+        SoundEffectType::Plugout,
+        SoundEffectType::RemoveDevConnected,
+        SoundEffectType::RemovableDevRemoved,
+        SoundEffectType::ErrorSound,
     ];
+    /// Event ID of the freedesktop sound naming specification.
+    pub fn event_id(&self) -> &'static str {
+        match self {
+            SoundEffectType::Bootup => "desktop-login",
+            SoundEffectType::ShutDown => "system-shutdown",
+            SoundEffectType::Logout => "desktop-logout",
+            SoundEffectType::Wakeup => "suspend-resume",
+            SoundEffectType::VolumnUpDown => "audio-volume-change",
+            SoundEffectType::Bell => "bell",
+            SoundEffectType::Complete => "complete",
+            SoundEffectType::Notification => "message-new-instant",
+            SoundEffectType::LowBattery => "battery-low",
+            SoundEffectType::EmptyTrash => "trash-empty",
+            SoundEffectType::Plugin => "power-plug",
+            SoundEffectType::Plugout => "power-unplug",
+            SoundEffectType::RemoveDevConnected => "device-added",
+            SoundEffectType::RemovableDevRemoved => "device-removed",
+            SoundEffectType::ErrorSound => "dialog-error",
+        }
+    }
+    /// Sound shipped in `assets/sounds` used when the theme has none.
+    fn bundled_sound(&self) -> Option<&'static str> {
+        match self {
+            SoundEffectType::Bootup => Some("startup.wav"),
+            SoundEffectType::ShutDown => Some("shutdown.wav"),
+            SoundEffectType::Notification => Some("notifications.wav"),
+            SoundEffectType::EmptyTrash => Some("empty_trash.wav"),
+            SoundEffectType::ErrorSound => Some("error.wav"),
+            _ => None,
+        }
+    }
 }
 impl fmt::Display for SoundEffectType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -193,15 +336,16 @@ impl fmt::Display for SoundEffectType {
                 SoundEffectType::Logout => "Log out",
                 SoundEffectType::Wakeup => "Wake Up",
                 SoundEffectType::VolumnUpDown => "Volume +/-",
-                // SoundEffectType::Notification => "Notifications",
-                // SoundEffectType::LowBattery => "Low battery",
-                // SoundEffectType::SendIconLauncher => "Send icon in Launcher to Desktop",
-                // SoundEffectType::EmptyTrash => "Empty Trash",
-                // SoundEffectType::Plugin => "Plug in",
-                // SoundEffectType::Plugout => "Plug out",
-                // SoundEffectType::RemoveDevConnected => "Removable device connected",
-                // SoundEffectType::RemovableDevRemoved => "Removable device removed",
-                // SoundEffectType::ErrorSound => "Error",
+                SoundEffectType::Bell => "Alert",
+                SoundEffectType::Complete => "Task completed",
+                SoundEffectType::Notification => "Notifications",
+                SoundEffectType::LowBattery => "Low battery",
+                SoundEffectType::EmptyTrash => "Empty Trash",
+                SoundEffectType::Plugin => "Plug in",
+                SoundEffectType::Plugout => "Plug out",
+                SoundEffectType::RemoveDevConnected => "Removable device connected",
+                SoundEffectType::RemovableDevRemoved => "Removable device removed",
+                SoundEffectType::ErrorSound => "Error",
             }
         )
     }
@@ -214,109 +358,106 @@ impl Default for SoundEffectType {
 
 impl SettingsSoundEffect {
     pub fn new() -> Self {
-        Self {
-            file: dirs::config_dir().unwrap(),
-            ..Default::default()
+        Self { volume: 100, speed: 100, ..Default::default() }
+    }
+    /// Set the volume in percent, also applied to the sound being played.
+    pub fn set_volume(&mut self, volume: u32) {
+        self.volume = volume;
+        if let Some(player) = &self.player {
+            player.set_volume(volume as f32 / 100.0);
         }
     }
+    /// Set the playback rate in percent, also applied to the sound being played.
+    pub fn set_speed(&mut self, speed: u32) {
+        self.speed = speed;
+        if let Some(player) = &self.player {
+            player.set_speed(speed as f64 / 100.0);
+        }
+    }
+    pub fn is_playing(&self) -> bool {
+        self.player.as_ref().map(|player| !player.is_finished()).unwrap_or(false)
+    }
+    pub fn is_paused(&self) -> bool {
+        self.player.as_ref().map(Player::is_paused).unwrap_or(false)
+    }
+    /// The player of `file` if it is still playing.
+    fn player_of(&self, file: &PathBuf) -> Option<&Player> {
+        self.player.as_ref().filter(|player| &self.file == file && !player.is_finished())
+    }
 }
 impl SoundEffect for SettingsSoundEffect {
     fn play(&mut self, file: PathBuf) -> SoundEffectErorr {
-        unimplemented!("Function is unimplemented");
+        if let Some(player) = self.player_of(&file) {
+            if player.is_paused() {
+                player.set_paused(false);
+                return Ok(true);
+            }
+        }
+        if let Some(player) = self.player.take() {
+            player.stop();
+        }
+        let decoded = player::decode(&file).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let player = Player::start(decoded, self.volume as f32 / 100.0, self.speed as f64 / 100.0).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        self.player = Some(player);
+        self.file = file;
+        Ok(true)
     }
     fn pause(&mut self, file: PathBuf) -> SoundEffectErorr {
-        unimplemented!("Function is unimplemented");
+        match self.player_of(&file) {
+            Some(player) => {
+                player.set_paused(true);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
     }
     fn stop(&mut self, file: PathBuf) -> SoundEffectErorr {
-        unimplemented!("Function is unimplemented");
+        match self.player_of(&file) {
+            Some(player) => {
+                player.stop();
+                self.player = None;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
     }
     fn speed(&self) -> u32 {
-        unimplemented!("Function is unimplemented");
+        self.speed
     }
     fn volume(&self) -> u32 {
-        unimplemented!("Function is unimplemented");
+        self.volume
     }
 }
 
-mod playback {
+pub(super) mod playback {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
 
-    use sdl2::audio::{AudioCVT, AudioCallback, AudioSpecDesired, AudioSpecWAV};
-    use std::fs::read_dir;
-    use std::path::PathBuf;
-    use std::time::Duration;
-    // NOTE: You probably want to investigate the
-    // mixer feature for real use cases.
-    struct Sound {
-        data: Vec<u8>,
-        volume: f32,
-        pos: usize,
-    }
-    pub fn read_directory(in_path: std::path::PathBuf) -> Result<Vec<PathBuf>, std::io::Error> {
-        let mut list_sounds: Vec<PathBuf> = Vec::new();
-        if in_path.exists() {
-            for path in read_dir(in_path)? {
-                let dir = path?;
-                list_sounds.push(dir.path());
-            }
-        } else {
-            let paths = read_dir(in_path)?;
-        }
-        Ok(list_sounds)
-    }
-    pub fn make_dir(in_path: &std::path::PathBuf, name: &str) -> Result<bool, std::io::Error> {
-        std::fs::create_dir(in_path.join(name))?;
-        Ok(true)
-    }
-    impl AudioCallback for Sound {
-        type Channel = u8;
+    const RATE: usize = 44_100;
 
-        fn callback(&mut self, out: &mut [u8]) {
-            for dst in out.iter_mut() {
-                // With channel type u8 the "silence" value is 128 (middle of the 0-2^8 range) so we need
-                // to both fill in the silence and scale the wav data accordingly. Filling the silence
-                // once the wav is finished is trivial, applying the volume is more tricky. We need to:
-                // * Change the range of the values from [0, 255] to [-128, 127] so we can multiply
-                // * Apply the volume by multiplying, this gives us range [-128*volume, 127*volume]
-                // * Move the resulting range to a range centered around the value 128, the final range
-                //   is [128 - 128*volume, 128 + 127*volume] – scaled and correctly positioned
-                //
-                // Using value 0 instead of 128 would result in clicking. Scaling by simply multiplying
-                // would not give correct results.
-                let pre_scale = *self.data.get(self.pos).unwrap_or(&128);
-                let scaled_signed_float = (pre_scale as f32 - 128.0) * self.volume;
-                let scaled = (scaled_signed_float + 128.0) as u8;
-                *dst = scaled;
-                self.pos += 1;
-            }
+    /// Play a short sine tone on channel `channel` of an output device, used to test each speaker.
+    /// Blocks until the tone has been played.
+    pub fn tone(device: &str, channel_map: &[String], channel: usize) -> Result<(), String> {
+        let channels = channel_map.len().max(1);
+        let len = RATE * 3 / 4;
+        let mut data = Vec::with_capacity(len * channels);
+        for idx in 0..len {
+            let t = idx as f32 / RATE as f32;
+            // Fade in and out to avoid clicks.
+            let envelope = (t * 20.0).min(1.0).min((len - idx) as f32 / RATE as f32 * 20.0);
+            let sample = (128.0 + 100.0 * envelope * (2.0 * std::f32::consts::PI * 440.0 * t).sin()) as u8;
+            // With unsigned 8 bit samples silence is 128.
+            data.extend((0..channels).map(|slot| if slot == channel { sample } else { 128 }));
         }
-    }
-    pub fn run(path: &std::path::PathBuf) -> Result<(), String> {
-        let sdl_context = sdl2::init().unwrap();
-        let audio_subsystem = sdl_context.audio().unwrap();
-        let desired_spec = AudioSpecDesired {
-            freq: Some(44_100),
-            channels: Some(1), // mono
-            samples: None,     // default
-        };
-        let device = audio_subsystem
-            .open_playback(None, &desired_spec, |spec| {
-                let wav = AudioSpecWAV::load_wav(path).expect("Could not load test WAV file");
-                let cvt = AudioCVT::new(wav.format, wav.channels, wav.freq, spec.format, spec.channels, spec.freq).expect("Could not convert WAV file");
-                let data = cvt.convert(wav.buffer().to_vec());
-                // initialize the audio callback
-                Sound { data: data, volume: 0.50, pos: 0 }
-            })
-            .unwrap();
-        // Start playback
-        device.resume();
-        // std::thread::spawn(|| {
-        // Play for a second
-        std::thread::sleep(Duration::from_millis(1_000));
-        // });
-
-        // Device is automatically closed when dropped
-
-        Ok(())
+        let mut args = vec![format!("--device={}", device), String::from("--raw"), String::from("--format=u8"), format!("--rate={}", RATE), format!("--channels={}", channels)];
+        if !channel_map.is_empty() {
+            args.push(format!("--channel-map={}", channel_map.join(",")));
+        }
+        let mut child = Command::new("pacat").args(&args).stdin(Stdio::piped()).spawn().map_err(|e| format!("Failed to run pacat: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&data).map_err(|e| e.to_string())?;
+        }
+        child.wait().map(|_| ()).map_err(|e| e.to_string())
     }
 }
 
@@ -325,4 +466,11 @@ mod standart_path {
     pub fn sys_data_dir() -> Option<PathBuf> {
         Some(PathBuf::new().join("/usr/share/"))
     }
+    pub fn bundled_sounds_dir() -> PathBuf {
+        if cfg!(debug_assertions) {
+            PathBuf::new().join(&format!("{}/assets/sounds", env!("CARGO_MANIFEST_DIR")))
+        } else {
+            sys_data_dir().unwrap_or_default().join("syssettings/sounds")
+        }
+    }
 }
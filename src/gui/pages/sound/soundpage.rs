@@ -2,7 +2,7 @@ use super::audio::{AudioTab, AudioTabMsg};
 use super::configure::{ConfigureAudio, ConfigureAudioMsg};
 use super::soundeffect::{SndEffect, SndEffectMsg};
 use crate::gui::styles::containers::ContainerStyle;
-use iced::{scrollable, Align, Column, Container, Element, Length, Row, Rule, Scrollable, Text};
+use iced::{scrollable, time, Align, Column, Command, Container, Element, Length, Row, Rule, Scrollable, Subscription, Text};
 use iced_custom_widget as icw;
 use icw::components::Tab;
 use icw::components::{Icon, Icons};
@@ -23,12 +23,27 @@ impl SoundPage {
             ..Default::default()
         }
     }
-    pub fn update(&mut self, msg: SoundMessage) {
+    pub fn update(&mut self, msg: SoundMessage) -> Command<SoundMessage> {
         match msg {
-            SoundMessage::TabSelect(choice) => self.choice = choice,
+            SoundMessage::TabSelect(choice) => {
+                if choice != Choice::A {
+                    self.auddio_tab.stop_input_test();
+                }
+                self.choice = choice;
+            }
             SoundMessage::SndEffectMsg(msg) => self.sound_effects.update(msg),
             SoundMessage::AudioTabMsg(msg) => self.auddio_tab.update(msg),
-            SoundMessage::ConfigureAudioMsg(msg) => self.configure.update(msg),
+            SoundMessage::ConfigureAudioMsg(msg) => return self.configure.update(msg).map(SoundMessage::ConfigureAudioMsg),
+        }
+        Command::none()
+    }
+    pub fn subscription(&self) -> Subscription<SoundMessage> {
+        match self.choice {
+            // Pick up the events collected from the sound server and the input level.
+            Choice::A => time::every(std::time::Duration::from_millis(100)).map(|_| SoundMessage::AudioTabMsg(AudioTabMsg::ServerTick)),
+            // Follow the preview until it finishes.
+            Choice::B if self.sound_effects.is_previewing() => time::every(std::time::Duration::from_millis(250)).map(|_| SoundMessage::SndEffectMsg(SndEffectMsg::PreviewTick)),
+            _ => Subscription::none(),
         }
     }
     pub fn view(&mut self) -> Element<SoundMessage> {
@@ -1,26 +1,33 @@
 mod add_group_page;
 mod edit_group_page;
+mod validation;
 
-use crate::gui::styles::{CustomButton, CustomContainer};
-use iced::{button, scrollable, Button, Column, Container, Element, Length, Row, Scrollable, Text};
+use crate::gui::addon_widgets::lock_btn;
+use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer};
+use crate::helpers::{authority, Action};
+use iced::{button, scrollable, Align, Button, Checkbox, Column, Command, Container, Element, Length, Row, Scrollable, Space, Text};
 use iced_custom_widget::{Icon, Icons};
 use libkoompi::system_settings::users_groups::{Group, User, UsersGroupsManager};
 use std::cell::RefCell;
+use std::future::Future;
 use {
    add_group_page::{AddGroupMsg, AddGroupPage},
    edit_group_page::{EditGroupMsg, EditGroupPage},
+   validation::GidRanges,
 };
 
 #[derive(Debug, Default)]
 pub struct GroupsTab {
-   usrgrp_mn: RefCell<UsersGroupsManager>,
    curr_usr_is_admin: bool,
+   ranges: GidRanges,
    ls_grps: Vec<(RefCell<Group>, button::State)>,
    ls_users: RefCell<Vec<User>>,
    selected_grp: Option<usize>,
+   is_show_sys_grps: bool,
    scroll_grps: scrollable::State,
    add_state: button::State,
    remove_state: button::State,
+   lock_state: button::State,
 
    // dynamic section
    content: ContentPage,
@@ -44,178 +51,183 @@ pub enum GroupsMsg {
    SelecteGroup(usize),
    AddClicked,
    RemoveClicked,
+   LockClicked,
+   LockToggled(Result<(), String>),
+   /// The helper is done with the group now named by the first field.
+   GroupChanged(String, Result<(), String>),
+   SystemGroupsToggled(bool),
    EditGroupMSG(EditGroupMsg),
    AddGroupMSG(AddGroupMsg),
 }
 
 impl GroupsTab {
-   pub fn new(usrgrp_mn: &mut UsersGroupsManager, ls_grps: &mut Vec<Group>, ls_usrs: &mut Vec<User>, curr_usr_is_admin: bool) -> Self {
-      use ContentPage::*;
-      let curr_group = ls_grps.first();
-
-      Self {
-         usrgrp_mn: RefCell::new(usrgrp_mn.clone()),
+   pub fn new(ls_grps: &mut Vec<Group>, ls_usrs: &mut Vec<User>, curr_usr_is_admin: bool) -> Self {
+      let mut tab = Self {
          curr_usr_is_admin,
+         ranges: GidRanges::load(),
          ls_grps: ls_grps.iter().map(|grp| (RefCell::new(grp.to_owned()), button::State::new())).collect(),
          ls_users: RefCell::new(ls_usrs.to_vec()),
-         selected_grp: curr_group.map(|_| 0),
-         content: if let Some(curr_group) = curr_group {
-            EditGroup(EditGroupPage::new(curr_group, ls_usrs.as_slice(), curr_usr_is_admin))
-         } else {
-            Empty
-         },
          ..Self::default()
+      };
+      tab.select_first();
+      tab
+   }
+
+   fn is_visible(&self, group: &Group) -> bool {
+      self.is_show_sys_grps || !self.ranges.is_system(u32::from(group.gid()))
+   }
+
+   fn group_names(&self) -> Vec<String> {
+      self.ls_grps.iter().map(|(grp, _)| grp.borrow().name().to_string()).collect()
+   }
+
+   fn select(&mut self, idx: usize) {
+      use ContentPage::*;
+      self.content = match self.ls_grps.get(idx) {
+         Some((grp, _)) => {
+            self.selected_grp = Some(idx);
+            EditGroup(EditGroupPage::new(&grp.borrow(), self.ls_users.borrow().as_slice(), self.group_names(), self.curr_usr_is_admin))
+         }
+         None => {
+            self.selected_grp = None;
+            Empty
+         }
+      };
+   }
+
+   fn select_first(&mut self) {
+      let first = self.ls_grps.iter().position(|(grp, _)| self.is_visible(&grp.borrow()));
+      self.select(first.unwrap_or(self.ls_grps.len()));
+   }
+
+   /// Read the groups again after the helper changed them, and select the group named `name`.
+   fn reload(&mut self, name: &str) {
+      match UsersGroupsManager::new() {
+         Ok(usrgrp_mn) => self.ls_grps = usrgrp_mn.all_groups().iter().map(|grp| (RefCell::new(grp.to_owned()), button::State::new())).collect(),
+         Err(err) => eprintln!("{:?}", err),
+      }
+      match self.ls_grps.iter().position(|(grp, _)| grp.borrow().name() == name) {
+         Some(idx) => self.select(idx),
+         None => self.select_first(),
       }
    }
 
-   pub fn update(&mut self, msg: GroupsMsg) {
+   pub fn update(&mut self, msg: GroupsMsg) -> Command<GroupsMsg> {
       use ContentPage::*;
       use GroupsMsg::*;
 
-      let Self { usrgrp_mn, ls_grps, ls_users, content, .. } = self;
-
       let is_admin = self.curr_usr_is_admin;
-      let ls_users = ls_users.borrow();
 
       match msg {
          SelecteGroup(idx) => {
             self.selected_grp = Some(idx);
+            let names = self.group_names();
+            let Self { ls_grps, ls_users, content, .. } = self;
             if let EditGroup(edit_group_page) = content {
                if let Some((grp, _)) = ls_grps.get(idx) {
-                  edit_group_page.with_grp(&grp.borrow(), ls_users.as_slice());
+                  edit_group_page.with_grp(&grp.borrow(), ls_users.borrow().as_slice(), names);
                }
             }
          }
          AddClicked => {
             if is_admin {
-               self.content = AddGroup(AddGroupPage::new())
+               let used_gids = self.ls_grps.iter().map(|(grp, _)| (u32::from(grp.borrow().gid()), grp.borrow().name().to_string())).collect();
+               self.content = AddGroup(AddGroupPage::new(self.group_names(), used_gids, self.ranges))
             }
          }
          RemoveClicked => {
             if is_admin {
-               if let Some(idx) = self.selected_grp {
-                  if let Some((group, _)) = ls_grps.get(idx) {
-                     let grp = group.borrow();
-                     match usrgrp_mn.borrow_mut().delete_group(grp.name()) {
-                        Ok(is_ok) => {
-                           if is_ok {
-                              std::mem::drop(grp);
-                              ls_grps.remove(idx);
-                              if let Some((grp, _)) = ls_grps.first() {
-                                 self.selected_grp = Some(0);
-                                 self.content = EditGroup(EditGroupPage::new(&grp.borrow(), ls_users.as_slice(), self.curr_usr_is_admin));
-                              } else {
-                                 self.selected_grp = None;
-                                 self.content = Empty;
-                              }
-                           } else {
-                              println!("can not delete group");
-                           }
-                        }
-                        Err(err) => eprintln!("{:?}", err),
-                     }
-                  }
+               if let Some((group, _)) = self.selected_grp.and_then(|idx| self.ls_grps.get(idx)) {
+                  let name = group.borrow().name().to_string();
+                  return change_group(&name, authority::exec(Action::DeleteGroup, vec![name.clone()]));
+               }
+            }
+         }
+         LockClicked => return Command::perform(authority::toggle(), LockToggled),
+         LockToggled(res) => {
+            if let Err(err) = res {
+               eprintln!("{:?}", err);
+            }
+         }
+         GroupChanged(name, res) => match res {
+            Ok(()) => self.reload(&name),
+            Err(err) => match &mut self.content {
+               EditGroup(edit_group_page) => edit_group_page.set_error(err),
+               AddGroup(add_group_page) => add_group_page.set_error(err),
+               Empty => eprintln!("{}", err),
+            },
+         },
+         SystemGroupsToggled(is_checked) => {
+            self.is_show_sys_grps = is_checked;
+            let is_selected_visible = self.selected_grp.and_then(|idx| self.ls_grps.get(idx)).map(|(grp, _)| self.is_visible(&grp.borrow())).unwrap_or(false);
+            if let EditGroup(_) | Empty = self.content {
+               if !is_selected_visible {
+                  self.select_first();
                }
             }
          }
          EditGroupMSG(edit_group_msg) => {
-            if let EditGroup(edit_group_page) = content {
-               if let Some(idx) = self.selected_grp {
-                  if let Some((group, _)) = ls_grps.get_mut(idx) {
-                     use EditGroupMsg::*;
-                     let mut grp = group.borrow_mut();
-                     match edit_group_msg {
-                        GroupNameSubmitted(grp_name) => {
-                           println!("Group name submitted: {}", grp_name);
-                           // edit_group_page.with_grp(&grp, ls_users.as_slice());
+            if let EditGroup(edit_group_page) = &mut self.content {
+               match edit_group_msg {
+                  EditGroupMsg::OkayClicked(edit) => {
+                     if let Some(name) = self.selected_grp.and_then(|idx| self.ls_grps.get(idx)).map(|(grp, _)| grp.borrow().name().to_string()) {
+                        let mut args = vec![name.clone()];
+                        if let Some(members) = &edit.members {
+                           args.push(String::from("--members"));
+                           args.push(members.join(","));
                         }
-                        OkayClicked(grp_name, ls_members) => {
-                           {
-                              match usrgrp_mn.borrow_mut().change_group_members(grp.name(), ls_members.iter().map(|usr| usr.as_str()).collect()) {
-                                 Ok(newgrp) => {
-                                    if let Some(newgrp) = newgrp {
-                                       println!("change group members success");
-                                       *grp = newgrp;
-                                    } else {
-                                       println!("did not change group members")
-                                    }
-                                 }
-                                 Err(err) => eprintln!("{:?}", err),
-                              }
-                           }
-                           {
-                              match usrgrp_mn.borrow_mut().change_group_name(grp.name(), &grp_name) {
-                                 Ok(newgrp) => {
-                                    if let Some(newgrp) = newgrp {
-                                       println!("change group name success");
-                                       *grp = newgrp;
-                                    } else {
-                                       println!("did not change group name")
-                                    }
-                                 }
-                                 Err(err) => eprintln!("{:?}", err),
-                              }
-                           }
-                           edit_group_page.with_grp(&grp, ls_users.as_slice());
+                        if let Some(new_name) = &edit.rename {
+                           args.push(String::from("--rename"));
+                           args.push(new_name.to_owned());
                         }
-                        _ => edit_group_page.update(edit_group_msg),
+                        return change_group(edit.rename.as_deref().unwrap_or(&name), authority::exec(Action::EditGroup, args));
                      }
                   }
+                  _ => edit_group_page.update(edit_group_msg),
                }
             }
          }
          AddGroupMSG(add_group_msg) => {
-            if let AddGroup(add_group_page) = content {
+            if let AddGroup(add_group_page) = &mut self.content {
                use AddGroupMsg::*;
                match add_group_msg {
-                  CreateClicked(group_name) => match usrgrp_mn.borrow_mut().create_group(group_name.as_str()) {
-                     Ok(grp) => {
-                        if let Some(grp) = grp {
-                           ls_grps.push((RefCell::new(grp.to_owned()), button::State::new()));
-                           self.selected_grp = Some(ls_grps.len() - 1);
-                           if let Some((grp, _)) = ls_grps.last() {
-                              self.content = EditGroup(EditGroupPage::new(&grp.borrow(), ls_users.as_slice(), self.curr_usr_is_admin));
-                           } else {
-                              self.content = Empty;
-                           }
-                        } else {
-                           if let Some(idx) = self.selected_grp {
-                              if let Some((grp, _)) = ls_grps.get(idx) {
-                                 self.content = EditGroup(EditGroupPage::new(&grp.borrow(), ls_users.as_slice(), self.curr_usr_is_admin));
-                              }
-                           } else {
-                              self.content = Empty;
-                           }
-                        }
+                  CreateClicked(req) => {
+                     let mut args = vec![req.name.clone()];
+                     if let Some(gid) = req.gid {
+                        args.push(String::from("--gid"));
+                        args.push(gid.to_string());
                      }
-                     Err(err) => eprintln!("{:?}", err),
-                  },
-                  CancelClicked => {
-                     if let Some(idx) = self.selected_grp {
-                        if let Some((grp, _)) = ls_grps.get(idx) {
-                           self.content = EditGroup(EditGroupPage::new(&grp.borrow(), ls_users.as_slice(), self.curr_usr_is_admin));
-                        }
-                     } else {
-                        self.content = Empty;
+                     if req.is_system {
+                        args.push(String::from("--system"));
+                        self.is_show_sys_grps = true;
                      }
+                     return change_group(&req.name, authority::exec(Action::AddGroup, args));
                   }
+                  CancelClicked => match self.selected_grp {
+                     Some(idx) => self.select(idx),
+                     None => self.select_first(),
+                  },
                   _ => add_group_page.update(add_group_msg),
                }
             }
          }
       }
+      Command::none()
    }
 
    pub fn view(&mut self) -> Element<GroupsMsg> {
       use ContentPage::*;
       use GroupsMsg::*;
 
+      let visible: Vec<bool> = self.ls_grps.iter().map(|(grp, _)| self.is_visible(&grp.borrow())).collect();
       let Self {
          ls_grps,
          selected_grp,
+         is_show_sys_grps,
          scroll_grps,
          add_state,
          remove_state,
+         lock_state,
          content,
          ..
       } = self;
@@ -224,6 +236,7 @@ impl GroupsTab {
       let scrollable_group = ls_grps
          .iter_mut()
          .enumerate()
+         .filter(|(idx, _)| visible[*idx])
          .fold(Scrollable::new(scroll_grps).height(Length::Fill).padding(7).spacing(4).scroller_width(4).scrollbar_width(4), |scrollable, (idx, (grp, state))| {
             let group = grp.borrow();
             let mut btn = Button::new(state, Text::new(group.formatted_name())).width(Length::Fill).style(if let Some(selected) = *selected_grp {
@@ -242,6 +255,7 @@ impl GroupsTab {
             }
             scrollable.push(btn)
          });
+      let chb_sys_grps = Container::new(Checkbox::new(*is_show_sys_grps, "Show system groups", SystemGroupsToggled).spacing(10).style(CustomCheckbox::Default)).padding(7);
       let mut btn_add = Button::new(add_state, Icon::new(Icons::Ad).size(23)).padding(2).style(CustomButton::Text);
       let mut btn_remove = Button::new(remove_state, Icon::new(Icons::Minus).size(23)).padding(2).style(CustomButton::Text);
       if is_admin {
@@ -250,11 +264,21 @@ impl GroupsTab {
       if selected_grp.is_some() && is_admin {
          btn_remove = btn_remove.on_press(RemoveClicked);
       }
-      let btn_group = Container::new(Row::new().push(btn_add).push(btn_remove)).width(Length::Fill).style(CustomContainer::Header);
-      let group_pane = Container::new(Column::new().push(Container::new(Text::new("Groups")).width(Length::Fill).padding(7).style(CustomContainer::Header)).push(scrollable_group).push(btn_group))
-         .height(Length::Fill)
-         .width(Length::FillPortion(3))
-         .style(CustomContainer::ForegroundWhite);
+      let mut btn_group = Row::new().align_items(Align::Center).push(btn_add).push(btn_remove).push(Space::with_width(Length::Fill));
+      if is_admin {
+         btn_group = btn_group.push(lock_btn(lock_state, LockClicked).padding(2));
+      }
+      let btn_group = Container::new(btn_group).width(Length::Fill).style(CustomContainer::Header);
+      let group_pane = Container::new(
+         Column::new()
+            .push(Container::new(Text::new("Groups")).width(Length::Fill).padding(7).style(CustomContainer::Header))
+            .push(scrollable_group)
+            .push(chb_sys_grps)
+            .push(btn_group),
+      )
+      .height(Length::Fill)
+      .width(Length::FillPortion(3))
+      .style(CustomContainer::ForegroundWhite);
 
       let right_sec = match content {
          AddGroup(add_group_page) => add_group_page.view().map(|msg| AddGroupMSG(msg)),
@@ -268,3 +292,9 @@ impl GroupsTab {
          .into()
    }
 }
+
+/// Run an operation of the helper on the group named `name`, which is selected again once it is done.
+fn change_group(name: &str, operation: impl Future<Output = Result<(), String>> + Send + 'static) -> Command<GroupsMsg> {
+   let name = name.to_string();
+   Command::perform(operation, move |res| GroupsMsg::GroupChanged(name.clone(), res))
+}
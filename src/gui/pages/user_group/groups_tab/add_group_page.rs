@@ -1,59 +1,143 @@
+use super::validation::{check_gid, check_groupname, GidRanges};
 use crate::gui::addon_widgets::icon_btn;
-use crate::gui::styles::{CustomButton, CustomTextInput};
-use iced::{button, text_input, Align, Column, Container, Element, Length, Row, Space, Text, TextInput};
+use crate::gui::styles::{CustomButton, CustomCheckbox, CustomTextInput, ERROR};
+use crate::helpers::authority;
+use iced::{button, text_input, Align, Checkbox, Column, Container, Element, Length, Row, Space, Text, TextInput};
 use iced_custom_widget::Icons;
 
 #[derive(Debug, Default)]
 pub struct AddGroupPage {
+   existing_names: Vec<String>,
+   used_gids: Vec<(u32, String)>,
+   ranges: GidRanges,
    fullname_state: text_input::State,
    fullname_val: String,
+   is_system: bool,
+   is_explicit_gid: bool,
+   gid_state: text_input::State,
+   gid_val: String,
+   error: Option<String>,
    btn_create_state: button::State,
    btn_cancel_state: button::State,
 }
 
+/// A group to create, with the id chosen by `groupadd` when `gid` is `None`.
+#[derive(Debug, Clone)]
+pub struct GroupReq {
+   pub name: String,
+   pub gid: Option<u32>,
+   pub is_system: bool,
+}
+
 #[derive(Debug, Clone)]
 pub enum AddGroupMsg {
    FullNameChanged(String),
-   CreateClicked(String),
+   SystemToggled(bool),
+   ExplicitGidToggled(bool),
+   GidChanged(String),
+   CreateClicked(GroupReq),
    CancelClicked,
 }
 
 impl AddGroupPage {
-   pub fn new() -> Self {
-      Self::default()
+   pub fn new(existing_names: Vec<String>, used_gids: Vec<(u32, String)>, ranges: GidRanges) -> Self {
+      Self {
+         existing_names,
+         used_gids,
+         ranges,
+         ..Self::default()
+      }
+   }
+
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
    }
 
    pub fn update(&mut self, msg: AddGroupMsg) {
       use AddGroupMsg::*;
       match msg {
          FullNameChanged(val) => self.fullname_val = val,
-         _ => {}
+         SystemToggled(is_checked) => self.is_system = is_checked,
+         ExplicitGidToggled(is_checked) => {
+            self.is_explicit_gid = is_checked;
+            // Suggest the first free id of the range.
+            if is_checked && self.gid_val.is_empty() {
+               let (min, max) = if self.is_system { (self.ranges.sys_min, self.ranges.sys_max) } else { (self.ranges.min, self.ranges.max) };
+               if let Some(gid) = (min..=max).find(|gid| self.used_gids.iter().all(|(used, _)| used != gid)) {
+                  self.gid_val = gid.to_string();
+               }
+            }
+         }
+         GidChanged(val) => self.gid_val = val,
+         CreateClicked(_) | CancelClicked => {}
       }
    }
 
    pub fn view(&mut self) -> Element<AddGroupMsg> {
       use AddGroupMsg::*;
       let Self {
+         existing_names,
+         used_gids,
+         ranges,
          fullname_state,
          fullname_val,
+         is_system,
+         is_explicit_gid,
+         gid_state,
+         gid_val,
+         error,
          btn_create_state,
          btn_cancel_state,
       } = self;
 
+      let name_check = if fullname_val.is_empty() { None } else { check_groupname(fullname_val, existing_names).err() };
+      let gid_check = if *is_explicit_gid { Some(check_gid(gid_val, *is_system, ranges, used_gids)) } else { None };
+
       let lb_grp_name = Text::new("Group name:");
       let txt_grp_name = TextInput::new(fullname_state, "Group name", &fullname_val, FullNameChanged).padding(7).width(Length::Fill).style(CustomTextInput::Default);
+      let mut name_sec = Column::new().spacing(3).width(Length::Fill).push(txt_grp_name);
+      if let Some(err) = &name_check {
+         name_sec = name_sec.push(Text::new(err.as_str()).size(12).color(ERROR));
+      }
+      let chb_system = Checkbox::new(*is_system, "System group, for services rather than people", SystemToggled).spacing(10).style(CustomCheckbox::Default);
+      let mut gid_row = Row::new()
+         .spacing(10)
+         .align_items(Align::Center)
+         .push(Checkbox::new(*is_explicit_gid, "Group ID:", ExplicitGidToggled).spacing(10).style(CustomCheckbox::Default));
+      if *is_explicit_gid {
+         gid_row = gid_row.push(TextInput::new(gid_state, "GID", &gid_val, GidChanged).padding(7).width(Length::Units(100)).style(CustomTextInput::Default));
+      } else {
+         gid_row = gid_row.push(Text::new("chosen automatically").size(12));
+      }
+      let mut gid_sec = Column::new().spacing(3).push(gid_row);
+      if let Some(Err(err)) = &gid_check {
+         gid_sec = gid_sec.push(Text::new(err.as_str()).size(12).color(ERROR));
+      }
 
       let mut btn_create = icon_btn(btn_create_state, Icons::Ad, "Create", None).style(CustomButton::Primary);
       let btn_cancel = icon_btn(btn_cancel_state, Icons::RemoveUser, "Cancel", None).on_press(CancelClicked).style(CustomButton::Hovered);
-      if !fullname_val.is_empty() {
-         btn_create = btn_create.on_press(CreateClicked(fullname_val.to_owned()));
+      if !fullname_val.is_empty() && name_check.is_none() && authority::is_unlocked() {
+         let gid = match gid_check {
+            Some(Ok(gid)) => Some(Some(gid)),
+            Some(Err(_)) => None,
+            None => Some(None),
+         };
+         if let Some(gid) = gid {
+            btn_create = btn_create.on_press(CreateClicked(GroupReq {
+               name: fullname_val.to_owned(),
+               gid,
+               is_system: *is_system,
+            }));
+         }
+      }
+
+      let mut content = Column::new().width(Length::Fill).spacing(10).push(Row::new().spacing(10).push(lb_grp_name).push(name_sec)).push(chb_system).push(gid_sec);
+      if let Some(err) = error {
+         content = content.push(Text::new(err.as_str()).size(12).color(ERROR));
       }
 
       Container::new(
-         Column::new()
-            .width(Length::Fill)
-            .spacing(10)
-            .push(Row::new().spacing(10).align_items(Align::Center).push(lb_grp_name).push(txt_grp_name))
+         content
             .push(Space::with_height(Length::Fill))
             .push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_cancel).push(btn_create)),
       )
@@ -1,87 +1,114 @@
+use super::validation::{check_groupname, MembershipDiff};
 use crate::gui::addon_widgets::icon_btn;
-use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomTextInput, FOREGROUND};
+use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomTextInput, ERROR, FOREGROUND, SUCCESS};
+use crate::helpers::authority;
 use iced::{button, scrollable, text_input, Align, Checkbox, Column, Container, Element, Length, Row, Scrollable, Space, Text, TextInput};
 use iced_custom_widget::Icons;
 use iced_custom_widget::Stack;
 use libkoompi::system_settings::users_groups::{Group, User};
 #[derive(Debug, Default)]
 pub struct EditGroupPage {
+   orig_name: String,
+   orig_members: Vec<String>,
+   existing_names: Vec<String>,
    group_name_state: text_input::State,
    group_name_val: String,
    ls_members: Vec<(bool, User)>,
-   ls_users: Vec<User>,
    scroll_members: scrollable::State,
    allow_admin_access: bool,
-   is_changed: bool,
+   error: Option<String>,
    btn_ok_state: button::State,
+   btn_revert_state: button::State,
+}
+
+/// Changes of a group, applied together.
+#[derive(Debug, Clone)]
+pub struct GroupEdit {
+   pub rename: Option<String>,
+   /// The whole list of members, when it changed.
+   pub members: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
 pub enum EditGroupMsg {
    GroupNameChanged(String),
-   GroupNameSubmitted(String),
    MemberToggled(usize, bool),
-   OkayClicked(String, Vec<String>),
+   RevertClicked,
+   OkayClicked(GroupEdit),
 }
 
 impl EditGroupPage {
-   pub fn new(group: &Group, ls_users: &[User], allow_admin_access: bool) -> Self {
-      let grp_members = group.members();
-      Self {
-         group_name_val: group.formatted_name(),
-         ls_users: ls_users.iter().map(ToOwned::to_owned).map(|usr| usr.clone()).collect(),
-         ls_members: ls_users.into_iter().map(|usr| (grp_members.contains(usr.username()), usr.to_owned())).collect(),
-         allow_admin_access,
-         ..Self::default()
-      }
+   pub fn new(group: &Group, ls_users: &[User], existing_names: Vec<String>, allow_admin_access: bool) -> Self {
+      let mut page = Self { allow_admin_access, ..Self::default() };
+      page.with_grp(group, ls_users, existing_names);
+      page
    }
 
-   pub fn with_grp(&mut self, group: &Group, ls_users: &[User]) {
-      self.group_name_val = group.formatted_name();
-      self.ls_members = self.ls_users.iter().map(|usr| (group.members().contains(usr.username()), usr.to_owned())).collect();
-      self.ls_users = ls_users.to_vec();
-      self.is_changed = false;
+   pub fn with_grp(&mut self, group: &Group, ls_users: &[User], existing_names: Vec<String>) {
+      self.orig_name = group.name().to_string();
+      self.orig_members = group.members().iter().map(ToString::to_string).collect();
+      self.existing_names = existing_names;
+      self.group_name_val = self.orig_name.clone();
+      self.ls_members = ls_users.iter().map(|usr| (self.orig_members.iter().any(|member| member == usr.username()), usr.to_owned())).collect();
+      self.error = None;
+   }
+
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
+   }
+
+   /// Members which are not listed as users, like the accounts of services, kept as they are.
+   fn other_members(&self) -> Vec<String> {
+      self.orig_members.iter().filter(|member| self.ls_members.iter().all(|(_, usr)| *member != usr.username())).cloned().collect()
+   }
+
+   fn members(&self) -> Vec<String> {
+      let mut members = self.other_members();
+      members.extend(self.ls_members.iter().filter(|(is_checked, _)| *is_checked).map(|(_, usr)| usr.username().to_owned()));
+      members
    }
 
    pub fn update(&mut self, msg: EditGroupMsg) {
       use EditGroupMsg::*;
       match msg {
-         GroupNameChanged(val) => {
-            self.group_name_val = val;
-            if !self.is_changed {
-               self.is_changed = true;
-            }
-         }
+         GroupNameChanged(val) => self.group_name_val = val,
          MemberToggled(idx, is_checked) => {
             if let Some(member) = self.ls_members.get_mut(idx) {
                member.0 = is_checked;
-               if !self.is_changed {
-                  self.is_changed = true;
-               }
             }
          }
-         _ => {}
+         RevertClicked => {
+            self.group_name_val = self.orig_name.clone();
+            let orig_members = &self.orig_members;
+            self.ls_members.iter_mut().for_each(|(is_checked, usr)| *is_checked = orig_members.iter().any(|member| member == usr.username()));
+            self.error = None;
+         }
+         OkayClicked(_) => {}
       }
    }
 
    pub fn view(&mut self) -> Element<EditGroupMsg> {
       use EditGroupMsg::*;
+      let members = self.members();
+      let other_members = self.other_members();
+      let diff = MembershipDiff::new(&self.orig_members, &members);
+      let is_renamed = self.group_name_val != self.orig_name;
+      let name_check = if is_renamed { check_groupname(&self.group_name_val, &self.existing_names).err() } else { None };
+
       let Self {
          group_name_state,
          group_name_val,
          ls_members,
          scroll_members,
+         error,
          btn_ok_state,
+         btn_revert_state,
          ..
       } = self;
 
       let is_admin = self.allow_admin_access;
       let lb_grp_name = Text::new("Group name:");
-      let txt_grp_name = TextInput::new(group_name_state, "Group name", &group_name_val, GroupNameChanged)
-         .padding(7)
-         .width(Length::Fill)
-         .style(CustomTextInput::Default)
-         .on_submit(GroupNameSubmitted(group_name_val.clone()));
+      let txt_grp_name = TextInput::new(group_name_state, "Group name", &group_name_val, GroupNameChanged).padding(7).width(Length::Fill).style(CustomTextInput::Default);
       let con_grp_name: Element<_> = if is_admin {
          txt_grp_name.into()
       } else {
@@ -90,6 +117,10 @@ impl EditGroupPage {
             .push(Container::new(Row::new()).width(Length::Units(120)).height(Length::Units(25)).style(CustomContainer::Transparent(FOREGROUND)), None)
             .into()
       };
+      let mut name_sec = Column::new().spacing(3).width(Length::Fill).push(con_grp_name);
+      if let Some(err) = &name_check {
+         name_sec = name_sec.push(Text::new(err.as_str()).size(12).color(ERROR));
+      }
 
       let scrollable_members = ls_members
          .iter_mut()
@@ -106,26 +137,40 @@ impl EditGroupPage {
             };
             scrollable.push(con_member)
          });
-      let member_pane = Container::new(Column::new().push(Container::new(Text::new("Members")).width(Length::Fill).padding(7).style(CustomContainer::Header)).push(scrollable_members))
-         .height(Length::Fill)
-         .width(Length::Fill)
-         .style(CustomContainer::ForegroundWhite);
+      let mut member_col = Column::new().push(Container::new(Text::new("Members")).width(Length::Fill).padding(7).style(CustomContainer::Header)).push(scrollable_members);
+      if !other_members.is_empty() {
+         member_col = member_col.push(Container::new(Text::new(format!("Also contains: {}", other_members.join(", "))).size(12)).padding(7));
+      }
+      let member_pane = Container::new(member_col).height(Length::Fill).width(Length::Fill).style(CustomContainer::ForegroundWhite);
 
+      let mut content = Column::new().width(Length::Fill).spacing(10).push(Row::new().spacing(10).align_items(Align::Center).push(lb_grp_name).push(name_sec)).push(member_pane);
+      if !diff.is_empty() {
+         let pending = diff
+            .added
+            .iter()
+            .fold(Column::new().spacing(3).push(Text::new("Pending changes:").size(12)), |col, name| col.push(Text::new(format!("+ {}", name)).size(12).color(SUCCESS)));
+         content = content.push(diff.removed.iter().fold(pending, |col, name| col.push(Text::new(format!("− {}", name)).size(12).color(ERROR))));
+      }
+      if let Some(err) = error {
+         content = content.push(Text::new(err.as_str()).size(12).color(ERROR));
+      }
+
+      let is_changed = is_renamed || !diff.is_empty();
+      let mut btn_revert = icon_btn(btn_revert_state, Icons::Minus, "Revert", None).style(CustomButton::Hovered);
       let mut btn_okay = icon_btn(btn_ok_state, Icons::CheckCircle, "Okay", None).style(CustomButton::Primary);
-      if self.is_changed {
-         btn_okay = btn_okay.on_press(OkayClicked(group_name_val.clone(), ls_members.iter().filter(|(is_checked, _)| *is_checked).map(|(_, usr)| usr.username().to_owned()).collect()));
+      if is_changed {
+         btn_revert = btn_revert.on_press(RevertClicked);
+         if name_check.is_none() && authority::is_unlocked() {
+            btn_okay = btn_okay.on_press(OkayClicked(GroupEdit {
+               rename: if is_renamed { Some(group_name_val.clone()) } else { None },
+               members: if diff.is_empty() { None } else { Some(members) },
+            }));
+         }
       }
 
-      Container::new(
-         Column::new()
-            .width(Length::Fill)
-            .spacing(10)
-            .push(Row::new().spacing(10).align_items(Align::Center).push(lb_grp_name).push(con_grp_name))
-            .push(member_pane)
-            .push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_okay)),
-      )
-      .width(Length::Fill)
-      .height(Length::Fill)
-      .into()
+      Container::new(content.push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_revert).push(btn_okay)))
+         .width(Length::Fill)
+         .height(Length::Fill)
+         .into()
    }
 }
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+const LOGIN_DEFS: &str = "/etc/login.defs";
+/// Longest group name accepted by `groupadd`.
+const MAX_GROUPNAME_LEN: usize = 32;
+
+/// Ranges of the ids `groupadd` gives to system and regular groups, from `/etc/login.defs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GidRanges {
+   pub sys_min: u32,
+   pub sys_max: u32,
+   pub min: u32,
+   pub max: u32,
+}
+
+impl Default for GidRanges {
+   fn default() -> Self {
+      Self {
+         sys_min: 101,
+         sys_max: 999,
+         min: 1000,
+         max: 60000,
+      }
+   }
+}
+
+impl GidRanges {
+   pub fn parse(content: &str) -> Self {
+      let values: HashMap<&str, u32> = content
+         .lines()
+         .map(str::trim)
+         .filter(|line| !line.starts_with('#'))
+         .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            Some((parts.next()?, parts.next()?.parse().ok()?))
+         })
+         .collect();
+      let defaults = Self::default();
+      let min = values.get("GID_MIN").copied().unwrap_or(defaults.min);
+      Self {
+         sys_min: values.get("SYS_GID_MIN").copied().unwrap_or(defaults.sys_min),
+         sys_max: values.get("SYS_GID_MAX").copied().unwrap_or_else(|| min.saturating_sub(1)),
+         min,
+         max: values.get("GID_MAX").copied().unwrap_or(defaults.max),
+      }
+   }
+
+   pub fn load() -> Self {
+      std::fs::read_to_string(LOGIN_DEFS).map(|content| Self::parse(&content)).unwrap_or_default()
+   }
+
+   /// Groups below the regular range belong to the system and its services.
+   pub fn is_system(&self, gid: u32) -> bool {
+      gid < self.min
+   }
+}
+
+/// Check a group name against the default rules of `groupadd` and the names already taken.
+pub fn check_groupname(name: &str, existing: &[String]) -> Result<(), String> {
+   if name.is_empty() {
+      return Err(String::from("Please enter a group name"));
+   }
+   if name.len() > MAX_GROUPNAME_LEN {
+      return Err(format!("The group name must not be longer than {} characters", MAX_GROUPNAME_LEN));
+   }
+   let mut chars = name.chars();
+   if !chars.next().map(|c| c.is_ascii_lowercase() || c == '_').unwrap_or(false) {
+      return Err(String::from("The group name must start with a lowercase letter or an underscore"));
+   }
+   if !chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-') {
+      return Err(String::from("The group name may only contain lowercase letters, digits, underscores and dashes"));
+   }
+   if existing.iter().any(|existing| existing == name) {
+      return Err(format!("The name {} is already used by a group", name));
+   }
+   Ok(())
+}
+
+/// Check an explicit group id: a number in the range of system or regular groups, not used by another group.
+pub fn check_gid(val: &str, is_system: bool, ranges: &GidRanges, used: &[(u32, String)]) -> Result<u32, String> {
+   let gid: u32 = val.trim().parse().map_err(|_| String::from("The group ID must be a number"))?;
+   let (min, max) = if is_system { (ranges.sys_min, ranges.sys_max) } else { (ranges.min, ranges.max) };
+   if gid < min || gid > max {
+      return Err(format!("The ID of a {} group must be between {} and {}", if is_system { "system" } else { "regular" }, min, max));
+   }
+   if let Some((_, name)) = used.iter().find(|(used_gid, _)| *used_gid == gid) {
+      return Err(format!("The group ID {} is already used by {}", gid, name));
+   }
+   Ok(gid)
+}
+
+/// Members added to and removed from a group, applied together.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MembershipDiff {
+   pub added: Vec<String>,
+   pub removed: Vec<String>,
+}
+
+impl MembershipDiff {
+   pub fn new(before: &[String], after: &[String]) -> Self {
+      Self {
+         added: after.iter().filter(|name| !before.contains(name)).cloned().collect(),
+         removed: before.iter().filter(|name| !after.contains(name)).cloned().collect(),
+      }
+   }
+
+   pub fn is_empty(&self) -> bool {
+      self.added.is_empty() && self.removed.is_empty()
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_parse_login_defs() {
+      let ranges = GidRanges::parse("# GID_MIN 100\nGID_MIN\t\t 2000\nGID_MAX 50000\nSYS_GID_MIN 500\n");
+      assert_eq!(
+         ranges,
+         GidRanges {
+            sys_min: 500,
+            sys_max: 1999,
+            min: 2000,
+            max: 50000
+         }
+      );
+      assert!(ranges.is_system(1999));
+      assert!(!ranges.is_system(2000));
+   }
+
+   #[test]
+   fn test_check_gid() {
+      let ranges = GidRanges::default();
+      let used = vec![(1000, String::from("dara")), (977, String::from("docker"))];
+      assert_eq!(check_gid("1001", false, &ranges, &used), Ok(1001));
+      assert_eq!(check_gid("1000", false, &ranges, &used), Err(String::from("The group ID 1000 is already used by dara")));
+      assert!(check_gid("977", true, &ranges, &used).is_err());
+      assert!(check_gid("500", false, &ranges, &used).is_err());
+      assert!(check_gid("abc", false, &ranges, &used).is_err());
+   }
+
+   #[test]
+   fn test_check_groupname() {
+      let existing = vec![String::from("audio")];
+      assert!(check_groupname("teachers", &existing).is_ok());
+      assert!(check_groupname("audio", &existing).is_err());
+      assert!(check_groupname("Teachers", &existing).is_err());
+      assert!(check_groupname("-g", &existing).is_err());
+   }
+
+   #[test]
+   fn test_membership_diff() {
+      let before = vec![String::from("dara"), String::from("sokha")];
+      let after = vec![String::from("sokha"), String::from("lina")];
+      let diff = MembershipDiff::new(&before, &after);
+      assert_eq!(diff.added, vec!["lina"]);
+      assert_eq!(diff.removed, vec!["dara"]);
+      assert!(MembershipDiff::new(&before, &before).is_empty());
+   }
+}
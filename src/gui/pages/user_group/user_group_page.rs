@@ -6,7 +6,7 @@ use libkoompi::system_settings::users_groups::{UsersGroupsManager, User, Group};
 use crate::gui::addon_widgets::tabbar;
 use crate::gui::styles::CustomContainer;
 use iced::{
-   button, Container, Length, Column, Text, Element
+   button, Container, Length, Column, Text, Element, Command
 };
 
 #[derive(Debug, Default)]
@@ -51,7 +51,7 @@ impl UserGroupPage {
       match UsersGroupsManager::new() {
          Ok(mut usrgrp_mn) => {
             let mut ls_users: Vec<User> = usrgrp_mn.list_users().to_owned();
-            let ls_grps: Vec<Group> = usrgrp_mn.list_groups().to_owned();
+            let ls_grps: Vec<Group> = usrgrp_mn.all_groups().to_owned();
             let mut curr_usr = usrgrp_mn.current_user().map(ToOwned::to_owned);
 
             Self {
@@ -71,7 +71,7 @@ impl UserGroupPage {
       }
    }
 
-   pub fn update(&mut self, msg: UserGroupMsg) {
+   pub fn update(&mut self, msg: UserGroupMsg) -> Command<UserGroupMsg> {
       use UserGroupMsg::*;
       use ContentPage::*;
       let Self {
@@ -83,21 +83,22 @@ impl UserGroupPage {
             self.curr_tab_idx = idx;
             match idx {
                0 => self.content = Users(UsersTab::new(usrgrp_mn, ls_users, curr_usr)),
-               1 => self.content = Groups(GroupsTab::new(usrgrp_mn, ls_grps, ls_users, curr_usr.as_ref().map(|usr| usr.is_admin()).unwrap_or(false))),
+               1 => self.content = Groups(GroupsTab::new(ls_grps, ls_users, curr_usr.as_ref().map(|usr| usr.is_admin()).unwrap_or(false))),
                _ => self.content = Empty,
             }
          },
          UsersMSG(users_msg) => {
             if let Users(users_tab) = content {
-               users_tab.update(users_msg);
+               return users_tab.update(users_msg).map(UsersMSG);
             }
          },
          GroupsMSG(groups_msg) => {
             if let Groups(groups_tab) = content {
-               groups_tab.update(groups_msg);
+               return groups_tab.update(groups_msg).map(GroupsMSG);
             }
          }, 
       }
+      Command::none()
    }
 
    pub fn view(&mut self) -> Element<UserGroupMsg> {
@@ -1,20 +1,40 @@
 mod add_user_page;
+mod avatar;
+mod change_avatar_page;
 mod change_groups_page;
 mod change_pwd_page;
 mod change_user_info_page;
+mod delete_user_page;
+mod home_dir;
+mod import_users_page;
+mod login_options_page;
+mod provisioning;
 mod user_info_page;
+mod validation;
 
+use crate::gui::addon_widgets::lock_btn;
 use crate::gui::styles::{CustomButton, CustomContainer};
-use iced::{button, scrollable, Align, Button, Column, Container, Element, Image, Length, Row, Scrollable, Space, Text};
+use crate::helpers::{authority, login_policy::LoginPolicy, Action, NewPassword, UserEdit};
+use iced::{button, scrollable, Align, Button, Column, Command, Container, Element, Image, Length, Row, Scrollable, Space, Text};
 use iced_custom_widget::{Icon, Icons};
-use libkoompi::system_settings::users_groups::{AccountType, User, UsersGroupsManager};
+use libkoompi::system_settings::users_groups::{User, UsersGroupsManager};
 use std::cell::RefCell;
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::PathBuf;
+use std::rc::Rc;
 use {
    add_user_page::{AddUserMsg, AddUserPage},
+   change_avatar_page::{ChangeAvatarMsg, ChangeAvatarPage},
    change_groups_page::{ChangeGroupsMsg, ChangeGroupsPage},
    change_pwd_page::{ChangePwdMsg, ChangePwdPage},
    change_user_info_page::{ChangeInfoMsg, ChangeInfoPage},
+   delete_user_page::{DeleteUserMsg, DeleteUserPage},
+   import_users_page::{ImportUsersMsg, ImportUsersPage},
+   login_options_page::{LoginOptionsMsg, LoginOptionsPage},
+   provisioning::Outcome,
    user_info_page::{UserInfoMsg, UserInfoPage},
+   validation::{existing_names, load_dictionary, PwdQualityConf},
 };
 
 #[derive(Debug, Default)]
@@ -26,6 +46,10 @@ pub struct UsersTab {
    scroll_users: scrollable::State,
    add_state: button::State,
    remove_state: button::State,
+   import_state: button::State,
+   lock_state: button::State,
+   /// Words refused as passwords, read when a password is first checked.
+   dictionary: Option<Rc<HashSet<String>>>,
 
    // dynamic section
    content: ContentPage,
@@ -38,6 +62,10 @@ pub enum ContentPage {
    ChangeInfo(ChangeInfoPage),
    ChangeGroups(ChangeGroupsPage),
    AddUser(AddUserPage),
+   ChangeAvatar(ChangeAvatarPage),
+   LoginOptions(LoginOptionsPage),
+   ImportUsers(ImportUsersPage),
+   DeleteUser(DeleteUserPage),
    Empty,
 }
 
@@ -52,11 +80,21 @@ pub enum UsersMsg {
    SelectedUsr(usize),
    AddClicked,
    RemoveClicked,
+   ImportClicked,
+   LockClicked,
+   LockToggled(Result<(), String>),
+   /// The helper is done with the account now named by the first field.
+   AccountChanged(String, Result<(), String>),
+   UsersCreated(Vec<Outcome>),
    UserInfoMSG(UserInfoMsg),
    ChangePwdMSG(ChangePwdMsg),
    AddUserMSG(AddUserMsg),
    ChangeInfoMSG(ChangeInfoMsg),
    ChangeGroupsMSG(ChangeGroupsMsg),
+   ChangeAvatarMSG(ChangeAvatarMsg),
+   LoginOptionsMSG(LoginOptionsMsg),
+   ImportUsersMSG(ImportUsersMsg),
+   DeleteUserMSG(DeleteUserMsg),
 }
 
 impl UsersTab {
@@ -75,18 +113,95 @@ impl UsersTab {
          scroll_users: Default::default(),
          add_state: Default::default(),
          remove_state: Default::default(),
+         import_state: Default::default(),
+         lock_state: Default::default(),
+         dictionary: None,
       }
    }
 
-   pub fn update(&mut self, msg: UsersMsg) {
+   /// Read the users again after the helper changed them, and show the user named `username`, or else the first one.
+   fn reload(&mut self, username: &str) {
+      self.load_users(username);
+      self.show_selected();
+   }
+
+   /// Read the users again and select the user named `username`, or else the first one.
+   fn load_users(&mut self, username: &str) {
+      match UsersGroupsManager::new() {
+         Ok(usrgrp_mn) => {
+            self.ls_users = usrgrp_mn.list_users().iter().map(|usr| (RefCell::new(usr.to_owned()), button::State::new())).collect();
+            if let Some(usr) = usrgrp_mn.current_user() {
+               *self.curr_usr.borrow_mut() = usr.to_owned();
+            }
+            *self.usrgrp_mn.borrow_mut() = usrgrp_mn;
+         }
+         Err(err) => eprintln!("{:?}", err),
+      }
+      self.selected_user = match self.ls_users.iter().position(|(usr, _)| usr.borrow().username() == username) {
+         Some(idx) => Some(idx),
+         None if self.ls_users.is_empty() => None,
+         None => Some(0),
+      };
+   }
+
+   /// Show the details of the selected user.
+   fn show_selected(&mut self) {
+      self.content = Self::selected_page(&self.ls_users, self.selected_user, &self.curr_usr.borrow());
+   }
+
+   fn selected_page(ls_users: &[(RefCell<User>, button::State)], selected_user: Option<usize>, curr_usr: &User) -> ContentPage {
+      match selected_user.and_then(|idx| ls_users.get(idx)) {
+         Some((user, _)) => {
+            let usr = user.borrow();
+            ContentPage::UserInfo(UserInfoPage::new(&usr, usr.uid() == curr_usr.uid(), curr_usr.is_admin()))
+         }
+         None => ContentPage::Empty,
+      }
+   }
+
+   /// Show an error of the helper on the page that asked for the change.
+   fn set_error(&mut self, error: String) {
+      use ContentPage::*;
+      match &mut self.content {
+         UserInfo(page) => page.set_error(error),
+         ChangePwd(page) => page.set_error(error),
+         ChangeInfo(page) => page.set_error(error),
+         ChangeGroups(page) => page.set_error(error),
+         AddUser(page) => page.set_error(error),
+         ChangeAvatar(page) => page.set_error(error),
+         LoginOptions(page) => page.set_error(error),
+         DeleteUser(page) => page.set_error(error),
+         ImportUsers(_) | Empty => eprintln!("{}", error),
+      }
+   }
+
+   pub fn update(&mut self, msg: UsersMsg) -> Command<UsersMsg> {
       use ContentPage::*;
       use UsersMsg::*;
 
-      let Self { usrgrp_mn, ls_users, content, curr_usr, .. } = self;
+      if let AccountChanged(username, res) = &msg {
+         match res {
+            Ok(()) => self.reload(username),
+            Err(err) => self.set_error(err.to_owned()),
+         }
+         return Command::none();
+      }
+      if let UsersCreated(outcomes) = msg {
+         // The report stays shown over the new list of users.
+         let username = self.curr_usr.borrow().username().to_string();
+         self.load_users(&username);
+         if let ImportUsers(import_users_page) = &mut self.content {
+            import_users_page.set_outcomes(outcomes);
+         }
+         return Command::none();
+      }
+
+      let Self { usrgrp_mn, ls_users, content, curr_usr, dictionary, .. } = self;
 
       let curr_usr = curr_usr.borrow();
       let curr_is_admin = curr_usr.is_admin();
       let is_curr_usr = |uid: u16| curr_usr.uid().eq(&uid);
+      let mut dictionary = || Rc::clone(dictionary.get_or_insert_with(|| Rc::new(load_dictionary())));
 
       match msg {
          SelectedUsr(idx) => {
@@ -109,42 +224,47 @@ impl UsersTab {
          }
          AddClicked => {
             if curr_is_admin {
-               self.content = AddUser(AddUserPage::new());
+               let login_shells = usrgrp_mn.borrow().login_shells().to_vec();
+               self.content = AddUser(AddUserPage::new(existing_names(), PwdQualityConf::load(), dictionary(), login_shells));
             }
          }
-         RemoveClicked => {
+         ImportClicked => {
             if curr_is_admin {
+               let all_groups = usrgrp_mn.borrow().all_groups().iter().map(|grp| grp.name().to_string()).collect();
+               self.content = ImportUsers(ImportUsersPage::new(existing_names(), all_groups, PwdQualityConf::load(), dictionary()));
+            }
+         }
+         RemoveClicked => {
+            if curr_is_admin && authority::is_unlocked() {
                if let Some(selected) = self.selected_user {
                   if let Some((user, _)) = ls_users.get(selected) {
-                     let usr = user.borrow();
-
-                     match usrgrp_mn.borrow_mut().delete_user(usr.username(), false) {
-                        Ok(is_ok) => {
-                           if is_ok {
-                              std::mem::drop(usr);
-                              ls_users.remove(selected);
-                              self.selected_user = Some(0);
-                              self.content = UserInfo(UserInfoPage::new(&curr_usr, true, curr_is_admin));
-                           } else {
-                              println!("can not delete group");
-                           }
-                        }
-                        Err(err) => eprintln!("{:?}", err),
-                     }
+                     self.content = DeleteUser(DeleteUserPage::new(&user.borrow()));
                   }
                }
             }
          }
+         LockClicked => return Command::perform(authority::toggle(), LockToggled),
+         LockToggled(res) => {
+            if let Err(err) = res {
+               eprintln!("{:?}", err);
+            }
+         }
+         // Handled above, before the users are borrowed.
+         AccountChanged(..) | UsersCreated(_) => {}
          UserInfoMSG(usr_info_msg) => {
-            if let UserInfo(user_info_page) = content {
+            if let UserInfo(_) = content {
                if let Some(idx) = self.selected_user {
-                  if let Some((user, _)) = ls_users.get_mut(idx) {
-                     let mut usr = user.borrow_mut();
+                  if let Some((user, _)) = ls_users.get(idx) {
+                     let usr = user.borrow();
                      let is_curr_usr = is_curr_usr(usr.uid());
 
                      use UserInfoMsg::*;
                      match usr_info_msg {
-                        ChangePwdClicked => self.content = ChangePwd(ChangePwdPage::new(is_curr_usr)),
+                        ChangePwdClicked => {
+                           let mut names: Vec<String> = usr.fullname().split_whitespace().map(String::from).collect();
+                           names.push(usr.username().to_string());
+                           self.content = ChangePwd(ChangePwdPage::new(is_curr_usr, names, PwdQualityConf::load(), dictionary()));
+                        }
                         ChangeInfoClicked => {
                            let usrgrp_ref = usrgrp_mn.borrow();
                            let groupname = usrgrp_ref.all_groups().iter().find(|grp| grp.gid() == usr.gid()).map(|grp| grp.name());
@@ -153,18 +273,8 @@ impl UsersTab {
                         }
                         AllowUsrAdminToggled(is_checked) => {
                            if curr_is_admin {
-                              match usrgrp_mn.borrow_mut().change_user_type(usr.username(), if is_checked { AccountType::Admin } else { AccountType::default() }) {
-                                 Ok(newusr) => {
-                                    if let Some(newusr) = newusr {
-                                       println!("Change user type Success");
-                                       *usr = newusr;
-                                       user_info_page.with_user(&usr, is_curr_usr);
-                                    } else {
-                                       println!("can not Change user type");
-                                    }
-                                 }
-                                 Err(err) => eprintln!("{:?}", err),
-                              }
+                              let args = vec![usr.username().to_string(), String::from(if is_checked { "admin" } else { "standard" })];
+                              return change_account(usr.username(), authority::exec(Action::SetAccountType, args));
                            }
                         }
                         ChangeGroupsClicked => {
@@ -172,6 +282,16 @@ impl UsersTab {
                            let all_groups = usrgrp_ref.all_groups();
                            self.content = ChangeGroups(ChangeGroupsPage::new(&usr, all_groups));
                         }
+                        ChangeAvatarClicked => {
+                           if is_curr_usr || curr_is_admin {
+                              self.content = ChangeAvatar(ChangeAvatarPage::new());
+                           }
+                        }
+                        LoginOptionsClicked => {
+                           if curr_is_admin {
+                              self.content = LoginOptions(LoginOptionsPage::new(usr.username(), is_curr_usr, LoginPolicy::load().user(usr.username())));
+                           }
+                        }
                      }
                   }
                }
@@ -180,39 +300,20 @@ impl UsersTab {
          ChangePwdMSG(change_pwd_msg) => {
             if let ChangePwd(change_pwd_page) = content {
                if let Some(idx) = self.selected_user {
-                  if let Some((user, _)) = ls_users.get_mut(idx) {
-                     let mut usr = user.borrow_mut();
+                  if let Some((user, _)) = ls_users.get(idx) {
+                     let usr = user.borrow();
                      let is_curr_usr = is_curr_usr(usr.uid());
                      use ChangePwdMsg::*;
                      match change_pwd_msg {
                         CancelClicked => self.content = UserInfo(UserInfoPage::new(&usr, is_curr_usr, curr_is_admin)),
-                        ChangeClicked(old_pwd, new_pwd, verify_pwd) => {
+                        ChangeClicked(old_pwd, new_pwd, _) => {
+                           let username = usr.username().to_string();
+                           // Users give their current password to change their own, administrators reset the others.
                            if is_curr_usr {
-                              match usrgrp_mn.borrow_mut().change_user_password(usr.username(), &old_pwd, &new_pwd, &verify_pwd) {
-                                 Ok(newusr) => {
-                                    if let Some(newusr) = newusr {
-                                       println!("Change password Success");
-                                       *usr = newusr;
-                                    } else {
-                                       println!("can not Change password")
-                                    }
-                                 }
-                                 Err(err) => eprintln!("{:?}", err),
-                              }
+                              return change_account(usr.username(), authority::exec_json(Action::ChangePassword, &NewPassword { username, old_pwd: Some(old_pwd), new_pwd }));
                            } else if curr_is_admin {
-                              match usrgrp_mn.borrow_mut().reset_user_password(usr.username(), &new_pwd, &verify_pwd) {
-                                 Ok(newusr) => {
-                                    if let Some(newusr) = newusr {
-                                       println!("Reset password Success");
-                                       *usr = newusr;
-                                    } else {
-                                       println!("can not Reset password")
-                                    }
-                                 }
-                                 Err(err) => eprintln!("{:?}", err),
-                              }
+                              return change_account(usr.username(), authority::exec_json(Action::SetPassword, &NewPassword { username, old_pwd: None, new_pwd }));
                            }
-                           self.content = UserInfo(UserInfoPage::new(&usr, is_curr_usr, curr_is_admin));
                         }
                         _ => change_pwd_page.update(change_pwd_msg),
                      }
@@ -225,23 +326,9 @@ impl UsersTab {
                if let AddUser(add_user_page) = content {
                   use AddUserMsg::*;
                   match add_user_msg {
-                     CreateClicked(user) => match usrgrp_mn.borrow_mut().create_user(user.fullname, user.username.clone(), user.acc_type, user.pwd, user.pwd_verify) {
-                        Ok(newuser) => {
-                           if let Some(newuser) = newuser {
-                              ls_users.push((RefCell::new(newuser.to_owned()), button::State::new()));
-                              self.selected_user = Some(ls_users.len() - 1);
-                              self.content = UserInfo(UserInfoPage::new(&newuser, is_curr_usr(newuser.uid()), curr_is_admin));
-                           } else {
-                              self.selected_user = Some(0);
-                              self.content = UserInfo(UserInfoPage::new(&curr_usr, true, curr_is_admin));
-                           };
-                        }
-                        Err(err) => eprintln!("{:?}", err),
-                     },
+                     CreateClicked(user) => return change_account(&user.username, authority::exec_json(Action::CreateUser, &user.new_user())),
                      CancelClicked => {
-                        let user = ls_users.iter().map(|(usr, _)| usr).collect::<Vec<&RefCell<User>>>()[self.selected_user.unwrap_or(0)];
-                        let usr = user.borrow();
-                        self.content = UserInfo(UserInfoPage::new(&usr, is_curr_usr(usr.uid()), curr_is_admin));
+                        self.content = Self::selected_page(ls_users, self.selected_user, &curr_usr);
                      }
                      _ => add_user_page.update(add_user_msg),
                   }
@@ -251,29 +338,24 @@ impl UsersTab {
          ChangeInfoMSG(change_info_msg) => {
             if let ChangeInfo(change_info_page) = content {
                if let Some(idx) = self.selected_user {
-                  if let Some((user, _)) = ls_users.get_mut(idx) {
-                     let mut usr = user.borrow_mut();
+                  if let Some((user, _)) = ls_users.get(idx) {
+                     let usr = user.borrow();
                      let is_curr_usr = is_curr_usr(usr.uid());
 
                      use ChangeInfoMsg::*;
                      match change_info_msg {
                         CancelClicked => self.content = UserInfo(UserInfoPage::new(&usr, is_curr_usr, curr_is_admin)),
                         OkayClicked(usernew) => {
-                           match usrgrp_mn
-                              .borrow_mut()
-                              .change_user_info(usr.username().to_string(), usernew.uid.to_string(), usernew.gname, usernew.fullname, usernew.username, usernew.login_shell, usernew.home_dir)
-                           {
-                              Ok(newusr) => {
-                                 if let Some(newusr) = newusr {
-                                    println!("edit user success");
-                                    *usr = newusr;
-                                 } else {
-                                    println!("can not edit user");
-                                 }
-                              }
-                              Err(err) => eprintln!("{:?}", err),
-                           }
-                           self.content = UserInfo(UserInfoPage::new(&usr, is_curr_usr, curr_is_admin))
+                           let edit = UserEdit {
+                              username: usr.username().to_string(),
+                              new_username: usernew.username,
+                              fullname: usernew.fullname,
+                              uid: u32::from(usernew.uid),
+                              group: usernew.gname,
+                              shell: usernew.login_shell,
+                              home: PathBuf::from(usernew.home_dir),
+                           };
+                           return change_account(&edit.new_username, authority::exec_json(Action::EditUser, &edit));
                         }
                         _ => change_info_page.update(change_info_msg),
                      }
@@ -284,26 +366,16 @@ impl UsersTab {
          ChangeGroupsMSG(change_groups_msg) => {
             if let ChangeGroups(change_groups_page) = content {
                if let Some(idx) = self.selected_user {
-                  if let Some((user, _)) = ls_users.get_mut(idx) {
-                     let mut usr = user.borrow_mut();
+                  if let Some((user, _)) = ls_users.get(idx) {
+                     let usr = user.borrow();
                      let is_curr_usr = is_curr_usr(usr.uid());
 
                      use ChangeGroupsMsg::*;
                      match change_groups_msg {
                         CancelClicked => self.content = UserInfo(UserInfoPage::new(&usr, is_curr_usr, curr_is_admin)),
                         OkayClicked(ls_groups) => {
-                           match usrgrp_mn.borrow_mut().change_user_groups(usr.username(), ls_groups.iter().map(|grp| grp.as_str()).collect::<Vec<&str>>()) {
-                              Ok(newusr) => {
-                                 if let Some(newusr) = newusr {
-                                    println!("change user groups success");
-                                    *usr = newusr;
-                                 } else {
-                                    println!("can not change user groups");
-                                 }
-                              }
-                              Err(err) => eprintln!("{:?}", err),
-                           }
-                           self.content = UserInfo(UserInfoPage::new(&usr, is_curr_usr, curr_is_admin))
+                           let args = vec![usr.username().to_string(), ls_groups.join(",")];
+                           return change_account(usr.username(), authority::exec(Action::SetGroups, args));
                         }
                         _ => change_groups_page.update(change_groups_msg),
                      }
@@ -311,7 +383,82 @@ impl UsersTab {
                }
             }
          }
+         ChangeAvatarMSG(change_avatar_msg) => {
+            if let ChangeAvatar(change_avatar_page) = content {
+               if let Some(idx) = self.selected_user {
+                  if let Some((user, _)) = ls_users.get(idx) {
+                     let usr = user.borrow();
+                     let is_curr_usr = is_curr_usr(usr.uid());
+
+                     use ChangeAvatarMsg::*;
+                     match change_avatar_msg {
+                        CancelClicked => self.content = UserInfo(UserInfoPage::new(&usr, is_curr_usr, curr_is_admin)),
+                        OkayClicked => {
+                           if let Some(img) = change_avatar_page.cropped() {
+                              return change_account(usr.username(), avatar::apply(usr.username(), is_curr_usr, img));
+                           }
+                        }
+                        _ => change_avatar_page.update(change_avatar_msg),
+                     }
+                  }
+               }
+            }
+         }
+         LoginOptionsMSG(login_options_msg) => {
+            if let LoginOptions(login_options_page) = content {
+               if let Some(idx) = self.selected_user {
+                  if let Some((user, _)) = ls_users.get(idx) {
+                     let usr = user.borrow();
+                     let is_curr_usr = is_curr_usr(usr.uid());
+
+                     use LoginOptionsMsg::*;
+                     match login_options_msg {
+                        CancelClicked => self.content = UserInfo(UserInfoPage::new(&usr, is_curr_usr, curr_is_admin)),
+                        OkayClicked(options) => {
+                           if curr_is_admin {
+                              return change_account(usr.username(), authority::exec_json(Action::LoginOptions, &options));
+                           }
+                        }
+                        _ => login_options_page.update(login_options_msg),
+                     }
+                  }
+               }
+            }
+         }
+         ImportUsersMSG(import_users_msg) => {
+            if curr_is_admin {
+               if let ImportUsers(import_users_page) = content {
+                  use ImportUsersMsg::*;
+                  match import_users_msg {
+                     CreateClicked => {
+                        import_users_page.update(CreateClicked);
+                        return Command::perform(provisioning::create_users(import_users_page.rows().to_vec()), UsersCreated);
+                     }
+                     CancelClicked | DoneClicked => {
+                        self.content = Self::selected_page(ls_users, self.selected_user, &curr_usr);
+                     }
+                     _ => import_users_page.update(import_users_msg),
+                  }
+               }
+            }
+         }
+         DeleteUserMSG(delete_user_msg) => {
+            if curr_is_admin {
+               if let DeleteUser(delete_user_page) = content {
+                  use DeleteUserMsg::*;
+                  match delete_user_msg {
+                     // The current user is shown once the account is gone.
+                     DeleteClicked(args) => return change_account(curr_usr.username(), authority::exec(Action::DeleteUser, args)),
+                     CancelClicked => {
+                        self.content = Self::selected_page(ls_users, self.selected_user, &curr_usr);
+                     }
+                     _ => delete_user_page.update(delete_user_msg),
+                  }
+               }
+            }
+         }
       }
+      Command::none()
    }
 
    pub fn view(&mut self) -> Element<UsersMsg> {
@@ -324,6 +471,8 @@ impl UsersTab {
          scroll_users,
          add_state,
          remove_state,
+         import_state,
+         lock_state,
          content,
          ..
       } = self;
@@ -382,15 +531,21 @@ impl UsersTab {
          });
       let mut btn_add = Button::new(add_state, Icon::new(Icons::Ad).size(23)).padding(2).style(CustomButton::Text);
       let mut btn_remove = Button::new(remove_state, Icon::new(Icons::Minus).size(23)).padding(2).style(CustomButton::Text);
+      let mut btn_import = Button::new(import_state, Icon::new(Icons::FileUpload).size(23)).padding(2).style(CustomButton::Text);
       if let Some(idx) = *selected_user {
-         if idx.ne(&0) && is_admin {
+         if idx.ne(&0) && is_admin && authority::is_unlocked() {
             btn_remove = btn_remove.on_press(RemoveClicked);
          }
       }
       if is_admin {
          btn_add = btn_add.on_press(AddClicked);
+         btn_import = btn_import.on_press(ImportClicked);
+      }
+      let mut btn_group = Row::new().align_items(Align::Center).push(btn_add).push(btn_remove).push(btn_import).push(Space::with_width(Length::Fill));
+      if is_admin {
+         btn_group = btn_group.push(lock_btn(lock_state, LockClicked).padding(2));
       }
-      let btn_group = Container::new(Row::new().push(btn_add).push(btn_remove)).width(Length::Fill).style(CustomContainer::Header);
+      let btn_group = Container::new(btn_group).width(Length::Fill).style(CustomContainer::Header);
       let users_pane = Container::new(Column::new().push(Container::new(Text::new("Users")).width(Length::Fill).padding(7).style(CustomContainer::Header)).push(scrollable_users).push(btn_group))
          .height(Length::Fill)
          .width(Length::FillPortion(3))
@@ -402,6 +557,10 @@ impl UsersTab {
          ChangeInfo(change_info_page) => change_info_page.view().map(|msg| ChangeInfoMSG(msg)),
          UserInfo(user_info_page) => user_info_page.view().map(|msg| UserInfoMSG(msg)),
          ChangeGroups(change_groups_page) => change_groups_page.view().map(|msg| ChangeGroupsMSG(msg)),
+         ChangeAvatar(change_avatar_page) => change_avatar_page.view().map(|msg| ChangeAvatarMSG(msg)),
+         LoginOptions(login_options_page) => login_options_page.view().map(|msg| LoginOptionsMSG(msg)),
+         ImportUsers(import_users_page) => import_users_page.view().map(|msg| ImportUsersMSG(msg)),
+         DeleteUser(delete_user_page) => delete_user_page.view().map(|msg| DeleteUserMSG(msg)),
          Empty => Container::new(Text::new("There is no users available")).width(Length::Fill).height(Length::Fill).center_x().center_y().into(),
       };
 
@@ -411,3 +570,9 @@ impl UsersTab {
          .into()
    }
 }
+
+/// Run an operation of the helper on the account named `username`, which is shown again once it is done.
+fn change_account(username: &str, operation: impl Future<Output = Result<(), String>> + Send + 'static) -> Command<UsersMsg> {
+   let username = username.to_string();
+   Command::perform(operation, move |res| UsersMsg::AccountChanged(username.clone(), res))
+}
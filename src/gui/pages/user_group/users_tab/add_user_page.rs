@@ -1,8 +1,14 @@
+use super::home_dir::{self, HomeSetup};
+use super::validation::{check_password, check_username, error_view, pwd_check_view, PwdCheck, PwdQualityConf};
 use crate::gui::addon_widgets::icon_btn;
-use crate::gui::styles::{CustomButton, CustomSelect, CustomTextInput};
-use iced::{button, pick_list, text_input, Align, Button, Column, Container, Element, Length, PickList, Row, Space, Text, TextInput};
+use crate::gui::styles::{CustomButton, CustomCheckbox, CustomSelect, CustomTextInput};
+use crate::helpers::{is_skel_dir, NewUser};
+use iced::{button, pick_list, text_input, Align, Button, Checkbox, Column, Container, Element, Length, PickList, Row, Space, Text, TextInput};
 use iced_custom_widget::{Icon, Icons};
 use libkoompi::{helpers::to_account_name, system_settings::users_groups::AccountType};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 #[derive(Debug, Clone, Default)]
 pub struct AddUserPage {
@@ -21,6 +27,22 @@ pub struct AddUserPage {
    btn_create_state: button::State,
    btn_cancel_state: button::State,
    btn_show_pwd: button::State,
+   login_shells: Vec<String>,
+   default_shell: String,
+   shell_state: pick_list::State<String>,
+   selected_shell: Option<String>,
+   is_custom_skel: bool,
+   skel_state: text_input::State,
+   skel_val: String,
+   btn_browse_skel: button::State,
+   is_encryption_supported: bool,
+   is_encrypted: bool,
+   existing_names: Vec<String>,
+   pwd_conf: PwdQualityConf,
+   dictionary: Rc<HashSet<String>>,
+   username_err: Option<String>,
+   pwd_check: Option<PwdCheck>,
+   error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,7 +51,23 @@ pub struct UserReq {
    pub fullname: String,
    pub username: String,
    pub pwd: String,
-   pub pwd_verify: String,
+   pub home: HomeSetup,
+}
+
+impl UserReq {
+   /// The account for the helper to create, with its password and home folder.
+   pub fn new_user(&self) -> NewUser {
+      NewUser {
+         username: self.username.to_owned(),
+         fullname: self.fullname.to_owned(),
+         is_admin: matches!(self.acc_type, AccountType::Admin),
+         groups: Vec::new(),
+         password: self.pwd.to_owned(),
+         shell: self.home.shell.to_owned(),
+         skel: self.home.skel.to_owned(),
+         is_encrypted: self.home.is_encrypted,
+      }
+   }
 }
 
 #[derive(Debug, Clone)]
@@ -40,18 +78,48 @@ pub enum AddUserMsg {
    PwdChanged(String),
    VerifyPwdChanged(String),
    ShowPwdToggled,
+   ShellChanged(String),
+   CustomSkelToggled(bool),
+   SkelChanged(String),
+   BrowseSkelClicked,
+   EncryptToggled(bool),
    CreateClicked(UserReq),
    CancelClicked,
 }
 
 impl AddUserPage {
-   pub fn new() -> Self {
+   pub fn new(existing_names: Vec<String>, pwd_conf: PwdQualityConf, dictionary: Rc<HashSet<String>>, login_shells: Vec<String>) -> Self {
+      let default_shell = home_dir::default_shell();
       Self {
          selected_acc_type: Some(AccountType::default()),
+         selected_shell: Some(default_shell.clone()),
+         login_shells,
+         default_shell,
+         skel_val: String::from(home_dir::SKEL_DIR),
+         is_encryption_supported: home_dir::is_encryption_supported(),
+         existing_names,
+         pwd_conf,
+         dictionary,
          ..Self::default()
       }
    }
 
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
+   }
+
+   /// Check the user name and the password again after one of the fields changed.
+   fn validate(&mut self) {
+      self.username_err = if self.username_val.is_empty() { None } else { check_username(&self.username_val, &self.existing_names).err() };
+      self.pwd_check = if self.pwd_val.is_empty() {
+         None
+      } else {
+         let mut names: Vec<&str> = self.fullname_val.split_whitespace().collect();
+         names.push(&self.username_val);
+         Some(check_password(&self.pwd_val, &names, &self.pwd_conf, &self.dictionary))
+      };
+   }
+
    pub fn update(&mut self, msg: AddUserMsg) {
       use AddUserMsg::*;
 
@@ -63,15 +131,26 @@ impl AddUserPage {
                self.username_val = to_account_name(&self.fullname_val);
             }
          }
+         // A typed user name is kept as is and explained by the inline error when it breaks the rules.
          UserNameChanged(val) => {
             self.is_usrname_directly_changed = true;
-            self.username_val = to_account_name(&val);
+            self.username_val = val;
          }
          PwdChanged(val) => self.pwd_val = val,
          VerifyPwdChanged(val) => self.verify_pwd_val = val,
          ShowPwdToggled => self.is_show_pwd = !self.is_show_pwd,
+         ShellChanged(val) => self.selected_shell = Some(val),
+         CustomSkelToggled(is_checked) => self.is_custom_skel = is_checked,
+         SkelChanged(val) => self.skel_val = val,
+         BrowseSkelClicked => {
+            if let Ok(nfd2::Response::Okay(path)) = nfd2::open_pick_folder(Some(Path::new(&self.skel_val))) {
+               self.skel_val = path.display().to_string();
+            }
+         }
+         EncryptToggled(is_checked) => self.is_encrypted = is_checked,
          CreateClicked(_) | CancelClicked => {}
       }
+      self.validate();
    }
 
    pub fn view(&mut self) -> Element<AddUserMsg> {
@@ -90,6 +169,18 @@ impl AddUserPage {
          btn_create_state,
          btn_cancel_state,
          btn_show_pwd,
+         login_shells,
+         default_shell,
+         shell_state,
+         selected_shell,
+         is_custom_skel,
+         skel_state,
+         skel_val,
+         btn_browse_skel,
+         is_encrypted,
+         username_err,
+         pwd_check,
+         error,
          ..
       } = self;
       let lb_fullname = Text::new("Full Name:");
@@ -97,7 +188,6 @@ impl AddUserPage {
       let lb_acc_type = Text::new("Account Type:");
       let lb_pwd = Text::new("Password:");
       let lb_verify_pwd = Text::new("Verify Password:");
-      let lb_sec = Column::new().spacing(20).push(lb_fullname).push(lb_username).push(lb_acc_type).push(lb_pwd).push(lb_verify_pwd);
       let txt_fullname = TextInput::new(fullname_state, "Full name", &fullname_val, FullNameChanged).padding(7).width(Length::Units(227)).style(CustomTextInput::Default);
       let txt_username = TextInput::new(username_state, "User name", &username_val, UserNameChanged).padding(7).width(Length::Units(227)).style(CustomTextInput::Default);
       let pl_acc_type = PickList::new(acc_type_state, &AccountType::ALL[..], selected_acc_type.clone(), AccTypeChanged).style(CustomSelect::Primary);
@@ -111,24 +201,73 @@ impl AddUserPage {
          .padding(7)
          .width(Length::Units(227))
          .style(CustomTextInput::Default);
+      let mut username_sec = Column::new().spacing(3).push(txt_username);
+      if let Some(err) = username_err {
+         username_sec = username_sec.push(error_view(err));
+      }
+      let mut pwd_sec = Column::new().spacing(3).push(Row::new().spacing(5).align_items(Align::Center).push(txt_pwd).push(btn_show_pwd));
+      if let Some(check) = pwd_check {
+         pwd_sec = pwd_sec.push(pwd_check_view(check));
+      }
+      let mut verify_sec = Column::new().spacing(3).push(txt_verify);
+      if !verify_pwd_val.is_empty() && pwd_val.as_str().ne(verify_pwd_val.as_str()) {
+         verify_sec = verify_sec.push(error_view("The passwords do not match"));
+      }
       let info_sec = Column::new()
          .spacing(7)
-         .push(txt_fullname)
-         .push(txt_username)
-         .push(pl_acc_type)
-         .push(Row::new().spacing(5).align_items(Align::Center).push(txt_pwd).push(btn_show_pwd))
-         .push(txt_verify);
+         .push(field(lb_fullname, txt_fullname.into()))
+         .push(field(lb_username, username_sec.into()))
+         .push(field(lb_acc_type, pl_acc_type.into()))
+         .push(field(lb_pwd, pwd_sec.into()))
+         .push(field(lb_verify_pwd, verify_sec.into()));
+
+      let pl_shell = PickList::new(shell_state, &login_shells[..], selected_shell.clone(), ShellChanged).style(CustomSelect::Primary);
+      let skel_err = if *is_custom_skel && !is_skel_dir(Path::new(skel_val.as_str())) {
+         Some("Please choose a template folder in /etc, like /etc/skel")
+      } else {
+         None
+      };
+      let mut skel_sec = Column::new().spacing(7).push(Checkbox::new(*is_custom_skel, "Copy a custom template", CustomSkelToggled).spacing(10).style(CustomCheckbox::Default));
+      if *is_custom_skel {
+         let txt_skel = TextInput::new(skel_state, "Template folder", &skel_val, SkelChanged).padding(7).width(Length::Units(200)).style(CustomTextInput::Default);
+         let btn_browse = Button::new(btn_browse_skel, Text::new("...")).padding(7).on_press(BrowseSkelClicked).style(CustomButton::Default);
+         skel_sec = skel_sec.push(Row::new().spacing(5).align_items(Align::Center).push(txt_skel).push(btn_browse));
+      } else {
+         skel_sec = skel_sec.push(Text::new(format!("The default template is {}", home_dir::SKEL_DIR)).size(12));
+      }
+      if let Some(err) = skel_err {
+         skel_sec = skel_sec.push(error_view(err));
+      }
+      let encrypt_sec: Element<_> = if self.is_encryption_supported {
+         Checkbox::new(*is_encrypted, "Encrypt the home folder", EncryptToggled).spacing(10).style(CustomCheckbox::Default).into()
+      } else {
+         Text::new("Encryption is not supported on this system").size(12).into()
+      };
+      let mut home_sec = Column::new()
+         .spacing(7)
+         .push(field(Text::new("Login Shell:"), pl_shell.into()))
+         .push(field(Text::new("Home Template:"), skel_sec.into()))
+         .push(field(Text::new("Encryption:"), encrypt_sec));
+
+      if let Some(err) = error {
+         home_sec = home_sec.push(error_view(err));
+      }
 
       let mut btn_create = icon_btn(btn_create_state, Icons::Ad, "Create", None).style(CustomButton::Primary);
       let btn_cancel = icon_btn(btn_cancel_state, Icons::Minus, "Cancel", None).on_press(CancelClicked).style(CustomButton::Hovered);
       if let Some(acc_type) = selected_acc_type {
-         if !(fullname_val.is_empty() || username_val.is_empty() || pwd_val.is_empty() || pwd_val.as_str().ne(verify_pwd_val.as_str())) {
+         let is_valid = username_err.is_none() && skel_err.is_none() && pwd_check.as_ref().map(PwdCheck::is_acceptable).unwrap_or(false);
+         if is_valid && !(fullname_val.is_empty() || username_val.is_empty() || pwd_val.is_empty() || pwd_val.as_str().ne(verify_pwd_val.as_str())) {
             let user = UserReq {
                acc_type: *acc_type,
                fullname: fullname_val.to_owned(),
                username: username_val.to_owned(),
                pwd: pwd_val.to_owned(),
-               pwd_verify: verify_pwd_val.to_owned(),
+               home: HomeSetup {
+                  skel: if *is_custom_skel { Some(PathBuf::from(skel_val.as_str())) } else { None },
+                  shell: selected_shell.clone().filter(|shell| *shell != *default_shell),
+                  is_encrypted: *is_encrypted,
+               },
             };
             btn_create = btn_create.on_press(CreateClicked(user));
          }
@@ -139,7 +278,8 @@ impl AddUserPage {
             .width(Length::Fill)
             .spacing(10)
             .align_items(Align::Center)
-            .push(Row::new().padding(10).spacing(10).align_items(Align::Center).push(lb_sec).push(info_sec))
+            .push(Container::new(info_sec).padding(10))
+            .push(Container::new(home_sec).padding(10))
             .push(Space::with_height(Length::Fill))
             .push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_cancel).push(btn_create)),
       )
@@ -148,3 +288,8 @@ impl AddUserPage {
       .into()
    }
 }
+
+/// A label on the row of its field, so that the inline messages below the fields don't misalign the labels.
+fn field<'a>(label: Text, input: Element<'a, AddUserMsg>) -> Row<'a, AddUserMsg> {
+   Row::new().spacing(10).align_items(Align::Center).push(label.width(Length::Units(120))).push(input)
+}
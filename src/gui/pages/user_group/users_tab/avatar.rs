@@ -0,0 +1,148 @@
+use crate::helpers::{authority, ROOT_PATH};
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageFormat};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Width and height of the stored pictures, as used by AccountsService.
+pub const AVATAR_SIZE: u32 = 256;
+/// Directories of the pictures shipped by the desktop environments.
+const SYSTEM_AVATAR_DIRS: [&str; 2] = ["/usr/share/plasma/avatars", "/usr/share/pixmaps/faces"];
+const EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// The pictures bundled with System Settings followed by those of the system.
+pub fn bundled_avatars() -> Vec<PathBuf> {
+   let mut dirs = vec![PathBuf::from(format!("{}/assets/images/avatars", ROOT_PATH()))];
+   dirs.extend(SYSTEM_AVATAR_DIRS.iter().map(PathBuf::from));
+   dirs
+      .into_iter()
+      .flat_map(|dir| {
+         let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| {
+               entries
+                  .flatten()
+                  .map(|entry| entry.path())
+                  .filter(|path| path.extension().and_then(|ext| ext.to_str()).map(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str())).unwrap_or(false))
+                  .collect()
+            })
+            .unwrap_or_default();
+         paths.sort();
+         paths
+      })
+      .collect()
+}
+
+/// Part of a picture to keep: a square which is `1 / zoom` of the shorter side, its center moved by `x` and `y`
+/// from the left or top (0.0) to the right or bottom (1.0) edge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crop {
+   pub zoom: f32,
+   pub x: f32,
+   pub y: f32,
+}
+
+impl Default for Crop {
+   fn default() -> Self {
+      Self { zoom: 1.0, x: 0.5, y: 0.5 }
+   }
+}
+
+impl Crop {
+   /// Left, top and side of the square in a picture of `width` by `height` pixels.
+   pub fn rect(&self, width: u32, height: u32) -> (u32, u32, u32) {
+      let side = ((width.min(height) as f32 / self.zoom.max(1.0)) as u32).max(1);
+      let left = ((width - side) as f32 * self.x.max(0.0).min(1.0)).round() as u32;
+      let top = ((height - side) as f32 * self.y.max(0.0).min(1.0)).round() as u32;
+      (left, top, side)
+   }
+}
+
+pub fn open(path: &Path) -> Result<DynamicImage, String> {
+   image::open(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Crop a picture to a square and scale it to the size of an avatar.
+pub fn crop(img: &DynamicImage, crop: Crop) -> DynamicImage {
+   let (width, height) = img.dimensions();
+   let (left, top, side) = crop.rect(width, height);
+   img.crop_imm(left, top, side, side).resize_exact(AVATAR_SIZE, AVATAR_SIZE, FilterType::Lanczos3)
+}
+
+/// A string argument of `gdbus`, which reads its arguments as GVariant text.
+fn gvariant_string(val: &str) -> String {
+   format!("'{}'", val.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Call a method of AccountsService with `gdbus`. Interactive authorization lets polkit ask for the password of an
+/// administrator when the user isn't the one calling.
+fn call_accounts(object_path: &str, method: &str, arg: &str) -> Result<String, String> {
+   let output = Command::new("gdbus")
+      .args(&["call", "--system", "--interactive", "--dest", "org.freedesktop.Accounts", "--object-path", object_path, "--method", method, &gvariant_string(arg)])
+      .output()
+      .map_err(|e| format!("Failed to run gdbus: {}", e))?;
+   if output.status.success() {
+      Ok(String::from_utf8_lossy(&output.stdout).to_string())
+   } else {
+      Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+   }
+}
+
+/// Object path of a user in AccountsService, from the `(objectpath '/org/freedesktop/Accounts/User1000',)` reply.
+fn accounts_user_path(username: &str) -> Result<String, String> {
+   let reply = call_accounts("/org/freedesktop/Accounts", "org.freedesktop.Accounts.FindUserByName", username)?;
+   reply.split('\'').nth(1).filter(|path| path.starts_with('/')).map(String::from).ok_or_else(|| format!("{} is unknown to AccountsService", username))
+}
+
+/// Store the picture of a user in AccountsService, which shows it on the login screen, and in `~/.face` for the
+/// current user. It runs in the background, as AccountsService may ask for a password.
+pub fn apply(username: &str, is_curr_usr: bool, img: DynamicImage) -> impl Future<Output = Result<(), String>> {
+   let username = username.to_string();
+   async move { authority::in_background(move || store(&username, is_curr_usr, &img).map(|_| ()).map_err(|err| format!("Failed to change the picture: {}", err))).await }
+}
+
+/// Write the picture and give it to AccountsService. Returns the file the picture was written to.
+fn store(username: &str, is_curr_usr: bool, img: &DynamicImage) -> Result<PathBuf, String> {
+   let dir = dirs::cache_dir().ok_or_else(|| String::from("Unknown cache directory"))?.join("system_settings");
+   std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+   let path = dir.join(format!("avatar-{}.png", username));
+   img.save_with_format(&path, ImageFormat::Png).map_err(|e| format!("{}: {}", path.display(), e))?;
+   let user_path = accounts_user_path(username)?;
+   call_accounts(&user_path, "org.freedesktop.Accounts.User.SetIconFile", &path.to_string_lossy())?;
+   if is_curr_usr {
+      let face = dirs::home_dir().ok_or_else(|| String::from("Unknown home directory"))?.join(".face");
+      img.save_with_format(&face, ImageFormat::Png).map_err(|e| format!("{}: {}", face.display(), e))?;
+      // KDE reads `~/.face.icon`, which usually links to `~/.face`.
+      let face_icon = face.with_file_name(".face.icon");
+      if !face_icon.exists() {
+         let _ = std::os::unix::fs::symlink(&face, face_icon);
+      }
+      return Ok(face);
+   }
+   Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_crop_rect() {
+      assert_eq!(Crop::default().rect(400, 200), (100, 0, 200));
+      assert_eq!(Crop { zoom: 2.0, x: 0.0, y: 1.0 }.rect(400, 200), (0, 100, 100));
+      assert_eq!(Crop { zoom: 1.0, x: 1.0, y: 0.5 }.rect(300, 300), (0, 0, 300));
+   }
+
+   #[test]
+   fn test_gvariant_string() {
+      assert_eq!(gvariant_string("/home/sokha/.face"), "'/home/sokha/.face'");
+      assert_eq!(gvariant_string(r"it's\here"), r"'it\'s\\here'");
+   }
+
+   #[test]
+   fn test_crop_bundled_avatar() {
+      let avatars = bundled_avatars();
+      assert!(!avatars.is_empty());
+      let img = crop(&open(&avatars[0]).unwrap(), Crop { zoom: 1.5, x: 0.2, y: 0.8 });
+      assert_eq!(img.dimensions(), (AVATAR_SIZE, AVATAR_SIZE));
+   }
+}
@@ -0,0 +1,189 @@
+use super::avatar::{self, Crop};
+use super::validation::error_view;
+use crate::gui::addon_widgets::icon_btn;
+use crate::gui::styles::{CustomButton, CustomContainer, CustomSlider};
+use iced::{button, image, scrollable, slider, Align, Button, Column, Container, Element, Image, Length, Row, Scrollable, Slider, Space, Text};
+use iced_custom_widget::Icons;
+use std::path::PathBuf;
+
+/// Number of bundled pictures on a row of the grid.
+const GRID_COLUMNS: usize = 6;
+
+#[derive(Debug, Default)]
+pub struct ChangeAvatarPage {
+   avatars: Vec<(PathBuf, button::State)>,
+   source: Option<::image::DynamicImage>,
+   crop: Crop,
+   preview: Option<image::Handle>,
+   error: Option<String>,
+   scroll_avatars: scrollable::State,
+   zoom_state: slider::State,
+   x_state: slider::State,
+   y_state: slider::State,
+   btn_file_state: button::State,
+   btn_ok_state: button::State,
+   btn_cancel_state: button::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum ChangeAvatarMsg {
+   AvatarSelected(usize),
+   ChooseFileClicked,
+   ZoomChanged(f32),
+   HorizontalChanged(f32),
+   VerticalChanged(f32),
+   OkayClicked,
+   CancelClicked,
+}
+
+impl ChangeAvatarPage {
+   pub fn new() -> Self {
+      Self {
+         avatars: avatar::bundled_avatars().into_iter().map(|path| (path, button::State::new())).collect(),
+         ..Self::default()
+      }
+   }
+
+   /// The picture as it will be stored, once one was chosen.
+   pub fn cropped(&self) -> Option<::image::DynamicImage> {
+      self.source.as_ref().map(|img| avatar::crop(img, self.crop))
+   }
+
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
+   }
+
+   fn load(&mut self, path: PathBuf) {
+      match avatar::open(&path) {
+         Ok(img) => {
+            self.source = Some(img);
+            self.crop = Crop::default();
+            self.error = None;
+            self.update_preview();
+         }
+         Err(err) => self.error = Some(err),
+      }
+   }
+
+   fn update_preview(&mut self) {
+      self.preview = self.cropped().map(|img| {
+         let bgra = img.to_bgra8();
+         image::Handle::from_pixels(bgra.width(), bgra.height(), bgra.into_raw())
+      });
+   }
+
+   pub fn update(&mut self, msg: ChangeAvatarMsg) {
+      use ChangeAvatarMsg::*;
+      match msg {
+         AvatarSelected(idx) => {
+            if let Some((path, _)) = self.avatars.get(idx) {
+               let path = path.clone();
+               self.load(path);
+            }
+         }
+         ChooseFileClicked => {
+            if let Ok(nfd2::Response::Okay(path)) = nfd2::open_file_dialog(Some("png,jpg,jpeg,bmp,gif,webp"), dirs::picture_dir().as_deref()) {
+               self.load(path);
+            }
+         }
+         ZoomChanged(val) => {
+            self.crop.zoom = val;
+            self.update_preview();
+         }
+         HorizontalChanged(val) => {
+            self.crop.x = val;
+            self.update_preview();
+         }
+         VerticalChanged(val) => {
+            self.crop.y = val;
+            self.update_preview();
+         }
+         OkayClicked | CancelClicked => {}
+      }
+   }
+
+   pub fn view(&mut self) -> Element<ChangeAvatarMsg> {
+      use ChangeAvatarMsg::*;
+      let Self {
+         avatars,
+         source,
+         crop,
+         preview,
+         error,
+         scroll_avatars,
+         zoom_state,
+         x_state,
+         y_state,
+         btn_file_state,
+         btn_ok_state,
+         btn_cancel_state,
+      } = self;
+
+      let mut grid = Column::new().spacing(7);
+      let mut row = Row::new().spacing(7);
+      for (idx, (path, state)) in avatars.iter_mut().enumerate() {
+         row = row.push(
+            Button::new(state, Image::new(path.to_path_buf()).width(Length::Units(56)).height(Length::Units(56)))
+               .padding(2)
+               .on_press(AvatarSelected(idx))
+               .style(CustomButton::Text),
+         );
+         if (idx + 1) % GRID_COLUMNS == 0 {
+            grid = grid.push(row);
+            row = Row::new().spacing(7);
+         }
+      }
+      grid = grid.push(row);
+      let avatars_pane = Container::new(
+         Column::new()
+            .push(Container::new(Text::new("Pictures")).width(Length::Fill).padding(7).style(CustomContainer::Header))
+            .push(Scrollable::new(scroll_avatars).height(Length::Fill).padding(7).scroller_width(4).scrollbar_width(4).push(grid)),
+      )
+      .height(Length::Fill)
+      .width(Length::FillPortion(3))
+      .style(CustomContainer::ForegroundWhite);
+
+      let preview: Element<_> = match preview {
+         Some(handle) => Image::new(handle.clone()).width(Length::Units(128)).height(Length::Units(128)).into(),
+         None => Container::new(Text::new("Choose a picture").size(12))
+            .width(Length::Units(128))
+            .height(Length::Units(128))
+            .center_x()
+            .center_y()
+            .style(CustomContainer::Header)
+            .into(),
+      };
+      let mut crop_sec = Column::new().spacing(10).align_items(Align::Center).push(preview);
+      if source.is_some() {
+         crop_sec = crop_sec
+            .push(slider_row("Zoom", Slider::new(zoom_state, 1.0..=4.0, crop.zoom, ZoomChanged).step(0.05).style(CustomSlider::Default)))
+            .push(slider_row("Horizontal", Slider::new(x_state, 0.0..=1.0, crop.x, HorizontalChanged).step(0.01).style(CustomSlider::Default)))
+            .push(slider_row("Vertical", Slider::new(y_state, 0.0..=1.0, crop.y, VerticalChanged).step(0.01).style(CustomSlider::Default)));
+      }
+      if let Some(error) = error {
+         crop_sec = crop_sec.push(error_view(error));
+      }
+      crop_sec = crop_sec.push(icon_btn(btn_file_state, Icons::FileUpload, "Choose File...", None).on_press(ChooseFileClicked).style(CustomButton::Default));
+
+      let btn_cancel = icon_btn(btn_cancel_state, Icons::Minus, "Cancel", None).on_press(CancelClicked).style(CustomButton::Hovered);
+      let mut btn_okay = icon_btn(btn_ok_state, Icons::CheckCircle, "Okay", None).style(CustomButton::Primary);
+      if source.is_some() {
+         btn_okay = btn_okay.on_press(OkayClicked);
+      }
+
+      Container::new(
+         Column::new()
+            .width(Length::Fill)
+            .spacing(10)
+            .push(Row::new().spacing(10).height(Length::Fill).push(avatars_pane).push(Container::new(crop_sec).width(Length::FillPortion(2)).padding(7)))
+            .push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_cancel).push(btn_okay)),
+      )
+      .width(Length::Fill)
+      .height(Length::Fill)
+      .into()
+   }
+}
+
+fn slider_row<'a>(label: &str, slider: Slider<'a, f32, ChangeAvatarMsg>) -> Row<'a, ChangeAvatarMsg> {
+   Row::new().spacing(10).align_items(Align::Center).push(Text::new(label).size(12).width(Length::Units(70))).push(slider.width(Length::Fill))
+}
@@ -1,3 +1,4 @@
+use super::validation::error_view;
 use crate::gui::addon_widgets::icon_btn;
 use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomTextInput};
 use iced::{button, scrollable, text_input, Align, Checkbox, Column, Container, Element, Length, Row, Scrollable, Space, Text, TextInput};
@@ -14,6 +15,7 @@ pub struct ChangeGroupsPage {
    is_changed: bool,
    btn_ok_state: button::State,
    btn_cancel_state: button::State,
+   error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -35,6 +37,10 @@ impl ChangeGroupsPage {
       }
    }
 
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
+   }
+
    pub fn with_user(&mut self, usr: &User) {
       let ls_grps: Vec<(bool, Group)> = self.ls_all_grps.iter().map(|grp| (usr.groups().contains(grp.name()), grp.to_owned())).collect();
       self.filtered_ls_grps = ls_grps.clone();
@@ -52,6 +58,10 @@ impl ChangeGroupsPage {
          GroupToggled(idx, is_checked) => {
             if let Some(grp) = self.filtered_ls_grps.get_mut(idx) {
                grp.0 = is_checked;
+               // The search only shows some of the groups, so the change is kept in the full list.
+               if let Some(row) = self.ls_grps.iter_mut().find(|(_, row)| row.name() == grp.1.name()) {
+                  row.0 = is_checked;
+               }
                if !self.is_changed {
                   self.is_changed = true;
                }
@@ -66,10 +76,12 @@ impl ChangeGroupsPage {
       let Self {
          grpname_state,
          grpname,
+         ls_grps,
          filtered_ls_grps,
          scroll_grps,
          btn_cancel_state,
          btn_ok_state,
+         error,
          ..
       } = self;
 
@@ -89,19 +101,18 @@ impl ChangeGroupsPage {
       let btn_cancel = icon_btn(btn_cancel_state, Icons::Minus, "Cancel", None).on_press(CancelClicked).style(CustomButton::Hovered);
       let mut btn_okay = icon_btn(btn_ok_state, Icons::Ad, "Okay", None).style(CustomButton::Primary);
       if self.is_changed {
-         btn_okay = btn_okay.on_press(OkayClicked(filtered_ls_grps.iter().filter(|(is_checked, _)| *is_checked).map(|(_, grp)| grp.name().to_owned()).collect()));
+         // `usermod --groups` replaces all the groups of the user, so those hidden by the search are sent too.
+         btn_okay = btn_okay.on_press(OkayClicked(ls_grps.iter().filter(|(is_checked, _)| *is_checked).map(|(_, grp)| grp.name().to_owned()).collect()));
       }
 
-      Container::new(
-         Column::new()
-            .width(Length::Fill)
-            .spacing(10)
-            .push(txt_search)
-            .push(grps_pane)
-            .push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_cancel).push(btn_okay)),
-      )
-      .width(Length::Fill)
-      .height(Length::Fill)
-      .into()
+      let mut content = Column::new().width(Length::Fill).spacing(10).push(txt_search).push(grps_pane);
+      if let Some(err) = error {
+         content = content.push(error_view(err));
+      }
+
+      Container::new(content.push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_cancel).push(btn_okay)))
+         .width(Length::Fill)
+         .height(Length::Fill)
+         .into()
    }
 }
@@ -1,7 +1,10 @@
+use super::validation::{check_password, error_view, pwd_check_view, PwdCheck, PwdQualityConf};
 use crate::gui::addon_widgets::icon_btn;
 use crate::gui::styles::{CustomButton, CustomTextInput};
 use iced::{button, text_input, Align, Button, Column, Container, Element, Length, Row, Space, Text, TextInput};
 use iced_custom_widget::{Icon, Icons};
+use std::collections::HashSet;
+use std::rc::Rc;
 
 #[derive(Debug, Default)]
 pub struct ChangePwdPage {
@@ -16,6 +19,12 @@ pub struct ChangePwdPage {
    btn_cancel_state: button::State,
    is_show_pwd: bool,
    btn_show_pwd: button::State,
+   /// User name and parts of the full name, which the new password should not contain.
+   names: Vec<String>,
+   pwd_conf: PwdQualityConf,
+   dictionary: Rc<HashSet<String>>,
+   pwd_check: Option<PwdCheck>,
+   error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,7 +38,7 @@ pub enum ChangePwdMsg {
 }
 
 impl ChangePwdPage {
-   pub fn new(is_curr_usr: bool) -> Self {
+   pub fn new(is_curr_usr: bool, names: Vec<String>, pwd_conf: PwdQualityConf, dictionary: Rc<HashSet<String>>) -> Self {
       Self {
          is_curr_usr,
          old_pwd_state: Default::default(),
@@ -42,15 +51,28 @@ impl ChangePwdPage {
          btn_cancel_state: Default::default(),
          is_show_pwd: false,
          btn_show_pwd: Default::default(),
+         names,
+         pwd_conf,
+         dictionary,
+         pwd_check: None,
+         error: None,
       }
    }
 
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
+   }
+
    pub fn update(&mut self, msg: ChangePwdMsg) {
       use ChangePwdMsg::*;
       match msg {
-         OldPwdChanged(val) => self.old_pwd_val = val.trim().to_string(),
-         NewPwdChanged(val) => self.new_pwd_val = val.trim().to_string(),
-         VerifyPwdChanged(val) => self.verify_pwd_val = val.trim().to_string(),
+         OldPwdChanged(val) => self.old_pwd_val = val,
+         NewPwdChanged(val) => {
+            let names: Vec<&str> = self.names.iter().map(String::as_str).collect();
+            self.pwd_check = if val.is_empty() { None } else { Some(check_password(&val, &names, &self.pwd_conf, &self.dictionary)) };
+            self.new_pwd_val = val;
+         }
+         VerifyPwdChanged(val) => self.verify_pwd_val = val,
          ShowPwdToggled => self.is_show_pwd = !self.is_show_pwd,
          CancelClicked | ChangeClicked(..) => {}
       }
@@ -68,6 +90,8 @@ impl ChangePwdPage {
          btn_change_state,
          btn_cancel_state,
          btn_show_pwd,
+         pwd_check,
+         error,
          ..
       } = self;
 
@@ -90,11 +114,22 @@ impl ChangePwdPage {
       }
       lb_sec = lb_sec.push(lb_new_pwd).push(lb_verify_pwd);
       input_sec = input_sec.push(Row::new().spacing(5).align_items(Align::Center).push(txt_new_pwd).push(btn_show_pwd)).push(txt_verify_pwd);
+      let mut msg_sec = Column::new().spacing(3);
+      if let Some(check) = pwd_check {
+         msg_sec = msg_sec.push(pwd_check_view(check));
+      }
+      if !verify_pwd_val.is_empty() && new_pwd_val.as_str().ne(verify_pwd_val.as_str()) {
+         msg_sec = msg_sec.push(error_view("The passwords do not match"));
+      }
+      if let Some(err) = error {
+         msg_sec = msg_sec.push(error_view(err));
+      }
 
       let mut btn_change = icon_btn(btn_change_state, Icons::CheckCircle, "Okay", None).style(CustomButton::Primary);
       let btn_cancel = icon_btn(btn_cancel_state, Icons::Minus, "Cancel", None).on_press(CancelClicked).style(CustomButton::Hovered);
 
-      if (!self.is_curr_usr || !old_pwd_val.is_empty()) && (!new_pwd_val.is_empty() && new_pwd_val.as_str().eq(verify_pwd_val.as_str())) {
+      let is_acceptable = pwd_check.as_ref().map(PwdCheck::is_acceptable).unwrap_or(false);
+      if (!self.is_curr_usr || !old_pwd_val.is_empty()) && is_acceptable && new_pwd_val.as_str().eq(verify_pwd_val.as_str()) {
          btn_change = btn_change.on_press(ChangeClicked(old_pwd_val.clone(), new_pwd_val.clone(), verify_pwd_val.clone()));
       }
 
@@ -104,6 +139,7 @@ impl ChangePwdPage {
             .spacing(10)
             .align_items(Align::Center)
             .push(Row::new().padding(10).spacing(10).align_items(Align::Center).push(lb_sec).push(input_sec))
+            .push(msg_sec)
             .push(Space::with_height(Length::Fill))
             .push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_cancel).push(btn_change)),
       )
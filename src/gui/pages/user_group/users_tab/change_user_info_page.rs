@@ -1,3 +1,4 @@
+use super::validation::error_view;
 use crate::gui::addon_widgets::icon_btn;
 use crate::gui::styles::{CustomButton, CustomContainer, CustomSelect, CustomTextInput, FOREGROUND};
 use iced::{button, pick_list, text_input, Align, Column, Container, Element, Length, PickList, Row, Space, Text, TextInput};
@@ -27,6 +28,7 @@ pub struct ChangeInfoPage {
    btn_browse_home_dir: button::State,
    btn_ok_state: button::State,
    btn_cancel_state: button::State,
+   error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -73,9 +75,14 @@ impl ChangeInfoPage {
          btn_browse_home_dir: Default::default(),
          btn_ok_state: Default::default(),
          btn_cancel_state: Default::default(),
+         error: None,
       }
    }
 
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
+   }
+
    pub fn with_user(&mut self, user: &User, is_curr_usr: bool, groupname: Option<&String>) {
       self.is_curr_usr = is_curr_usr;
       self.uid = user.uid();
@@ -131,6 +138,7 @@ impl ChangeInfoPage {
          btn_ok_state,
          btn_cancel_state,
          login_shells,
+         error,
          ..
       } = self;
       let lb_user_id = Text::new("User ID");
@@ -185,12 +193,17 @@ impl ChangeInfoPage {
          }
       }
 
+      let mut content = Column::new()
+         .width(Length::Fill)
+         .spacing(10)
+         .align_items(Align::Center)
+         .push(Row::new().padding(10).spacing(10).width(Length::Units(400)).align_items(Align::Center).push(lb_sec).push(info_sec));
+      if let Some(err) = error {
+         content = content.push(error_view(err));
+      }
+
       Container::new(
-         Column::new()
-            .width(Length::Fill)
-            .spacing(10)
-            .align_items(Align::Center)
-            .push(Row::new().padding(10).spacing(10).width(Length::Units(400)).align_items(Align::Center).push(lb_sec).push(info_sec))
+         content
             .push(Space::with_height(Length::Fill))
             .push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_cancel).push(btn_okay)),
       )
@@ -0,0 +1,127 @@
+use super::home_dir::{self, HomeChoice};
+use super::validation::error_view;
+use crate::gui::addon_widgets::icon_btn;
+use crate::gui::styles::{CustomButton, CustomRadio, CustomTextInput};
+use crate::helpers::authority;
+use iced::{button, text_input, Align, Column, Container, Element, Length, Radio, Row, Space, Text, TextInput};
+use iced_custom_widget::Icons;
+use libkoompi::system_settings::users_groups::User;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default)]
+pub struct DeleteUserPage {
+   fullname: String,
+   username: String,
+   home_path: String,
+   home_choice: HomeChoice,
+   archive_state: text_input::State,
+   archive_val: String,
+   error: Option<String>,
+   btn_browse_state: button::State,
+   btn_delete_state: button::State,
+   btn_cancel_state: button::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum DeleteUserMsg {
+   HomeChoiceChanged(HomeChoice),
+   ArchiveChanged(String),
+   BrowseClicked,
+   DeleteClicked(Vec<String>),
+   CancelClicked,
+}
+
+impl DeleteUserPage {
+   pub fn new(user: &User) -> Self {
+      Self {
+         fullname: user.fullname().to_string(),
+         username: user.username().to_string(),
+         home_path: user.home_dir().display().to_string(),
+         archive_val: home_dir::default_archive(user.username(), chrono::Local::now()).display().to_string(),
+         ..Self::default()
+      }
+   }
+
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
+   }
+
+   pub fn update(&mut self, msg: DeleteUserMsg) {
+      use DeleteUserMsg::*;
+      match msg {
+         HomeChoiceChanged(choice) => self.home_choice = choice,
+         ArchiveChanged(val) => self.archive_val = val,
+         BrowseClicked => {
+            let dir = Path::new(&self.archive_val).parent().filter(|dir| dir.is_dir()).map(Path::to_path_buf);
+            if let Ok(nfd2::Response::Okay(path)) = nfd2::open_save_dialog(Some("tar.gz"), dir.as_deref()) {
+               self.archive_val = path.display().to_string();
+            }
+         }
+         DeleteClicked(_) | CancelClicked => {}
+      }
+   }
+
+   pub fn view(&mut self) -> Element<DeleteUserMsg> {
+      use DeleteUserMsg::*;
+      let Self {
+         fullname,
+         username,
+         home_path,
+         home_choice,
+         archive_state,
+         archive_val,
+         error,
+         btn_browse_state,
+         btn_delete_state,
+         btn_cancel_state,
+      } = self;
+
+      let lb_question = Text::new(format!("Delete the account of {} ({})?", fullname, username)).size(16);
+      let lb_home = Text::new(format!("Its home folder is {}.", home_path)).size(12);
+      let choices = HomeChoice::ALL.iter().fold(Column::new().spacing(7), |col, choice| {
+         col.push(Radio::new(*choice, choice.label(), Some(*home_choice), HomeChoiceChanged).size(18).style(CustomRadio::Default))
+      });
+      let mut content = Column::new().spacing(10).push(lb_question).push(lb_home).push(choices);
+
+      let archive = PathBuf::from(archive_val.as_str());
+      let archive_err = if *home_choice != HomeChoice::Archive {
+         None
+      } else if !archive.is_absolute() || archive_val.is_empty() {
+         Some("Please choose where to save the archive")
+      } else if archive.exists() {
+         Some("This file already exists")
+      } else {
+         None
+      };
+      if *home_choice == HomeChoice::Archive {
+         let txt_archive = TextInput::new(archive_state, "Archive file", &archive_val, ArchiveChanged).padding(7).width(Length::Fill).style(CustomTextInput::Default);
+         let btn_browse = icon_btn(btn_browse_state, Icons::Download, "Choose...", None).on_press(BrowseClicked).style(CustomButton::Default);
+         let mut archive_sec = Column::new().spacing(3).push(Row::new().spacing(10).align_items(Align::Center).push(Text::new("Save to:")).push(txt_archive).push(btn_browse));
+         if let Some(err) = archive_err {
+            archive_sec = archive_sec.push(error_view(err));
+         }
+         content = content.push(archive_sec);
+      }
+      if let Some(err) = error {
+         content = content.push(error_view(err));
+      }
+
+      let mut btn_delete = icon_btn(btn_delete_state, Icons::RemoveUser, "Delete", None).style(CustomButton::Primary);
+      let btn_cancel = icon_btn(btn_cancel_state, Icons::Minus, "Cancel", None).on_press(CancelClicked).style(CustomButton::Hovered);
+      if archive_err.is_none() && authority::is_unlocked() {
+         btn_delete = btn_delete.on_press(DeleteClicked(home_dir::delete_args(username, *home_choice, &archive)));
+      }
+
+      Container::new(
+         Column::new()
+            .width(Length::Fill)
+            .spacing(10)
+            .push(Container::new(content).padding(10))
+            .push(Space::with_height(Length::Fill))
+            .push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_cancel).push(btn_delete)),
+      )
+      .width(Length::Fill)
+      .height(Length::Fill)
+      .into()
+   }
+}
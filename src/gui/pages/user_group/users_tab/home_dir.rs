@@ -0,0 +1,111 @@
+use chrono::{DateTime, Local};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Template copied into new home directories by `useradd`.
+pub const SKEL_DIR: &str = "/etc/skel";
+/// Where archived home directories are kept unless another file is chosen.
+pub const ARCHIVE_DIR: &str = "/var/backups/home";
+const USERADD_DEFAULTS: &str = "/etc/default/useradd";
+const FALLBACK_SHELL: &str = "/bin/bash";
+
+/// What happens to the home directory of a deleted account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HomeChoice {
+   Keep,
+   Archive,
+   Remove,
+}
+
+impl Default for HomeChoice {
+   fn default() -> Self {
+      Self::Keep
+   }
+}
+
+impl HomeChoice {
+   pub const ALL: [HomeChoice; 3] = [HomeChoice::Keep, HomeChoice::Archive, HomeChoice::Remove];
+
+   pub fn label(&self) -> &'static str {
+      match self {
+         HomeChoice::Keep => "Keep the home folder",
+         HomeChoice::Archive => "Archive the home folder to a compressed file, then delete it",
+         HomeChoice::Remove => "Delete the home folder",
+      }
+   }
+}
+
+/// Arguments of the Delete User action of the helper.
+pub fn delete_args(username: &str, choice: HomeChoice, archive: &Path) -> Vec<String> {
+   let mut args = vec![username.to_string()];
+   match choice {
+      HomeChoice::Keep => {}
+      HomeChoice::Archive => {
+         args.push(String::from("--archive-home"));
+         args.push(archive.display().to_string());
+      }
+      HomeChoice::Remove => args.push(String::from("--remove-home")),
+   }
+   args
+}
+
+/// Archive named after the account and the time it was deleted, so that older archives are never replaced.
+pub fn default_archive(username: &str, now: DateTime<Local>) -> PathBuf {
+   Path::new(ARCHIVE_DIR).join(format!("{}-{}.tar.gz", username, now.format("%Y%m%d-%H%M%S")))
+}
+
+/// Options of a new home directory which would otherwise follow the defaults of `useradd`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HomeSetup {
+   /// Template copied instead of `/etc/skel`.
+   pub skel: Option<PathBuf>,
+   /// Login shell instead of the default one.
+   pub shell: Option<String>,
+   pub is_encrypted: bool,
+}
+
+/// The `SHELL` given to new accounts by `useradd`.
+pub fn parse_default_shell(content: &str) -> Option<String> {
+   content
+      .lines()
+      .map(str::trim)
+      .find_map(|line| line.strip_prefix("SHELL="))
+      .map(|shell| shell.trim_matches('"').to_string())
+      .filter(|shell| !shell.is_empty())
+}
+
+pub fn default_shell() -> String {
+   std::fs::read_to_string(USERADD_DEFAULTS).ok().and_then(|content| parse_default_shell(&content)).unwrap_or_else(|| String::from(FALLBACK_SHELL))
+}
+
+/// Whether the file system of the home directories supports native encryption set up with `fscrypt`.
+pub fn is_encryption_supported() -> bool {
+   Command::new("fscrypt").args(&["status", "/home"]).output().map(|output| output.status.success()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use chrono::TimeZone;
+
+   #[test]
+   fn test_delete_args() {
+      let archive = Path::new("/var/backups/home/dara.tar.gz");
+      assert_eq!(delete_args("dara", HomeChoice::Keep, archive), vec!["dara"]);
+      assert_eq!(delete_args("dara", HomeChoice::Archive, archive), vec!["dara", "--archive-home", "/var/backups/home/dara.tar.gz"]);
+      assert_eq!(delete_args("dara", HomeChoice::Remove, archive), vec!["dara", "--remove-home"]);
+   }
+
+   #[test]
+   fn test_default_archive() {
+      let now = Local.ymd(2021, 3, 4).and_hms(5, 6, 7);
+      assert_eq!(default_archive("dara", now), PathBuf::from("/var/backups/home/dara-20210304-050607.tar.gz"));
+   }
+
+   #[test]
+   fn test_parse_default_shell() {
+      assert_eq!(parse_default_shell("GROUP=users\nHOME=/home\nSHELL=/bin/zsh\n"), Some(String::from("/bin/zsh")));
+      assert_eq!(parse_default_shell("SHELL=\"/bin/fish\""), Some(String::from("/bin/fish")));
+      assert_eq!(parse_default_shell("GROUP=users\n"), None);
+   }
+}
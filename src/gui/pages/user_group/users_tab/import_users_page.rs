@@ -0,0 +1,209 @@
+use super::provisioning::{self, ImportRow, Outcome};
+use super::validation::{error_view, PwdQualityConf};
+use crate::gui::addon_widgets::icon_btn;
+use crate::gui::styles::{CustomButton, CustomContainer, ERROR, SUCCESS};
+use iced::{button, scrollable, Align, Column, Container, Element, Length, Row, Scrollable, Space, Text};
+use iced_custom_widget::Icons;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+#[derive(Debug, Default)]
+pub struct ImportUsersPage {
+   existing_names: Vec<String>,
+   all_groups: Vec<String>,
+   pwd_conf: PwdQualityConf,
+   dictionary: Rc<HashSet<String>>,
+   path: Option<PathBuf>,
+   rows: Vec<ImportRow>,
+   is_creating: bool,
+   /// Set once the accounts were created.
+   outcomes: Option<Vec<Outcome>>,
+   status: Option<Result<String, String>>,
+   scroll_rows: scrollable::State,
+   btn_file_state: button::State,
+   btn_create_state: button::State,
+   btn_export_state: button::State,
+   btn_cancel_state: button::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum ImportUsersMsg {
+   ChooseFileClicked,
+   CreateClicked,
+   ExportClicked,
+   CancelClicked,
+   DoneClicked,
+}
+
+impl ImportUsersPage {
+   pub fn new(existing_names: Vec<String>, all_groups: Vec<String>, pwd_conf: PwdQualityConf, dictionary: Rc<HashSet<String>>) -> Self {
+      Self {
+         existing_names,
+         all_groups,
+         pwd_conf,
+         dictionary,
+         ..Self::default()
+      }
+   }
+
+   pub fn rows(&self) -> &[ImportRow] {
+      &self.rows
+   }
+
+   /// Show the report of the created accounts.
+   pub fn set_outcomes(&mut self, outcomes: Vec<Outcome>) {
+      let created = outcomes.iter().filter(|outcome| matches!(outcome, Outcome::Created)).count();
+      self.status = Some(Ok(format!("Created {} of {} accounts", created, outcomes.len())));
+      self.is_creating = false;
+      self.outcomes = Some(outcomes);
+   }
+
+   fn load(&mut self, path: PathBuf) {
+      let rows = std::fs::read_to_string(&path)
+         .map_err(|e| format!("{}: {}", path.display(), e))
+         .and_then(|content| provisioning::parse_csv(&content, &self.existing_names, &self.all_groups, &self.pwd_conf, &self.dictionary));
+      match rows {
+         Ok(rows) => {
+            let valid = rows.iter().filter(|row| row.is_valid()).count();
+            self.status = Some(if valid == rows.len() {
+               Ok(format!("{} accounts ready to be created", valid))
+            } else {
+               Err(format!("{} of {} rows have errors and will be skipped", rows.len() - valid, rows.len()))
+            });
+            self.rows = rows;
+         }
+         Err(err) => {
+            self.rows.clear();
+            self.status = Some(Err(err));
+         }
+      }
+      self.path = Some(path);
+   }
+
+   pub fn update(&mut self, msg: ImportUsersMsg) {
+      use ImportUsersMsg::*;
+      match msg {
+         ChooseFileClicked => {
+            if let Ok(nfd2::Response::Okay(path)) = nfd2::open_file_dialog(Some("csv"), dirs::document_dir().as_deref()) {
+               self.load(path);
+            }
+         }
+         ExportClicked => {
+            if let (Some(outcomes), Ok(nfd2::Response::Okay(path))) = (&self.outcomes, nfd2::open_save_dialog(Some("csv"), dirs::document_dir().as_deref())) {
+               self.status = Some(provisioning::export_credentials(&path, &self.rows, outcomes).map(|_| format!("Credentials saved to {}", path.display())));
+            }
+         }
+         CreateClicked => {
+            self.is_creating = true;
+            self.status = Some(Ok(String::from("Creating the accounts...")));
+         }
+         CancelClicked | DoneClicked => {}
+      }
+   }
+
+   pub fn view(&mut self) -> Element<ImportUsersMsg> {
+      use ImportUsersMsg::*;
+      let Self {
+         path,
+         rows,
+         is_creating,
+         outcomes,
+         status,
+         scroll_rows,
+         btn_file_state,
+         btn_create_state,
+         btn_export_state,
+         btn_cancel_state,
+         ..
+      } = self;
+
+      let cell = |text: &str, portion: u16| Text::new(text).size(12).width(Length::FillPortion(portion));
+      let header = Container::new(
+         Row::new()
+            .spacing(7)
+            .push(cell("Line", 1))
+            .push(cell("Full Name", 4))
+            .push(cell("User Name", 3))
+            .push(cell("Type", 2))
+            .push(cell("Groups", 3))
+            .push(cell("Password", 2))
+            .push(cell("Status", 5)),
+      )
+      .width(Length::Fill)
+      .padding(7)
+      .style(CustomContainer::Header);
+      let table = rows
+         .iter()
+         .enumerate()
+         .fold(Scrollable::new(scroll_rows).height(Length::Fill).padding(7).spacing(7).scroller_width(4).scrollbar_width(4), |table, (idx, row)| {
+            let status: Element<_> = match outcomes.as_ref().and_then(|outcomes| outcomes.get(idx)) {
+               Some(Outcome::Created) => Text::new("Created").size(12).color(SUCCESS).into(),
+               Some(Outcome::Failed(err)) => Text::new(format!("Failed: {}", err)).size(12).color(ERROR).into(),
+               Some(Outcome::Skipped) => Text::new("Skipped").size(12).color(ERROR).into(),
+               None if row.is_valid() => Text::new("Ready").size(12).color(SUCCESS).into(),
+               None => row.errors.iter().fold(Column::new().spacing(3), |col, err| col.push(error_view(err))).into(),
+            };
+            table.push(
+               Row::new()
+                  .spacing(7)
+                  .push(cell(&row.line.to_string(), 1))
+                  .push(cell(&row.fullname, 4))
+                  .push(cell(&row.username, 3))
+                  .push(cell(&row.acc_type.to_string(), 2))
+                  .push(cell(&row.groups.join(", "), 3))
+                  .push(cell(if row.is_generated { "Generated" } else { "Given" }, 2))
+                  .push(Container::new(status).width(Length::FillPortion(5))),
+            )
+         });
+      let table_pane: Element<_> = if rows.is_empty() {
+         Container::new(Text::new("Choose a CSV file with the columns: full name, user name, account type, groups, password or \"generate\"").size(12))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y()
+            .into()
+      } else {
+         Column::new().push(header).push(table).into()
+      };
+
+      let mut file_row = Row::new().spacing(10).align_items(Align::Center);
+      if outcomes.is_none() {
+         file_row = file_row.push(icon_btn(btn_file_state, Icons::FileUpload, "Choose File...", None).on_press(ChooseFileClicked).style(CustomButton::Default));
+      }
+      if let Some(path) = path {
+         file_row = file_row.push(Text::new(path.display().to_string()).size(12));
+      }
+      let mut content = Column::new()
+         .width(Length::Fill)
+         .spacing(10)
+         .push(file_row)
+         .push(Container::new(table_pane).width(Length::Fill).height(Length::Fill).style(CustomContainer::ForegroundWhite));
+      match status {
+         Some(Ok(msg)) => content = content.push(Text::new(msg.as_str()).size(12).color(SUCCESS)),
+         Some(Err(err)) => content = content.push(error_view(err)),
+         None => {}
+      }
+
+      let mut bottom_row = Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill));
+      match outcomes {
+         Some(outcomes) => {
+            let mut btn_export = icon_btn(btn_export_state, Icons::Download, "Export Credentials...", None).style(CustomButton::Default);
+            if rows.iter().zip(outcomes.iter()).any(|(row, outcome)| row.is_generated && matches!(outcome, Outcome::Created)) {
+               btn_export = btn_export.on_press(ExportClicked);
+            }
+            bottom_row = bottom_row.push(btn_export).push(icon_btn(btn_create_state, Icons::CheckCircle, "Done", None).on_press(DoneClicked).style(CustomButton::Primary));
+         }
+         None => {
+            let valid = rows.iter().filter(|row| row.is_valid()).count();
+            let mut btn_create = icon_btn(btn_create_state, Icons::Ad, &format!("Create {} Accounts", valid), None).style(CustomButton::Primary);
+            if valid > 0 && !*is_creating {
+               btn_create = btn_create.on_press(CreateClicked);
+            }
+            bottom_row = bottom_row.push(icon_btn(btn_cancel_state, Icons::Minus, "Cancel", None).on_press(CancelClicked).style(CustomButton::Hovered)).push(btn_create);
+         }
+      }
+
+      Container::new(content.push(bottom_row)).width(Length::Fill).height(Length::Fill).into()
+   }
+}
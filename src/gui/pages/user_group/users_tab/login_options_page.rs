@@ -0,0 +1,222 @@
+use super::validation::error_view;
+use crate::gui::addon_widgets::icon_btn;
+use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, CustomSelect, CustomTextInput};
+use crate::helpers::login_policy::{restrictable_apps, DisplayManager, LoginHours, LoginOptions, RestrictableApp, UserPolicy, DAYS};
+use chrono::NaiveDate;
+use iced::{button, pick_list, scrollable, text_input, Align, Checkbox, Column, Container, Element, Length, PickList, Row, Scrollable, Space, Text, TextInput};
+use iced_custom_widget::Icons;
+
+const HOURS: [u8; 25] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24];
+
+#[derive(Debug, Default)]
+pub struct LoginOptionsPage {
+   is_curr_usr: bool,
+   dm: Option<DisplayManager>,
+   options: LoginOptions,
+   is_expiring: bool,
+   expires_val: String,
+   expires_err: Option<String>,
+   is_hours_limited: bool,
+   hours: LoginHours,
+   apps: Vec<(bool, RestrictableApp)>,
+   error: Option<String>,
+   is_changed: bool,
+   expires_state: text_input::State,
+   start_state: pick_list::State<u8>,
+   end_state: pick_list::State<u8>,
+   scroll_content: scrollable::State,
+   btn_ok_state: button::State,
+   btn_cancel_state: button::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum LoginOptionsMsg {
+   AutoLoginToggled(bool),
+   GuestToggled(bool),
+   DisabledToggled(bool),
+   ExpiringToggled(bool),
+   ExpiresChanged(String),
+   HoursLimitedToggled(bool),
+   DayToggled(usize, bool),
+   StartChanged(u8),
+   EndChanged(u8),
+   AppToggled(usize, bool),
+   OkayClicked(LoginOptions),
+   CancelClicked,
+}
+
+impl LoginOptionsPage {
+   pub fn new(username: &str, is_curr_usr: bool, policy: UserPolicy) -> Self {
+      let dm = DisplayManager::detect();
+      let apps = restrictable_apps().into_iter().map(|app| (policy.restricted_apps.contains(&app.exec), app)).collect();
+      Self {
+         is_curr_usr,
+         is_expiring: policy.expires.is_some(),
+         expires_val: policy.expires.map(|date| date.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+         is_hours_limited: policy.login_hours.is_some(),
+         hours: policy.login_hours.unwrap_or_default(),
+         apps,
+         options: LoginOptions {
+            username: username.to_string(),
+            auto_login: dm.and_then(|dm| dm.autologin_user()).as_deref() == Some(username),
+            allow_guest: dm.map(|dm| dm.is_guest_allowed()).unwrap_or(false),
+            policy,
+         },
+         dm,
+         ..Self::default()
+      }
+   }
+
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
+   }
+
+   /// The options as edited, or `None` while the expiry date is invalid.
+   fn edited(&self) -> Option<LoginOptions> {
+      let mut options = self.options.clone();
+      options.policy.expires = if self.is_expiring && !options.policy.is_disabled {
+         Some(NaiveDate::parse_from_str(&self.expires_val, "%Y-%m-%d").ok()?)
+      } else {
+         None
+      };
+      options.policy.login_hours = if self.is_hours_limited { Some(self.hours) } else { None };
+      options.policy.restricted_apps = self.apps.iter().filter(|(is_restricted, _)| *is_restricted).map(|(_, app)| app.exec.clone()).collect();
+      Some(options)
+   }
+
+   pub fn update(&mut self, msg: LoginOptionsMsg) {
+      use LoginOptionsMsg::*;
+      match msg {
+         AutoLoginToggled(is_checked) => self.options.auto_login = is_checked,
+         GuestToggled(is_checked) => self.options.allow_guest = is_checked,
+         DisabledToggled(is_checked) => self.options.policy.is_disabled = is_checked,
+         ExpiringToggled(is_checked) => self.is_expiring = is_checked,
+         ExpiresChanged(val) => {
+            self.expires_err = match NaiveDate::parse_from_str(&val, "%Y-%m-%d") {
+               Ok(_) => None,
+               Err(_) => Some(String::from("Please enter a date such as 2026-12-31")),
+            };
+            self.expires_val = val;
+         }
+         HoursLimitedToggled(is_checked) => self.is_hours_limited = is_checked,
+         DayToggled(idx, is_checked) => {
+            if let Some(day) = self.hours.days.get_mut(idx) {
+               *day = is_checked;
+            }
+         }
+         StartChanged(hour) => self.hours.start = hour,
+         EndChanged(hour) => self.hours.end = hour,
+         AppToggled(idx, is_checked) => {
+            if let Some(app) = self.apps.get_mut(idx) {
+               app.0 = is_checked;
+            }
+         }
+         OkayClicked(_) | CancelClicked => return,
+      }
+      self.is_changed = true;
+   }
+
+   pub fn view(&mut self) -> Element<LoginOptionsMsg> {
+      use LoginOptionsMsg::*;
+      let edited = self.edited();
+      let Self {
+         is_curr_usr,
+         dm,
+         options,
+         is_expiring,
+         expires_val,
+         expires_err,
+         is_hours_limited,
+         hours,
+         apps,
+         error,
+         is_changed,
+         expires_state,
+         start_state,
+         end_state,
+         scroll_content,
+         btn_ok_state,
+         btn_cancel_state,
+      } = self;
+
+      let header = |title: &str| Container::new(Text::new(title).size(11)).width(Length::Fill).padding(3).style(CustomContainer::Header);
+      let mut content = Scrollable::new(scroll_content).height(Length::Fill).padding(7).spacing(10).scroller_width(4).scrollbar_width(4).push(header("Login Screen"));
+      match dm {
+         Some(dm) => {
+            content = content.push(Checkbox::new(options.auto_login, "Log in automatically", AutoLoginToggled).spacing(10).style(CustomCheckbox::Default));
+            // Only LightDM ships a guest session, so the option is left out with the other login screens.
+            if dm.supports_guest() {
+               content = content.push(
+                  Checkbox::new(options.allow_guest, "Allow guest sessions, whose files are removed at logout (all users)", GuestToggled)
+                     .spacing(10)
+                     .style(CustomCheckbox::Default),
+               );
+            }
+         }
+         None => content = content.push(Text::new("No supported login screen was found").size(12)),
+      }
+
+      // Users can't lock themselves out.
+      if !*is_curr_usr {
+         content = content
+            .push(header("Account"))
+            .push(Checkbox::new(options.policy.is_disabled, "Disable this account", DisabledToggled).spacing(10).style(CustomCheckbox::Default));
+         if !options.policy.is_disabled {
+            let mut expiry = Row::new()
+               .spacing(10)
+               .align_items(Align::Center)
+               .push(Checkbox::new(*is_expiring, "Account expires on", ExpiringToggled).spacing(10).style(CustomCheckbox::Default));
+            if *is_expiring {
+               expiry = expiry.push(TextInput::new(expires_state, "YYYY-MM-DD", expires_val, ExpiresChanged).padding(7).width(Length::Units(127)).style(CustomTextInput::Default));
+            }
+            content = content.push(expiry);
+            if let (true, Some(err)) = (*is_expiring, expires_err.as_ref()) {
+               content = content.push(error_view(err));
+            }
+         }
+
+         content = content
+            .push(header("Parental Controls"))
+            .push(Checkbox::new(*is_hours_limited, "Limit login hours", HoursLimitedToggled).spacing(10).style(CustomCheckbox::Default));
+         if *is_hours_limited {
+            let days = DAYS.iter().zip(hours.days.iter()).enumerate().fold(Row::new().spacing(10), |row, (idx, (day, is_allowed))| {
+               row.push(Checkbox::new(*is_allowed, *day, move |b| DayToggled(idx, b)).spacing(5).style(CustomCheckbox::Default))
+            });
+            content = content.push(days).push(
+               Row::new()
+                  .spacing(10)
+                  .align_items(Align::Center)
+                  .push(Text::new("From"))
+                  .push(PickList::new(start_state, &HOURS[..], Some(hours.start), StartChanged).style(CustomSelect::Primary))
+                  .push(Text::new(":00 to"))
+                  .push(PickList::new(end_state, &HOURS[..], Some(hours.end), EndChanged).style(CustomSelect::Primary))
+                  .push(Text::new(":00")),
+            );
+            if !hours.is_valid() {
+               content = content.push(error_view("The end of the login hours must be after their start"));
+            }
+         }
+         content = content.push(Text::new("Applications this user can't run:").size(12));
+         content = apps.iter().enumerate().fold(content, |content, (idx, (is_restricted, app))| {
+            content.push(Checkbox::new(*is_restricted, app.name.as_str(), move |b| AppToggled(idx, b)).spacing(10).style(CustomCheckbox::Default))
+         });
+      }
+
+      let btn_cancel = icon_btn(btn_cancel_state, Icons::Minus, "Cancel", None).on_press(CancelClicked).style(CustomButton::Hovered);
+      let mut btn_okay = icon_btn(btn_ok_state, Icons::CheckCircle, "Okay", None).style(CustomButton::Primary);
+      if let (true, Some(options)) = (*is_changed, edited) {
+         if !*is_hours_limited || hours.is_valid() {
+            btn_okay = btn_okay.on_press(OkayClicked(options));
+         }
+      }
+
+      let mut page = Column::new().width(Length::Fill).spacing(10).push(Container::new(content).height(Length::Fill).width(Length::Fill).style(CustomContainer::ForegroundWhite));
+      if let Some(error) = error {
+         page = page.push(error_view(error));
+      }
+      Container::new(page.push(Row::new().spacing(10).align_items(Align::Center).push(Space::with_width(Length::Fill)).push(btn_cancel).push(btn_okay)))
+         .width(Length::Fill)
+         .height(Length::Fill)
+         .into()
+   }
+}
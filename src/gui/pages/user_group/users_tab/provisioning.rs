@@ -0,0 +1,254 @@
+use super::validation::{check_password, check_username, PwdQualityConf};
+use crate::helpers::{authority, Action, NewUser};
+use libkoompi::{helpers::to_account_name, system_settings::users_groups::AccountType};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::Path;
+
+/// Password column value asking for a generated password.
+const GENERATE: &str = "generate";
+const GENERATED_LEN: usize = 12;
+/// Characters of generated passwords, without those easily mistaken for one another like `0` and `O`.
+const PWD_CHARS: &[u8] = b"abcdefghijkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789-_.!@#%+=";
+/// Separators of the groups column.
+const GROUP_SEPARATORS: [char; 3] = [';', ',', ' '];
+
+/// A row of the imported file, ready to be created when it has no errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportRow {
+   /// Line in the file, for the report.
+   pub line: usize,
+   pub fullname: String,
+   pub username: String,
+   pub acc_type: AccountType,
+   pub groups: Vec<String>,
+   pub pwd: String,
+   pub is_generated: bool,
+   pub errors: Vec<String>,
+}
+
+impl ImportRow {
+   pub fn is_valid(&self) -> bool {
+      self.errors.is_empty()
+   }
+
+   pub fn new_user(&self) -> NewUser {
+      NewUser {
+         username: self.username.to_owned(),
+         fullname: self.fullname.to_owned(),
+         is_admin: matches!(self.acc_type, AccountType::Admin),
+         groups: self.groups.to_owned(),
+         password: self.pwd.to_owned(),
+         ..NewUser::default()
+      }
+   }
+}
+
+/// What happened to a row once the accounts were created.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+   Created,
+   Failed(String),
+   Skipped,
+}
+
+/// Account type of the account type column, by its name or `admin`. An empty column is a standard account.
+fn parse_acc_type(val: &str) -> Option<AccountType> {
+   let val = val.to_lowercase();
+   match val.as_str() {
+      "" => Some(AccountType::default()),
+      "admin" => Some(AccountType::Admin),
+      _ => AccountType::ALL.iter().copied().find(|acc_type| acc_type.to_string().to_lowercase() == val),
+   }
+}
+
+/// A random password accepted by the password rules, read from `/dev/urandom`.
+pub fn generate_password(names: &[&str], conf: &PwdQualityConf, dictionary: &HashSet<String>) -> Result<String, String> {
+   let mut urandom = std::fs::File::open("/dev/urandom").map_err(|e| e.to_string())?;
+   let len = GENERATED_LEN.max(conf.minlen);
+   for _ in 0..100 {
+      let pwd = random_chars(&mut urandom, len).map_err(|e| e.to_string())?;
+      if check_password(&pwd, names, conf, dictionary).is_acceptable() {
+         return Ok(pwd);
+      }
+   }
+   Err(String::from("Failed to generate a password accepted by the password rules"))
+}
+
+/// Characters of `PWD_CHARS` picked from random bytes. Bytes above the last whole multiple of the number of characters
+/// are thrown away, so that every character is as likely as the others.
+fn random_chars(random: &mut impl Read, len: usize) -> std::io::Result<String> {
+   let limit = 256 - 256 % PWD_CHARS.len();
+   let mut pwd = String::with_capacity(len);
+   let mut byte = [0u8; 1];
+   while pwd.len() < len {
+      random.read_exact(&mut byte)?;
+      if (byte[0] as usize) < limit {
+         pwd.push(PWD_CHARS[byte[0] as usize % PWD_CHARS.len()] as char);
+      }
+   }
+   Ok(pwd)
+}
+
+/// Read the rows of a CSV file with the columns full name, user name, account type, groups and password (or
+/// `generate`), and check every row against the existing accounts and groups, the other rows and the password rules.
+/// A first row naming the columns and lines starting with `#` are skipped.
+pub fn parse_csv(content: &str, existing_names: &[String], all_groups: &[String], pwd_conf: &PwdQualityConf, dictionary: &HashSet<String>) -> Result<Vec<ImportRow>, String> {
+   let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).trim(csv::Trim::None).comment(Some(b'#')).from_reader(content.as_bytes());
+   let mut rows: Vec<ImportRow> = Vec::new();
+   for (idx, record) in reader.records().enumerate() {
+      let record = record.map_err(|e| e.to_string())?;
+      // Spaces around the password are part of it, so only the other columns are trimmed.
+      let col = |idx: usize| record.get(idx).unwrap_or_default().trim().to_string();
+      if record.iter().all(|field| field.trim().is_empty()) || (idx == 0 && ["full name", "fullname", "name"].contains(&col(0).to_lowercase().as_str())) {
+         continue;
+      }
+      let line = record.position().map(|pos| pos.line() as usize).unwrap_or(idx + 1);
+      let mut errors = Vec::new();
+
+      let fullname = col(0);
+      if fullname.is_empty() {
+         errors.push(String::from("Please enter a full name"));
+      }
+      let username = if col(1).is_empty() { to_account_name(&fullname) } else { col(1) };
+      if let Err(err) = check_username(&username, existing_names) {
+         errors.push(err);
+      } else if let Some(other) = rows.iter().find(|row| row.username == username) {
+         errors.push(format!("The user name {} is already used on line {}", username, other.line));
+      }
+      let acc_type = parse_acc_type(&col(2)).unwrap_or_else(|| {
+         errors.push(format!("Unknown account type {}", col(2)));
+         AccountType::default()
+      });
+      let groups: Vec<String> = col(3).split(&GROUP_SEPARATORS[..]).filter(|grp| !grp.is_empty()).map(String::from).collect();
+      for grp in groups.iter().filter(|grp| !all_groups.contains(grp)) {
+         errors.push(format!("Unknown group {}", grp));
+      }
+
+      let mut names: Vec<&str> = fullname.split_whitespace().collect();
+      names.push(&username);
+      let is_generated = col(4).is_empty() || col(4).eq_ignore_ascii_case(GENERATE);
+      let pwd = if is_generated {
+         generate_password(&names, pwd_conf, dictionary).unwrap_or_else(|err| {
+            errors.push(err);
+            String::new()
+         })
+      } else {
+         let pwd = record.get(4).unwrap_or_default().to_string();
+         errors.extend(check_password(&pwd, &names, pwd_conf, dictionary).problems);
+         pwd
+      };
+
+      rows.push(ImportRow {
+         line,
+         fullname,
+         username,
+         acc_type,
+         groups,
+         pwd,
+         is_generated,
+         errors,
+      });
+   }
+   Ok(rows)
+}
+
+/// Create the accounts of the valid rows one after another through the helper, each in its groups, skipping the
+/// others.
+pub async fn create_users(rows: Vec<ImportRow>) -> Vec<Outcome> {
+   let mut outcomes = Vec::with_capacity(rows.len());
+   for row in rows {
+      outcomes.push(if row.is_valid() {
+         match authority::exec_json(Action::CreateUser, &row.new_user()).await {
+            Ok(()) => Outcome::Created,
+            Err(err) => Outcome::Failed(err),
+         }
+      } else {
+         Outcome::Skipped
+      });
+   }
+   outcomes
+}
+
+/// Credentials of the created accounts whose password was generated, as CSV.
+pub fn credentials_csv(rows: &[ImportRow], outcomes: &[Outcome]) -> Result<String, String> {
+   let mut writer = csv::Writer::from_writer(Vec::new());
+   writer.write_record(&["Full Name", "User Name", "Password"]).map_err(|e| e.to_string())?;
+   for (row, _) in rows.iter().zip(outcomes).filter(|(row, outcome)| row.is_generated && matches!(outcome, Outcome::Created)) {
+      writer.write_record(&[&row.fullname, &row.username, &row.pwd]).map_err(|e| e.to_string())?;
+   }
+   String::from_utf8(writer.into_inner().map_err(|e| e.to_string())?).map_err(|e| e.to_string())
+}
+
+/// Write the credentials to a file only its owner can read.
+pub fn export_credentials(path: &Path, rows: &[ImportRow], outcomes: &[Outcome]) -> Result<(), String> {
+   use std::io::Write;
+   use std::os::unix::fs::OpenOptionsExt;
+
+   let content = credentials_csv(rows, outcomes)?;
+   let mut file = std::fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+   file.write_all(content.as_bytes()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn parse(content: &str) -> Vec<ImportRow> {
+      let existing = vec![String::from("root"), String::from("dara")];
+      let groups = vec![String::from("audio"), String::from("video")];
+      parse_csv(content, &existing, &groups, &PwdQualityConf::default(), &HashSet::new()).unwrap()
+   }
+
+   #[test]
+   fn test_parse_csv() {
+      let rows = parse("Full Name,User Name,Account Type,Groups,Password\nSok Chan,sokchan,admin,audio;video,generate\nVy Lina,vylina,,,tree Lamp 42 river\n");
+      assert_eq!(rows.len(), 2);
+      assert!(rows.iter().all(ImportRow::is_valid), "{:?}", rows);
+      assert_eq!(rows[0].line, 2);
+      assert_eq!(rows[0].acc_type, AccountType::Admin);
+      assert_eq!(rows[0].groups, vec!["audio", "video"]);
+      assert!(rows[0].is_generated && !rows[0].pwd.is_empty());
+      assert_eq!(rows[1].acc_type, AccountType::default());
+      assert_eq!(rows[1].pwd, "tree Lamp 42 river");
+      let rows = parse(" Vy Lina , vylina ,, audio; video , tree Lamp 42 river \n");
+      assert!(rows[0].is_valid(), "{:?}", rows);
+      assert_eq!((rows[0].fullname.as_str(), rows[0].username.as_str()), ("Vy Lina", "vylina"));
+      assert_eq!(rows[0].groups, vec!["audio", "video"]);
+      assert_eq!(rows[0].pwd, " tree Lamp 42 river ");
+   }
+
+   #[test]
+   fn test_parse_csv_errors() {
+      let rows = parse("Dara Kim,dara,,,generate\nSok Chan,sokchan,boss,games,generate\nSok Chan,sokchan,,,password\n");
+      assert_eq!(rows.len(), 3);
+      assert_eq!(rows[0].errors.len(), 1);
+      assert_eq!(rows[1].errors, vec!["Unknown account type boss", "Unknown group games"]);
+      assert!(rows[2].errors.iter().any(|err| err.contains("line 2")));
+      assert!(rows[2].errors.len() > 1);
+   }
+
+   #[test]
+   fn test_generate_password() {
+      let conf = PwdQualityConf::default();
+      let pwd = generate_password(&["sokchan"], &conf, &HashSet::new()).unwrap();
+      assert!(pwd.chars().count() >= GENERATED_LEN);
+      assert!(check_password(&pwd, &["sokchan"], &conf, &HashSet::new()).is_acceptable());
+   }
+
+   #[test]
+   fn test_random_chars() {
+      // 255 is past the last whole multiple of the number of characters, so it is thrown away.
+      let bytes = [255u8, 0, 1, PWD_CHARS.len() as u8];
+      assert_eq!(random_chars(&mut &bytes[..], 3).unwrap(), "aba");
+      assert!(random_chars(&mut &[255u8][..], 1).is_err());
+   }
+
+   #[test]
+   fn test_credentials_csv() {
+      let rows = parse("Sok Chan,sokchan,,,generate\nVy Lina,vylina,,,tree Lamp 42 river\n");
+      let outcomes = vec![Outcome::Created, Outcome::Created];
+      assert_eq!(credentials_csv(&rows, &outcomes).unwrap(), format!("Full Name,User Name,Password\nSok Chan,sokchan,{}\n", rows[0].pwd));
+      assert_eq!(credentials_csv(&rows, &[Outcome::Skipped, Outcome::Skipped]).unwrap(), "Full Name,User Name,Password\n");
+   }
+}
@@ -5,6 +5,7 @@ use iced::{
 };
 use crate::gui::styles::{CustomButton, CustomCheckbox, CustomContainer, FOREGROUND};
 use iced_custom_widget::Stack;
+use super::validation::error_view;
 
 #[derive(Debug, Default)]
 pub struct UserInfoPage {
@@ -16,6 +17,9 @@ pub struct UserInfoPage {
    btn_change_pwd_state: button::State,
    btn_change_info_state: button::State,
    btn_change_groups_state: button::State,
+   btn_change_avatar_state: button::State,
+   btn_login_options_state: button::State,
+   error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -23,6 +27,8 @@ pub enum UserInfoMsg {
    ChangePwdClicked,
    ChangeInfoClicked,
    ChangeGroupsClicked,
+   ChangeAvatarClicked,
+   LoginOptionsClicked,
    AllowUsrAdminToggled(bool),
 }
 
@@ -43,6 +49,11 @@ impl UserInfoPage {
       self.profile_path = user.profile_path();
       self.is_admin = user.is_admin();
       self.is_curr_usr = is_curr_usr;
+      self.error = None;
+   }
+
+   pub fn set_error(&mut self, error: String) {
+      self.error = Some(error);
    }
 
    // pub fn update(&mut self, msg: UserInfoMsg) {
@@ -55,7 +66,7 @@ impl UserInfoPage {
    pub fn view(&mut self) -> Element<UserInfoMsg> {
       use UserInfoMsg::*;
       let Self {
-         fullname, profile_path, btn_change_pwd_state, btn_change_info_state, btn_change_groups_state, ..
+         fullname, profile_path, btn_change_pwd_state, btn_change_info_state, btn_change_groups_state, btn_change_avatar_state, btn_login_options_state, error, ..
       } = self;
 
       // println!("{:?}", profile_path.display());
@@ -64,6 +75,7 @@ impl UserInfoPage {
       } else {
          Container::new(Row::new()).width(Length::Units(75)).height(Length::Units(75)).style(CustomContainer::Header).into()
       };
+      let mut btn_profile = Button::new(btn_change_avatar_state, profile).padding(0).style(CustomButton::Text);
       let txt_username = Text::new(fullname.as_str());
       let mut btn_change_info = Button::new(btn_change_info_state, Text::new("  Change Information  ")).style(CustomButton::Default);
       let chb_allow_usr_admin = Checkbox::new(self.is_admin, "Allow administrator access", AllowUsrAdminToggled).spacing(10).style(CustomCheckbox::Default);
@@ -75,31 +87,40 @@ impl UserInfoPage {
          .push(Container::new(Row::new()).width(Length::Units(200)).height(Length::Units(25)).style(CustomContainer::Transparent(FOREGROUND)), None).into()
       };
 
+      let mut sec_allow_usr_admin = Column::new().spacing(5).push(sec_allow_usr_admin);
+      if let Some(err) = error {
+         sec_allow_usr_admin = sec_allow_usr_admin.push(error_view(err));
+      }
+
       let mut btn_change_pwd = Button::new(btn_change_pwd_state, Text::new(format!("  {}  ", if self.is_curr_usr {"Change Password"} else {"Reset Password"})))
          .style(CustomButton::Default);
       let mut btn_change_grps = Button::new(btn_change_groups_state, Text::new("  Advanced  ")).style(CustomButton::Default);
+      let mut btn_login_options = Button::new(btn_login_options_state, Text::new("  Login Options  ")).style(CustomButton::Default);
       if self.allow_usr_admin || self.is_curr_usr {
          btn_change_info = btn_change_info.on_press(ChangeInfoClicked);
          btn_change_pwd = btn_change_pwd.on_press(ChangePwdClicked);
+         btn_profile = btn_profile.on_press(ChangeAvatarClicked);
       }
       if self.allow_usr_admin {
          btn_change_grps = btn_change_grps.on_press(ChangeGroupsClicked);
+         btn_login_options = btn_login_options.on_press(LoginOptionsClicked);
       }
 
       Container::new(
          Column::new().spacing(10).padding(10)
          .push(
             Row::new().spacing(15).align_items(Align::Center)
-            .push(profile)
+            .push(btn_profile)
             .push(txt_username)
             .push(Space::with_width(Length::Fill))
             .push(btn_change_info)
          )
          .push(sec_allow_usr_admin)
          .push(
-            Row::new().align_items(Align::Center)
+            Row::new().spacing(10).align_items(Align::Center)
             .push(btn_change_pwd)
             .push(Space::with_width(Length::Fill))
+            .push(btn_login_options)
             .push(btn_change_grps)
          )
       ).width(Length::Fill).height(Length::Fill).into()
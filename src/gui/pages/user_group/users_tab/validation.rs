@@ -0,0 +1,348 @@
+use crate::gui::styles::{CustomProgressBar, ERROR, SUCCESS, WARNING};
+use iced::{Column, Element, Length, ProgressBar, Row, Text};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+/// Longest user name accepted by `useradd`.
+const MAX_USERNAME_LEN: usize = 32;
+/// Frequently used passwords, rejected in addition to the words of the system dictionary.
+const COMMON_PASSWORDS: [&str; 24] = [
+   "password",
+   "123456",
+   "12345678",
+   "123456789",
+   "qwerty",
+   "abc123",
+   "letmein",
+   "welcome",
+   "monkey",
+   "dragon",
+   "iloveyou",
+   "admin",
+   "login",
+   "princess",
+   "sunshine",
+   "football",
+   "master",
+   "shadow",
+   "baseball",
+   "superman",
+   "trustno1",
+   "passw0rd",
+   "qwertyuiop",
+   "koompi",
+];
+const DICT_PATH: &str = "/usr/share/dict/words";
+
+/// Password rules of `pam_pwquality`, read from `/etc/security/pwquality.conf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PwdQualityConf {
+   pub minlen: usize,
+   /// Negative credits are the least number of characters of that class.
+   pub dcredit: i32,
+   pub ucredit: i32,
+   pub lcredit: i32,
+   pub ocredit: i32,
+   pub minclass: usize,
+   pub maxrepeat: usize,
+   pub dictcheck: bool,
+   pub usercheck: bool,
+}
+
+impl Default for PwdQualityConf {
+   fn default() -> Self {
+      Self {
+         minlen: 8,
+         dcredit: 0,
+         ucredit: 0,
+         lcredit: 0,
+         ocredit: 0,
+         minclass: 0,
+         maxrepeat: 0,
+         dictcheck: true,
+         usercheck: true,
+      }
+   }
+}
+
+impl PwdQualityConf {
+   pub fn parse(content: &str) -> Self {
+      let mut conf = Self::default();
+      conf.merge(content);
+      conf
+   }
+
+   fn merge(&mut self, content: &str) {
+      for line in content.lines().map(str::trim).filter(|line| !line.starts_with('#')) {
+         let (key, val) = match line.find('=') {
+            Some(idx) => (line[..idx].trim(), line[idx + 1..].trim()),
+            None => continue,
+         };
+         match key {
+            "minlen" => self.minlen = val.parse().unwrap_or(self.minlen),
+            "dcredit" => self.dcredit = val.parse().unwrap_or(self.dcredit),
+            "ucredit" => self.ucredit = val.parse().unwrap_or(self.ucredit),
+            "lcredit" => self.lcredit = val.parse().unwrap_or(self.lcredit),
+            "ocredit" => self.ocredit = val.parse().unwrap_or(self.ocredit),
+            "minclass" => self.minclass = val.parse().unwrap_or(self.minclass),
+            "maxrepeat" => self.maxrepeat = val.parse().unwrap_or(self.maxrepeat),
+            "dictcheck" => self.dictcheck = val != "0",
+            "usercheck" => self.usercheck = val != "0",
+            _ => {}
+         }
+      }
+   }
+
+   /// Read the system configuration, with the drop-in files applied on top of it.
+   pub fn load() -> Self {
+      let mut conf = Self::default();
+      if let Ok(content) = std::fs::read_to_string("/etc/security/pwquality.conf") {
+         conf.merge(&content);
+      }
+      if let Ok(entries) = std::fs::read_dir("/etc/security/pwquality.conf.d") {
+         let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).filter(|path| path.extension().map(|ext| ext == "conf").unwrap_or(false)).collect();
+         paths.sort();
+         for path in paths {
+            if let Ok(content) = std::fs::read_to_string(path) {
+               conf.merge(&content);
+            }
+         }
+      }
+      conf
+   }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+   VeryWeak,
+   Weak,
+   Fair,
+   Strong,
+   VeryStrong,
+}
+
+impl Strength {
+   /// Fraction of the strength meter to fill.
+   pub fn level(&self) -> f32 {
+      (*self as u8 + 1) as f32 / 5.0
+   }
+}
+
+impl fmt::Display for Strength {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      use Strength::*;
+      write!(
+         f,
+         "{}",
+         match self {
+            VeryWeak => "Very weak",
+            Weak => "Weak",
+            Fair => "Fair",
+            Strong => "Strong",
+            VeryStrong => "Very strong",
+         }
+      )
+   }
+}
+
+/// Result of checking a password: the rules it breaks and how hard it is to guess.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PwdCheck {
+   pub problems: Vec<String>,
+   pub strength: Strength,
+}
+
+impl PwdCheck {
+   pub fn is_acceptable(&self) -> bool {
+      self.problems.is_empty()
+   }
+}
+
+/// Whether a password is a word of the system dictionary or a common password, also after undoing the usual
+/// letter substitutions and removing leading and trailing digits and symbols.
+fn is_dictionary_word(pwd: &str, dictionary: &HashSet<String>) -> bool {
+   let lower = pwd.to_lowercase();
+   let unleet: String = lower
+      .chars()
+      .map(|c| match c {
+         '0' => 'o',
+         '1' => 'l',
+         '3' => 'e',
+         '4' | '@' => 'a',
+         '5' | '$' => 's',
+         '7' => 't',
+         _ => c,
+      })
+      .collect();
+   let core = lower.trim_matches(|c: char| !c.is_alphabetic());
+   [lower.as_str(), unleet.as_str(), core]
+      .iter()
+      .filter(|word| !word.is_empty())
+      .any(|word| COMMON_PASSWORDS.contains(word) || (word.len() >= 4 && dictionary.contains(*word)))
+}
+
+/// Words of the system dictionary with at least 4 letters, in lowercase.
+pub fn load_dictionary() -> HashSet<String> {
+   if !Path::new(DICT_PATH).exists() {
+      return HashSet::new();
+   }
+   std::fs::read_to_string(DICT_PATH)
+      .map(|content| content.lines().filter(|word| word.len() >= 4 && !word.contains('\'')).map(str::to_lowercase).collect())
+      .unwrap_or_default()
+}
+
+/// Check a password against the rules of `conf`. `names` are the user name and the parts of the full name, which
+/// the password should not contain.
+pub fn check_password(pwd: &str, names: &[&str], conf: &PwdQualityConf, dictionary: &HashSet<String>) -> PwdCheck {
+   let mut problems = Vec::new();
+   let len = pwd.chars().count();
+   let digits = pwd.chars().filter(char::is_ascii_digit).count();
+   let uppers = pwd.chars().filter(|c| c.is_uppercase()).count();
+   let lowers = pwd.chars().filter(|c| c.is_lowercase()).count();
+   let others = len - digits - uppers - lowers;
+   let classes = [digits, uppers, lowers, others].iter().filter(|count| **count > 0).count();
+
+   if len < conf.minlen {
+      problems.push(format!("The password must have at least {} characters", conf.minlen));
+   }
+   for (credit, count, class) in [(conf.dcredit, digits, "digit"), (conf.ucredit, uppers, "uppercase letter"), (conf.lcredit, lowers, "lowercase letter"), (conf.ocredit, others, "symbol")].iter() {
+      if *credit < 0 && *count < (-*credit) as usize {
+         problems.push(format!("The password must contain at least {} {}{}", -*credit, class, if *credit < -1 { "s" } else { "" }));
+      }
+   }
+   if classes < conf.minclass {
+      problems.push(format!("The password must mix at least {} kinds of characters (digits, uppercase, lowercase, symbols)", conf.minclass));
+   }
+   if conf.maxrepeat > 0 {
+      let chars: Vec<char> = pwd.chars().collect();
+      if chars.windows(conf.maxrepeat + 1).any(|window| window.iter().all(|c| *c == window[0])) {
+         problems.push(format!("The password must not repeat a character more than {} times in a row", conf.maxrepeat));
+      }
+   }
+   let lower = pwd.to_lowercase();
+   if conf.usercheck {
+      let is_similar = names
+         .iter()
+         .map(|name| name.to_lowercase())
+         .filter(|name| name.chars().count() >= 3)
+         .any(|name| lower.contains(&name) || lower.contains(&name.chars().rev().collect::<String>()));
+      if is_similar {
+         problems.push(String::from("The password must not contain the name of the user"));
+      }
+   }
+   let is_word = is_dictionary_word(pwd, dictionary);
+   if conf.dictcheck && is_word {
+      problems.push(String::from("The password is based on a dictionary word"));
+   }
+
+   // Rough estimate of the bits of entropy, from the size of the character set.
+   let charset = [(digits, 10.0), (uppers, 26.0), (lowers, 26.0), (others, 33.0)].iter().filter(|(count, _)| *count > 0).map(|(_, size)| size).sum::<f64>();
+   let unique = pwd.chars().collect::<HashSet<_>>().len();
+   let mut bits = if charset > 0.0 { (unique as f64 + (len - unique) as f64 / 2.0) * f64::log2(charset) } else { 0.0 };
+   if is_word {
+      bits = bits.min(20.0);
+   }
+   let strength = match bits as u32 {
+      0..=27 => Strength::VeryWeak,
+      28..=39 => Strength::Weak,
+      40..=59 => Strength::Fair,
+      60..=79 => Strength::Strong,
+      _ => Strength::VeryStrong,
+   };
+   PwdCheck {
+      strength: if problems.is_empty() { strength } else { strength.min(Strength::Weak) },
+      problems,
+   }
+}
+
+/// Strength meter of a password followed by the rules it breaks.
+pub fn pwd_check_view<'a, Msg: 'a>(check: &PwdCheck) -> Element<'a, Msg> {
+   let color = match check.strength {
+      Strength::VeryWeak | Strength::Weak => ERROR,
+      Strength::Fair => WARNING,
+      Strength::Strong | Strength::VeryStrong => SUCCESS,
+   };
+   let meter = Row::new()
+      .spacing(7)
+      .push(ProgressBar::new(0.0..=1.0, check.strength.level()).height(Length::Units(7)).width(Length::Units(150)).style(CustomProgressBar::Default))
+      .push(Text::new(check.strength.to_string()).size(12).color(color));
+   check.problems.iter().fold(Column::new().spacing(3).push(meter), |col, problem| col.push(Text::new(problem.as_str()).size(12).color(ERROR))).into()
+}
+
+/// Inline error message below a field.
+pub fn error_view<'a, Msg: 'a>(error: &str) -> Element<'a, Msg> {
+   Text::new(error).size(12).color(ERROR).into()
+}
+
+/// Names of the existing accounts and groups, which a new user name must not take.
+pub fn existing_names() -> Vec<String> {
+   ["/etc/passwd", "/etc/group"]
+      .iter()
+      .filter_map(|path| std::fs::read_to_string(path).ok())
+      .flat_map(|content| content.lines().filter_map(|line| line.split(':').next()).filter(|name| !name.is_empty()).map(String::from).collect::<Vec<_>>())
+      .collect()
+}
+
+/// Check a user name against the default rules of `useradd` (`NAME_REGEX` `^[a-z_][a-z0-9_-]*[$]?$`).
+pub fn check_username(name: &str, existing: &[String]) -> Result<(), String> {
+   if name.is_empty() {
+      return Err(String::from("Please enter a user name"));
+   }
+   if name.len() > MAX_USERNAME_LEN {
+      return Err(format!("The user name must not be longer than {} characters", MAX_USERNAME_LEN));
+   }
+   let body = name.strip_suffix('$').unwrap_or(name);
+   let mut chars = body.chars();
+   if !chars.next().map(|c| c.is_ascii_lowercase() || c == '_').unwrap_or(false) {
+      return Err(String::from("The user name must start with a lowercase letter or an underscore"));
+   }
+   if !chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-') {
+      return Err(String::from("The user name may only contain lowercase letters, digits, underscores and dashes"));
+   }
+   if existing.iter().any(|existing| existing == name) {
+      return Err(format!("The name {} is already used by an account or a group", name));
+   }
+   Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_parse_pwquality_conf() {
+      let conf = PwdQualityConf::parse("# minlen = 9\nminlen = 12\ndcredit = -1\nminclass=3\ndictcheck = 0\n");
+      assert_eq!(conf.minlen, 12);
+      assert_eq!(conf.dcredit, -1);
+      assert_eq!(conf.minclass, 3);
+      assert!(!conf.dictcheck);
+      assert!(conf.usercheck);
+   }
+
+   #[test]
+   fn test_check_password() {
+      let conf = PwdQualityConf::default();
+      let dictionary = [String::from("elephant")].iter().cloned().collect();
+      assert_eq!(check_password("abc", &[], &conf, &dictionary).strength, Strength::VeryWeak);
+      assert!(!check_password("P4ssw0rd", &[], &conf, &dictionary).is_acceptable());
+      assert!(!check_password("Elephant!", &[], &conf, &dictionary).is_acceptable());
+      assert!(!check_password("sokha2021xyz", &["sokha"], &conf, &dictionary).is_acceptable());
+      let check = check_password("  tree Lamp 42 river ", &["sokha"], &conf, &dictionary);
+      assert!(check.is_acceptable());
+      assert!(check.strength >= Strength::Strong);
+      let strict = PwdQualityConf::parse("dcredit = -2\nmaxrepeat = 2\n");
+      assert_eq!(check_password("aaab1cdefg", &[], &strict, &dictionary).problems.len(), 2);
+   }
+
+   #[test]
+   fn test_check_username() {
+      let existing = vec![String::from("root")];
+      assert!(check_username("sokha", &existing).is_ok());
+      assert!(check_username("_build-2", &existing).is_ok());
+      assert!(check_username("root", &existing).is_err());
+      assert!(check_username("2sokha", &existing).is_err());
+      assert!(check_username("So kha", &existing).is_err());
+      assert!(check_username(&"a".repeat(33), &existing).is_err());
+   }
+}
@@ -99,9 +99,7 @@ impl Application for SystemSetting {
             self.selected_pref = None;
             self.pages.set_current(0)
          }
-         Self::Message::PagesMessage(page_msg) => {
-            self.pages.update(page_msg);
-         }
+         Self::Message::PagesMessage(page_msg) => return self.pages.update(page_msg).map(Self::Message::PagesMessage),
       }
       Command::none()
    }
@@ -1,4 +1,15 @@
 #[macro_use]
 mod macros;
+pub mod authority;
 mod config;
+pub mod device_access;
+pub mod event_sounds;
+pub mod firewall;
+pub mod kbd_backlight;
+pub mod kconfig;
+mod icons;
+pub mod login_policy;
+mod polkit;
 pub use config::ROOT_PATH;
+pub use icons::resolve_icon;
+pub use polkit::{is_skel_dir, Action, NewPassword, NewUser, UserEdit, IDLE_LOGOUT_CONF};
@@ -0,0 +1,131 @@
+//! Runs privileged operations through `system_settings_helper` and keeps track of the authorization of the session.
+use super::polkit::Action;
+use iced::futures::channel::oneshot;
+use serde::Serialize;
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Where packages install the helper, as named by `org.koompi.system-settings.policy`.
+const HELPER_PATH: &str = "/usr/lib/system_settings/system_settings_helper";
+/// Exit codes of `pkexec` when the authentication dialog was dismissed or authorization was refused.
+const PKEXEC_DISMISSED: i32 = 126;
+const PKEXEC_NOT_AUTHORIZED: i32 = 127;
+
+static IS_UNLOCKED: AtomicBool = AtomicBool::new(false);
+
+fn action_id(action: Action) -> String {
+    format!("org.koompi.system-settings.{}", action.name())
+}
+
+/// The installed helper, or the one built next to System Settings during development.
+fn helper_path() -> PathBuf {
+    let installed = PathBuf::from(HELPER_PATH);
+    if installed.exists() {
+        return installed;
+    }
+    std::env::current_exe().map(|exe| exe.with_file_name("system_settings_helper")).unwrap_or(installed)
+}
+
+/// Whether privileged settings were unlocked in this session.
+pub fn is_unlocked() -> bool {
+    IS_UNLOCKED.load(Ordering::Relaxed)
+}
+
+/// Ask for the password of an administrator. polkit keeps the authorization for a few minutes, so that the
+/// following operations don't ask again.
+fn unlock() -> Result<(), String> {
+    let output = Command::new("pkcheck")
+        .args(&["--action-id", &action_id(Action::Unlock), "--process", &std::process::id().to_string(), "--allow-user-interaction"])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        IS_UNLOCKED.store(true, Ordering::Relaxed);
+        Ok(())
+    } else {
+        Err(String::from("Authorization failed"))
+    }
+}
+
+/// Forget the authorization, so that the next operation asks for the password again.
+fn lock() {
+    IS_UNLOCKED.store(false, Ordering::Relaxed);
+    if let Err(err) = Command::new("pkcheck").arg("--revoke-temp").output() {
+        eprintln!("{:?}", err);
+    }
+}
+
+/// Lock when unlocked, otherwise ask to unlock.
+pub async fn toggle() -> Result<(), String> {
+    in_background(|| {
+        if is_unlocked() {
+            lock();
+            Ok(())
+        } else {
+            unlock()
+        }
+    })
+    .await
+}
+
+/// Run a privileged operation with its arguments.
+pub async fn exec(action: Action, args: Vec<String>) -> Result<(), String> {
+    in_background(move || spawn(action, &args, None).map(|_| ())).await
+}
+
+/// Run a privileged operation that reports something, and get what it printed.
+pub async fn exec_output(action: Action, args: Vec<String>) -> Result<String, String> {
+    in_background(move || spawn(action, &args, None)).await
+}
+
+/// Run a privileged operation with its arguments, giving it secrets like a password on its standard input rather than
+/// on the command line where other users could read them.
+pub async fn exec_with_input(action: Action, args: Vec<String>, input: String) -> Result<(), String> {
+    in_background(move || spawn(action, &args, Some(&input)).map(|_| ())).await
+}
+
+/// Run a privileged operation that takes its settings as JSON on its standard input. The helper checks them and
+/// works out the changes itself.
+pub fn exec_json<T: Serialize>(action: Action, value: &T) -> impl Future<Output = Result<(), String>> {
+    let input = serde_json::to_string(value).map_err(|e| e.to_string());
+    async move { exec_with_input(action, Vec::new(), input?).await }
+}
+
+/// Run a blocking job on its own thread, so that the window keeps being drawn meanwhile, e.g. while `pkexec` waits for
+/// the password.
+pub async fn in_background<T: Send + 'static, F: FnOnce() -> Result<T, String> + Send + 'static>(job: F) -> Result<T, String> {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(job());
+    });
+    receiver.await.map_err(|_| String::from("The operation was interrupted"))?
+}
+
+fn spawn(action: Action, args: &[String], input: Option<&str>) -> Result<String, String> {
+    let mut child = Command::new("pkexec")
+        .arg(helper_path())
+        .arg(action.name())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let (Some(input), Some(stdin)) = (input, child.stdin.as_mut()) {
+        stdin.write_all(input.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    match output.status.code() {
+        Some(0) => {
+            IS_UNLOCKED.store(true, Ordering::Relaxed);
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        Some(PKEXEC_DISMISSED) | Some(PKEXEC_NOT_AUTHORIZED) => {
+            IS_UNLOCKED.store(false, Ordering::Relaxed);
+            Err(String::from("Not authorized"))
+        }
+        _ => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+    }
+}
@@ -0,0 +1,177 @@
+//! Log of the applications that used the camera or microphone, written by `system_settings_session` for the whole
+//! session and shown by the Privacy page.
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Application name of the recording stream of the level meter of the Sound page, which is not logged.
+pub const METER_CLIENT_NAME: &str = "System Settings Level Meter";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Device {
+    Camera,
+    Microphone,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessRecord {
+    pub device: Device,
+    pub app: String,
+    /// Unix timestamp of the last time the application was seen using the device.
+    pub last_used: i64,
+}
+
+/// Log of applications that were seen using the camera or microphone.
+#[derive(Debug, Clone, Default)]
+pub struct AccessLog {
+    pub records: Vec<AccessRecord>,
+    /// Applications using each device when last looked at.
+    in_use: Vec<(Device, String)>,
+}
+
+impl AccessLog {
+    const MAX_RECORDS: usize = 50;
+
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("system_settings/privacy_access.json"))
+    }
+
+    pub fn load() -> Self {
+        let records = Self::path().and_then(|path| std::fs::read_to_string(path).ok()).and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default();
+        Self { records, in_use: Vec::new() }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or_else(|| String::from("No data directory"))?;
+        path.parent()
+            .map(std::fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| std::fs::write(&path, serde_json::to_string(&self.records).unwrap_or_default()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Record the applications now using a device, and save the log when some started or stopped using it.
+    pub fn update(&mut self, device: Device, users: Vec<String>) -> Result<(), String> {
+        if self.record(device, users, Local::now().timestamp()) {
+            self.save()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Log the applications that started or stopped using a device as used at `now`. Returns whether the log changed.
+    fn record(&mut self, device: Device, mut users: Vec<String>, now: i64) -> bool {
+        users.sort();
+        users.dedup();
+        let prev: Vec<String> = self.in_use.iter().filter(|(dev, _)| *dev == device).map(|(_, app)| app.clone()).collect();
+        if prev == users {
+            return false;
+        }
+        for app in prev.iter().chain(users.iter()) {
+            self.records.retain(|record| !(record.device == device && &record.app == app));
+            self.records.insert(0, AccessRecord { device, app: app.clone(), last_used: now });
+        }
+        self.records.truncate(Self::MAX_RECORDS);
+        self.in_use.retain(|(dev, _)| *dev != device);
+        self.in_use.extend(users.into_iter().map(|app| (device, app)));
+        true
+    }
+
+    pub fn recent(&self, device: Device) -> impl Iterator<Item = &AccessRecord> {
+        self.records.iter().filter(move |record| record.device == device)
+    }
+}
+
+/// Whether there is a `/dev/video*` device, without which no process needs to be looked at.
+pub fn has_cameras() -> bool {
+    std::fs::read_dir("/dev").map(|entries| entries.flatten().any(|entry| entry.file_name().to_string_lossy().starts_with("video"))).unwrap_or(false)
+}
+
+/// Find processes holding a `/dev/video*` device open.
+pub fn camera_users() -> Vec<String> {
+    let mut users = Vec::new();
+    if let Ok(procs) = std::fs::read_dir("/proc") {
+        for proc_dir in procs.flatten() {
+            let pid_path = proc_dir.path();
+            let fds = match std::fs::read_dir(pid_path.join("fd")) {
+                Ok(fds) => fds,
+                Err(_) => continue,
+            };
+            let uses_camera = fds.flatten().any(|fd| std::fs::read_link(fd.path()).map(|target| target.to_string_lossy().starts_with("/dev/video")).unwrap_or(false));
+            if uses_camera {
+                if let Ok(comm) = std::fs::read_to_string(pid_path.join("comm")) {
+                    let comm = comm.trim().to_string();
+                    if !users.contains(&comm) {
+                        users.push(comm);
+                    }
+                }
+            }
+        }
+    }
+    users
+}
+
+/// Applications of the recording streams in the output of `pactl list source-outputs`.
+pub fn parse_source_outputs(content: &str) -> Vec<String> {
+    let mut users = Vec::new();
+    for line in content.lines() {
+        if let Some(name) = line.trim().strip_prefix("application.name = ") {
+            let name = name.trim_matches('"').to_string();
+            if name != METER_CLIENT_NAME && !users.contains(&name) {
+                users.push(name);
+            }
+        }
+    }
+    users
+}
+
+fn pactl_command() -> Command {
+    let mut cmd = Command::new("pactl");
+    cmd.env("LC_ALL", "C");
+    cmd
+}
+
+/// Find applications with an active recording stream on the sound server.
+pub fn microphone_users() -> Vec<String> {
+    pactl_command().args(&["list", "source-outputs"]).output().map(|output| parse_source_outputs(&String::from_utf8_lossy(&output.stdout))).unwrap_or_default()
+}
+
+/// Call `on_change` now and each time a recording stream starts or ends, until the sound server goes away.
+pub fn watch_source_outputs(mut on_change: impl FnMut()) -> Result<(), String> {
+    let mut child = pactl_command().arg("subscribe").stdout(Stdio::piped()).stderr(Stdio::null()).spawn().map_err(|e| format!("Failed to run pactl: {}", e))?;
+    on_change();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line.starts_with("Event 'new' on source-output #") || line.starts_with("Event 'remove' on source-output #") {
+                on_change();
+            }
+        }
+    }
+    let _ = child.wait();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_source_outputs() {
+        let content = format!("Source Output #7\n\tProperties:\n\t\tapplication.name = \"Firefox\"\n\nSource Output #8\n\tProperties:\n\t\tapplication.name = \"{}\"\n", METER_CLIENT_NAME);
+        assert_eq!(parse_source_outputs(&content), vec!["Firefox"]);
+    }
+
+    #[test]
+    fn test_record() {
+        let mut log = AccessLog::default();
+        assert!(log.record(Device::Microphone, vec![String::from("Firefox")], 10));
+        assert!(!log.record(Device::Microphone, vec![String::from("Firefox")], 20));
+        assert_eq!(log.records[0].last_used, 10);
+        assert!(log.record(Device::Microphone, Vec::new(), 30));
+        assert_eq!(log.records.len(), 1);
+        assert_eq!(log.records[0].last_used, 30);
+        assert!(!log.record(Device::Camera, Vec::new(), 40));
+    }
+}
@@ -0,0 +1,89 @@
+//! Volume of the event sounds of the desktop, e.g. notifications. libcanberra plays them as streams with the
+//! `event` media role, which `system_settings_session` turns to the alert volume of the Sound page as they start.
+use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Sound effect settings of the Sound page, in the config directory.
+pub const SOUND_EFFECTS_CONF: &str = "system_settings/sound_effects.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+struct AlertVolume {
+    /// Percent of the volume of the output device.
+    alert_volume: u8,
+}
+
+impl Default for AlertVolume {
+    fn default() -> Self {
+        Self { alert_volume: 100 }
+    }
+}
+
+pub fn conf_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(SOUND_EFFECTS_CONF))
+}
+
+/// The alert volume saved by the Sound page.
+pub fn alert_volume() -> u8 {
+    let conf: AlertVolume = conf_path().and_then(|path| std::fs::read_to_string(path).ok()).and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default();
+    conf.alert_volume.min(100)
+}
+
+/// Indexes of the streams of the output of `pactl list sink-inputs` that play event sounds.
+pub fn parse_event_streams(content: &str) -> Vec<u32> {
+    let mut streams = Vec::new();
+    let mut index: Option<u32> = None;
+    for line in content.lines().map(str::trim) {
+        if let Some(idx) = line.strip_prefix("Sink Input #") {
+            index = idx.parse().ok();
+        } else if line == "media.role = \"event\"" {
+            streams.extend(index.take());
+        }
+    }
+    streams
+}
+
+fn pactl_command() -> Command {
+    let mut cmd = Command::new("pactl");
+    cmd.env("LC_ALL", "C");
+    cmd
+}
+
+/// Set the alert volume on the event sound streams that are playing.
+pub fn apply_alert_volume() -> Result<(), String> {
+    let output = pactl_command().args(&["list", "sink-inputs"]).output().map_err(|e| format!("Failed to run pactl: {}", e))?;
+    let volume = format!("{}%", alert_volume());
+    for stream in parse_event_streams(&String::from_utf8_lossy(&output.stdout)) {
+        // The stream may already be over.
+        let _ = pactl_command().args(&["set-sink-input-volume", &stream.to_string(), &volume]).output();
+    }
+    Ok(())
+}
+
+/// Apply the alert volume to each new stream until the sound server goes away.
+pub fn watch_event_streams() -> Result<(), String> {
+    let mut child = pactl_command().arg("subscribe").stdout(Stdio::piped()).stderr(Stdio::null()).spawn().map_err(|e| format!("Failed to run pactl: {}", e))?;
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line.starts_with("Event 'new' on sink-input #") {
+                apply_alert_volume()?;
+            }
+        }
+    }
+    let _ = child.wait();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_event_streams() {
+        let content = "Sink Input #41\n\tDriver: protocol-native.c\n\tProperties:\n\t\tmedia.role = \"music\"\n\t\tapplication.name = \"Elisa\"\n\nSink Input #42\n\tDriver: protocol-native.c\n\tProperties:\n\t\tmedia.role = \"event\"\n\t\tapplication.name = \"plasmashell\"\n";
+        assert_eq!(parse_event_streams(content), vec![42]);
+        assert!(parse_event_streams("").is_empty());
+    }
+}
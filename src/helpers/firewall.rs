@@ -0,0 +1,560 @@
+//! Firewall rules of Security & Privacy, shared by System Settings, which edits them, and `system_settings_helper`,
+//! which checks them and runs the `ufw` or `nft` commands that apply them.
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+use std::fmt::{self, Display, Formatter};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+const NFT_TABLE: &str = "koompi_firewall";
+/// Where ufw keeps whether it is enabled, readable without root.
+const UFW_CONF: &str = "/etc/ufw/ufw.conf";
+const APP_PROFILES_DIR: &str = "/etc/ufw/applications.d";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SmartDefault, Serialize, Deserialize)]
+pub enum FwAction {
+    #[default]
+    Allow,
+    Deny,
+}
+
+impl FwAction {
+    pub const ALL: [FwAction; 2] = [FwAction::Allow, FwAction::Deny];
+}
+
+impl Display for FwAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FwAction::Allow => "Allow",
+                FwAction::Deny => "Deny",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SmartDefault, Serialize, Deserialize)]
+pub enum FwProtocol {
+    #[default]
+    Any,
+    Tcp,
+    Udp,
+}
+
+impl FwProtocol {
+    pub const ALL: [FwProtocol; 3] = [FwProtocol::Any, FwProtocol::Tcp, FwProtocol::Udp];
+
+    fn names(&self) -> Vec<&'static str> {
+        match self {
+            FwProtocol::Any => vec!["tcp", "udp"],
+            FwProtocol::Tcp => vec!["tcp"],
+            FwProtocol::Udp => vec!["udp"],
+        }
+    }
+}
+
+impl Display for FwProtocol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FwProtocol::Any => "TCP & UDP",
+                FwProtocol::Tcp => "TCP",
+                FwProtocol::Udp => "UDP",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SmartDefault)]
+pub enum FwTargetKind {
+    #[default]
+    Port,
+    Application,
+}
+
+impl FwTargetKind {
+    pub const ALL: [FwTargetKind; 2] = [FwTargetKind::Port, FwTargetKind::Application];
+}
+
+impl Display for FwTargetKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FwTargetKind::Port => "Port",
+                FwTargetKind::Application => "Application",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FwTarget {
+    Port(String),
+    App(String),
+}
+
+/// A single incoming rule. An empty `source` means the rule applies to any address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FwRule {
+    pub action: FwAction,
+    pub protocol: FwProtocol,
+    pub target: FwTarget,
+    pub source: String,
+}
+
+impl FwRule {
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.target {
+            FwTarget::Port(port) => {
+                let (start, end) = match port.find(':') {
+                    Some(idx) => (&port[..idx], &port[idx + 1..]),
+                    None => (port.as_str(), port.as_str()),
+                };
+                match (start.parse::<u16>(), end.parse::<u16>()) {
+                    (Ok(start), Ok(end)) if start > 0 && start <= end => {}
+                    _ => return Err(format!("'{}' is not a valid port or port range (e.g. 22 or 6000:6010)", port)),
+                }
+                if start != end && self.protocol == FwProtocol::Any {
+                    return Err(String::from("A port range requires choosing either TCP or UDP"));
+                }
+            }
+            FwTarget::App(name) => {
+                if name.trim().is_empty() {
+                    return Err(String::from("Application name is empty"));
+                }
+            }
+        }
+        if !self.source.is_empty() {
+            parse_source(&self.source)?;
+        }
+        Ok(())
+    }
+
+    /// The rule as written after `ufw` to add it, or after `ufw delete` to remove it.
+    fn ufw_args(&self) -> Vec<String> {
+        let mut args = vec![self.action.to_string().to_lowercase()];
+        if let (FwTarget::Port(_), FwProtocol::Tcp) | (FwTarget::Port(_), FwProtocol::Udp) = (&self.target, self.protocol) {
+            args.push(String::from("proto"));
+            args.push(self.protocol.names()[0].to_string());
+        }
+        args.push(String::from("from"));
+        args.push(if self.source.is_empty() { String::from("any") } else { self.source.clone() });
+        args.push(String::from("to"));
+        args.push(String::from("any"));
+        match &self.target {
+            FwTarget::Port(port) => {
+                args.push(String::from("port"));
+                args.push(port.clone());
+            }
+            FwTarget::App(name) => {
+                args.push(String::from("app"));
+                args.push(name.clone());
+            }
+        }
+        args
+    }
+
+    fn nft_lines(&self, profiles: &[AppProfile]) -> Result<Vec<String>, String> {
+        let ports: Vec<(String, FwProtocol)> = match &self.target {
+            FwTarget::Port(port) => vec![(port.replace(':', "-"), self.protocol)],
+            FwTarget::App(name) => profiles
+                .iter()
+                .find(|profile| &profile.name == name)
+                .ok_or_else(|| format!("Unknown application profile '{}'", name))?
+                .ports
+                .iter()
+                .map(|(port, proto)| (port.replace(':', "-"), *proto))
+                .collect(),
+        };
+        let source = if self.source.is_empty() {
+            String::new()
+        } else {
+            match parse_source(&self.source)? {
+                IpAddr::V4(_) => format!("ip saddr {} ", self.source),
+                IpAddr::V6(_) => format!("ip6 saddr {} ", self.source),
+            }
+        };
+        let verdict = match self.action {
+            FwAction::Allow => "accept",
+            FwAction::Deny => "drop",
+        };
+        Ok(ports
+            .iter()
+            .flat_map(|(port, proto)| proto.names().into_iter().map(move |name| (port.clone(), name)))
+            .map(|(port, name)| format!("{}{} dport {{ {} }} {}", source, name, port, verdict))
+            .collect())
+    }
+}
+
+impl Display for FwRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let target = match &self.target {
+            FwTarget::Port(port) => format!("port {} ({})", port, self.protocol),
+            FwTarget::App(name) => name.clone(),
+        };
+        write!(f, "{} {} from {}", self.action, target, if self.source.is_empty() { "anywhere" } else { self.source.as_str() })
+    }
+}
+
+fn parse_source(source: &str) -> Result<IpAddr, String> {
+    let (addr, prefix) = match source.find('/') {
+        Some(idx) => (&source[..idx], Some(&source[idx + 1..])),
+        None => (source, None),
+    };
+    let ip = addr.parse::<IpAddr>().map_err(|_| format!("'{}' is not a valid address or network", source))?;
+    if let Some(prefix) = prefix {
+        let max = if ip.is_ipv4() { 32 } else { 128 };
+        match prefix.parse::<u8>() {
+            Ok(len) if len <= max => {}
+            _ => return Err(format!("'{}' is not a valid address or network", source)),
+        }
+    }
+    Ok(ip)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirewallConf {
+    pub enabled: bool,
+    pub block_all_incoming: bool,
+    pub rules: Vec<FwRule>,
+}
+
+/// Rules applied by `firewall`, with those the firewall follows now so that only the differences are changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirewallChange {
+    pub prev: FirewallConf,
+    pub conf: FirewallConf,
+}
+
+/// Whether the `ENABLED` setting of ufw.conf turns the firewall on at boot.
+pub fn parse_ufw_enabled(content: &str) -> bool {
+    content.lines().map(str::trim).find_map(|line| line.strip_prefix("ENABLED=")).map(|val| val.trim_matches('"') == "yes").unwrap_or(false)
+}
+
+impl FirewallConf {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("system_settings/firewall.json"))
+    }
+
+    /// Load the rule set saved by the previous session.
+    pub fn load() -> Option<Self> {
+        Self::path().and_then(|path| Self::import(&path).ok())
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or_else(|| String::from("config directory not found"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        self.export(&path)
+    }
+
+    pub fn import(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let conf: Self = serde_json::from_str(&content).map_err(|e| format!("Invalid firewall rules file: {}", e))?;
+        for rule in conf.rules.iter() {
+            rule.validate()?;
+        }
+        Ok(conf)
+    }
+
+    /// Check the rules before they are applied, including that their applications have a profile.
+    pub fn check(&self, profiles: &[AppProfile]) -> Result<(), String> {
+        for rule in self.rules.iter() {
+            rule.validate()?;
+            if let FwTarget::App(name) = &rule.target {
+                if !profiles.iter().any(|profile| &profile.name == name) {
+                    return Err(format!("Unknown application profile '{}'", name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn export(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// The ufw rules this page owns: its own rules, or a single rule in front of all the others blocking every incoming
+    /// connection.
+    fn ufw_rules(&self) -> Vec<Vec<String>> {
+        if self.block_all_incoming {
+            vec![["deny", "from", "any", "to", "any"].iter().map(ToString::to_string).collect()]
+        } else {
+            self.rules.iter().map(FwRule::ufw_args).collect()
+        }
+    }
+
+    fn nft_script(&self, profiles: &[AppProfile]) -> Result<String, String> {
+        let mut script = format!("table inet {} {{\n   chain input {{\n      type filter hook input priority 0; policy drop;\n", NFT_TABLE);
+        script.push_str("      ct state established,related accept\n      ct state invalid drop\n      iif lo accept\n");
+        script.push_str("      meta l4proto { icmp, ipv6-icmp } accept\n      udp dport { 67, 68, 546, 547 } accept\n");
+        if !self.block_all_incoming {
+            for rule in self.rules.iter() {
+                for line in rule.nft_lines(profiles)? {
+                    script.push_str(&format!("      {}\n", line));
+                }
+            }
+        }
+        script.push_str("   }\n}\n");
+        Ok(script)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppProfile {
+    pub name: String,
+    pub ports: Vec<(String, FwProtocol)>,
+}
+
+/// Parse ufw application profiles, e.g. `ports=80,443/tcp|53/udp`.
+pub fn parse_app_profiles(content: &str) -> Vec<AppProfile> {
+    let mut profiles: Vec<AppProfile> = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') && line.ends_with(']') {
+            profiles.push(AppProfile {
+                name: line[1..line.len() - 1].to_string(),
+                ports: Vec::new(),
+            });
+        } else if let (Some(profile), true) = (profiles.last_mut(), line.starts_with("ports=")) {
+            for spec in line["ports=".len()..].split('|') {
+                let (ports, proto) = match spec.find('/') {
+                    Some(idx) => (&spec[..idx], if &spec[idx + 1..] == "udp" { FwProtocol::Udp } else { FwProtocol::Tcp }),
+                    None => (spec, FwProtocol::Any),
+                };
+                profile.ports.extend(ports.split(',').filter(|port| !port.is_empty()).map(|port| (port.to_string(), proto)));
+            }
+        }
+    }
+    profiles
+}
+
+pub fn app_profiles() -> Vec<AppProfile> {
+    let mut profiles = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(APP_PROFILES_DIR) {
+        for entry in entries.flatten() {
+            if let Ok(content) = std::fs::read_to_string(entry.path()) {
+                profiles.extend(parse_app_profiles(&content));
+            }
+        }
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+pub trait CommandRunner {
+    fn run(&mut self, program: &str, args: &[String], stdin: Option<&str>) -> Result<String, String>;
+}
+
+/// Records the commands instead of running them, so rule generation can be checked without root.
+#[derive(Debug, Clone, Default)]
+pub struct DryRunRunner {
+    pub log: Vec<String>,
+}
+
+impl CommandRunner for DryRunRunner {
+    fn run(&mut self, program: &str, args: &[String], stdin: Option<&str>) -> Result<String, String> {
+        let mut cmd = std::iter::once(program.to_string()).chain(args.iter().cloned()).collect::<Vec<String>>().join(" ");
+        if let Some(input) = stdin {
+            cmd.push('\n');
+            cmd.push_str(input);
+        }
+        self.log.push(cmd);
+        Ok(String::new())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirewallKind {
+    Ufw,
+    Nftables,
+}
+
+impl FirewallKind {
+    pub fn detect() -> Self {
+        if ["/usr/bin/ufw", "/usr/sbin/ufw"].iter().any(|path| Path::new(path).exists()) {
+            FirewallKind::Ufw
+        } else {
+            FirewallKind::Nftables
+        }
+    }
+
+    /// Whether the firewall is on, as the system reports it, or `None` when that can't be told. The table of nftables
+    /// can only be listed by root, so only ufw tells.
+    pub fn is_enabled(&self) -> Option<bool> {
+        match self {
+            FirewallKind::Ufw => std::fs::read_to_string(UFW_CONF).ok().map(|content| parse_ufw_enabled(&content)),
+            FirewallKind::Nftables => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Firewall<R: CommandRunner> {
+    kind: FirewallKind,
+    runner: R,
+}
+
+impl<R: CommandRunner> Firewall<R> {
+    pub fn new(kind: FirewallKind, runner: R) -> Self {
+        Self { kind, runner }
+    }
+
+    pub fn runner(&self) -> &R {
+        &self.runner
+    }
+
+    fn run(&mut self, program: &str, args: &[&str], stdin: Option<&str>) -> Result<String, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        self.runner.run(program, &args, stdin)
+    }
+
+    /// Change the firewall from the rules of `prev`, which it follows now, to those of `conf`. With ufw, only the rules
+    /// this page added are deleted, so that those of other tools are kept. With nftables, the page has a table of its own,
+    /// which is replaced.
+    pub fn apply(&mut self, prev: &FirewallConf, conf: &FirewallConf, profiles: &[AppProfile]) -> Result<(), String> {
+        match self.kind {
+            FirewallKind::Ufw => {
+                let (prev_rules, rules) = (prev.ufw_rules(), conf.ufw_rules());
+                for rule in prev_rules.iter().filter(|rule| !rules.contains(rule)) {
+                    let args: Vec<String> = std::iter::once(String::from("delete")).chain(rule.iter().cloned()).collect();
+                    self.runner.run("ufw", &args, None)?;
+                }
+                for rule in rules.iter().filter(|rule| !prev_rules.contains(rule)) {
+                    let args: Vec<String> = if conf.block_all_incoming {
+                        std::iter::once(String::from("prepend")).chain(rule.iter().cloned()).collect()
+                    } else {
+                        rule.clone()
+                    };
+                    self.runner.run("ufw", &args, None)?;
+                }
+                if conf.enabled != prev.enabled {
+                    if conf.enabled {
+                        self.run("ufw", &["--force", "enable"], None)?;
+                    } else {
+                        self.run("ufw", &["disable"], None)?;
+                    }
+                }
+            }
+            FirewallKind::Nftables => {
+                let script = conf.nft_script(profiles)?;
+                // The table may not exist yet, so a failure to delete it is not an error.
+                let _ = self.run("nft", &["delete", "table", "inet", NFT_TABLE], None);
+                if conf.enabled {
+                    self.run("nft", &["-f", "-"], Some(&script))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn ruleset(&mut self) -> Result<String, String> {
+        match self.kind {
+            FirewallKind::Ufw => self.run("ufw", &["status", "verbose"], None),
+            FirewallKind::Nftables => self.run("nft", &["list", "ruleset"], None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> FirewallConf {
+        FirewallConf {
+            enabled: true,
+            block_all_incoming: false,
+            rules: vec![
+                FwRule {
+                    action: FwAction::Allow,
+                    protocol: FwProtocol::Tcp,
+                    target: FwTarget::Port(String::from("22")),
+                    source: String::from("10.0.0.0/8"),
+                },
+                FwRule {
+                    action: FwAction::Deny,
+                    protocol: FwProtocol::Any,
+                    target: FwTarget::App(String::from("Samba")),
+                    source: String::new(),
+                },
+            ],
+        }
+    }
+
+    fn profiles() -> Vec<AppProfile> {
+        parse_app_profiles("[Samba]\ntitle=LanManager-like file and printer server\nports=137,138/udp|139,445/tcp\n")
+    }
+
+    #[test]
+    fn test_ufw_dry_run() {
+        let mut firewall = Firewall::new(FirewallKind::Ufw, DryRunRunner::default());
+        firewall.apply(&FirewallConf::default(), &rules(), &profiles()).unwrap();
+        assert_eq!(firewall.runner().log, vec!["ufw allow proto tcp from 10.0.0.0/8 to any port 22", "ufw deny from any to any app Samba", "ufw --force enable"]);
+    }
+
+    #[test]
+    fn test_ufw_changes() {
+        let prev = rules();
+        let mut conf = rules();
+        conf.rules.remove(0);
+        let mut firewall = Firewall::new(FirewallKind::Ufw, DryRunRunner::default());
+        firewall.apply(&prev, &conf, &profiles()).unwrap();
+        assert_eq!(firewall.runner().log, vec!["ufw delete allow proto tcp from 10.0.0.0/8 to any port 22"]);
+
+        conf.block_all_incoming = true;
+        conf.enabled = false;
+        let mut firewall = Firewall::new(FirewallKind::Ufw, DryRunRunner::default());
+        firewall.apply(&prev, &conf, &profiles()).unwrap();
+        assert_eq!(
+            firewall.runner().log,
+            vec!["ufw delete allow proto tcp from 10.0.0.0/8 to any port 22", "ufw delete deny from any to any app Samba", "ufw prepend deny from any to any", "ufw disable",]
+        );
+    }
+
+    #[test]
+    fn test_parse_ufw_enabled() {
+        assert!(parse_ufw_enabled("# comment\nENABLED=yes\nLOGLEVEL=low\n"));
+        assert!(!parse_ufw_enabled("ENABLED=no\n"));
+        assert!(!parse_ufw_enabled(""));
+    }
+
+    #[test]
+    fn test_nft_dry_run() {
+        let mut firewall = Firewall::new(FirewallKind::Nftables, DryRunRunner::default());
+        firewall.apply(&FirewallConf::default(), &rules(), &profiles()).unwrap();
+        let log = &firewall.runner().log;
+        assert_eq!(log[0], "nft delete table inet koompi_firewall");
+        assert!(log[1].starts_with("nft -f -\ntable inet koompi_firewall {"));
+        assert!(log[1].contains("ip saddr 10.0.0.0/8 tcp dport { 22 } accept"));
+        assert!(log[1].contains("udp dport { 137 } drop"));
+        assert!(log[1].contains("tcp dport { 445 } drop"));
+    }
+
+    #[test]
+    fn test_check_conf() {
+        assert!(rules().check(&profiles()).is_ok());
+        assert!(rules().check(&[]).is_err());
+    }
+
+    #[test]
+    fn test_validate_rule() {
+        let mut rule = rules().rules[0].clone();
+        assert!(rule.validate().is_ok());
+        rule.target = FwTarget::Port(String::from("6000:6010"));
+        assert!(rule.validate().is_ok());
+        rule.protocol = FwProtocol::Any;
+        assert!(rule.validate().is_err());
+        rule.target = FwTarget::Port(String::from("70000"));
+        assert!(rule.validate().is_err());
+        rule.target = FwTarget::Port(String::from("80"));
+        rule.source = String::from("10.0.0.0/40");
+        assert!(rule.validate().is_err());
+    }
+}
@@ -0,0 +1,21 @@
+use std::path::{Path, PathBuf};
+
+/// Resolve an icon name from a `.desktop` file or a sound stream to an image file of the hicolor theme.
+pub fn resolve_icon(icon: &str) -> Option<PathBuf> {
+    if Path::new(icon).is_absolute() {
+        return Some(PathBuf::from(icon)).filter(|path| path.exists());
+    }
+    let mut roots = vec![PathBuf::from("/usr/share/icons/hicolor"), PathBuf::from("/var/lib/flatpak/exports/share/icons/hicolor")];
+    if let Some(data_dir) = dirs::data_dir() {
+        roots.push(data_dir.join("flatpak/exports/share/icons/hicolor"));
+    }
+    for root in roots.iter() {
+        for (size, ext) in [("scalable", "svg"), ("48x48", "png"), ("64x64", "png"), ("128x128", "png")].iter() {
+            let path = root.join(format!("{}/apps/{}.{}", size, icon, ext));
+            if path.exists() {
+                return Some(path);
+            }
+        }
+    }
+    Some(PathBuf::from(format!("/usr/share/pixmaps/{}.png", icon))).filter(|path| path.exists())
+}
@@ -0,0 +1,188 @@
+//! Keyboard backlight of the sysfs LED devices, set by the Keyboard page and by `system_settings_session`, which
+//! applies the saved settings at login and turns the backlight off while the session is idle.
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// LED class devices of the kernel, where keyboard backlights are named `<device>::kbd_backlight`.
+const LEDS: &str = "/sys/class/leds";
+const KBD_BACKLIGHT: &str = "::kbd_backlight";
+
+/// Backlight of the keyboard, driven through its sysfs LED device.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KbdBacklight {
+    pub name: String,
+    path: PathBuf,
+    pub max_brightness: u32,
+}
+
+/// Keyboard backlight settings, saved so that they are applied again at the next login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BacklightSettings {
+    /// Percent of the maximum brightness.
+    pub brightness: u8,
+    pub turn_off: bool,
+    /// Seconds of inactivity before the backlight is turned off.
+    pub turn_off_after: u64,
+}
+
+impl Default for BacklightSettings {
+    fn default() -> Self {
+        Self {
+            brightness: 100,
+            turn_off: false,
+            turn_off_after: 5,
+        }
+    }
+}
+
+/// Turns the backlight off once the session has been idle long enough and gives back the brightness it had on input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IdleTimer {
+    saved: Option<u32>,
+}
+
+impl KbdBacklight {
+    pub fn find() -> Option<Self> {
+        Self::find_in(Path::new(LEDS))
+    }
+
+    /// First keyboard backlight of the LED devices of the directory that can be lit.
+    fn find_in(dir: &Path) -> Option<Self> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(KBD_BACKLIGHT))
+            .collect();
+        names.sort();
+        names.into_iter().find_map(|name| {
+            let path = dir.join(&name);
+            let max_brightness = read_u32(&path.join("max_brightness")).filter(|max| *max > 0)?;
+            Some(Self { name, path, max_brightness })
+        })
+    }
+
+    pub fn brightness(&self) -> u32 {
+        read_u32(&self.path.join("brightness")).unwrap_or_default()
+    }
+
+    /// Write the brightness to the device, or ask logind for it when the device is only writable by root.
+    pub fn set_brightness(&self, val: u32) -> Result<(), String> {
+        let val = val.min(self.max_brightness);
+        if std::fs::write(self.path.join("brightness"), val.to_string()).is_ok() {
+            return Ok(());
+        }
+        let output = Command::new("dbus-send")
+            .args(&[
+                "--system",
+                "--print-reply",
+                "--dest=org.freedesktop.login1",
+                "/org/freedesktop/login1/session/auto",
+                "org.freedesktop.login1.Session.SetBrightness",
+                "string:leds",
+                &format!("string:{}", self.name),
+                &format!("uint32:{}", val),
+            ])
+            .output()
+            .map_err(|e| format!("Failed to run dbus-send: {}", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!("Failed to set the brightness of {}: {}", self.name, String::from_utf8_lossy(&output.stderr).trim()))
+        }
+    }
+
+    pub fn from_percent(&self, percent: u8) -> u32 {
+        (percent.min(100) as f64 * self.max_brightness as f64 / 100.0).round() as u32
+    }
+}
+
+impl BacklightSettings {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("system_settings/kbd_backlight.json"))
+    }
+
+    pub fn load() -> Self {
+        Self::path().and_then(|path| std::fs::read_to_string(path).ok()).and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(path) = Self::path() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+            }
+            std::fs::write(&path, serde_json::to_string_pretty(self).unwrap_or_default()).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+impl IdleTimer {
+    /// Brightness to set for how long the session has been idle, if it has to change.
+    pub fn tick(&mut self, idle: Duration, timeout: Duration, current: u32) -> Option<u32> {
+        if idle < timeout {
+            self.restore()
+        } else if self.saved.is_none() && current > 0 {
+            self.saved = Some(current);
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// Brightness to give back when the backlight was turned off by the timer.
+    pub fn restore(&mut self) -> Option<u32> {
+        self.saved.take()
+    }
+}
+
+/// Time since the last input of the X session, as reported by `xprintidle`.
+pub fn idle_time() -> Result<Duration, String> {
+    let output = Command::new("xprintidle").output().map_err(|e| format!("Failed to run xprintidle: {}", e))?;
+    String::from_utf8_lossy(&output.stdout).trim().parse::<u64>().map(Duration::from_millis).map_err(|e| format!("xprintidle: {}", e))
+}
+
+fn read_u32(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok().and_then(|content| content.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_in() {
+        let dir = std::env::temp_dir().join(format!("kbd_backlight_{}", std::process::id()));
+        for (name, max) in &[("input3::capslock", "1"), ("tpacpi::kbd_backlight", "2"), ("dell::kbd_backlight", "0")] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            std::fs::write(dir.join(name).join("max_brightness"), max).unwrap();
+        }
+        std::fs::write(dir.join("tpacpi::kbd_backlight/brightness"), "1\n").unwrap();
+        let backlight = KbdBacklight::find_in(&dir).unwrap();
+        assert_eq!(backlight.name, "tpacpi::kbd_backlight");
+        assert_eq!(backlight.max_brightness, 2);
+        assert_eq!(backlight.brightness(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_percent() {
+        let backlight = KbdBacklight { max_brightness: 3, ..KbdBacklight::default() };
+        assert_eq!(backlight.from_percent(50), 2);
+        assert_eq!(backlight.from_percent(0), 0);
+        assert_eq!(backlight.from_percent(150), 3);
+    }
+
+    #[test]
+    fn test_idle_timer() {
+        let timeout = Duration::from_secs(5);
+        let mut timer = IdleTimer::default();
+        assert_eq!(timer.tick(Duration::from_secs(1), timeout, 2), None);
+        assert_eq!(timer.tick(Duration::from_secs(5), timeout, 2), Some(0));
+        assert_eq!(timer.tick(Duration::from_secs(6), timeout, 0), None);
+        assert_eq!(timer.tick(Duration::from_millis(200), timeout, 0), Some(2));
+        assert_eq!(timer.tick(Duration::from_secs(9), timeout, 0), None);
+    }
+}
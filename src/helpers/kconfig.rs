@@ -0,0 +1,28 @@
+//! Keys of the KConfig files of the Plasma session, read and written with `kreadconfig5` and `kwriteconfig5`.
+use std::process::Command;
+
+/// Value of a key of a file of `~/.config`, or of the absolute path. `None` when the key is missing or empty.
+pub fn read_key(file: &str, group: &str, key: &str) -> Option<String> {
+    let output = Command::new("kreadconfig5").args(&["--file", file, "--group", group, "--key", key]).output().ok()?;
+    let val = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if val.is_empty() {
+        None
+    } else {
+        Some(val)
+    }
+}
+
+/// Write the value of a key, or delete the key when there is none.
+pub fn write_key(file: &str, group: &str, key: &str, val: Option<&str>) -> Result<(), String> {
+    let mut cmd = Command::new("kwriteconfig5");
+    cmd.args(&["--file", file, "--group", group, "--key", key]);
+    match val {
+        Some(val) => cmd.arg(val),
+        None => cmd.arg("--delete"),
+    };
+    match cmd.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(_) => Err(format!("Failed to write {} to {}", key, file)),
+        Err(e) => Err(format!("Failed to run kwriteconfig5: {}", e)),
+    }
+}
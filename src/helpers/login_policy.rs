@@ -0,0 +1,224 @@
+//! Login options of the users, shared by System Settings, which edits them, and `system_settings_helper`, which checks
+//! them and turns them into the files and commands of the system.
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Settings that can't be read back from the system by a normal user, kept next to the rules they produce.
+pub const POLICY_PATH: &str = "/var/lib/system_settings/login_policy.json";
+pub const DAYS: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+const APPLICATIONS: &str = "/usr/share/applications";
+/// Programs which run the code of others, so that restricting one would keep the user from much more than an app.
+const INTERPRETERS: [&str; 16] = ["sh", "bash", "dash", "zsh", "env", "python", "perl", "ruby", "node", "java", "mono", "gjs", "wine", "flatpak", "snap", "electron"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayManager {
+    Sddm,
+    LightDm,
+    Gdm,
+}
+
+impl DisplayManager {
+    /// The display manager started by `display-manager.service`.
+    pub fn detect() -> Option<Self> {
+        let target = std::fs::read_link("/etc/systemd/system/display-manager.service").ok()?;
+        match target.file_stem()?.to_str()? {
+            "sddm" => Some(Self::Sddm),
+            "lightdm" => Some(Self::LightDm),
+            "gdm" | "gdm3" => Some(Self::Gdm),
+            _ => None,
+        }
+    }
+
+    /// The configuration file and its section and keys for automatic login.
+    pub fn autologin_conf(&self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            Self::Sddm => ("/etc/sddm.conf.d/autologin.conf", "Autologin", "User"),
+            Self::LightDm => ("/etc/lightdm/lightdm.conf", "Seat:*", "autologin-user"),
+            Self::Gdm => (if Path::new("/etc/gdm3").exists() { "/etc/gdm3/custom.conf" } else { "/etc/gdm/custom.conf" }, "daemon", "AutomaticLogin"),
+        }
+    }
+
+    /// The user logged in automatically, if any.
+    pub fn autologin_user(&self) -> Option<String> {
+        let (path, section, key) = self.autologin_conf();
+        let content = std::fs::read_to_string(path).ok()?;
+        if *self == Self::Gdm && ini_value(&content, section, "AutomaticLoginEnable").as_deref() != Some("true") {
+            return None;
+        }
+        ini_value(&content, section, key).filter(|user| !user.is_empty())
+    }
+
+    /// Only LightDM ships a guest session.
+    pub fn supports_guest(&self) -> bool {
+        *self == Self::LightDm
+    }
+
+    pub fn is_guest_allowed(&self) -> bool {
+        self.supports_guest() && std::fs::read_to_string("/etc/lightdm/lightdm.conf").ok().and_then(|content| ini_value(&content, "Seat:*", "allow-guest")).as_deref() == Some("true")
+    }
+}
+
+/// Value of a key in a section of an INI file.
+pub fn ini_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let mut curr_section = "";
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') && line.ends_with(']') {
+            curr_section = &line[1..line.len() - 1];
+        } else if curr_section == section {
+            if let Some(idx) = line.find('=') {
+                if line[..idx].trim() == key {
+                    return Some(line[idx + 1..].trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Days and hours a user may log in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginHours {
+    pub days: [bool; 7],
+    pub start: u8,
+    pub end: u8,
+}
+
+impl Default for LoginHours {
+    fn default() -> Self {
+        Self { days: [true; 7], start: 8, end: 20 }
+    }
+}
+
+impl LoginHours {
+    pub fn is_valid(&self) -> bool {
+        self.start < self.end && self.end <= 24
+    }
+}
+
+/// Login settings of a user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserPolicy {
+    pub is_disabled: bool,
+    pub expires: Option<NaiveDate>,
+    pub login_hours: Option<LoginHours>,
+    /// Executables the user may not run, denied through ACLs. Upgrading a package replaces its executables without
+    /// their ACLs, so a pacman hook has `system_settings_helper restore-acls` apply them again from `POLICY_PATH`.
+    pub restricted_apps: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoginPolicy {
+    pub users: HashMap<String, UserPolicy>,
+}
+
+impl LoginPolicy {
+    pub fn load() -> Self {
+        std::fs::read_to_string(POLICY_PATH).ok().and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+    }
+
+    pub fn user(&self, username: &str) -> UserPolicy {
+        self.users.get(username).cloned().unwrap_or_default()
+    }
+}
+
+/// Options of the login screen and of a user, as edited on the Login Options page and sent to the helper.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginOptions {
+    pub username: String,
+    pub auto_login: bool,
+    pub allow_guest: bool,
+    pub policy: UserPolicy,
+}
+
+/// An application the user can be kept from running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestrictableApp {
+    /// Names of the applications starting the executable.
+    pub name: String,
+    pub exec: PathBuf,
+}
+
+/// Applications of the system with the executable they start, sorted by name. Applications started through an
+/// interpreter are left out, and those starting the same executable are listed once.
+pub fn restrictable_apps() -> Vec<RestrictableApp> {
+    let path_dirs: Vec<PathBuf> = std::env::var("PATH").unwrap_or_default().split(':').map(PathBuf::from).collect();
+    let mut apps: Vec<RestrictableApp> = Vec::new();
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(APPLICATIONS).map(|entries| entries.flatten().map(|entry| entry.path()).collect()).unwrap_or_default();
+    entries.sort();
+    for content in entries.iter().filter_map(|path| std::fs::read_to_string(path).ok()) {
+        let value = |key: &str| ini_value(&content, "Desktop Entry", key);
+        if value("NoDisplay").as_deref() == Some("true") {
+            continue;
+        }
+        let program = match value("TryExec").or_else(|| value("Exec").and_then(|exec| exec_program(&exec))) {
+            Some(program) => program,
+            None => continue,
+        };
+        let exec = if program.starts_with('/') {
+            Some(PathBuf::from(program))
+        } else {
+            path_dirs.iter().map(|dir| dir.join(&program)).find(|path| path.exists())
+        };
+        let (exec, name) = match (exec.and_then(|exec| exec.canonicalize().ok()), value("Name")) {
+            (Some(exec), Some(name)) if !is_interpreter(&exec) => (exec, name),
+            _ => continue,
+        };
+        match apps.iter_mut().find(|app| app.exec == exec) {
+            Some(app) => app.name = format!("{}, {}", app.name, name),
+            None => apps.push(RestrictableApp { name, exec }),
+        }
+    }
+    apps.sort_by_key(|app| app.name.to_lowercase());
+    apps
+}
+
+/// The program of the `Exec` key of a desktop entry, after the `env` command setting its environment, if any.
+pub fn exec_program(exec: &str) -> Option<String> {
+    let mut words = exec.split_whitespace().map(|word| word.trim_matches('"'));
+    let mut program = words.next()?;
+    if Path::new(program).file_name() == Some(std::ffi::OsStr::new("env")) {
+        loop {
+            program = words.next()?;
+            match program {
+                // Options followed by their value.
+                "-u" | "--unset" | "-C" | "--chdir" => {
+                    words.next();
+                }
+                _ if program.starts_with('-') || program.contains('=') => {}
+                _ => break,
+            }
+        }
+    }
+    Some(program.to_string())
+}
+
+fn is_interpreter(exec: &Path) -> bool {
+    // Versioned names like `python3.11` are the same interpreter.
+    let name = exec.file_name().map(|name| name.to_string_lossy().trim_end_matches(|c: char| c.is_ascii_digit() || c == '.').to_string()).unwrap_or_default();
+    INTERPRETERS.contains(&name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exec_program() {
+        assert_eq!(exec_program("firefox %u").as_deref(), Some("firefox"));
+        assert_eq!(exec_program("\"/opt/app/bin/app\" --new-window").as_deref(), Some("/opt/app/bin/app"));
+        assert_eq!(exec_program("env GDK_BACKEND=x11 -u LANG gimp %U").as_deref(), Some("gimp"));
+        assert_eq!(exec_program("/usr/bin/env"), None);
+        assert_eq!(exec_program(""), None);
+    }
+
+    #[test]
+    fn test_is_interpreter() {
+        assert!(is_interpreter(Path::new("/usr/bin/python3.11")));
+        assert!(is_interpreter(Path::new("/usr/bin/bash")));
+        assert!(!is_interpreter(Path::new("/usr/lib/firefox/firefox")));
+    }
+}
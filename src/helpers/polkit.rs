@@ -0,0 +1,130 @@
+//! Operations of the privileged helper, shared by System Settings and `system_settings_helper`.
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// logind drop-in of the idle logout of Security & Privacy, written by `idle-logout`.
+pub const IDLE_LOGOUT_CONF: &str = "/etc/systemd/logind.conf.d/60-system-settings-idle.conf";
+
+/// A privileged operation, authorized by its own polkit action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Authorizes all the other actions for the rest of the session.
+    Unlock,
+    DeleteUser,
+    SetDateTime,
+    SetTimeZone,
+    WriteLocale,
+    LoginOptions,
+    AddGroup,
+    EditGroup,
+    DeleteGroup,
+    CreateUser,
+    EditUser,
+    SetAccountType,
+    SetGroups,
+    /// Password of another user, set by an administrator.
+    SetPassword,
+    /// Password of the user running System Settings, who has to give the current one.
+    ChangePassword,
+    IdleLogout,
+    /// Firewall rules, applied or shown with `--ruleset`.
+    Firewall,
+}
+
+impl Action {
+    const ALL: [Action; 17] = [
+        Action::Unlock,
+        Action::DeleteUser,
+        Action::SetDateTime,
+        Action::SetTimeZone,
+        Action::WriteLocale,
+        Action::LoginOptions,
+        Action::AddGroup,
+        Action::EditGroup,
+        Action::DeleteGroup,
+        Action::CreateUser,
+        Action::EditUser,
+        Action::SetAccountType,
+        Action::SetGroups,
+        Action::SetPassword,
+        Action::ChangePassword,
+        Action::IdleLogout,
+        Action::Firewall,
+    ];
+
+    /// First argument of the helper, also the last part of the polkit action id.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Unlock => "unlock",
+            Action::DeleteUser => "delete-user",
+            Action::SetDateTime => "set-datetime",
+            Action::SetTimeZone => "set-timezone",
+            Action::WriteLocale => "write-locale",
+            Action::LoginOptions => "login-options",
+            Action::AddGroup => "add-group",
+            Action::EditGroup => "edit-group",
+            Action::DeleteGroup => "delete-group",
+            Action::CreateUser => "create-user",
+            Action::EditUser => "edit-user",
+            Action::SetAccountType => "set-account-type",
+            Action::SetGroups => "set-groups",
+            Action::SetPassword => "set-password",
+            Action::ChangePassword => "change-password",
+            Action::IdleLogout => "idle-logout",
+            Action::Firewall => "firewall",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.iter().copied().find(|action| action.name() == s).ok_or_else(|| format!("Unknown action: {}", s))
+    }
+}
+
+/// A new account, created by `create-user` together with its password and home folder.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewUser {
+    pub username: String,
+    pub fullname: String,
+    pub is_admin: bool,
+    /// Groups joined besides the administrators one.
+    pub groups: Vec<String>,
+    pub password: String,
+    pub shell: Option<String>,
+    /// Template of the home folder, when it isn't the default one.
+    pub skel: Option<PathBuf>,
+    pub is_encrypted: bool,
+}
+
+/// Whether a folder may be the template of a new home folder: `/etc/skel` or one next to it, like `/etc/skel.kids`.
+/// Other folders could hold files the user creating the account may not read.
+pub fn is_skel_dir(path: &Path) -> bool {
+    match path.canonicalize() {
+        Ok(path) => path.is_dir() && path.parent() == Some(Path::new("/etc")) && path.file_name().map(|name| name.to_string_lossy().starts_with("skel")).unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+/// Details of an existing account, as changed by `edit-user`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserEdit {
+    pub username: String,
+    pub new_username: String,
+    pub fullname: String,
+    pub uid: u32,
+    pub group: String,
+    pub shell: String,
+    pub home: PathBuf,
+}
+
+/// New password of a user, with the current one when users change their own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewPassword {
+    pub username: String,
+    pub old_pwd: Option<String>,
+    pub new_pwd: String,
+}